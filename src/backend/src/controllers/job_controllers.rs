@@ -0,0 +1,21 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use crate::{
+    services::job_services,
+    transformers::job_transformer::PublicJob,
+    utils::{error::SMSManagerError, job_registry::JobRegistry},
+};
+
+pub async fn get_job_by_id(
+    State(jobs): State<JobRegistry>,
+    Path(job_id): Path<String>,
+) -> Result<Json<PublicJob>, SMSManagerError> {
+    let job = job_services::get_job_by_id(&jobs, job_id).await?;
+
+    let transformed_job = PublicJob::from(job.as_ref());
+
+    Ok(Json::from(transformed_job))
+}
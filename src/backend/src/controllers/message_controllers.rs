@@ -0,0 +1,84 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::{
+    repositories::{MessageSort, Repositories},
+    services::message_services::{self, MessageFilters},
+    transformers::{
+        delivery_attempt_transformer::PublicDeliveryAttempt,
+        message_transformer::{PublicMessage, PublicMessagePage},
+    },
+    utils::{error::SMSManagerError, pagination::SortOrder},
+};
+
+/// Which page of a producers messages to list, see message_services::list_messages
+#[derive(Deserialize)]
+pub struct MessageListArgs {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    /// One or more states separated by commas, such as SENT,FAILED
+    pub state: Option<String>,
+    pub body: Option<String>,
+    pub min_time_took_ms: Option<i32>,
+    pub max_time_took_ms: Option<i32>,
+    /// id, time_took or attempts
+    #[serde(default)]
+    pub sort: MessageSort,
+    /// asc or desc
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+pub async fn get_producer_messages(
+    State(repos): State<Repositories>,
+    Path(producer_id): Path<String>,
+    Query(args): Query<MessageListArgs>,
+) -> Result<Json<PublicMessagePage>, SMSManagerError> {
+    let page = message_services::list_messages(
+        &repos,
+        producer_id,
+        args.limit,
+        args.cursor,
+        MessageFilters {
+            states: args.state,
+            body: args.body,
+            min_time_took_ms: args.min_time_took_ms,
+            max_time_took_ms: args.max_time_took_ms,
+        },
+        args.sort,
+        args.order,
+    )
+    .await?;
+
+    let transformed_page: PublicMessagePage = PublicMessagePage::from(page);
+
+    Ok(Json::from(transformed_page))
+}
+
+pub async fn get_message_by_id(
+    State(repos): State<Repositories>,
+    Path(message_id): Path<String>,
+) -> Result<Json<PublicMessage>, SMSManagerError> {
+    let message = message_services::get_message_by_id(&repos, message_id).await?;
+
+    let transformed_message: PublicMessage = PublicMessage::from(message);
+
+    Ok(Json::from(transformed_message))
+}
+
+pub async fn get_message_attempts(
+    State(repos): State<Repositories>,
+    Path((producer_id, message_id)): Path<(String, String)>,
+) -> Result<Json<Vec<PublicDeliveryAttempt>>, SMSManagerError> {
+    let attempts = message_services::get_message_attempts(&repos, producer_id, message_id).await?;
+
+    let transformed_attempts: Vec<PublicDeliveryAttempt> = attempts
+        .into_iter()
+        .map(PublicDeliveryAttempt::from)
+        .collect();
+
+    Ok(Json::from(transformed_attempts))
+}
@@ -1 +1,3 @@
 pub mod producer_controllers;
+pub mod job_controllers;
+pub mod message_controllers;
\ No newline at end of file
@@ -1,16 +1,21 @@
-use std::sync::Arc;
-
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     Json,
 };
 use serde::Deserialize;
 
 use crate::{
+    repositories::{ProducerSort, Repositories},
     services::producer_services,
-    transformers::producer_transformer::{ProgressData, PublicProducer},
-    utils::error::SMSManagerError,
-    PoolHandle,
+    transformers::{
+        job_transformer::PublicJob,
+        producer_transformer::{ProgressData, PublicProducer, PublicProducerPage},
+    },
+    transports::TransportConfig,
+    utils::{
+        error::SMSManagerError, job_registry::JobRegistry, pagination::SortOrder,
+        rate_limiter::RateLimit, retry_policy::RetryPolicy,
+    },
 };
 
 #[derive(Deserialize)]
@@ -22,13 +27,34 @@ pub struct ProducerUpdateArgs {
     pub num_senders: Option<i32>,
 }
 
+/// Which page of the producers to list, see producer_services::list_producers
+#[derive(Deserialize)]
+pub struct ProducerListArgs {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    /// One or more statuses separated by commas, such as SENDING,PAUSED
+    pub status: Option<String>,
+    pub name: Option<String>,
+    /// name or created_at
+    #[serde(default)]
+    pub sort: ProducerSort,
+    /// asc or desc
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+/// The seed a single generate or send call runs with, overriding the producers seed
+#[derive(Deserialize)]
+pub struct SeedArgs {
+    pub seed: Option<i64>,
+}
+
 pub async fn create_producer(
-    State(pool): State<PoolHandle>,
+    State(repos): State<Repositories>,
     Json(payload): Json<ProducerUpdateArgs>,
 ) -> Result<Json<PublicProducer>, SMSManagerError> {
-    let mut db = pool.get()?;
     let producer = producer_services::create_producer(
-        &mut db,
+        &repos,
         payload.name,
         payload.number_messages,
         payload.average_send_delay,
@@ -43,13 +69,12 @@ pub async fn create_producer(
 }
 
 pub async fn update_producer(
-    State(pool): State<PoolHandle>,
+    State(repos): State<Repositories>,
     Path(producer_id): Path<String>,
     Json(payload): Json<ProducerUpdateArgs>,
 ) -> Result<Json<PublicProducer>, SMSManagerError> {
-    let mut db = pool.get()?;
     let producer = producer_services::update_producer(
-        &mut db,
+        &repos,
         producer_id,
         payload.name,
         payload.number_messages,
@@ -64,26 +89,82 @@ pub async fn update_producer(
     Ok(Json::from(transformed_producer))
 }
 
+pub async fn update_producer_transport(
+    State(repos): State<Repositories>,
+    Path(producer_id): Path<String>,
+    Json(payload): Json<TransportConfig>,
+) -> Result<Json<PublicProducer>, SMSManagerError> {
+    let producer =
+        producer_services::update_producer_transport(&repos, producer_id, payload).await?;
+
+    let transformed_producer: PublicProducer = PublicProducer::from(producer);
+
+    Ok(Json::from(transformed_producer))
+}
+
+pub async fn update_producer_retry_policy(
+    State(repos): State<Repositories>,
+    Path(producer_id): Path<String>,
+    Json(payload): Json<RetryPolicy>,
+) -> Result<Json<PublicProducer>, SMSManagerError> {
+    let producer =
+        producer_services::update_producer_retry_policy(&repos, producer_id, payload).await?;
+
+    let transformed_producer: PublicProducer = PublicProducer::from(producer);
+
+    Ok(Json::from(transformed_producer))
+}
+
+pub async fn update_producer_rate_limit(
+    State(repos): State<Repositories>,
+    Path(producer_id): Path<String>,
+    Json(payload): Json<Option<RateLimit>>,
+) -> Result<Json<PublicProducer>, SMSManagerError> {
+    let producer =
+        producer_services::update_producer_rate_limit(&repos, producer_id, payload).await?;
+
+    let transformed_producer: PublicProducer = PublicProducer::from(producer);
+
+    Ok(Json::from(transformed_producer))
+}
+
+pub async fn update_producer_seed(
+    State(repos): State<Repositories>,
+    Path(producer_id): Path<String>,
+    Json(payload): Json<Option<i64>>,
+) -> Result<Json<PublicProducer>, SMSManagerError> {
+    let producer = producer_services::update_producer_seed(&repos, producer_id, payload).await?;
+
+    let transformed_producer: PublicProducer = PublicProducer::from(producer);
+
+    Ok(Json::from(transformed_producer))
+}
+
 pub async fn get_all_producers(
-    State(pool): State<PoolHandle>,
-) -> Result<Json<Vec<PublicProducer>>, SMSManagerError> {
-    let mut db = pool.get()?;
-    let producers = producer_services::get_all_producers(&mut db).await?;
+    State(repos): State<Repositories>,
+    Query(args): Query<ProducerListArgs>,
+) -> Result<Json<PublicProducerPage>, SMSManagerError> {
+    let page = producer_services::list_producers(
+        &repos,
+        args.limit,
+        args.cursor,
+        args.status,
+        args.name,
+        args.sort,
+        args.order,
+    )
+    .await?;
 
-    let transformed_producers: Vec<PublicProducer> =
-        producers.into_iter().map(PublicProducer::from).collect();
+    let transformed_page: PublicProducerPage = PublicProducerPage::from(page);
 
-    Ok(Json::from(transformed_producers))
+    Ok(Json::from(transformed_page))
 }
 
 pub async fn get_producer_by_id(
-    State(pool): State<PoolHandle>,
+    State(repos): State<Repositories>,
     Path(producer_id): Path<String>,
 ) -> Result<Json<PublicProducer>, SMSManagerError> {
-    let mut db: diesel::r2d2::PooledConnection<
-        diesel::r2d2::ConnectionManager<diesel::PgConnection>,
-    > = pool.get()?;
-    let producer = producer_services::get_producer_by_id(&mut db, producer_id).await?;
+    let producer = producer_services::get_producer_by_id(&repos, producer_id).await?;
 
     let transformed_producer: PublicProducer = PublicProducer::from(producer);
 
@@ -91,46 +172,79 @@ pub async fn get_producer_by_id(
 }
 
 pub async fn generate_messages(
-    State(pool): State<PoolHandle>,
+    State(repos): State<Repositories>,
     Path(producer_id): Path<String>,
+    Query(args): Query<SeedArgs>,
 ) -> Result<Json<i32>, SMSManagerError> {
-    let mut db: diesel::r2d2::PooledConnection<
-        diesel::r2d2::ConnectionManager<diesel::PgConnection>,
-    > = pool.get()?;
-    let number_messages = producer_services::generate_messages(&mut db, producer_id).await?;
+    let number_messages =
+        producer_services::generate_messages(&repos, producer_id, args.seed).await?;
 
     Ok(Json::from(number_messages))
 }
 
 pub async fn activate_producer(
-    State(pool): State<PoolHandle>,
+    State(repos): State<Repositories>,
+    State(jobs): State<JobRegistry>,
     Path(producer_id): Path<String>,
-) -> Result<Json<String>, SMSManagerError> {
-    let success_message = producer_services::activate_producer(Arc::new(pool), producer_id).await?;
+    Query(args): Query<SeedArgs>,
+) -> Result<Json<PublicJob>, SMSManagerError> {
+    let job = producer_services::activate_producer(&repos, &jobs, producer_id, args.seed).await?;
 
-    Ok(Json::from(success_message))
+    let transformed_job = PublicJob::from(job.as_ref());
+
+    Ok(Json::from(transformed_job))
+}
+
+pub async fn pause_producer(
+    State(repos): State<Repositories>,
+    State(jobs): State<JobRegistry>,
+    Path(producer_id): Path<String>,
+) -> Result<Json<PublicJob>, SMSManagerError> {
+    let job = producer_services::pause_producer(&repos, &jobs, producer_id).await?;
+
+    let transformed_job = PublicJob::from(job.as_ref());
+
+    Ok(Json::from(transformed_job))
+}
+
+pub async fn resume_producer(
+    State(repos): State<Repositories>,
+    State(jobs): State<JobRegistry>,
+    Path(producer_id): Path<String>,
+) -> Result<Json<PublicJob>, SMSManagerError> {
+    let job = producer_services::resume_producer(&repos, &jobs, producer_id).await?;
+
+    let transformed_job = PublicJob::from(job.as_ref());
+
+    Ok(Json::from(transformed_job))
+}
+
+pub async fn cancel_producer(
+    State(repos): State<Repositories>,
+    State(jobs): State<JobRegistry>,
+    Path(producer_id): Path<String>,
+) -> Result<Json<PublicJob>, SMSManagerError> {
+    let job = producer_services::cancel_producer(&repos, &jobs, producer_id).await?;
+
+    let transformed_job = PublicJob::from(job.as_ref());
+
+    Ok(Json::from(transformed_job))
 }
 
 pub async fn get_producer_progress_data(
-    State(pool): State<PoolHandle>,
+    State(repos): State<Repositories>,
     Path(producer_id): Path<String>,
 ) -> Result<Json<ProgressData>, SMSManagerError> {
-    let mut db: diesel::r2d2::PooledConnection<
-        diesel::r2d2::ConnectionManager<diesel::PgConnection>,
-    > = pool.get()?;
-    let progress_data = producer_services::get_producer_progress_data(&mut db, producer_id).await?;
+    let progress_data = producer_services::get_producer_progress_data(&repos, producer_id).await?;
 
     Ok(Json::from(progress_data))
 }
 
 pub async fn delete_producer(
-    State(pool): State<PoolHandle>,
+    State(repos): State<Repositories>,
     Path(producer_id): Path<String>,
 ) -> Result<Json<String>, SMSManagerError> {
-    let mut db: diesel::r2d2::PooledConnection<
-        diesel::r2d2::ConnectionManager<diesel::PgConnection>,
-    > = pool.get()?;
-    let success_message = producer_services::delete_producer(&mut db, producer_id).await?;
+    let success_message = producer_services::delete_producer(&repos, producer_id).await?;
 
     Ok(Json::from(success_message))
 }
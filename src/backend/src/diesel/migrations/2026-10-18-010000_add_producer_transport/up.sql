@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE "producers" ADD COLUMN "transport" JSONB NOT NULL DEFAULT '{"kind": "SIMULATED"}';
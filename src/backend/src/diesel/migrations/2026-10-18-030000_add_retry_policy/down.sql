@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "messages" DROP COLUMN IF EXISTS "attempts";
+ALTER TABLE "producers" DROP COLUMN IF EXISTS "retry_policy";
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE "producers" ADD COLUMN "retry_policy" JSONB NOT NULL DEFAULT '{}';
+ALTER TABLE "messages" ADD COLUMN "attempts" INTEGER NOT NULL DEFAULT 0;
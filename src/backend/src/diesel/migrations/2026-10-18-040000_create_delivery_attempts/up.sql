@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE "delivery_attempts"(
+	"id" UUID NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(),
+	"message_id" UUID NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+	"attempt_number" INTEGER NOT NULL,
+	"started_at" TIMESTAMPTZ NOT NULL,
+	"finished_at" TIMESTAMPTZ NOT NULL,
+	"outcome" TEXT NOT NULL,
+	"error_code" TEXT,
+	"error_reason" TEXT,
+	"worker_id" INTEGER NOT NULL
+);
+
+CREATE INDEX "delivery_attempts_message_id_idx" ON "delivery_attempts"("message_id", "attempt_number");
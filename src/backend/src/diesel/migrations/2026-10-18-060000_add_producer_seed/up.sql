@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE "producers" ADD COLUMN "seed" BIGINT;
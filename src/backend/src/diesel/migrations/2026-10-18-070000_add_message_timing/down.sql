@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "messages" DROP COLUMN IF EXISTS "time_took_ms";
+ALTER TABLE "messages" DROP COLUMN IF EXISTS "finished_at";
+ALTER TABLE "messages" DROP COLUMN IF EXISTS "started_at";
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE "messages" ADD COLUMN "started_at" TIMESTAMPTZ;
+ALTER TABLE "messages" ADD COLUMN "finished_at" TIMESTAMPTZ;
+ALTER TABLE "messages" ADD COLUMN "time_took_ms" INTEGER;
+
+UPDATE "messages" SET "time_took_ms" = "time_took" * 1000 WHERE "time_took" IS NOT NULL;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "messages_in_flight_idx";
+
+ALTER TABLE "messages" DROP COLUMN IF EXISTS "lease_expires_at";
+ALTER TABLE "messages" DROP COLUMN IF EXISTS "leased_by";
+ALTER TABLE "messages" DROP COLUMN IF EXISTS "in_flight";
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE "messages" ADD COLUMN "in_flight" BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE "messages" ADD COLUMN "leased_by" UUID;
+ALTER TABLE "messages" ADD COLUMN "lease_expires_at" TIMESTAMPTZ;
+
+CREATE INDEX "messages_in_flight_idx" ON "messages"("lease_expires_at") WHERE "in_flight";
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE "producers" ADD COLUMN "generated_messages" INTEGER NOT NULL DEFAULT 0;
+
+UPDATE "producers" SET "generated_messages" = (SELECT COUNT(*) FROM "messages" WHERE "messages"."produced_by" = "producers"."id");
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "producers" ALTER COLUMN "status" TYPE TEXT USING "status"::TEXT;
+
+DROP TYPE IF EXISTS "producer_status";
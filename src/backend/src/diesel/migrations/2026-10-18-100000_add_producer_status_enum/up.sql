@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TYPE "producer_status" AS ENUM (
+	'INACTIVE',
+	'GENERATING',
+	'GENERATED',
+	'SENDING',
+	'PAUSED',
+	'CANCELLED',
+	'EMPTY',
+	'INTERRUPTED'
+);
+
+ALTER TABLE "producers" ALTER COLUMN "status" TYPE "producer_status" USING "status"::"producer_status";
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE "producers" ADD COLUMN "created_at" TIMESTAMPTZ NOT NULL DEFAULT now();
+
+CREATE INDEX "producers_created_at_idx" ON "producers"("created_at", "id");
+CREATE INDEX "producers_name_idx" ON "producers"("name", "id");
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+-- The position of a message among the messages generated with it, which seeded draws are keyed on so they repeat across generations
+ALTER TABLE "messages" ADD COLUMN "message_index" INTEGER NOT NULL DEFAULT 0;
+
+UPDATE "messages" SET "message_index" = "numbered"."message_index"
+FROM (
+    SELECT "id", ROW_NUMBER() OVER (PARTITION BY "produced_by" ORDER BY "id") - 1 AS "message_index"
+    FROM "messages"
+) AS "numbered"
+WHERE "messages"."id" = "numbered"."id";
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+-- Every server sending a producer holds a lease on it, so a server starting up only interrupts the sends no live server holds
+CREATE TABLE "producer_leases"(
+	"producer_id" UUID NOT NULL REFERENCES producers(id) ON DELETE CASCADE,
+	"leased_by" UUID NOT NULL,
+	"lease_expires_at" TIMESTAMPTZ NOT NULL,
+	PRIMARY KEY ("producer_id", "leased_by")
+);
@@ -1,2 +1,4 @@
 pub mod models;
 pub mod schema;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_schema;
\ No newline at end of file
@@ -3,12 +3,14 @@
 #![allow(unused)]
 #![allow(clippy::all)]
 
+use chrono::{DateTime, Utc};
 use diesel::{prelude::*, sql_types::Bool};
 use uuid::Uuid;
 
-use super::schema::{messages, producers};
+use super::schema::{delivery_attempts, messages, producers};
+use crate::utils::producer_status::ProducerStatus;
 
-#[derive(Queryable, Debug)]
+#[derive(Queryable, Debug, Clone)]
 pub struct Message {
     pub id: Uuid,
     pub message_body: String,
@@ -16,6 +18,16 @@ pub struct Message {
     pub failed: bool,
     pub time_took: Option<i32>,
     pub produced_by: Uuid,
+    pub provider_message_id: Option<String>,
+    pub attempts: i32,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub time_took_ms: Option<i32>,
+    pub in_flight: bool,
+    pub leased_by: Option<Uuid>,
+    pub lease_expires_at: Option<DateTime<Utc>>,
+    /// The position of the message among the messages generated with it
+    pub message_index: i32,
 }
 
 #[derive(Insertable)]
@@ -29,10 +41,11 @@ pub struct NewMessageFull {
 }
 
 #[derive(Insertable)]
-#[diesel(table_name = messages)]
+#[diesel(table_name = messages, treat_none_as_default_value = false)]
 pub struct NewMessage {
     pub message_body: String,
     pub produced_by: Uuid,
+    pub message_index: i32,
 }
 
 #[derive(Queryable, Identifiable, AsChangeset, Debug)]
@@ -44,7 +57,14 @@ pub struct Producer {
     pub average_send_delay: i32,
     pub failure_rate: i32,
     pub num_senders: Option<i32>,
-    pub status: String,
+    pub status: ProducerStatus,
+    pub transport: serde_json::Value,
+    pub retry_policy: serde_json::Value,
+    pub rate_limit: Option<serde_json::Value>,
+    pub seed: Option<i64>,
+    /// How many messages have been written so far while the producer is GENERATING
+    pub generated_messages: i32,
+    pub created_at: DateTime<Utc>,
 }
 
 impl Clone for Producer {
@@ -56,7 +76,13 @@ impl Clone for Producer {
             failure_rate: self.failure_rate,
             number_messages: self.number_messages,
             average_send_delay: self.average_send_delay,
-            status: self.status.to_string(),
+            status: self.status,
+            transport: self.transport.clone(),
+            retry_policy: self.retry_policy.clone(),
+            rate_limit: self.rate_limit.clone(),
+            seed: self.seed,
+            generated_messages: self.generated_messages,
+            created_at: self.created_at,
         }
     }
 }
@@ -69,5 +95,46 @@ pub struct NewProducer {
     pub average_send_delay: i32,
     pub failure_rate: i32,
     pub num_senders: Option<i32>,
-    pub status: String,
+    pub status: ProducerStatus,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct DeliveryAttempt {
+    pub id: Uuid,
+    pub message_id: Uuid,
+    pub attempt_number: i32,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub outcome: String,
+    pub error_code: Option<String>,
+    pub error_reason: Option<String>,
+    pub worker_id: i32,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = delivery_attempts)]
+pub struct NewDeliveryAttempt {
+    pub message_id: Uuid,
+    pub attempt_number: i32,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub outcome: String,
+    pub error_code: Option<String>,
+    pub error_reason: Option<String>,
+    pub worker_id: i32,
+}
+
+/// The settings of a producer to change, every field left as None keeps its stored value
+#[derive(AsChangeset, Debug, Default, Clone)]
+#[diesel(table_name = producers)]
+pub struct ProducerChanges {
+    pub name: Option<String>,
+    pub number_messages: Option<i32>,
+    pub average_send_delay: Option<i32>,
+    pub failure_rate: Option<i32>,
+    pub num_senders: Option<Option<i32>>,
+    pub transport: Option<serde_json::Value>,
+    pub retry_policy: Option<serde_json::Value>,
+    pub rate_limit: Option<Option<serde_json::Value>>,
+    pub seed: Option<Option<i64>>,
 }
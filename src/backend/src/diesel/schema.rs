@@ -1,5 +1,25 @@
 // @generated automatically by Diesel CLI.
 
+pub mod sql_types {
+    #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "producer_status"))]
+    pub struct ProducerStatus;
+}
+
+diesel::table! {
+    delivery_attempts (id) {
+        id -> Uuid,
+        message_id -> Uuid,
+        attempt_number -> Int4,
+        started_at -> Timestamptz,
+        finished_at -> Timestamptz,
+        outcome -> Text,
+        error_code -> Nullable<Text>,
+        error_reason -> Nullable<Text>,
+        worker_id -> Int4,
+    }
+}
+
 diesel::table! {
     messages (id) {
         id -> Uuid,
@@ -8,10 +28,30 @@ diesel::table! {
         failed -> Bool,
         time_took -> Nullable<Int4>,
         produced_by -> Uuid,
+        provider_message_id -> Nullable<Text>,
+        attempts -> Int4,
+        started_at -> Nullable<Timestamptz>,
+        finished_at -> Nullable<Timestamptz>,
+        time_took_ms -> Nullable<Int4>,
+        in_flight -> Bool,
+        leased_by -> Nullable<Uuid>,
+        lease_expires_at -> Nullable<Timestamptz>,
+        message_index -> Int4,
+    }
+}
+
+diesel::table! {
+    producer_leases (producer_id, leased_by) {
+        producer_id -> Uuid,
+        leased_by -> Uuid,
+        lease_expires_at -> Timestamptz,
     }
 }
 
 diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ProducerStatus;
+
     producers (id) {
         id -> Uuid,
         name -> Text,
@@ -19,13 +59,23 @@ diesel::table! {
         average_send_delay -> Int4,
         failure_rate -> Int4,
         num_senders -> Nullable<Int4>,
-        status -> Text,
+        status -> ProducerStatus,
+        transport -> Jsonb,
+        retry_policy -> Jsonb,
+        rate_limit -> Nullable<Jsonb>,
+        seed -> Nullable<Int8>,
+        generated_messages -> Int4,
+        created_at -> Timestamptz,
     }
 }
 
+diesel::joinable!(delivery_attempts -> messages (message_id));
 diesel::joinable!(messages -> producers (produced_by));
+diesel::joinable!(producer_leases -> producers (producer_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    delivery_attempts,
     messages,
+    producer_leases,
     producers,
 );
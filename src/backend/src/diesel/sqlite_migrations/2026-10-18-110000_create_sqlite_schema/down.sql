@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS "delivery_attempts";
+DROP TABLE IF EXISTS "messages";
+DROP TABLE IF EXISTS "producers";
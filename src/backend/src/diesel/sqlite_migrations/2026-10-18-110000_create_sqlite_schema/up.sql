@@ -0,0 +1,60 @@
+-- Your SQL goes here
+-- Uuids are stored as lowercase hyphenated text, which orders the same as Postgres orders uuids
+-- Timestamps are stored as UTC text, which orders the same as the instants they stand for
+CREATE TABLE "producers"(
+	"id" TEXT NOT NULL PRIMARY KEY,
+	"name" TEXT NOT NULL,
+	"number_messages" INTEGER NOT NULL,
+	"average_send_delay" INTEGER NOT NULL,
+	"failure_rate" INTEGER NOT NULL,
+	"num_senders" INTEGER,
+	"status" TEXT NOT NULL CHECK ("status" IN (
+		'INACTIVE',
+		'GENERATING',
+		'GENERATED',
+		'SENDING',
+		'PAUSED',
+		'CANCELLED',
+		'EMPTY',
+		'INTERRUPTED'
+	)),
+	"transport" TEXT NOT NULL DEFAULT '{"kind": "SIMULATED"}',
+	"retry_policy" TEXT NOT NULL DEFAULT '{}',
+	"rate_limit" TEXT,
+	"seed" BIGINT,
+	"generated_messages" INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE "messages"(
+	"id" TEXT NOT NULL PRIMARY KEY,
+	"message_body" TEXT NOT NULL,
+	"sent" BOOLEAN NOT NULL DEFAULT FALSE,
+	"failed" BOOLEAN NOT NULL DEFAULT FALSE,
+	"time_took" INTEGER,
+	"produced_by" TEXT NOT NULL REFERENCES producers(id),
+	"provider_message_id" TEXT,
+	"attempts" INTEGER NOT NULL DEFAULT 0,
+	"started_at" TEXT,
+	"finished_at" TEXT,
+	"time_took_ms" INTEGER,
+	"in_flight" BOOLEAN NOT NULL DEFAULT FALSE,
+	"leased_by" TEXT,
+	"lease_expires_at" TEXT
+);
+
+CREATE INDEX "messages_produced_by_idx" ON "messages"("produced_by", "id");
+CREATE INDEX "messages_in_flight_idx" ON "messages"("lease_expires_at") WHERE "in_flight";
+
+CREATE TABLE "delivery_attempts"(
+	"id" TEXT NOT NULL PRIMARY KEY,
+	"message_id" TEXT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+	"attempt_number" INTEGER NOT NULL,
+	"started_at" TEXT NOT NULL,
+	"finished_at" TEXT NOT NULL,
+	"outcome" TEXT NOT NULL,
+	"error_code" TEXT,
+	"error_reason" TEXT,
+	"worker_id" INTEGER NOT NULL
+);
+
+CREATE INDEX "delivery_attempts_message_id_idx" ON "delivery_attempts"("message_id", "attempt_number");
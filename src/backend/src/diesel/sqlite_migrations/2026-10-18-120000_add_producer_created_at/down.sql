@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "producers_name_idx";
+DROP INDEX IF EXISTS "producers_created_at_idx";
+
+ALTER TABLE "producers" DROP COLUMN "created_at";
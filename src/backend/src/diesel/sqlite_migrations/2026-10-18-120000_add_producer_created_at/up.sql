@@ -0,0 +1,8 @@
+-- Your SQL goes here
+-- SQLite can only add a column with a constant default, producers that already exist are dated to when this runs instead
+ALTER TABLE "producers" ADD COLUMN "created_at" TEXT NOT NULL DEFAULT '1970-01-01 00:00:00+00:00';
+
+UPDATE "producers" SET "created_at" = strftime('%Y-%m-%d %H:%M:%f+00:00', 'now');
+
+CREATE INDEX "producers_created_at_idx" ON "producers"("created_at", "id");
+CREATE INDEX "producers_name_idx" ON "producers"("name", "id");
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- Finds the messages of a producer, and which of them are sent or failed, without reading the whole table
+CREATE INDEX "messages_progress_idx" ON "messages"("produced_by", "sent", "failed");
@@ -0,0 +1,96 @@
+// The SQLite counterpart of schema.rs, kept in step with src/diesel/sqlite_migrations by hand
+
+pub mod sql_types {
+    /// A uuid stored as lowercase hyphenated text, see utils::uuid
+    #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
+    #[diesel(sqlite_type(name = "Text"))]
+    pub struct Uuid;
+
+    /// A producer status stored as its name
+    #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
+    #[diesel(sqlite_type(name = "Text"))]
+    pub struct ProducerStatus;
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Uuid;
+
+    delivery_attempts (id) {
+        id -> Uuid,
+        message_id -> Uuid,
+        attempt_number -> Integer,
+        started_at -> TimestamptzSqlite,
+        finished_at -> TimestamptzSqlite,
+        outcome -> Text,
+        error_code -> Nullable<Text>,
+        error_reason -> Nullable<Text>,
+        worker_id -> Integer,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Uuid;
+
+    messages (id) {
+        id -> Uuid,
+        message_body -> Text,
+        sent -> Bool,
+        failed -> Bool,
+        time_took -> Nullable<Integer>,
+        produced_by -> Uuid,
+        provider_message_id -> Nullable<Text>,
+        attempts -> Integer,
+        started_at -> Nullable<TimestamptzSqlite>,
+        finished_at -> Nullable<TimestamptzSqlite>,
+        time_took_ms -> Nullable<Integer>,
+        in_flight -> Bool,
+        leased_by -> Nullable<Uuid>,
+        lease_expires_at -> Nullable<TimestamptzSqlite>,
+        message_index -> Integer,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Uuid;
+
+    producer_leases (producer_id, leased_by) {
+        producer_id -> Uuid,
+        leased_by -> Uuid,
+        lease_expires_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::{ProducerStatus, Uuid};
+
+    producers (id) {
+        id -> Uuid,
+        name -> Text,
+        number_messages -> Integer,
+        average_send_delay -> Integer,
+        failure_rate -> Integer,
+        num_senders -> Nullable<Integer>,
+        status -> ProducerStatus,
+        transport -> Json,
+        retry_policy -> Json,
+        rate_limit -> Nullable<Json>,
+        seed -> Nullable<BigInt>,
+        generated_messages -> Integer,
+        created_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::joinable!(delivery_attempts -> messages (message_id));
+diesel::joinable!(messages -> producers (produced_by));
+diesel::joinable!(producer_leases -> producers (producer_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    delivery_attempts,
+    messages,
+    producer_leases,
+    producers,
+);
@@ -1,9 +1,14 @@
 use ::diesel::{r2d2, PgConnection};
+use axum::extract::FromRef;
+use repositories::Repositories;
+use utils::job_registry::JobRegistry;
 
 pub mod controllers;
 pub mod diesel;
+pub mod repositories;
 pub mod services;
 pub mod transformers;
+pub mod transports;
 pub mod utils;
 pub mod routes;
 
@@ -11,3 +16,22 @@ pub mod routes;
 pub type Database = PgConnection;
 
 pub type PoolHandle = r2d2::Pool<r2d2::ConnectionManager<PgConnection>>;
+
+/// The state shared by every route, handlers can extract any of the fields directly through axum's State extractor
+#[derive(Clone)]
+pub struct AppState {
+    pub repositories: Repositories,
+    pub jobs: JobRegistry,
+}
+
+impl FromRef<AppState> for Repositories {
+    fn from_ref(state: &AppState) -> Self {
+        state.repositories.clone()
+    }
+}
+
+impl FromRef<AppState> for JobRegistry {
+    fn from_ref(state: &AppState) -> Self {
+        state.jobs.clone()
+    }
+}
@@ -1,25 +1,48 @@
+use std::time::Duration;
+
 use axum::{
     http::{header::CONTENT_TYPE, HeaderValue, Method},
     Router,
 };
 use backend::{
-    diesel::schema::producers::dsl::*, routes::producer_routes::get_producer_router, PoolHandle,
+    repositories::Repositories,
+    routes::{
+        job_routes::get_job_router, message_routes::get_message_router,
+        producer_routes::get_producer_router,
+    },
+    services::{
+        producer_services::activate_producer, recovery_services::recover_interrupted_sends,
+    },
+    utils::{job_registry::JobRegistry, rate_limiter::RateLimit, sender::UpdaterConfig},
+    AppState, PoolHandle,
 };
 use diesel::{
     r2d2::{ConnectionManager, Pool},
-    ExpressionMethods, PgConnection, RunQueryDsl,
+    PgConnection,
 };
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use dotenvy::dotenv;
 use tower_http::cors::CorsLayer;
+use uuid::Uuid;
 
 const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./src/diesel/migrations");
 
-#[tokio::main]
-async fn main() {
-    println!("Initializing database connections...");
-    dotenv().ok();
-    let manager = ConnectionManager::<PgConnection>::new(std::env::var("DATABASE_URL").unwrap());
+/// Connects to the storage DATABASE_URL points at and migrates it
+/// A sqlite:// url, such as sqlite://sms_manager.db, stores everything in that SQLite file, any other url is a Postgres database
+fn connect_repositories(database_url: &str) -> Repositories {
+    #[cfg(feature = "sqlite")]
+    if let Some(path) = database_url.strip_prefix("sqlite://") {
+        let repositories = Repositories::sqlite(path).expect("Could not open the SQLite database");
+        println!("Successfully migrated DB!");
+        return repositories;
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    if database_url.starts_with("sqlite://") {
+        panic!("Storing in SQLite needs the server to be built with --features sqlite");
+    }
+
+    let manager = ConnectionManager::<PgConnection>::new(database_url);
     let db: PoolHandle = Pool::builder()
         .test_on_check_out(true)
         .build(manager)
@@ -28,13 +51,104 @@ async fn main() {
     conn.run_pending_migrations(MIGRATIONS)
         .expect("Could not run migrations!");
     println!("Successfully migrated DB!");
+    Repositories::postgres(db)
+}
+
+#[tokio::main]
+async fn main() {
+    println!("Initializing database connections...");
+    dotenv().ok();
+    let repositories = connect_repositories(&std::env::var("DATABASE_URL").unwrap());
+
+    // An optional limit shared by every producer, such as {"rate": 100, "per": "SECOND", "burst": 100}
+    let jobs = match std::env::var("GLOBAL_RATE_LIMIT") {
+        Ok(value) => {
+            let limit: RateLimit =
+                serde_json::from_str(&value).expect("GLOBAL_RATE_LIMIT must be a valid rate limit");
+            limit
+                .validate()
+                .expect("GLOBAL_RATE_LIMIT must be a valid rate limit");
+            println!(
+                "Limiting every producer to {} messages per second together",
+                limit.per_second()
+            );
+            JobRegistry::with_global_rate_limit(limit)
+        }
+        Err(_) => JobRegistry::new(),
+    };
+
+    // INSTANCE_ID keeps the id of this server across restarts, so it can take back the sends it was running as soon as it starts
+    // Without it every run gets an id of its own, and the sends cut off by a restart are only recovered once their leases expire
+    let jobs = match std::env::var("INSTANCE_ID") {
+        Ok(value) => jobs
+            .with_instance_id(Uuid::parse_str(&value).expect("INSTANCE_ID must be a valid uuid")),
+        Err(_) => {
+            println!(
+                "INSTANCE_ID is not set, running as {} until the server stops",
+                jobs.instance_id()
+            );
+            jobs
+        }
+    };
+
+    // The updater writes the results of a send in batches of UPDATE_BATCH_SIZE, or whatever it has every UPDATE_FLUSH_INTERVAL_MS
+    let mut updater_config = UpdaterConfig::default();
+    if let Ok(value) = std::env::var("UPDATE_BATCH_SIZE") {
+        updater_config.batch_size = value
+            .parse()
+            .expect("UPDATE_BATCH_SIZE must be a positive number");
+    }
+    if let Ok(value) = std::env::var("UPDATE_FLUSH_INTERVAL_MS") {
+        updater_config.flush_interval = Duration::from_millis(
+            value
+                .parse()
+                .expect("UPDATE_FLUSH_INTERVAL_MS must be a positive number"),
+        );
+    }
+    updater_config
+        .validate()
+        .expect("UPDATE_BATCH_SIZE and UPDATE_FLUSH_INTERVAL_MS must be valid");
+    let jobs = jobs.with_updater_config(updater_config);
+
+    // Finished jobs can be looked up for FINISHED_JOB_RETENTION_SECS, an hour by default, before they are dropped
+    let jobs = match std::env::var("FINISHED_JOB_RETENTION_SECS") {
+        Ok(value) => jobs.with_finished_job_retention(Duration::from_secs(
+            value
+                .parse()
+                .expect("FINISHED_JOB_RETENTION_SECS must be a positive number"),
+        )),
+        Err(_) => jobs,
+    };
+
+    // Sends that were running when the server last stopped are picked back up instead of forgotten
+    let recovery = recover_interrupted_sends(&repositories, jobs.instance_id())
+        .await
+        .expect("Could not recover interrupted sends");
+    println!(
+        "Requeued {} in flight messages, {} producers were interrupted",
+        recovery.requeued_messages,
+        recovery.interrupted_sends.len() + recovery.interrupted_pauses.len()
+    );
 
-    let _ = diesel::update(producers)
-        .set(status.eq("INACTIVE"))
-        .execute(&mut conn);
+    // Paused sends stay interrupted, as they were stopped on purpose
+    if std::env::var("RESUME_INTERRUPTED_SENDS").is_ok_and(|value| value == "true") {
+        for interrupted_producer in recovery.interrupted_sends {
+            match activate_producer(&repositories, &jobs, interrupted_producer.to_string(), None)
+                .await
+            {
+                Ok(_) => println!("Resumed sending producer {}", interrupted_producer),
+                Err(err) => eprintln!(
+                    "Could not resume sending producer {}: {}",
+                    interrupted_producer, err
+                ),
+            }
+        }
+    }
 
     let app = Router::new()
         .nest("/producers", get_producer_router())
+        .nest("/jobs", get_job_router())
+        .nest("/messages", get_message_router())
         .layer(
             CorsLayer::new()
                 .allow_methods([Method::GET, Method::POST])
@@ -46,7 +160,7 @@ async fn main() {
                 )
                 .allow_headers([CONTENT_TYPE]),
         )
-        .with_state(db.clone());
+        .with_state(AppState { repositories, jobs });
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
     axum::serve(listener, app).await.unwrap();
@@ -0,0 +1,631 @@
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    sync::{Mutex, MutexGuard},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::{
+    status_conflict, MessageChunks, MessageCursor, MessagePage, MessageQuery, MessageRepository,
+    ProducerCursor, ProducerPage, ProducerQuery, ProducerRepository, ProgressStats, RecoveryReport,
+    StatusClaim, LEASE_DURATION,
+};
+use crate::{
+    diesel::models::{
+        DeliveryAttempt, Message, NewDeliveryAttempt, NewMessage, NewProducer, Producer,
+        ProducerChanges,
+    },
+    utils::{
+        error::SMSManagerError,
+        message_state::MessageState,
+        message_utils::get_progress_stats_from_messages,
+        pagination::SortOrder,
+        producer_status::{ProducerAction, ProducerStatus},
+    },
+};
+
+/// Everything the in memory repository stores
+#[derive(Default)]
+struct MemoryState {
+    /// In the order they were created
+    producers: Vec<Producer>,
+    /// Ordered by id, the same as Postgres orders uuids
+    messages: BTreeMap<Uuid, Message>,
+    attempts: Vec<DeliveryAttempt>,
+    /// When the lease each server holds on a producer it is sending expires, keyed by the producer and the server
+    producer_leases: BTreeMap<(Uuid, Uuid), DateTime<Utc>>,
+}
+
+impl MemoryState {
+    fn producer(&self, producer_id: Uuid) -> Result<&Producer, SMSManagerError> {
+        self.producers
+            .iter()
+            .find(|producer| producer.id == producer_id)
+            .ok_or(SMSManagerError::EmptyResult)
+    }
+
+    fn producer_mut(&mut self, producer_id: Uuid) -> Result<&mut Producer, SMSManagerError> {
+        self.producers
+            .iter_mut()
+            .find(|producer| producer.id == producer_id)
+            .ok_or(SMSManagerError::EmptyResult)
+    }
+
+    /// The producers unsent messages that are not held by a live lease
+    fn claimable(&mut self, producer_id: Uuid) -> impl Iterator<Item = &mut Message> + '_ {
+        let now = Utc::now();
+        self.messages.values_mut().filter(move |message| {
+            message.produced_by == producer_id
+                && !message.sent
+                && (!message.in_flight || message.lease_expires_at.is_some_and(|at| at < now))
+        })
+    }
+
+    /// The producers in flight messages leased to the owner
+    fn leased_to(
+        &mut self,
+        producer_id: Uuid,
+        owner: Uuid,
+    ) -> impl Iterator<Item = &mut Message> + '_ {
+        self.messages.values_mut().filter(move |message| {
+            message.produced_by == producer_id
+                && message.in_flight
+                && message.leased_by == Some(owner)
+        })
+    }
+}
+
+/// Stores producers and messages in the memory of this process, behaving like the Postgres repository so services can be tested without a database
+/// Every call holds one lock over everything for as long as it runs, which makes each of them atomic
+#[derive(Default)]
+pub struct MemoryRepository {
+    state: Mutex<MemoryState>,
+}
+
+impl MemoryRepository {
+    pub fn new() -> Self {
+        MemoryRepository::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, MemoryState> {
+        // A test that panicked while holding the lock leaves nothing half written, as no call can fail part way through
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl ProducerRepository for MemoryRepository {
+    async fn insert(&self, new_producer: NewProducer) -> Result<Producer, SMSManagerError> {
+        let producer = Producer {
+            id: Uuid::new_v4(),
+            name: new_producer.name,
+            number_messages: new_producer.number_messages,
+            average_send_delay: new_producer.average_send_delay,
+            failure_rate: new_producer.failure_rate,
+            num_senders: new_producer.num_senders,
+            status: new_producer.status,
+            transport: serde_json::json!({"kind": "SIMULATED"}),
+            retry_policy: serde_json::json!({}),
+            rate_limit: None,
+            seed: None,
+            generated_messages: 0,
+            created_at: Utc::now(),
+        };
+
+        self.state().producers.push(producer.clone());
+        Ok(producer)
+    }
+
+    async fn find(&self, producer_id: Uuid) -> Result<Producer, SMSManagerError> {
+        self.state().producer(producer_id).cloned()
+    }
+
+    async fn list_page(&self, query: ProducerQuery) -> Result<ProducerPage, SMSManagerError> {
+        let state = self.state();
+        let name_filter = query.name.as_ref().map(|name| name.to_lowercase());
+
+        let mut status_counts: Vec<(ProducerStatus, i64)> = vec![];
+        let mut filtered: Vec<Producer> = vec![];
+        for producer in &state.producers {
+            if name_filter
+                .as_ref()
+                .is_some_and(|name| !producer.name.to_lowercase().contains(name))
+            {
+                continue;
+            }
+
+            match status_counts
+                .iter_mut()
+                .find(|(status, _)| *status == producer.status)
+            {
+                Some((_, count)) => *count += 1,
+                None => status_counts.push((producer.status, 1)),
+            }
+            if query.statuses.is_empty() || query.statuses.contains(&producer.status) {
+                filtered.push(producer.clone());
+            }
+        }
+        let total = filtered.len() as i64;
+
+        filtered.sort_by(|a, b| position(a, &ProducerCursor::of(b, query.sort)));
+        if query.order == SortOrder::Desc {
+            filtered.reverse();
+        }
+        let rows = filtered
+            .into_iter()
+            .filter(|producer| {
+                query.after.as_ref().is_none_or(|after| match query.order {
+                    SortOrder::Asc => position(producer, after) == Ordering::Greater,
+                    SortOrder::Desc => position(producer, after) == Ordering::Less,
+                })
+            })
+            .take(query.limit as usize + 1)
+            .collect();
+
+        Ok(ProducerPage::from_rows(rows, &query, total, status_counts))
+    }
+
+    async fn update(
+        &self,
+        producer_id: Uuid,
+        changes: ProducerChanges,
+    ) -> Result<Producer, SMSManagerError> {
+        let (allowed_from, _) = ProducerAction::Update.transition();
+
+        let mut state = self.state();
+        let producer = state.producer_mut(producer_id)?;
+        if !allowed_from.contains(&producer.status) {
+            return Err(status_conflict(producer.status, ProducerAction::Update));
+        }
+
+        if let Some(name) = changes.name {
+            producer.name = name;
+        }
+        if let Some(number_messages) = changes.number_messages {
+            producer.number_messages = number_messages;
+        }
+        if let Some(average_send_delay) = changes.average_send_delay {
+            producer.average_send_delay = average_send_delay;
+        }
+        if let Some(failure_rate) = changes.failure_rate {
+            producer.failure_rate = failure_rate;
+        }
+        if let Some(num_senders) = changes.num_senders {
+            producer.num_senders = num_senders;
+        }
+        if let Some(transport) = changes.transport {
+            producer.transport = transport;
+        }
+        if let Some(retry_policy) = changes.retry_policy {
+            producer.retry_policy = retry_policy;
+        }
+        if let Some(rate_limit) = changes.rate_limit {
+            producer.rate_limit = rate_limit;
+        }
+        if let Some(seed) = changes.seed {
+            producer.seed = seed;
+        }
+
+        Ok(producer.clone())
+    }
+
+    async fn transition(
+        &self,
+        producer_id: Uuid,
+        action: ProducerAction,
+    ) -> Result<bool, SMSManagerError> {
+        let (allowed_from, next) = action.transition();
+        let Some(next) = next else {
+            return Ok(true);
+        };
+
+        let mut state = self.state();
+        match state.producer_mut(producer_id) {
+            Ok(producer) if allowed_from.contains(&producer.status) => {
+                producer.status = next;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn claim_status(
+        &self,
+        producer_id: Uuid,
+        owner: Uuid,
+        claim: StatusClaim,
+    ) -> Result<ProducerStatus, SMSManagerError> {
+        let mut state = self.state();
+        let producer = state.producer_mut(producer_id)?;
+
+        producer.status = claim(producer.status)?;
+        let status = producer.status;
+        state
+            .producer_leases
+            .insert((producer_id, owner), Utc::now() + LEASE_DURATION);
+        Ok(status)
+    }
+
+    async fn delete(&self, producer_id: Uuid) -> Result<(), SMSManagerError> {
+        let (allowed_from, _) = ProducerAction::Delete.transition();
+
+        let mut state = self.state();
+        let status = state.producer_mut(producer_id)?.status;
+        if !allowed_from.contains(&status) {
+            return Err(status_conflict(status, ProducerAction::Delete));
+        }
+
+        state
+            .messages
+            .retain(|_, message| message.produced_by != producer_id);
+        state
+            .producers
+            .retain(|producer| producer.id != producer_id);
+        state
+            .producer_leases
+            .retain(|(leased_producer, _), _| *leased_producer != producer_id);
+        Ok(())
+    }
+}
+
+/// Generated messages are held aside until the last chunk is written, so none of them can be seen before the producer is generated
+#[async_trait]
+impl MessageRepository for MemoryRepository {
+    async fn insert_generated(
+        &self,
+        producer: Producer,
+        chunks: MessageChunks,
+        generating: ProducerStatus,
+        finished: ProducerStatus,
+    ) -> Result<i32, SMSManagerError> {
+        let (allowed_from, _) = ProducerAction::Generate.transition();
+
+        {
+            let mut state = self.state();
+            let stored = state.producer_mut(producer.id)?;
+            if !allowed_from.contains(&stored.status) {
+                return Err(status_conflict(stored.status, ProducerAction::Generate));
+            }
+            stored.status = generating;
+            stored.generated_messages = 0;
+        }
+
+        let mut generated: Vec<Message> = vec![];
+        for chunk in chunks {
+            generated.extend(chunk.into_iter().map(new_message));
+
+            if let Ok(stored) = self.state().producer_mut(producer.id) {
+                stored.generated_messages = generated.len() as i32;
+            }
+        }
+
+        let mut state = self.state();
+        let stored = state.producer_mut(producer.id)?;
+        stored.status = finished;
+        stored.generated_messages = generated.len() as i32;
+
+        let count = generated.len() as i32;
+        state
+            .messages
+            .extend(generated.into_iter().map(|message| (message.id, message)));
+        Ok(count)
+    }
+
+    async fn find(&self, message_id: Uuid) -> Result<Message, SMSManagerError> {
+        self.state()
+            .messages
+            .get(&message_id)
+            .cloned()
+            .ok_or(SMSManagerError::EmptyResult)
+    }
+
+    async fn list_for_producer(&self, producer_id: Uuid) -> Result<Vec<Message>, SMSManagerError> {
+        Ok(self
+            .state()
+            .messages
+            .values()
+            .filter(|message| message.produced_by == producer_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn list_page(&self, query: MessageQuery) -> Result<MessagePage, SMSManagerError> {
+        let state = self.state();
+        let body_filter = query.body.as_ref().map(|body| body.to_lowercase());
+
+        let mut filtered: Vec<Message> = state
+            .messages
+            .values()
+            .filter(|message| {
+                message.produced_by == query.producer_id
+                    && (query.states.is_empty()
+                        || query.states.contains(&MessageState::of(message)))
+                    && body_filter
+                        .as_ref()
+                        .is_none_or(|body| message.message_body.to_lowercase().contains(body))
+                    && query.min_time_took_ms.is_none_or(|min_time_took_ms| {
+                        message
+                            .time_took_ms
+                            .is_some_and(|time_took_ms| time_took_ms >= min_time_took_ms)
+                    })
+                    && query.max_time_took_ms.is_none_or(|max_time_took_ms| {
+                        message
+                            .time_took_ms
+                            .is_some_and(|time_took_ms| time_took_ms <= max_time_took_ms)
+                    })
+            })
+            .cloned()
+            .collect();
+        let total = filtered.len() as i64;
+
+        filtered.sort_by(|a, b| message_position(a, &MessageCursor::of(b, query.sort)));
+        if query.order == SortOrder::Desc {
+            filtered.reverse();
+        }
+        let rows = filtered
+            .into_iter()
+            .filter(|message| {
+                query.after.as_ref().is_none_or(|after| match query.order {
+                    SortOrder::Asc => message_position(message, after) == Ordering::Greater,
+                    SortOrder::Desc => message_position(message, after) == Ordering::Less,
+                })
+            })
+            .take(query.limit as usize + 1)
+            .collect();
+
+        Ok(MessagePage::from_rows(rows, &query, total))
+    }
+
+    async fn progress_stats(&self, producer_id: Uuid) -> Result<ProgressStats, SMSManagerError> {
+        let messages: Vec<Message> = self
+            .state()
+            .messages
+            .values()
+            .filter(|message| message.produced_by == producer_id)
+            .cloned()
+            .collect();
+
+        Ok(get_progress_stats_from_messages(&messages))
+    }
+
+    async fn count_unsent(&self, producer_id: Uuid) -> Result<i64, SMSManagerError> {
+        Ok(self
+            .state()
+            .messages
+            .values()
+            .filter(|message| message.produced_by == producer_id && !message.sent)
+            .count() as i64)
+    }
+
+    async fn count_claimable(&self, producer_id: Uuid) -> Result<i64, SMSManagerError> {
+        Ok(self.state().claimable(producer_id).count() as i64)
+    }
+
+    async fn claim(
+        &self,
+        producer_id: Uuid,
+        owner: Uuid,
+        batch_size: i64,
+    ) -> Result<Vec<Message>, SMSManagerError> {
+        let lease_expires_at = Utc::now() + LEASE_DURATION;
+
+        Ok(self
+            .state()
+            .claimable(producer_id)
+            .take(batch_size.max(0) as usize)
+            .map(|message| {
+                message.in_flight = true;
+                message.leased_by = Some(owner);
+                message.lease_expires_at = Some(lease_expires_at);
+                message.clone()
+            })
+            .collect())
+    }
+
+    async fn renew_leases(&self, producer_id: Uuid, owner: Uuid) -> Result<usize, SMSManagerError> {
+        let lease_expires_at = Utc::now() + LEASE_DURATION;
+        let mut state = self.state();
+
+        if let Some(producer_lease) = state.producer_leases.get_mut(&(producer_id, owner)) {
+            *producer_lease = lease_expires_at;
+        }
+        Ok(state
+            .leased_to(producer_id, owner)
+            .map(|message| message.lease_expires_at = Some(lease_expires_at))
+            .count())
+    }
+
+    async fn release_leases(
+        &self,
+        producer_id: Uuid,
+        owner: Uuid,
+    ) -> Result<usize, SMSManagerError> {
+        let mut state = self.state();
+
+        state.producer_leases.remove(&(producer_id, owner));
+        Ok(state.leased_to(producer_id, owner).map(release).count())
+    }
+
+    async fn recover(&self, owner: Uuid) -> Result<RecoveryReport, SMSManagerError> {
+        let now = Utc::now();
+        let mut state = self.state();
+
+        let requeued_messages = state
+            .messages
+            .values_mut()
+            .filter(|message| {
+                message.in_flight
+                    && (message.lease_expires_at.is_some_and(|at| at < now)
+                        || message.leased_by.is_none_or(|leased_by| leased_by == owner))
+            })
+            .map(release)
+            .count();
+        state
+            .producer_leases
+            .retain(|(_, leased_by), lease_expires_at| {
+                *lease_expires_at >= now && *leased_by != owner
+            });
+
+        let MemoryState {
+            producers,
+            producer_leases,
+            ..
+        } = &mut *state;
+        let mut report = RecoveryReport {
+            requeued_messages,
+            ..RecoveryReport::default()
+        };
+        for producer in producers.iter_mut() {
+            let leased = producer_leases
+                .keys()
+                .any(|(leased_producer, _)| *leased_producer == producer.id);
+            if leased {
+                continue;
+            }
+
+            match producer.status {
+                ProducerStatus::Sending => report.interrupted_sends.push(producer.id),
+                ProducerStatus::Paused => report.interrupted_pauses.push(producer.id),
+                _ => continue,
+            }
+            producer.status = ProducerStatus::Interrupted;
+        }
+
+        Ok(report)
+    }
+
+    async fn update_results(
+        &self,
+        updated_messages: Vec<Message>,
+    ) -> Result<usize, SMSManagerError> {
+        let mut state = self.state();
+        let mut updated = 0;
+
+        for message in updated_messages {
+            if let Some(stored) = state.messages.get_mut(&message.id) {
+                // Only the delivery columns are written, the same as the Postgres repository, and a message no longer in flight gives its lease up
+                let (leased_by, lease_expires_at) = if message.in_flight {
+                    (stored.leased_by, stored.lease_expires_at)
+                } else {
+                    (None, None)
+                };
+                *stored = Message {
+                    id: stored.id,
+                    message_body: stored.message_body.clone(),
+                    produced_by: stored.produced_by,
+                    leased_by,
+                    lease_expires_at,
+                    ..message
+                };
+                updated += 1;
+            }
+        }
+
+        Ok(updated)
+    }
+
+    async fn insert_attempts(
+        &self,
+        attempts: Vec<NewDeliveryAttempt>,
+    ) -> Result<usize, SMSManagerError> {
+        let count = attempts.len();
+
+        self.state()
+            .attempts
+            .extend(attempts.into_iter().map(|attempt| DeliveryAttempt {
+                id: Uuid::new_v4(),
+                message_id: attempt.message_id,
+                attempt_number: attempt.attempt_number,
+                started_at: attempt.started_at,
+                finished_at: attempt.finished_at,
+                outcome: attempt.outcome,
+                error_code: attempt.error_code,
+                error_reason: attempt.error_reason,
+                worker_id: attempt.worker_id,
+            }));
+        Ok(count)
+    }
+
+    async fn find_attempts(
+        &self,
+        producer_id: Uuid,
+        message_id: Uuid,
+    ) -> Result<Vec<DeliveryAttempt>, SMSManagerError> {
+        let state = self.state();
+
+        if state
+            .messages
+            .get(&message_id)
+            .is_none_or(|message| message.produced_by != producer_id)
+        {
+            return Err(SMSManagerError::EmptyResult);
+        }
+
+        let mut attempts: Vec<DeliveryAttempt> = state
+            .attempts
+            .iter()
+            .filter(|attempt| attempt.message_id == message_id)
+            .cloned()
+            .collect();
+        attempts.sort_by_key(|attempt| attempt.attempt_number);
+        Ok(attempts)
+    }
+}
+
+/// A stored message with the defaults Postgres gives a new row
+fn new_message(new_message: NewMessage) -> Message {
+    Message {
+        id: Uuid::new_v4(),
+        message_body: new_message.message_body,
+        sent: false,
+        failed: false,
+        time_took: None,
+        produced_by: new_message.produced_by,
+        provider_message_id: None,
+        attempts: 0,
+        started_at: None,
+        finished_at: None,
+        time_took_ms: None,
+        in_flight: false,
+        leased_by: None,
+        lease_expires_at: None,
+        message_index: new_message.message_index,
+    }
+}
+
+/// Puts the message back in its producers queue
+fn release(message: &mut Message) {
+    message.in_flight = false;
+    message.leased_by = None;
+    message.lease_expires_at = None;
+}
+
+/// Where the message sorts relative to the cursor, by the sort key and then the id
+fn message_position(message: &Message, cursor: &MessageCursor) -> Ordering {
+    match cursor {
+        MessageCursor::Id { id } => message.id.cmp(id),
+        MessageCursor::TimeTook { time_took_ms, id } => {
+            (MessageCursor::time_took_key(message), message.id).cmp(&(*time_took_ms, *id))
+        }
+        MessageCursor::Attempts { attempts, id } => {
+            (message.attempts, message.id).cmp(&(*attempts, *id))
+        }
+    }
+}
+
+/// Where the producer sorts relative to the cursor, by the sort key and then the id
+fn position(producer: &Producer, cursor: &ProducerCursor) -> Ordering {
+    match cursor {
+        ProducerCursor::Name { name, id } => {
+            (producer.name.as_str(), producer.id).cmp(&(name, *id))
+        }
+        ProducerCursor::CreatedAt { created_at, id } => {
+            (producer.created_at, producer.id).cmp(&(*created_at, *id))
+        }
+    }
+}
@@ -0,0 +1,663 @@
+use std::{fmt::Display, str::FromStr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use diesel::{
+    define_sql_function,
+    sql_types::{Integer, Nullable},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    diesel::models::{
+        DeliveryAttempt, Message, NewDeliveryAttempt, NewMessage, NewProducer, Producer,
+        ProducerChanges,
+    },
+    utils::{
+        error::SMSManagerError,
+        message_state::MessageState,
+        pagination::{contains_pattern, decode_cursor, encode_cursor, SortOrder},
+        producer_status::{ProducerAction, ProducerStatus},
+    },
+    PoolHandle,
+};
+
+use self::{memory_repository::MemoryRepository, pg_repository::PgRepository};
+
+pub mod memory_repository;
+pub mod pg_repository;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_repository;
+
+/// Picks the status a claimed producer moves to from the status it is in, see ProducerRepository::claim_status
+pub type StatusClaim =
+    Box<dyn FnOnce(ProducerStatus) -> Result<ProducerStatus, SMSManagerError> + Send>;
+
+/// The messages generated for a producer, a chunk at a time
+pub type MessageChunks = Box<dyn Iterator<Item = Vec<NewMessage>> + Send>;
+
+/// The error of a generation that failed to be written
+/// If the producer could not be put back the way it was either, that is told as well, as it is left GENERATING
+///
+/// # Parameters
+/// - err: Why writing the messages failed
+/// - restored: The write putting the producer back
+fn generation_error<T>(
+    err: diesel::result::Error,
+    restored: Result<T, diesel::result::Error>,
+) -> SMSManagerError {
+    match restored {
+        Ok(_) => SMSManagerError::DbError(err),
+        Err(restore_err) => SMSManagerError::GeneralException(format!(
+            "Failed to write the generated messages: {}, and the producer could not be put back: {}",
+            err, restore_err
+        )),
+    }
+}
+
+/// The error of a write that was only allowed from some statuses and found the producer in another
+///
+/// # Parameters
+/// - current: The status the producer was in when the write was refused
+/// - action: The action the write was taken for
+fn status_conflict(current: ProducerStatus, action: ProducerAction) -> SMSManagerError {
+    match current.apply(action) {
+        Err(err) => err,
+        // The status changed back to one the action is allowed from before it could be read again
+        Ok(_) => SMSManagerError::Conflict(format!(
+            "Can not {} the producer, its status changed while it was being updated",
+            action
+        )),
+    }
+}
+
+/// How long a send holds its messages without renewing the lease, after which another send or a restart can take them back
+pub const LEASE_DURATION: Duration = Duration::from_secs(60);
+
+/// What was recovered from sends that were cut off by the server stopping
+#[derive(Debug, Default)]
+pub struct RecoveryReport {
+    /// How many in flight messages were put back in their producers queue
+    pub requeued_messages: usize,
+    /// The producers that were sending, which are now INTERRUPTED
+    pub interrupted_sends: Vec<Uuid>,
+    /// The producers that were paused, which are now INTERRUPTED
+    pub interrupted_pauses: Vec<Uuid>,
+}
+
+/// What a listing of producers is sorted by, producers that tie are sorted by id
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProducerSort {
+    #[default]
+    CreatedAt,
+    Name,
+}
+
+/// Where a page of a producer listing ended, the next page starts right after it
+/// It holds the sort key as well as the id, so the next page is found with an index seek rather than by counting past the earlier pages
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProducerCursor {
+    Name { name: String, id: Uuid },
+    CreatedAt { created_at: DateTime<Utc>, id: Uuid },
+}
+
+impl ProducerCursor {
+    /// The position of the producer in a listing sorted by sort
+    pub fn of(producer: &Producer, sort: ProducerSort) -> Self {
+        match sort {
+            ProducerSort::Name => ProducerCursor::Name {
+                name: producer.name.clone(),
+                id: producer.id,
+            },
+            ProducerSort::CreatedAt => ProducerCursor::CreatedAt {
+                created_at: producer.created_at,
+                id: producer.id,
+            },
+        }
+    }
+
+    /// The sort of the listing the cursor was taken from
+    pub fn sort(&self) -> ProducerSort {
+        match self {
+            ProducerCursor::Name { .. } => ProducerSort::Name,
+            ProducerCursor::CreatedAt { .. } => ProducerSort::CreatedAt,
+        }
+    }
+}
+
+impl Display for ProducerCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let position = match self {
+            ProducerCursor::Name { name, id } => format!("name:{}:{}", id, name),
+            ProducerCursor::CreatedAt { created_at, id } => {
+                format!("created_at:{}:{}", id, created_at.to_rfc3339())
+            }
+        };
+        write!(f, "{}", encode_cursor(&position))
+    }
+}
+
+impl FromStr for ProducerCursor {
+    type Err = SMSManagerError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || SMSManagerError::InvalidEncoding("Cursor Is Invalid".to_string());
+        let position = decode_cursor(value)?;
+
+        // The id never holds a colon, so anything after it is the sort key
+        let mut parts = position.splitn(3, ':');
+        let (Some(sort), Some(id), Some(key)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        let id = Uuid::parse_str(id).map_err(|_err| invalid())?;
+
+        match sort {
+            "name" => Ok(ProducerCursor::Name {
+                name: key.to_string(),
+                id,
+            }),
+            "created_at" => Ok(ProducerCursor::CreatedAt {
+                created_at: DateTime::parse_from_rfc3339(key)
+                    .map_err(|_err| invalid())?
+                    .with_timezone(&Utc),
+                id,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Which producers to list and in what order
+#[derive(Debug, Clone)]
+pub struct ProducerQuery {
+    /// Only producers in one of these statuses, empty lists every status
+    pub statuses: Vec<ProducerStatus>,
+    /// Only producers whose name contains this, ignoring case
+    pub name: Option<String>,
+    pub sort: ProducerSort,
+    pub order: SortOrder,
+    /// The most producers in the page
+    pub limit: i64,
+    /// Where the previous page ended, None starts from the first producer
+    /// Its sort is the same as the querys
+    pub after: Option<ProducerCursor>,
+}
+
+impl ProducerQuery {
+    /// The LIKE pattern for names containing the name filter, the wildcards in the filter itself are escaped with a backslash
+    pub fn name_pattern(&self) -> Option<String> {
+        self.name.as_deref().map(contains_pattern)
+    }
+}
+
+/// A page of a producer listing
+#[derive(Debug, Clone)]
+pub struct ProducerPage {
+    pub producers: Vec<Producer>,
+    /// How many producers pass the filters, across every page
+    pub total: i64,
+    /// How many producers whose name passes the name filter are in each status, whatever the status filter is
+    pub status_counts: Vec<(ProducerStatus, i64)>,
+    /// Where the next page starts, None on the last page
+    pub next_cursor: Option<ProducerCursor>,
+}
+
+impl ProducerPage {
+    /// Builds the page from the producers read for it
+    ///
+    /// # Parameters
+    /// - producers: Up to one more producer than the page holds, in the order of the query, the extra one only tells there is a next page
+    /// - query: The query the producers were read for
+    /// - total: How many producers pass the filters
+    /// - status_counts: How many producers passing the name filter are in each status
+    pub fn from_rows(
+        mut producers: Vec<Producer>,
+        query: &ProducerQuery,
+        total: i64,
+        status_counts: Vec<(ProducerStatus, i64)>,
+    ) -> Self {
+        let mut next_cursor = None;
+        if producers.len() as i64 > query.limit {
+            producers.truncate(query.limit as usize);
+            next_cursor = producers
+                .last()
+                .map(|producer| ProducerCursor::of(producer, query.sort));
+        }
+
+        ProducerPage {
+            producers,
+            total,
+            status_counts,
+            next_cursor,
+        }
+    }
+}
+
+define_sql_function! {
+    /// The value, or the fallback where the value is null, so a nullable column can be sorted and compared as a whole
+    fn coalesce(value: Nullable<Integer>, fallback: Integer) -> Integer;
+}
+
+/// What a listing of messages is sorted by, messages that tie are sorted by id
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageSort {
+    #[default]
+    Id,
+    /// How long the latest attempt took, messages that have no time yet sort as the quickest
+    TimeTook,
+    Attempts,
+}
+
+/// Where a page of a message listing ended, the next page starts right after it
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageCursor {
+    Id { id: Uuid },
+    TimeTook { time_took_ms: i32, id: Uuid },
+    Attempts { attempts: i32, id: Uuid },
+}
+
+impl MessageCursor {
+    /// The time a message sorts by in a listing sorted by TimeTook
+    pub fn time_took_key(message: &Message) -> i32 {
+        message.time_took_ms.unwrap_or(-1)
+    }
+
+    /// The position of the message in a listing sorted by sort
+    pub fn of(message: &Message, sort: MessageSort) -> Self {
+        match sort {
+            MessageSort::Id => MessageCursor::Id { id: message.id },
+            MessageSort::TimeTook => MessageCursor::TimeTook {
+                time_took_ms: MessageCursor::time_took_key(message),
+                id: message.id,
+            },
+            MessageSort::Attempts => MessageCursor::Attempts {
+                attempts: message.attempts,
+                id: message.id,
+            },
+        }
+    }
+
+    /// The sort of the listing the cursor was taken from
+    pub fn sort(&self) -> MessageSort {
+        match self {
+            MessageCursor::Id { .. } => MessageSort::Id,
+            MessageCursor::TimeTook { .. } => MessageSort::TimeTook,
+            MessageCursor::Attempts { .. } => MessageSort::Attempts,
+        }
+    }
+}
+
+impl Display for MessageCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let position = match self {
+            MessageCursor::Id { id } => format!("id:{}", id),
+            MessageCursor::TimeTook { time_took_ms, id } => {
+                format!("time_took:{}:{}", id, time_took_ms)
+            }
+            MessageCursor::Attempts { attempts, id } => format!("attempts:{}:{}", id, attempts),
+        };
+        write!(f, "{}", encode_cursor(&position))
+    }
+}
+
+impl FromStr for MessageCursor {
+    type Err = SMSManagerError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || SMSManagerError::InvalidEncoding("Cursor Is Invalid".to_string());
+        let position = decode_cursor(value)?;
+
+        let mut parts = position.splitn(3, ':');
+        let (Some(sort), Some(id)) = (parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        let id = Uuid::parse_str(id).map_err(|_err| invalid())?;
+        let key = parts.next().map(str::parse::<i32>);
+
+        match (sort, key) {
+            ("id", None) => Ok(MessageCursor::Id { id }),
+            ("time_took", Some(Ok(time_took_ms))) => {
+                Ok(MessageCursor::TimeTook { time_took_ms, id })
+            }
+            ("attempts", Some(Ok(attempts))) => Ok(MessageCursor::Attempts { attempts, id }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Which messages of a producer to list and in what order
+#[derive(Debug, Clone)]
+pub struct MessageQuery {
+    pub producer_id: Uuid,
+    /// Only messages in one of these states, empty lists every state
+    pub states: Vec<MessageState>,
+    /// Only messages whose body contains this, ignoring case
+    pub body: Option<String>,
+    /// Only messages whose latest attempt took at least this long
+    pub min_time_took_ms: Option<i32>,
+    /// Only messages whose latest attempt took at most this long
+    pub max_time_took_ms: Option<i32>,
+    pub sort: MessageSort,
+    pub order: SortOrder,
+    /// The most messages in the page
+    pub limit: i64,
+    /// Where the previous page ended, None starts from the first message
+    /// Its sort is the same as the querys
+    pub after: Option<MessageCursor>,
+}
+
+impl MessageQuery {
+    /// The LIKE pattern for bodies containing the body filter
+    pub fn body_pattern(&self) -> Option<String> {
+        self.body.as_deref().map(contains_pattern)
+    }
+}
+
+/// A page of a message listing
+#[derive(Debug, Clone)]
+pub struct MessagePage {
+    pub messages: Vec<Message>,
+    /// How many messages pass the filters, across every page
+    pub total: i64,
+    /// Where the next page starts, None on the last page
+    pub next_cursor: Option<MessageCursor>,
+}
+
+impl MessagePage {
+    /// Builds the page from the messages read for it
+    ///
+    /// # Parameters
+    /// - messages: Up to one more message than the page holds, in the order of the query, the extra one only tells there is a next page
+    /// - query: The query the messages were read for
+    /// - total: How many messages pass the filters
+    pub fn from_rows(mut messages: Vec<Message>, query: &MessageQuery, total: i64) -> Self {
+        let mut next_cursor = None;
+        if messages.len() as i64 > query.limit {
+            messages.truncate(query.limit as usize);
+            next_cursor = messages
+                .last()
+                .map(|message| MessageCursor::of(message, query.sort));
+        }
+
+        MessagePage {
+            messages,
+            total,
+            next_cursor,
+        }
+    }
+}
+
+/// How many of a producers sent messages took a time within the bucket
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MessageTimeBucket {
+    /// The shortest time in the bucket, in milliseconds
+    pub from_ms: i32,
+    /// The time the bucket ends before, in milliseconds
+    pub to_ms: i32,
+    pub count: i64,
+}
+
+/// How far a producers messages have got, counted where they are stored rather than by reading every message
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProgressStats {
+    pub number_messages_created: i64,
+    /// Sent messages that have a time recorded
+    pub number_messages_sent: i64,
+    pub number_messages_failed: i64,
+    /// Messages that needed more than one attempt
+    pub number_messages_retried: i64,
+    /// Messages that needed more than one attempt and were delivered in the end
+    pub number_messages_recovered: i64,
+    /// Over the sent messages that have a time recorded, 0 when there are none
+    pub average_message_time_ms: f64,
+    /// How the times of the sent messages are spread, see message_utils::message_time_histogram
+    pub message_time_histogram: Vec<MessageTimeBucket>,
+}
+
+/// Where producers are stored
+#[async_trait]
+pub trait ProducerRepository: Send + Sync {
+    /// Stores a new producer, the settings it is not given take their defaults
+    ///
+    /// ### Errors if the producer can not be stored
+    async fn insert(&self, new_producer: NewProducer) -> Result<Producer, SMSManagerError>;
+
+    /// Gets the producer with the given id
+    ///
+    /// ### Errors with EmptyResult if there is no such producer
+    async fn find(&self, producer_id: Uuid) -> Result<Producer, SMSManagerError>;
+
+    /// Gets a page of the producers that pass the filters of the query, in its order
+    ///
+    /// ### Errors if the producers can not be read
+    async fn list_page(&self, query: ProducerQuery) -> Result<ProducerPage, SMSManagerError>;
+
+    /// Changes the given settings of the producer, in one write that only applies while its status allows ProducerAction::Update
+    ///
+    /// # Returns
+    /// The producer as it is stored after the change
+    ///
+    /// ### Errors with EmptyResult if the producer is not found, with a conflict if its status does not allow updates, or if the change can not be stored
+    async fn update(
+        &self,
+        producer_id: Uuid,
+        changes: ProducerChanges,
+    ) -> Result<Producer, SMSManagerError>;
+
+    /// Moves the producer along the transition table in one conditional write, so a status another request changed in the meantime is left alone
+    ///
+    /// # Returns
+    /// Whether the producer was in a status the action can be taken from
+    ///
+    /// ### Errors if the write fails
+    async fn transition(
+        &self,
+        producer_id: Uuid,
+        action: ProducerAction,
+    ) -> Result<bool, SMSManagerError>;
+
+    /// Moves the producer to the status the claim picks from its current one, and leases the producer to the owner for LEASE_DURATION in the same write
+    /// Claims of the same producer run one after the other, on every server sharing the storage, so a claim sees whatever the claim before it did
+    ///
+    /// # Parameters
+    /// - producer_id: The producer to claim
+    /// - owner: The server claiming the producer, its lease keeps recover from interrupting the send until it expires, see MessageRepository::renew_leases
+    /// - claim: Picks the status to move to
+    ///
+    /// # Returns
+    /// The status the producer moved to
+    ///
+    /// ### Errors if the producer is not found, the claim errors, or a write that does not claim changed the status before it could be written
+    async fn claim_status(
+        &self,
+        producer_id: Uuid,
+        owner: Uuid,
+        claim: StatusClaim,
+    ) -> Result<ProducerStatus, SMSManagerError>;
+
+    /// Deletes the producer along with its messages, if its status allows ProducerAction::Delete when it is deleted
+    ///
+    /// ### Errors with EmptyResult if the producer is not found, with a conflict if its status does not allow deleting, or if the producer can not be deleted
+    async fn delete(&self, producer_id: Uuid) -> Result<(), SMSManagerError>;
+}
+
+/// Where messages and the attempts made to deliver them are stored
+#[async_trait]
+pub trait MessageRepository: Send + Sync {
+    /// Writes the generated messages of the producer, all of them or none
+    /// The producer is moved to generating before the first chunk and to finished with the last, in between its generated_messages counts the messages written so far
+    /// Moving it to generating only applies while its status allows ProducerAction::Generate, so generations that start together can not both write their messages
+    /// If writing fails the producer is put back the way it was
+    ///
+    /// # Parameters
+    /// - producer: The producer as it was before generating
+    /// - chunks: The messages to write
+    /// - generating: The status the producer is in while the messages are written
+    /// - finished: The status the producer is in once every message is written
+    ///
+    /// # Returns
+    /// How many messages were written
+    ///
+    /// ### Errors with a conflict if the status of the producer does not allow generating, or if writing the messages or the producer fails
+    async fn insert_generated(
+        &self,
+        producer: Producer,
+        chunks: MessageChunks,
+        generating: ProducerStatus,
+        finished: ProducerStatus,
+    ) -> Result<i32, SMSManagerError>;
+
+    /// Gets the message with the given id
+    ///
+    /// ### Errors with EmptyResult if there is no such message
+    async fn find(&self, message_id: Uuid) -> Result<Message, SMSManagerError>;
+
+    /// Gets every message of the producer
+    ///
+    /// ### Errors if the messages can not be read
+    async fn list_for_producer(&self, producer_id: Uuid) -> Result<Vec<Message>, SMSManagerError>;
+
+    /// Gets a page of the producers messages that pass the filters of the query, in its order
+    ///
+    /// ### Errors if the messages can not be read
+    async fn list_page(&self, query: MessageQuery) -> Result<MessagePage, SMSManagerError>;
+
+    /// Counts the producers messages by how far they have got and spreads their times into a histogram, from one snapshot of the messages
+    ///
+    /// ### Errors if the messages can not be counted
+    async fn progress_stats(&self, producer_id: Uuid) -> Result<ProgressStats, SMSManagerError>;
+
+    /// Counts the messages of the producer that have not been sent
+    ///
+    /// ### Errors if the messages can not be counted
+    async fn count_unsent(&self, producer_id: Uuid) -> Result<i64, SMSManagerError>;
+
+    /// Counts the producers unsent messages that are not held by a live lease, which is what a new send has left to claim
+    ///
+    /// ### Errors if the messages can not be counted
+    async fn count_claimable(&self, producer_id: Uuid) -> Result<i64, SMSManagerError>;
+
+    /// Claims a batch of the producers unsent messages that no other send holds, leasing them to the owner for LEASE_DURATION
+    /// Sends claiming at the same time never get the same message
+    ///
+    /// # Parameters
+    /// - producer_id: The producer whose messages to claim
+    /// - owner: The server taking the lease
+    /// - batch_size: The most messages to claim
+    ///
+    /// # Returns
+    /// The claimed messages in id order, empty once every unsent message is sent or held by a live lease
+    ///
+    /// ### Errors if the messages can not be claimed
+    async fn claim(
+        &self,
+        producer_id: Uuid,
+        owner: Uuid,
+        batch_size: i64,
+    ) -> Result<Vec<Message>, SMSManagerError>;
+
+    /// Extends the leases the owner holds on the producer and its in flight messages
+    ///
+    /// # Returns
+    /// How many message leases were renewed
+    ///
+    /// ### Errors if the leases can not be written
+    async fn renew_leases(&self, producer_id: Uuid, owner: Uuid) -> Result<usize, SMSManagerError>;
+
+    /// Gives up the leases the owner holds on the producer and its messages, such as the unsent messages of a cancelled send
+    ///
+    /// # Returns
+    /// How many messages went back in the queue
+    ///
+    /// ### Errors if the leases can not be written
+    async fn release_leases(
+        &self,
+        producer_id: Uuid,
+        owner: Uuid,
+    ) -> Result<usize, SMSManagerError>;
+
+    /// Puts back every in flight message and drops every producer lease that has expired or was held by the owner, then sets the SENDING and PAUSED producers that no server holds a lease on to INTERRUPTED, all at once
+    /// A producer is only recovered by its leases, as a live send has nothing in flight between the batches it claims
+    ///
+    /// ### Errors if any of the writes fail, in which case nothing is changed
+    async fn recover(&self, owner: Uuid) -> Result<RecoveryReport, SMSManagerError>;
+
+    /// Writes the delivery columns of every message, clearing the lease of every message that is no longer in flight
+    ///
+    /// # Parameters
+    /// - updated_messages: The messages as they should be stored, each id at most once
+    ///
+    /// # Returns
+    /// How many messages were written
+    ///
+    /// ### Errors if the messages can not be written
+    async fn update_results(
+        &self,
+        updated_messages: Vec<Message>,
+    ) -> Result<usize, SMSManagerError>;
+
+    /// Records the attempts made to deliver messages
+    ///
+    /// ### Errors if the attempts can not be written
+    async fn insert_attempts(
+        &self,
+        attempts: Vec<NewDeliveryAttempt>,
+    ) -> Result<usize, SMSManagerError>;
+
+    /// Gets every attempt made to deliver the message, in the order they were made
+    ///
+    /// ### Errors with EmptyResult if the producer has no message with the given id
+    async fn find_attempts(
+        &self,
+        producer_id: Uuid,
+        message_id: Uuid,
+    ) -> Result<Vec<DeliveryAttempt>, SMSManagerError>;
+}
+
+/// The repositories the services store everything through, shared by every route through axum state
+#[derive(Clone)]
+pub struct Repositories {
+    pub producers: Arc<dyn ProducerRepository>,
+    pub messages: Arc<dyn MessageRepository>,
+}
+
+impl Repositories {
+    /// Stores everything in Postgres
+    ///
+    /// # Parameters
+    /// - pool: The database pool to retrieve connections from
+    pub fn postgres(pool: PoolHandle) -> Self {
+        let repository = Arc::new(PgRepository::new(pool));
+        Repositories {
+            producers: repository.clone(),
+            messages: repository,
+        }
+    }
+
+    /// Stores everything in a SQLite file, for a single server that should not need a database server alongside it
+    ///
+    /// # Parameters
+    /// - path: The path of the database file, which is created and migrated if need be
+    ///
+    /// ### Errors if the file can not be opened or migrated
+    #[cfg(feature = "sqlite")]
+    pub fn sqlite(path: &str) -> Result<Self, SMSManagerError> {
+        let repository = Arc::new(sqlite_repository::SqliteRepository::connect(path)?);
+        Ok(Repositories {
+            producers: repository.clone(),
+            messages: repository,
+        })
+    }
+
+    /// Stores everything in the memory of this process, such as for tests that do not need a database
+    pub fn in_memory() -> Self {
+        let repository = Arc::new(MemoryRepository::new());
+        Repositories {
+            producers: repository.clone(),
+            messages: repository,
+        }
+    }
+}
@@ -0,0 +1,950 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use diesel::{
+    dsl::{count_star, exists, not},
+    pg::Pg,
+    sql_types::{BigInt, Bool, Double, Integer, Nullable, Text, Timestamptz, Uuid as SqlUuid},
+    BoolExpressionMethods, BoxableExpression, Connection, ExecuteCopyFromDsl, ExpressionMethods,
+    Insertable, OptionalExtension, PgTextExpressionMethods, QueryDsl, QueryResult, QueryableByName,
+    RunQueryDsl,
+};
+use uuid::Uuid;
+
+use super::{
+    coalesce, generation_error, status_conflict, MessageChunks, MessageCursor, MessagePage,
+    MessageQuery, MessageRepository, MessageSort, ProducerCursor, ProducerPage, ProducerQuery,
+    ProducerRepository, ProducerSort, ProgressStats, RecoveryReport, StatusClaim, LEASE_DURATION,
+};
+use crate::{
+    diesel::{
+        models::{
+            DeliveryAttempt, Message, NewDeliveryAttempt, NewProducer, Producer, ProducerChanges,
+        },
+        schema::{delivery_attempts, messages, producer_leases, producers},
+    },
+    utils::{
+        blocking::run_blocking,
+        error::SMSManagerError,
+        message_state::MessageState,
+        message_utils::{message_time_bucket_width, message_time_histogram, MESSAGE_TIME_BUCKETS},
+        pagination::SortOrder,
+        producer_status::{ProducerAction, ProducerStatus},
+    },
+    Database, PoolHandle,
+};
+
+/// Stores producers and messages in Postgres, every query runs on the blocking thread pool, see run_blocking
+pub struct PgRepository {
+    pool: PoolHandle,
+}
+
+impl PgRepository {
+    /// # Parameters
+    /// - pool: The database pool to retrieve connections from
+    pub fn new(pool: PoolHandle) -> Self {
+        PgRepository { pool }
+    }
+}
+
+#[async_trait]
+impl ProducerRepository for PgRepository {
+    async fn insert(&self, new_producer: NewProducer) -> Result<Producer, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            diesel::insert_into(producers::table)
+                .values(new_producer)
+                .get_result(db)
+                .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    async fn find(&self, producer_id: Uuid) -> Result<Producer, SMSManagerError> {
+        run_blocking(&self.pool, move |db| find_producer(db, producer_id)).await
+    }
+
+    async fn list_page(&self, query: ProducerQuery) -> Result<ProducerPage, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            let total = filtered_producers(&query, true).count().get_result(db)?;
+            let mut counted = producers::table
+                .group_by(producers::status)
+                .select((producers::status, count_star()))
+                .into_boxed();
+            if let Some(pattern) = query.name_pattern() {
+                counted = counted.filter(producers::name.ilike(pattern));
+            }
+            let status_counts = counted.load(db)?;
+
+            let mut page = filtered_producers(&query, true);
+            page = match (&query.after, query.order) {
+                (None, _) => page,
+                (Some(ProducerCursor::Name { name, id }), SortOrder::Asc) => page.filter(
+                    producers::name
+                        .gt(name.clone())
+                        .or(producers::name.eq(name.clone()).and(producers::id.gt(*id))),
+                ),
+                (Some(ProducerCursor::Name { name, id }), SortOrder::Desc) => page.filter(
+                    producers::name
+                        .lt(name.clone())
+                        .or(producers::name.eq(name.clone()).and(producers::id.lt(*id))),
+                ),
+                (Some(ProducerCursor::CreatedAt { created_at, id }), SortOrder::Asc) => page
+                    .filter(
+                        producers::created_at
+                            .gt(*created_at)
+                            .or(producers::created_at
+                                .eq(*created_at)
+                                .and(producers::id.gt(*id))),
+                    ),
+                (Some(ProducerCursor::CreatedAt { created_at, id }), SortOrder::Desc) => page
+                    .filter(
+                        producers::created_at
+                            .lt(*created_at)
+                            .or(producers::created_at
+                                .eq(*created_at)
+                                .and(producers::id.lt(*id))),
+                    ),
+            };
+            page = match (query.sort, query.order) {
+                (ProducerSort::Name, SortOrder::Asc) => {
+                    page.order((producers::name.asc(), producers::id.asc()))
+                }
+                (ProducerSort::Name, SortOrder::Desc) => {
+                    page.order((producers::name.desc(), producers::id.desc()))
+                }
+                (ProducerSort::CreatedAt, SortOrder::Asc) => {
+                    page.order((producers::created_at.asc(), producers::id.asc()))
+                }
+                (ProducerSort::CreatedAt, SortOrder::Desc) => {
+                    page.order((producers::created_at.desc(), producers::id.desc()))
+                }
+            };
+
+            let rows = page.limit(query.limit + 1).load::<Producer>(db)?;
+            Ok(ProducerPage::from_rows(rows, &query, total, status_counts))
+        })
+        .await
+    }
+
+    async fn update(
+        &self,
+        producer_id: Uuid,
+        changes: ProducerChanges,
+    ) -> Result<Producer, SMSManagerError> {
+        let (allowed_from, _) = ProducerAction::Update.transition();
+
+        run_blocking(&self.pool, move |db| {
+            let updated = diesel::update(
+                producers::table
+                    .find(producer_id)
+                    .filter(producers::status.eq_any(allowed_from)),
+            )
+            .set(changes)
+            .get_result(db)
+            .optional()?;
+
+            match updated {
+                Some(producer) => Ok(producer),
+                None => Err(status_conflict(
+                    find_producer(db, producer_id)?.status,
+                    ProducerAction::Update,
+                )),
+            }
+        })
+        .await
+    }
+
+    async fn transition(
+        &self,
+        producer_id: Uuid,
+        action: ProducerAction,
+    ) -> Result<bool, SMSManagerError> {
+        let (allowed_from, next) = action.transition();
+        let Some(next) = next else {
+            return Ok(true);
+        };
+
+        run_blocking(&self.pool, move |db| {
+            diesel::update(
+                producers::table
+                    .find(producer_id)
+                    .filter(producers::status.eq_any(allowed_from)),
+            )
+            .set(producers::status.eq(next))
+            .execute(db)
+            .map(|updated| updated > 0)
+            .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    /// The claim runs in a transaction holding a Postgres advisory lock keyed on the producer, which every server sharing the database takes
+    async fn claim_status(
+        &self,
+        producer_id: Uuid,
+        owner: Uuid,
+        claim: StatusClaim,
+    ) -> Result<ProducerStatus, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            db.transaction(|db| {
+                diesel::sql_query("SELECT pg_advisory_xact_lock($1)")
+                    .bind::<BigInt, _>(advisory_lock_key(producer_id))
+                    .execute(db)?;
+
+                let current: ProducerStatus = producers::table
+                    .find(producer_id)
+                    .select(producers::status)
+                    .first(db)?;
+                let next = claim(current)?;
+
+                // Writes that do not take the lock, such as a delete, may still have moved the producer on since it was read
+                let claimed = diesel::update(
+                    producers::table
+                        .find(producer_id)
+                        .filter(producers::status.eq(current)),
+                )
+                .set(producers::status.eq(next))
+                .execute(db)?;
+                if claimed == 0 {
+                    return Err(SMSManagerError::Conflict(format!(
+                        "Can not move the producer from {} to {}, its status changed while it was being sent",
+                        current, next
+                    )));
+                }
+
+                let lease_expires_at = Utc::now() + LEASE_DURATION;
+                diesel::insert_into(producer_leases::table)
+                    .values((
+                        producer_leases::producer_id.eq(producer_id),
+                        producer_leases::leased_by.eq(owner),
+                        producer_leases::lease_expires_at.eq(lease_expires_at),
+                    ))
+                    .on_conflict((producer_leases::producer_id, producer_leases::leased_by))
+                    .do_update()
+                    .set(producer_leases::lease_expires_at.eq(lease_expires_at))
+                    .execute(db)?;
+
+                Ok(next)
+            })
+        })
+        .await
+    }
+
+    /// The producer row is locked while its status is checked, so a claim that would send it waits until it is gone
+    async fn delete(&self, producer_id: Uuid) -> Result<(), SMSManagerError> {
+        let (allowed_from, _) = ProducerAction::Delete.transition();
+
+        run_blocking(&self.pool, move |db| {
+            db.transaction(|db| {
+                let locked: Option<Uuid> = producers::table
+                    .find(producer_id)
+                    .filter(producers::status.eq_any(allowed_from))
+                    .select(producers::id)
+                    .for_update()
+                    .first(db)
+                    .optional()?;
+                if locked.is_none() {
+                    return Err(status_conflict(
+                        find_producer(db, producer_id)?.status,
+                        ProducerAction::Delete,
+                    ));
+                }
+
+                diesel::delete(messages::table)
+                    .filter(messages::produced_by.eq(producer_id))
+                    .execute(db)?;
+                diesel::delete(producers::table)
+                    .filter(producers::id.eq(producer_id))
+                    .execute(db)?;
+
+                Ok(())
+            })
+        })
+        .await
+    }
+}
+
+/// Every chunk is copied in with COPY FROM STDIN and committed together with the progress, so the progress can be followed on the one connection writing the messages
+/// The producer is GENERATING until the last chunk, so none of its messages can be sent before then, and if a chunk fails the chunks written before it are deleted again
+#[async_trait]
+impl MessageRepository for PgRepository {
+    async fn insert_generated(
+        &self,
+        producer: Producer,
+        chunks: MessageChunks,
+        generating: ProducerStatus,
+        finished: ProducerStatus,
+    ) -> Result<i32, SMSManagerError> {
+        let (allowed_from, _) = ProducerAction::Generate.transition();
+
+        run_blocking(&self.pool, move |db| {
+            let started = diesel::update(
+                producers::table
+                    .find(producer.id)
+                    .filter(producers::status.eq_any(allowed_from)),
+            )
+            .set((
+                producers::status.eq(generating),
+                producers::generated_messages.eq(0),
+            ))
+            .execute(db)?;
+            if started == 0 {
+                return Err(status_conflict(
+                    find_producer(db, producer.id)?.status,
+                    ProducerAction::Generate,
+                ));
+            }
+
+            let mut written: Vec<Uuid> = vec![];
+            let result = write_generated(db, &producer, chunks, finished, &mut written);
+
+            result.map_err(|err| {
+                let restored = db.transaction(|db| {
+                    diesel::delete(messages::table.filter(messages::id.eq_any(&written)))
+                        .execute(db)?;
+
+                    diesel::update(producers::table.find(producer.id))
+                        .set((
+                            producers::status.eq(producer.status),
+                            producers::generated_messages.eq(producer.generated_messages),
+                        ))
+                        .execute(db)
+                });
+                generation_error(err, restored)
+            })
+        })
+        .await
+    }
+
+    async fn find(&self, message_id: Uuid) -> Result<Message, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            messages::table
+                .find(message_id)
+                .first(db)
+                .optional()?
+                .ok_or(SMSManagerError::EmptyResult)
+        })
+        .await
+    }
+
+    async fn list_for_producer(&self, producer_id: Uuid) -> Result<Vec<Message>, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            messages::table
+                .filter(messages::produced_by.eq(producer_id))
+                .load(db)
+                .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    async fn list_page(&self, query: MessageQuery) -> Result<MessagePage, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            let total = filtered_messages(&query).count().get_result(db)?;
+
+            let time_took = || coalesce(messages::time_took_ms, -1);
+            let mut page = filtered_messages(&query);
+            page = match (&query.after, query.order) {
+                (None, _) => page,
+                (Some(MessageCursor::Id { id }), SortOrder::Asc) => {
+                    page.filter(messages::id.gt(*id))
+                }
+                (Some(MessageCursor::Id { id }), SortOrder::Desc) => {
+                    page.filter(messages::id.lt(*id))
+                }
+                (Some(MessageCursor::TimeTook { time_took_ms, id }), SortOrder::Asc) => page
+                    .filter(
+                        time_took()
+                            .gt(*time_took_ms)
+                            .or(time_took().eq(*time_took_ms).and(messages::id.gt(*id))),
+                    ),
+                (Some(MessageCursor::TimeTook { time_took_ms, id }), SortOrder::Desc) => page
+                    .filter(
+                        time_took()
+                            .lt(*time_took_ms)
+                            .or(time_took().eq(*time_took_ms).and(messages::id.lt(*id))),
+                    ),
+                (Some(MessageCursor::Attempts { attempts, id }), SortOrder::Asc) => page.filter(
+                    messages::attempts
+                        .gt(*attempts)
+                        .or(messages::attempts.eq(*attempts).and(messages::id.gt(*id))),
+                ),
+                (Some(MessageCursor::Attempts { attempts, id }), SortOrder::Desc) => page.filter(
+                    messages::attempts
+                        .lt(*attempts)
+                        .or(messages::attempts.eq(*attempts).and(messages::id.lt(*id))),
+                ),
+            };
+            page = match (query.sort, query.order) {
+                (MessageSort::Id, SortOrder::Asc) => page.order(messages::id.asc()),
+                (MessageSort::Id, SortOrder::Desc) => page.order(messages::id.desc()),
+                (MessageSort::TimeTook, SortOrder::Asc) => {
+                    page.order((time_took().asc(), messages::id.asc()))
+                }
+                (MessageSort::TimeTook, SortOrder::Desc) => {
+                    page.order((time_took().desc(), messages::id.desc()))
+                }
+                (MessageSort::Attempts, SortOrder::Asc) => {
+                    page.order((messages::attempts.asc(), messages::id.asc()))
+                }
+                (MessageSort::Attempts, SortOrder::Desc) => {
+                    page.order((messages::attempts.desc(), messages::id.desc()))
+                }
+            };
+
+            let rows = page.limit(query.limit + 1).load::<Message>(db)?;
+            Ok(MessagePage::from_rows(rows, &query, total))
+        })
+        .await
+    }
+
+    /// Reads from one read only repeatable read transaction, so the counts and the histogram agree while a send is writing results
+    async fn progress_stats(&self, producer_id: Uuid) -> Result<ProgressStats, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            db.build_transaction()
+                .read_only()
+                .repeatable_read()
+                .run(|db| progress_stats_with_connection(db, producer_id))
+        })
+        .await
+    }
+
+    async fn count_unsent(&self, producer_id: Uuid) -> Result<i64, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            messages::table
+                .filter(messages::produced_by.eq(producer_id))
+                .filter(messages::sent.eq(false))
+                .count()
+                .get_result(db)
+                .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    async fn count_claimable(&self, producer_id: Uuid) -> Result<i64, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            messages::table
+                .filter(
+                    messages::produced_by
+                        .eq(producer_id)
+                        .and(messages::sent.eq(false))
+                        .and(
+                            messages::in_flight
+                                .eq(false)
+                                .or(messages::lease_expires_at.lt(Utc::now())),
+                        ),
+                )
+                .count()
+                .get_result(db)
+                .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    /// The messages are locked with FOR UPDATE SKIP LOCKED, so a claim never waits on another
+    async fn claim(
+        &self,
+        producer_id: Uuid,
+        owner: Uuid,
+        batch_size: i64,
+    ) -> Result<Vec<Message>, SMSManagerError> {
+        let now = Utc::now();
+
+        run_blocking(&self.pool, move |db| {
+            db.transaction(|db| {
+                let claimed: Vec<Uuid> = messages::table
+                    .select(messages::id)
+                    .filter(
+                        messages::produced_by
+                            .eq(producer_id)
+                            .and(messages::sent.eq(false))
+                            .and(
+                                messages::in_flight
+                                    .eq(false)
+                                    .or(messages::lease_expires_at.lt(now)),
+                            ),
+                    )
+                    .order(messages::id)
+                    .limit(batch_size)
+                    .for_update()
+                    .skip_locked()
+                    .load(db)?;
+
+                diesel::update(messages::table.filter(messages::id.eq_any(claimed)))
+                    .set((
+                        messages::in_flight.eq(true),
+                        messages::leased_by.eq(owner),
+                        messages::lease_expires_at.eq(now + LEASE_DURATION),
+                    ))
+                    .get_results(db)
+            })
+            .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    async fn renew_leases(&self, producer_id: Uuid, owner: Uuid) -> Result<usize, SMSManagerError> {
+        let lease_expires_at = Utc::now() + LEASE_DURATION;
+
+        run_blocking(&self.pool, move |db| {
+            db.transaction(|db| {
+                diesel::update(producer_leases::table.find((producer_id, owner)))
+                    .set(producer_leases::lease_expires_at.eq(lease_expires_at))
+                    .execute(db)?;
+
+                diesel::update(
+                    messages::table.filter(
+                        messages::produced_by
+                            .eq(producer_id)
+                            .and(messages::in_flight.eq(true))
+                            .and(messages::leased_by.eq(owner)),
+                    ),
+                )
+                .set(messages::lease_expires_at.eq(lease_expires_at))
+                .execute(db)
+            })
+            .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    async fn release_leases(
+        &self,
+        producer_id: Uuid,
+        owner: Uuid,
+    ) -> Result<usize, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            db.transaction(|db| {
+                diesel::delete(producer_leases::table.find((producer_id, owner))).execute(db)?;
+
+                diesel::update(
+                    messages::table.filter(
+                        messages::produced_by
+                            .eq(producer_id)
+                            .and(messages::in_flight.eq(true))
+                            .and(messages::leased_by.eq(owner)),
+                    ),
+                )
+                .set((
+                    messages::in_flight.eq(false),
+                    messages::leased_by.eq(None::<Uuid>),
+                    messages::lease_expires_at.eq(None::<chrono::DateTime<Utc>>),
+                ))
+                .execute(db)
+            })
+            .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    async fn recover(&self, owner: Uuid) -> Result<RecoveryReport, SMSManagerError> {
+        run_blocking(&self.pool, move |db| recover_with_connection(db, owner)).await
+    }
+
+    async fn update_results(
+        &self,
+        updated_messages: Vec<Message>,
+    ) -> Result<usize, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            update_messages(db, &updated_messages).map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    async fn insert_attempts(
+        &self,
+        attempts: Vec<NewDeliveryAttempt>,
+    ) -> Result<usize, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            diesel::insert_into(delivery_attempts::table)
+                .values(&attempts)
+                .execute(db)
+                .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    async fn find_attempts(
+        &self,
+        producer_id: Uuid,
+        message_id: Uuid,
+    ) -> Result<Vec<DeliveryAttempt>, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            let found_messages: Vec<Message> = messages::table
+                .filter(
+                    messages::id
+                        .eq(message_id)
+                        .and(messages::produced_by.eq(producer_id)),
+                )
+                .load(db)
+                .map_err(SMSManagerError::DbError)?;
+
+            if found_messages.is_empty() {
+                return Err(SMSManagerError::EmptyResult);
+            }
+
+            delivery_attempts::table
+                .filter(delivery_attempts::message_id.eq(message_id))
+                .order(delivery_attempts::attempt_number.asc())
+                .load(db)
+                .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+}
+
+/// Gets the producer with the supplied id on a connection the caller already holds
+///
+/// ### Errors if producer is not found
+fn find_producer(db: &mut Database, producer_id: Uuid) -> Result<Producer, SMSManagerError> {
+    let found_producers: Vec<Producer> = producers::table
+        .filter(producers::id.eq(producer_id))
+        .load(db)
+        .map_err(SMSManagerError::DbError)?;
+
+    if let Some(producer) = found_producers.first() {
+        return Ok(producer.clone());
+    }
+    Err(SMSManagerError::EmptyResult)
+}
+
+/// The producers passing the filters of the query, the status filter only if with_status is set
+fn filtered_producers<'a>(
+    query: &ProducerQuery,
+    with_status: bool,
+) -> producers::BoxedQuery<'a, Pg> {
+    let mut filtered = producers::table.into_boxed();
+    if with_status && !query.statuses.is_empty() {
+        filtered = filtered.filter(producers::status.eq_any(query.statuses.clone()));
+    }
+    if let Some(pattern) = query.name_pattern() {
+        filtered = filtered.filter(producers::name.ilike(pattern));
+    }
+    filtered
+}
+
+/// The messages of the producer passing the filters of the query
+fn filtered_messages<'a>(query: &MessageQuery) -> messages::BoxedQuery<'a, Pg> {
+    let mut filtered = messages::table
+        .filter(messages::produced_by.eq(query.producer_id))
+        .into_boxed();
+    if let Some(in_states) = query
+        .states
+        .iter()
+        .map(|state| in_state(*state))
+        .reduce(|either, or| Box::new(either.or(or)))
+    {
+        filtered = filtered.filter(in_states);
+    }
+    if let Some(pattern) = query.body_pattern() {
+        filtered = filtered.filter(messages::message_body.ilike(pattern));
+    }
+    if let Some(min_time_took_ms) = query.min_time_took_ms {
+        filtered = filtered.filter(messages::time_took_ms.ge(min_time_took_ms));
+    }
+    if let Some(max_time_took_ms) = query.max_time_took_ms {
+        filtered = filtered.filter(messages::time_took_ms.le(max_time_took_ms));
+    }
+    filtered
+}
+
+/// The condition a message in the state meets, see MessageState::of
+fn in_state(
+    state: MessageState,
+) -> Box<dyn BoxableExpression<messages::table, Pg, SqlType = Bool>> {
+    match state {
+        MessageState::Pending => Box::new(messages::sent.eq(false)),
+        MessageState::Sent => Box::new(messages::sent.eq(true).and(messages::failed.eq(false))),
+        MessageState::Failed => Box::new(messages::sent.eq(true).and(messages::failed.eq(true))),
+    }
+}
+
+/// The key of the advisory lock taken on the producer while it is claimed
+fn advisory_lock_key(producer_id: Uuid) -> i64 {
+    let (high, low) = producer_id.as_u64_pair();
+    (high ^ low) as i64
+}
+
+/// Does the work of recover on a connection, in one transaction
+fn recover_with_connection(
+    db: &mut Database,
+    owner: Uuid,
+) -> Result<RecoveryReport, SMSManagerError> {
+    db.transaction(|db| {
+        let requeued_messages = diesel::update(
+            messages::table.filter(
+                messages::in_flight.eq(true).and(
+                    messages::lease_expires_at
+                        .lt(Utc::now())
+                        .or(messages::leased_by.eq(owner))
+                        .or(messages::leased_by.is_null()),
+                ),
+            ),
+        )
+        .set((
+            messages::in_flight.eq(false),
+            messages::leased_by.eq(None::<Uuid>),
+            messages::lease_expires_at.eq(None::<chrono::DateTime<Utc>>),
+        ))
+        .execute(db)?;
+
+        diesel::delete(
+            producer_leases::table.filter(
+                producer_leases::lease_expires_at
+                    .lt(Utc::now())
+                    .or(producer_leases::leased_by.eq(owner)),
+            ),
+        )
+        .execute(db)?;
+
+        let interrupted_sends = diesel::update(
+            producers::table.filter(producers::status.eq(ProducerStatus::Sending).and(not(
+                exists(
+                    producer_leases::table.filter(producer_leases::producer_id.eq(producers::id)),
+                ),
+            ))),
+        )
+        .set(producers::status.eq(ProducerStatus::Interrupted))
+        .returning(producers::id)
+        .get_results(db)?;
+
+        let interrupted_pauses = diesel::update(producers::table.filter(
+            producers::status.eq(ProducerStatus::Paused).and(not(exists(
+                producer_leases::table.filter(producer_leases::producer_id.eq(producers::id)),
+            ))),
+        ))
+        .set(producers::status.eq(ProducerStatus::Interrupted))
+        .returning(producers::id)
+        .get_results(db)?;
+
+        Ok(RecoveryReport {
+            requeued_messages,
+            interrupted_sends,
+            interrupted_pauses,
+        })
+    })
+    .map_err(SMSManagerError::DbError)
+}
+
+/// A generated message with the id it is copied in with, so a failed generation knows which messages to delete again
+#[derive(Insertable)]
+#[diesel(table_name = messages, treat_none_as_default_value = false)]
+struct GeneratedMessage {
+    id: Uuid,
+    message_body: String,
+    produced_by: Uuid,
+    message_index: i32,
+}
+
+/// Copies the chunks in, committing every chunk with the number of messages generated so far, then sets the producer to finished
+/// The ids of the messages committed are added to written as each chunk is committed
+///
+/// # Parameters
+/// - db: The connection to write the messages and the progress on
+/// - producer: The producer the messages are generated for
+/// - chunks: The generated messages
+/// - finished: The status the producer is set to once every chunk is written
+/// - written: Collects the ids of the messages committed
+///
+/// # Returns
+/// The number of messages written
+///
+/// ### Errors if copying a chunk or updating the producer fails
+fn write_generated(
+    db: &mut Database,
+    producer: &Producer,
+    chunks: MessageChunks,
+    finished: ProducerStatus,
+    written: &mut Vec<Uuid>,
+) -> QueryResult<i32> {
+    let mut generated = 0;
+    for chunk in chunks {
+        let chunk: Vec<GeneratedMessage> = chunk
+            .into_iter()
+            .map(|message| GeneratedMessage {
+                id: Uuid::new_v4(),
+                message_body: message.message_body,
+                produced_by: message.produced_by,
+                message_index: message.message_index,
+            })
+            .collect();
+
+        let copied = db.transaction(|db| {
+            let copied = diesel::copy_from(messages::table)
+                .from_insertable(&chunk)
+                .execute(db)? as i32;
+
+            diesel::update(producers::table.find(producer.id))
+                .set(producers::generated_messages.eq(generated + copied))
+                .execute(db)?;
+
+            Ok::<_, diesel::result::Error>(copied)
+        })?;
+
+        generated += copied;
+        written.extend(chunk.into_iter().map(|message| message.id));
+        println!("GENERATING {}/{}", generated, producer.number_messages);
+    }
+
+    diesel::update(producers::table.find(producer.id))
+        .set((
+            producers::status.eq(finished),
+            producers::generated_messages.eq(generated),
+        ))
+        .execute(db)?;
+
+    Ok(generated)
+}
+
+/// The counts of a producers messages, see progress_stats_with_connection
+#[derive(QueryableByName)]
+struct ProgressRow {
+    #[diesel(sql_type = BigInt)]
+    number_messages_created: i64,
+    #[diesel(sql_type = BigInt)]
+    number_messages_sent: i64,
+    #[diesel(sql_type = BigInt)]
+    number_messages_failed: i64,
+    #[diesel(sql_type = BigInt)]
+    number_messages_retried: i64,
+    #[diesel(sql_type = BigInt)]
+    number_messages_recovered: i64,
+    #[diesel(sql_type = Nullable<Double>)]
+    average_message_time_ms: Option<f64>,
+    #[diesel(sql_type = Nullable<Integer>)]
+    min_time_ms: Option<i32>,
+    #[diesel(sql_type = Nullable<Integer>)]
+    max_time_ms: Option<i32>,
+}
+
+/// How many of a producers message times fell in a bucket of the histogram
+#[derive(QueryableByName)]
+struct BucketRow {
+    #[diesel(sql_type = Integer)]
+    bucket: i32,
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+/// Counts a producers messages in one pass over them
+/// A message is timed in milliseconds, or in whole seconds if it was sent before sends were timed in milliseconds, see message_utils::get_message_time_ms
+const PROGRESS_STATS_SQL: &str = "SELECT COUNT(*) AS number_messages_created, \
+    COUNT(COALESCE(time_took_ms, time_took * 1000)) FILTER (WHERE sent) AS number_messages_sent, \
+    COUNT(*) FILTER (WHERE failed) AS number_messages_failed, \
+    COUNT(*) FILTER (WHERE attempts > 1) AS number_messages_retried, \
+    COUNT(*) FILTER (WHERE attempts > 1 AND sent AND NOT failed) AS number_messages_recovered, \
+    CAST(AVG(GREATEST(COALESCE(time_took_ms, time_took * 1000), 0)) FILTER (WHERE sent) AS DOUBLE PRECISION) AS average_message_time_ms, \
+    MIN(COALESCE(time_took_ms, time_took * 1000)) FILTER (WHERE sent) AS min_time_ms, \
+    MAX(COALESCE(time_took_ms, time_took * 1000)) FILTER (WHERE sent) AS max_time_ms \
+    FROM messages WHERE produced_by = $1";
+
+/// Counts the times of a producers sent messages in each bucket between $2 and $3 that has any
+const MESSAGE_TIME_BUCKETS_SQL: &str = "SELECT \
+    width_bucket(CAST(COALESCE(time_took_ms, time_took * 1000) AS DOUBLE PRECISION), $2, $3, $4) AS bucket, \
+    COUNT(*) AS count \
+    FROM messages \
+    WHERE produced_by = $1 AND sent AND COALESCE(time_took_ms, time_took * 1000) IS NOT NULL \
+    GROUP BY bucket";
+
+/// Does the work of progress_stats on a connection
+fn progress_stats_with_connection(
+    db: &mut Database,
+    producer_id: Uuid,
+) -> Result<ProgressStats, SMSManagerError> {
+    let row: ProgressRow = diesel::sql_query(PROGRESS_STATS_SQL)
+        .bind::<SqlUuid, _>(producer_id)
+        .get_result(db)?;
+
+    let histogram = match (row.min_time_ms, row.max_time_ms) {
+        (Some(min_ms), Some(max_ms)) => {
+            let width = message_time_bucket_width(min_ms, max_ms);
+            let buckets: Vec<BucketRow> = diesel::sql_query(MESSAGE_TIME_BUCKETS_SQL)
+                .bind::<SqlUuid, _>(producer_id)
+                .bind::<Double, _>(min_ms as f64)
+                .bind::<Double, _>((min_ms as i64 + width * MESSAGE_TIME_BUCKETS) as f64)
+                .bind::<Integer, _>(MESSAGE_TIME_BUCKETS as i32)
+                .load(db)?;
+
+            message_time_histogram(
+                min_ms,
+                max_ms,
+                buckets
+                    .into_iter()
+                    .map(|bucket| (bucket.bucket as i64, bucket.count))
+                    .collect(),
+            )
+        }
+        _ => vec![],
+    };
+
+    Ok(ProgressStats {
+        number_messages_created: row.number_messages_created,
+        number_messages_sent: row.number_messages_sent,
+        number_messages_failed: row.number_messages_failed,
+        number_messages_retried: row.number_messages_retried,
+        number_messages_recovered: row.number_messages_recovered,
+        average_message_time_ms: row.average_message_time_ms.unwrap_or(0.0),
+        message_time_histogram: histogram,
+    })
+}
+
+/// Updates the delivery columns of every message in one UPDATE ... FROM (VALUES ...) statement
+/// A message that is no longer in flight gives its lease up in the same statement, a message still in flight keeps the lease its send renewed
+///
+/// # Paramters
+/// - db: The database connection to make requests with
+/// - updated_messages: The messages as they should be stored, each id at most once
+///
+/// # Returns
+/// The number of messages updated
+fn update_messages(db: &mut Database, updated_messages: &[Message]) -> QueryResult<usize> {
+    if updated_messages.is_empty() {
+        return Ok(0);
+    }
+
+    let rows = (0..updated_messages.len())
+        .map(|row| {
+            let parameter = |column: usize| format!("${}", row * 10 + column + 1);
+            format!(
+                "({}::uuid, {}::boolean, {}::integer, {}::boolean, {}::integer, {}::text, {}::timestamptz, {}::timestamptz, {}::integer, {}::boolean)",
+                parameter(0),
+                parameter(1),
+                parameter(2),
+                parameter(3),
+                parameter(4),
+                parameter(5),
+                parameter(6),
+                parameter(7),
+                parameter(8),
+                parameter(9)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let mut query = diesel::sql_query(format!(
+        "UPDATE messages SET sent = batch.sent, time_took = batch.time_took, failed = batch.failed, attempts = batch.attempts, \
+        provider_message_id = batch.provider_message_id, started_at = batch.started_at, finished_at = batch.finished_at, \
+        time_took_ms = batch.time_took_ms, in_flight = batch.in_flight, \
+        leased_by = CASE WHEN batch.in_flight THEN messages.leased_by ELSE NULL END, \
+        lease_expires_at = CASE WHEN batch.in_flight THEN messages.lease_expires_at ELSE NULL END \
+        FROM (VALUES {}) AS batch(id, sent, time_took, failed, attempts, provider_message_id, started_at, finished_at, time_took_ms, in_flight) \
+        WHERE messages.id = batch.id",
+        rows
+    ))
+    .into_boxed::<Pg>();
+
+    for message in updated_messages {
+        query = query
+            .bind::<SqlUuid, _>(message.id)
+            .bind::<Bool, _>(message.sent)
+            .bind::<Nullable<Integer>, _>(message.time_took)
+            .bind::<Bool, _>(message.failed)
+            .bind::<Integer, _>(message.attempts)
+            .bind::<Nullable<Text>, _>(message.provider_message_id.clone())
+            .bind::<Nullable<Timestamptz>, _>(message.started_at)
+            .bind::<Nullable<Timestamptz>, _>(message.finished_at)
+            .bind::<Nullable<Integer>, _>(message.time_took_ms)
+            .bind::<Bool, _>(message.in_flight);
+    }
+
+    query.execute(db)
+}
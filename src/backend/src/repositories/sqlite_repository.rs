@@ -0,0 +1,1028 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use diesel::{
+    connection::SimpleConnection,
+    dsl::{count_star, exists, not},
+    r2d2::{ConnectionManager, CustomizeConnection, Pool},
+    sql_types::{BigInt, Bool, Double, Integer, Nullable},
+    sqlite::Sqlite,
+    BoolExpressionMethods, BoxableExpression, Connection, EscapeExpressionMethods,
+    ExpressionMethods, OptionalExtension, QueryDsl, QueryResult, QueryableByName, RunQueryDsl,
+    SqliteConnection, TextExpressionMethods,
+};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use uuid::Uuid;
+
+use super::{
+    coalesce, generation_error, status_conflict, MessageChunks, MessageCursor, MessagePage,
+    MessageQuery, MessageRepository, MessageSort, ProducerCursor, ProducerPage, ProducerQuery,
+    ProducerRepository, ProducerSort, ProgressStats, RecoveryReport, StatusClaim, LEASE_DURATION,
+};
+use crate::{
+    diesel::{
+        models::{
+            DeliveryAttempt, Message, NewDeliveryAttempt, NewMessage, NewProducer, Producer,
+            ProducerChanges,
+        },
+        sqlite_schema::{
+            delivery_attempts, messages, producer_leases, producers, sql_types::Uuid as TextUuid,
+        },
+    },
+    utils::{
+        blocking::run_blocking,
+        error::SMSManagerError,
+        message_state::MessageState,
+        message_utils::{message_time_bucket_width, message_time_histogram},
+        pagination::SortOrder,
+        producer_status::{ProducerAction, ProducerStatus},
+        uuid::SqliteUuid,
+    },
+};
+
+pub type SqlitePoolHandle = Pool<ConnectionManager<SqliteConnection>>;
+
+pub const SQLITE_MIGRATIONS: EmbeddedMigrations =
+    embed_migrations!("./src/diesel/sqlite_migrations");
+
+/// The most rows written by one INSERT, which keeps every statement well under the number of variables SQLite can bind
+const INSERT_BATCH_SIZE: usize = 1_000;
+
+/// How long a connection waits for another connection to finish writing before giving up
+const BUSY_TIMEOUT_MS: u32 = 5_000;
+
+/// Sets up every connection the pool opens
+#[derive(Debug)]
+struct SqliteCustomizer;
+
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for SqliteCustomizer {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+        // WAL lets reads go on while another connection writes, foreign keys are off in SQLite unless asked for
+        conn.batch_execute(&format!(
+            "PRAGMA busy_timeout = {}; PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;",
+            BUSY_TIMEOUT_MS
+        ))
+        .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+/// Stores producers and messages in a SQLite file, every query runs on the blocking thread pool, see run_blocking
+/// SQLite lets one connection write at a time, so work that reads and then writes takes the write lock up front with BEGIN IMMEDIATE
+/// That lock is what keeps claims apart, in place of the row locks and advisory locks the Postgres repository takes
+pub struct SqliteRepository {
+    pool: SqlitePoolHandle,
+}
+
+impl SqliteRepository {
+    /// # Parameters
+    /// - pool: The database pool to retrieve connections from
+    pub fn new(pool: SqlitePoolHandle) -> Self {
+        SqliteRepository { pool }
+    }
+
+    /// Opens the database file, creating it if it does not exist, and brings its tables up to date
+    ///
+    /// # Parameters
+    /// - path: The path of the database file
+    ///
+    /// ### Errors if the file can not be opened or the migrations fail
+    pub fn connect(path: &str) -> Result<Self, SMSManagerError> {
+        let pool = Pool::builder()
+            .connection_customizer(Box::new(SqliteCustomizer))
+            .build(ConnectionManager::<SqliteConnection>::new(path))
+            .map_err(|err| {
+                SMSManagerError::GeneralException(format!(
+                    "Could not open the SQLite database {}: {}",
+                    path, err
+                ))
+            })?;
+
+        pool.get()?
+            .run_pending_migrations(SQLITE_MIGRATIONS)
+            .map_err(|err| {
+                SMSManagerError::GeneralException(format!(
+                    "Could not migrate the SQLite database {}: {}",
+                    path, err
+                ))
+            })?;
+
+        Ok(SqliteRepository::new(pool))
+    }
+}
+
+#[async_trait]
+impl ProducerRepository for SqliteRepository {
+    async fn insert(&self, new_producer: NewProducer) -> Result<Producer, SMSManagerError> {
+        let producer_id = Uuid::new_v4();
+
+        run_blocking(&self.pool, move |db| {
+            diesel::insert_into(producers::table)
+                .values((
+                    producers::id.eq(SqliteUuid(producer_id)),
+                    producers::name.eq(new_producer.name),
+                    producers::number_messages.eq(new_producer.number_messages),
+                    producers::average_send_delay.eq(new_producer.average_send_delay),
+                    producers::failure_rate.eq(new_producer.failure_rate),
+                    producers::num_senders.eq(new_producer.num_senders),
+                    producers::status.eq(new_producer.status),
+                    producers::created_at.eq(Utc::now()),
+                ))
+                .execute(db)
+                .map_err(SMSManagerError::DbError)?;
+
+            find_producer(db, producer_id)
+        })
+        .await
+    }
+
+    async fn find(&self, producer_id: Uuid) -> Result<Producer, SMSManagerError> {
+        run_blocking(&self.pool, move |db| find_producer(db, producer_id)).await
+    }
+
+    async fn list_page(&self, query: ProducerQuery) -> Result<ProducerPage, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            let total = filtered_producers(&query, true).count().get_result(db)?;
+            let mut counted = producers::table
+                .group_by(producers::status)
+                .select((producers::status, count_star()))
+                .into_boxed();
+            if let Some(pattern) = query.name_pattern() {
+                counted = counted.filter(producers::name.like(pattern).escape('\\'));
+            }
+            let status_counts = counted.load(db)?;
+
+            let mut page = filtered_producers(&query, true);
+            page = match (&query.after, query.order) {
+                (None, _) => page,
+                (Some(ProducerCursor::Name { name, id }), SortOrder::Asc) => page.filter(
+                    producers::name.gt(name.clone()).or(producers::name
+                        .eq(name.clone())
+                        .and(producers::id.gt(SqliteUuid(*id)))),
+                ),
+                (Some(ProducerCursor::Name { name, id }), SortOrder::Desc) => page.filter(
+                    producers::name.lt(name.clone()).or(producers::name
+                        .eq(name.clone())
+                        .and(producers::id.lt(SqliteUuid(*id)))),
+                ),
+                (Some(ProducerCursor::CreatedAt { created_at, id }), SortOrder::Asc) => page
+                    .filter(
+                        producers::created_at
+                            .gt(*created_at)
+                            .or(producers::created_at
+                                .eq(*created_at)
+                                .and(producers::id.gt(SqliteUuid(*id)))),
+                    ),
+                (Some(ProducerCursor::CreatedAt { created_at, id }), SortOrder::Desc) => page
+                    .filter(
+                        producers::created_at
+                            .lt(*created_at)
+                            .or(producers::created_at
+                                .eq(*created_at)
+                                .and(producers::id.lt(SqliteUuid(*id)))),
+                    ),
+            };
+            page = match (query.sort, query.order) {
+                (ProducerSort::Name, SortOrder::Asc) => {
+                    page.order((producers::name.asc(), producers::id.asc()))
+                }
+                (ProducerSort::Name, SortOrder::Desc) => {
+                    page.order((producers::name.desc(), producers::id.desc()))
+                }
+                (ProducerSort::CreatedAt, SortOrder::Asc) => {
+                    page.order((producers::created_at.asc(), producers::id.asc()))
+                }
+                (ProducerSort::CreatedAt, SortOrder::Desc) => {
+                    page.order((producers::created_at.desc(), producers::id.desc()))
+                }
+            };
+
+            let rows = page.limit(query.limit + 1).load::<Producer>(db)?;
+            Ok(ProducerPage::from_rows(rows, &query, total, status_counts))
+        })
+        .await
+    }
+
+    async fn update(
+        &self,
+        producer_id: Uuid,
+        changes: ProducerChanges,
+    ) -> Result<Producer, SMSManagerError> {
+        let (allowed_from, _) = ProducerAction::Update.transition();
+
+        run_blocking(&self.pool, move |db| {
+            db.immediate_transaction(|db| {
+                let updated = diesel::update(
+                    producers::table
+                        .find(SqliteUuid(producer_id))
+                        .filter(producers::status.eq_any(allowed_from)),
+                )
+                .set((
+                    changes.name.map(|name| producers::name.eq(name)),
+                    changes
+                        .number_messages
+                        .map(|number_messages| producers::number_messages.eq(number_messages)),
+                    changes.average_send_delay.map(|average_send_delay| {
+                        producers::average_send_delay.eq(average_send_delay)
+                    }),
+                    changes
+                        .failure_rate
+                        .map(|failure_rate| producers::failure_rate.eq(failure_rate)),
+                    changes
+                        .num_senders
+                        .map(|num_senders| producers::num_senders.eq(num_senders)),
+                    changes
+                        .transport
+                        .map(|transport| producers::transport.eq(transport)),
+                    changes
+                        .retry_policy
+                        .map(|retry_policy| producers::retry_policy.eq(retry_policy)),
+                    changes
+                        .rate_limit
+                        .map(|rate_limit| producers::rate_limit.eq(rate_limit)),
+                    changes.seed.map(|seed| producers::seed.eq(seed)),
+                ))
+                .execute(db)?;
+                if updated == 0 {
+                    return Err(status_conflict(
+                        find_producer(db, producer_id)?.status,
+                        ProducerAction::Update,
+                    ));
+                }
+
+                find_producer(db, producer_id)
+            })
+        })
+        .await
+    }
+
+    async fn transition(
+        &self,
+        producer_id: Uuid,
+        action: ProducerAction,
+    ) -> Result<bool, SMSManagerError> {
+        let (allowed_from, next) = action.transition();
+        let Some(next) = next else {
+            return Ok(true);
+        };
+
+        run_blocking(&self.pool, move |db| {
+            diesel::update(
+                producers::table
+                    .find(SqliteUuid(producer_id))
+                    .filter(producers::status.eq_any(allowed_from)),
+            )
+            .set(producers::status.eq(next))
+            .execute(db)
+            .map(|updated| updated > 0)
+            .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    /// The claim runs in a BEGIN IMMEDIATE transaction, which holds the write lock of the whole database until the claimed status is written
+    async fn claim_status(
+        &self,
+        producer_id: Uuid,
+        owner: Uuid,
+        claim: StatusClaim,
+    ) -> Result<ProducerStatus, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            db.immediate_transaction(|db| {
+                let current: ProducerStatus = producers::table
+                    .find(SqliteUuid(producer_id))
+                    .select(producers::status)
+                    .first(db)?;
+                let next = claim(current)?;
+
+                diesel::update(producers::table.find(SqliteUuid(producer_id)))
+                    .set(producers::status.eq(next))
+                    .execute(db)?;
+
+                let lease_expires_at = Utc::now() + LEASE_DURATION;
+                diesel::insert_into(producer_leases::table)
+                    .values((
+                        producer_leases::producer_id.eq(SqliteUuid(producer_id)),
+                        producer_leases::leased_by.eq(SqliteUuid(owner)),
+                        producer_leases::lease_expires_at.eq(lease_expires_at),
+                    ))
+                    .on_conflict((producer_leases::producer_id, producer_leases::leased_by))
+                    .do_update()
+                    .set(producer_leases::lease_expires_at.eq(lease_expires_at))
+                    .execute(db)?;
+
+                Ok(next)
+            })
+        })
+        .await
+    }
+
+    async fn delete(&self, producer_id: Uuid) -> Result<(), SMSManagerError> {
+        let (allowed_from, _) = ProducerAction::Delete.transition();
+
+        run_blocking(&self.pool, move |db| {
+            db.immediate_transaction(|db| {
+                let producer = find_producer(db, producer_id)?;
+                if !allowed_from.contains(&producer.status) {
+                    return Err(status_conflict(producer.status, ProducerAction::Delete));
+                }
+
+                diesel::delete(messages::table)
+                    .filter(messages::produced_by.eq(SqliteUuid(producer_id)))
+                    .execute(db)?;
+                diesel::delete(producers::table)
+                    .filter(producers::id.eq(SqliteUuid(producer_id)))
+                    .execute(db)?;
+
+                Ok(())
+            })
+        })
+        .await
+    }
+}
+
+/// A transaction open for the whole generation would hold the write lock throughout and keep the progress from being written, so every chunk is committed on its own
+/// The producer is GENERATING until the last chunk, so none of its messages can be sent before then, and if a chunk fails the chunks written before it are deleted again
+#[async_trait]
+impl MessageRepository for SqliteRepository {
+    async fn insert_generated(
+        &self,
+        producer: Producer,
+        chunks: MessageChunks,
+        generating: ProducerStatus,
+        finished: ProducerStatus,
+    ) -> Result<i32, SMSManagerError> {
+        let (allowed_from, _) = ProducerAction::Generate.transition();
+
+        run_blocking(&self.pool, move |db| {
+            let started = diesel::update(
+                producers::table
+                    .find(SqliteUuid(producer.id))
+                    .filter(producers::status.eq_any(allowed_from)),
+            )
+            .set((
+                producers::status.eq(generating),
+                producers::generated_messages.eq(0),
+            ))
+            .execute(db)?;
+            if started == 0 {
+                return Err(status_conflict(
+                    find_producer(db, producer.id)?.status,
+                    ProducerAction::Generate,
+                ));
+            }
+
+            let mut written: Vec<Uuid> = vec![];
+            let result = write_generated(db, &producer, chunks, finished, &mut written);
+
+            result.map_err(|err| {
+                let restored = db.immediate_transaction(|db| {
+                    for batch in written.chunks(INSERT_BATCH_SIZE) {
+                        diesel::delete(
+                            messages::table
+                                .filter(messages::id.eq_any(batch.iter().copied().map(SqliteUuid))),
+                        )
+                        .execute(db)?;
+                    }
+
+                    diesel::update(producers::table.find(SqliteUuid(producer.id)))
+                        .set((
+                            producers::status.eq(producer.status),
+                            producers::generated_messages.eq(producer.generated_messages),
+                        ))
+                        .execute(db)
+                });
+                generation_error(err, restored)
+            })
+        })
+        .await
+    }
+
+    async fn find(&self, message_id: Uuid) -> Result<Message, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            messages::table
+                .find(SqliteUuid(message_id))
+                .first(db)
+                .optional()?
+                .ok_or(SMSManagerError::EmptyResult)
+        })
+        .await
+    }
+
+    async fn list_for_producer(&self, producer_id: Uuid) -> Result<Vec<Message>, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            messages::table
+                .filter(messages::produced_by.eq(SqliteUuid(producer_id)))
+                .load(db)
+                .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    async fn list_page(&self, query: MessageQuery) -> Result<MessagePage, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            let total = filtered_messages(&query).count().get_result(db)?;
+
+            let time_took = || coalesce(messages::time_took_ms, -1);
+            let mut page = filtered_messages(&query);
+            page = match (&query.after, query.order) {
+                (None, _) => page,
+                (Some(MessageCursor::Id { id }), SortOrder::Asc) => {
+                    page.filter(messages::id.gt(SqliteUuid(*id)))
+                }
+                (Some(MessageCursor::Id { id }), SortOrder::Desc) => {
+                    page.filter(messages::id.lt(SqliteUuid(*id)))
+                }
+                (Some(MessageCursor::TimeTook { time_took_ms, id }), SortOrder::Asc) => page
+                    .filter(
+                        time_took().gt(*time_took_ms).or(time_took()
+                            .eq(*time_took_ms)
+                            .and(messages::id.gt(SqliteUuid(*id)))),
+                    ),
+                (Some(MessageCursor::TimeTook { time_took_ms, id }), SortOrder::Desc) => page
+                    .filter(
+                        time_took().lt(*time_took_ms).or(time_took()
+                            .eq(*time_took_ms)
+                            .and(messages::id.lt(SqliteUuid(*id)))),
+                    ),
+                (Some(MessageCursor::Attempts { attempts, id }), SortOrder::Asc) => page.filter(
+                    messages::attempts.gt(*attempts).or(messages::attempts
+                        .eq(*attempts)
+                        .and(messages::id.gt(SqliteUuid(*id)))),
+                ),
+                (Some(MessageCursor::Attempts { attempts, id }), SortOrder::Desc) => page.filter(
+                    messages::attempts.lt(*attempts).or(messages::attempts
+                        .eq(*attempts)
+                        .and(messages::id.lt(SqliteUuid(*id)))),
+                ),
+            };
+            page = match (query.sort, query.order) {
+                (MessageSort::Id, SortOrder::Asc) => page.order(messages::id.asc()),
+                (MessageSort::Id, SortOrder::Desc) => page.order(messages::id.desc()),
+                (MessageSort::TimeTook, SortOrder::Asc) => {
+                    page.order((time_took().asc(), messages::id.asc()))
+                }
+                (MessageSort::TimeTook, SortOrder::Desc) => {
+                    page.order((time_took().desc(), messages::id.desc()))
+                }
+                (MessageSort::Attempts, SortOrder::Asc) => {
+                    page.order((messages::attempts.asc(), messages::id.asc()))
+                }
+                (MessageSort::Attempts, SortOrder::Desc) => {
+                    page.order((messages::attempts.desc(), messages::id.desc()))
+                }
+            };
+
+            let rows = page.limit(query.limit + 1).load::<Message>(db)?;
+            Ok(MessagePage::from_rows(rows, &query, total))
+        })
+        .await
+    }
+
+    /// Reads in one transaction, so the counts and the histogram come from the same snapshot
+    async fn progress_stats(&self, producer_id: Uuid) -> Result<ProgressStats, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            db.transaction(|db| progress_stats_with_connection(db, producer_id))
+        })
+        .await
+    }
+
+    async fn count_unsent(&self, producer_id: Uuid) -> Result<i64, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            messages::table
+                .filter(messages::produced_by.eq(SqliteUuid(producer_id)))
+                .filter(messages::sent.eq(false))
+                .count()
+                .get_result(db)
+                .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    async fn count_claimable(&self, producer_id: Uuid) -> Result<i64, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            messages::table
+                .filter(
+                    messages::produced_by
+                        .eq(SqliteUuid(producer_id))
+                        .and(messages::sent.eq(false))
+                        .and(
+                            messages::in_flight
+                                .eq(false)
+                                .or(messages::lease_expires_at.lt(Utc::now())),
+                        ),
+                )
+                .count()
+                .get_result(db)
+                .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    /// The messages are picked and leased in one BEGIN IMMEDIATE transaction, so a claim waits for the claim before it to finish
+    async fn claim(
+        &self,
+        producer_id: Uuid,
+        owner: Uuid,
+        batch_size: i64,
+    ) -> Result<Vec<Message>, SMSManagerError> {
+        let now = Utc::now();
+
+        run_blocking(&self.pool, move |db| {
+            db.immediate_transaction(|db| {
+                let claimed: Vec<SqliteUuid> = messages::table
+                    .select(messages::id)
+                    .filter(
+                        messages::produced_by
+                            .eq(SqliteUuid(producer_id))
+                            .and(messages::sent.eq(false))
+                            .and(
+                                messages::in_flight
+                                    .eq(false)
+                                    .or(messages::lease_expires_at.lt(now)),
+                            ),
+                    )
+                    .order(messages::id)
+                    .limit(batch_size)
+                    .load::<Uuid>(db)?
+                    .into_iter()
+                    .map(SqliteUuid)
+                    .collect();
+
+                diesel::update(messages::table.filter(messages::id.eq_any(claimed.clone())))
+                    .set((
+                        messages::in_flight.eq(true),
+                        messages::leased_by.eq(SqliteUuid(owner)),
+                        messages::lease_expires_at.eq(now + LEASE_DURATION),
+                    ))
+                    .execute(db)?;
+
+                messages::table
+                    .filter(messages::id.eq_any(claimed))
+                    .order(messages::id)
+                    .load(db)
+            })
+            .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    async fn renew_leases(&self, producer_id: Uuid, owner: Uuid) -> Result<usize, SMSManagerError> {
+        let lease_expires_at = Utc::now() + LEASE_DURATION;
+
+        run_blocking(&self.pool, move |db| {
+            db.immediate_transaction(|db| {
+                diesel::update(
+                    producer_leases::table.find((SqliteUuid(producer_id), SqliteUuid(owner))),
+                )
+                .set(producer_leases::lease_expires_at.eq(lease_expires_at))
+                .execute(db)?;
+
+                diesel::update(
+                    messages::table.filter(
+                        messages::produced_by
+                            .eq(SqliteUuid(producer_id))
+                            .and(messages::in_flight.eq(true))
+                            .and(messages::leased_by.eq(SqliteUuid(owner))),
+                    ),
+                )
+                .set(messages::lease_expires_at.eq(lease_expires_at))
+                .execute(db)
+            })
+            .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    async fn release_leases(
+        &self,
+        producer_id: Uuid,
+        owner: Uuid,
+    ) -> Result<usize, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            db.immediate_transaction(|db| {
+                diesel::delete(
+                    producer_leases::table.find((SqliteUuid(producer_id), SqliteUuid(owner))),
+                )
+                .execute(db)?;
+
+                diesel::update(
+                    messages::table.filter(
+                        messages::produced_by
+                            .eq(SqliteUuid(producer_id))
+                            .and(messages::in_flight.eq(true))
+                            .and(messages::leased_by.eq(SqliteUuid(owner))),
+                    ),
+                )
+                .set((
+                    messages::in_flight.eq(false),
+                    messages::leased_by.eq(None::<SqliteUuid>),
+                    messages::lease_expires_at.eq(None::<DateTime<Utc>>),
+                ))
+                .execute(db)
+            })
+            .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+
+    async fn recover(&self, owner: Uuid) -> Result<RecoveryReport, SMSManagerError> {
+        run_blocking(&self.pool, move |db| recover_with_connection(db, owner)).await
+    }
+
+    /// Every message is written by its own UPDATE, all of them in one transaction
+    async fn update_results(
+        &self,
+        updated_messages: Vec<Message>,
+    ) -> Result<usize, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            db.immediate_transaction(|db| {
+                let mut updated = 0;
+                for message in updated_messages {
+                    let target = messages::table.find(SqliteUuid(message.id));
+                    let results = (
+                        messages::sent.eq(message.sent),
+                        messages::time_took.eq(message.time_took),
+                        messages::failed.eq(message.failed),
+                        messages::attempts.eq(message.attempts),
+                        messages::provider_message_id.eq(message.provider_message_id),
+                        messages::started_at.eq(message.started_at),
+                        messages::finished_at.eq(message.finished_at),
+                        messages::time_took_ms.eq(message.time_took_ms),
+                        messages::in_flight.eq(message.in_flight),
+                    );
+
+                    // A message that is no longer in flight gives its lease up, one still in flight keeps the lease its send renewed
+                    updated += if message.in_flight {
+                        diesel::update(target).set(results).execute(db)?
+                    } else {
+                        diesel::update(target)
+                            .set((
+                                results,
+                                messages::leased_by.eq(None::<SqliteUuid>),
+                                messages::lease_expires_at.eq(None::<DateTime<Utc>>),
+                            ))
+                            .execute(db)?
+                    };
+                }
+
+                Ok(updated)
+            })
+        })
+        .await
+    }
+
+    async fn insert_attempts(
+        &self,
+        attempts: Vec<NewDeliveryAttempt>,
+    ) -> Result<usize, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            db.immediate_transaction(|db| {
+                let mut inserted = 0;
+                for batch in attempts.chunks(INSERT_BATCH_SIZE) {
+                    let rows: Vec<_> = batch
+                        .iter()
+                        .map(|attempt| {
+                            (
+                                delivery_attempts::id.eq(SqliteUuid(Uuid::new_v4())),
+                                delivery_attempts::message_id.eq(SqliteUuid(attempt.message_id)),
+                                delivery_attempts::attempt_number.eq(attempt.attempt_number),
+                                delivery_attempts::started_at.eq(attempt.started_at),
+                                delivery_attempts::finished_at.eq(attempt.finished_at),
+                                delivery_attempts::outcome.eq(&attempt.outcome),
+                                delivery_attempts::error_code.eq(&attempt.error_code),
+                                delivery_attempts::error_reason.eq(&attempt.error_reason),
+                                delivery_attempts::worker_id.eq(attempt.worker_id),
+                            )
+                        })
+                        .collect();
+
+                    inserted += diesel::insert_into(delivery_attempts::table)
+                        .values(rows)
+                        .execute(db)?;
+                }
+
+                Ok(inserted)
+            })
+        })
+        .await
+    }
+
+    async fn find_attempts(
+        &self,
+        producer_id: Uuid,
+        message_id: Uuid,
+    ) -> Result<Vec<DeliveryAttempt>, SMSManagerError> {
+        run_blocking(&self.pool, move |db| {
+            let found_messages: i64 = messages::table
+                .filter(
+                    messages::id
+                        .eq(SqliteUuid(message_id))
+                        .and(messages::produced_by.eq(SqliteUuid(producer_id))),
+                )
+                .count()
+                .get_result(db)
+                .map_err(SMSManagerError::DbError)?;
+
+            if found_messages == 0 {
+                return Err(SMSManagerError::EmptyResult);
+            }
+
+            delivery_attempts::table
+                .filter(delivery_attempts::message_id.eq(SqliteUuid(message_id)))
+                .order(delivery_attempts::attempt_number.asc())
+                .load(db)
+                .map_err(SMSManagerError::DbError)
+        })
+        .await
+    }
+}
+
+/// Gets the producer with the supplied id on a connection the caller already holds
+///
+/// ### Errors if producer is not found
+fn find_producer(
+    db: &mut SqliteConnection,
+    producer_id: Uuid,
+) -> Result<Producer, SMSManagerError> {
+    producers::table
+        .find(SqliteUuid(producer_id))
+        .first::<Producer>(db)
+        .optional()
+        .map_err(SMSManagerError::DbError)?
+        .ok_or(SMSManagerError::EmptyResult)
+}
+
+/// The producers passing the filters of the query, the status filter only if with_status is set
+/// SQLite's LIKE already ignores the case of ASCII letters
+fn filtered_producers<'a>(
+    query: &ProducerQuery,
+    with_status: bool,
+) -> producers::BoxedQuery<'a, Sqlite> {
+    let mut filtered = producers::table.into_boxed();
+    if with_status && !query.statuses.is_empty() {
+        filtered = filtered.filter(producers::status.eq_any(query.statuses.clone()));
+    }
+    if let Some(pattern) = query.name_pattern() {
+        filtered = filtered.filter(producers::name.like(pattern).escape('\\'));
+    }
+    filtered
+}
+
+/// The messages of the producer passing the filters of the query
+fn filtered_messages<'a>(query: &MessageQuery) -> messages::BoxedQuery<'a, Sqlite> {
+    let mut filtered = messages::table
+        .filter(messages::produced_by.eq(SqliteUuid(query.producer_id)))
+        .into_boxed();
+    if let Some(in_states) = query
+        .states
+        .iter()
+        .map(|state| in_state(*state))
+        .reduce(|either, or| Box::new(either.or(or)))
+    {
+        filtered = filtered.filter(in_states);
+    }
+    if let Some(pattern) = query.body_pattern() {
+        filtered = filtered.filter(messages::message_body.like(pattern).escape('\\'));
+    }
+    if let Some(min_time_took_ms) = query.min_time_took_ms {
+        filtered = filtered.filter(messages::time_took_ms.ge(min_time_took_ms));
+    }
+    if let Some(max_time_took_ms) = query.max_time_took_ms {
+        filtered = filtered.filter(messages::time_took_ms.le(max_time_took_ms));
+    }
+    filtered
+}
+
+/// The condition a message in the state meets, see MessageState::of
+fn in_state(
+    state: MessageState,
+) -> Box<dyn BoxableExpression<messages::table, Sqlite, SqlType = Bool>> {
+    match state {
+        MessageState::Pending => Box::new(messages::sent.eq(false)),
+        MessageState::Sent => Box::new(messages::sent.eq(true).and(messages::failed.eq(false))),
+        MessageState::Failed => Box::new(messages::sent.eq(true).and(messages::failed.eq(true))),
+    }
+}
+
+/// The counts of a producers messages, see progress_stats_with_connection
+#[derive(QueryableByName)]
+struct ProgressRow {
+    #[diesel(sql_type = BigInt)]
+    number_messages_created: i64,
+    #[diesel(sql_type = BigInt)]
+    number_messages_sent: i64,
+    #[diesel(sql_type = BigInt)]
+    number_messages_failed: i64,
+    #[diesel(sql_type = BigInt)]
+    number_messages_retried: i64,
+    #[diesel(sql_type = BigInt)]
+    number_messages_recovered: i64,
+    #[diesel(sql_type = Nullable<Double>)]
+    average_message_time_ms: Option<f64>,
+    #[diesel(sql_type = Nullable<Integer>)]
+    min_time_ms: Option<i32>,
+    #[diesel(sql_type = Nullable<Integer>)]
+    max_time_ms: Option<i32>,
+}
+
+/// How many of a producers message times fell in a bucket of the histogram
+#[derive(QueryableByName)]
+struct BucketRow {
+    #[diesel(sql_type = BigInt)]
+    bucket: i64,
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+/// Counts a producers messages in one pass over them, the same as the Postgres repository
+/// Two argument MAX is the larger of its arguments in SQLite, which Postgres calls GREATEST
+const PROGRESS_STATS_SQL: &str = "SELECT COUNT(*) AS number_messages_created, \
+    COUNT(COALESCE(time_took_ms, time_took * 1000)) FILTER (WHERE sent) AS number_messages_sent, \
+    COUNT(*) FILTER (WHERE failed) AS number_messages_failed, \
+    COUNT(*) FILTER (WHERE attempts > 1) AS number_messages_retried, \
+    COUNT(*) FILTER (WHERE attempts > 1 AND sent AND NOT failed) AS number_messages_recovered, \
+    AVG(MAX(COALESCE(time_took_ms, time_took * 1000), 0)) FILTER (WHERE sent) AS average_message_time_ms, \
+    MIN(COALESCE(time_took_ms, time_took * 1000)) FILTER (WHERE sent) AS min_time_ms, \
+    MAX(COALESCE(time_took_ms, time_took * 1000)) FILTER (WHERE sent) AS max_time_ms \
+    FROM messages WHERE produced_by = ?";
+
+/// Counts the times of a producers sent messages in each bucket that has any
+/// SQLite has no width_bucket, so the bucket is worked out from the lowest time and the width of the buckets with integer division
+const MESSAGE_TIME_BUCKETS_SQL: &str = "SELECT \
+    (COALESCE(time_took_ms, time_took * 1000) - ?) / ? + 1 AS bucket, \
+    COUNT(*) AS count \
+    FROM messages \
+    WHERE produced_by = ? AND sent AND COALESCE(time_took_ms, time_took * 1000) IS NOT NULL \
+    GROUP BY bucket";
+
+/// Does the work of progress_stats on a connection
+fn progress_stats_with_connection(
+    db: &mut SqliteConnection,
+    producer_id: Uuid,
+) -> Result<ProgressStats, SMSManagerError> {
+    let row: ProgressRow = diesel::sql_query(PROGRESS_STATS_SQL)
+        .bind::<TextUuid, _>(SqliteUuid(producer_id))
+        .get_result(db)?;
+
+    let histogram = match (row.min_time_ms, row.max_time_ms) {
+        (Some(min_ms), Some(max_ms)) => {
+            let buckets: Vec<BucketRow> = diesel::sql_query(MESSAGE_TIME_BUCKETS_SQL)
+                .bind::<Integer, _>(min_ms)
+                .bind::<BigInt, _>(message_time_bucket_width(min_ms, max_ms))
+                .bind::<TextUuid, _>(SqliteUuid(producer_id))
+                .load(db)?;
+
+            message_time_histogram(
+                min_ms,
+                max_ms,
+                buckets
+                    .into_iter()
+                    .map(|bucket| (bucket.bucket, bucket.count))
+                    .collect(),
+            )
+        }
+        _ => vec![],
+    };
+
+    Ok(ProgressStats {
+        number_messages_created: row.number_messages_created,
+        number_messages_sent: row.number_messages_sent,
+        number_messages_failed: row.number_messages_failed,
+        number_messages_retried: row.number_messages_retried,
+        number_messages_recovered: row.number_messages_recovered,
+        average_message_time_ms: row.average_message_time_ms.unwrap_or(0.0),
+        message_time_histogram: histogram,
+    })
+}
+
+/// Writes the generated messages a chunk at a time, committing each chunk along with the progress of the producer
+///
+/// # Parameters
+/// - db: The database connection to make requests with
+/// - producer: The producer as it was before generating
+/// - chunks: The messages to write
+/// - finished: The status the producer is in once every message is written
+/// - written: Collects the ids of the messages committed so far, so the caller can delete them again if a later chunk fails
+///
+/// # Returns
+/// How many messages were written
+fn write_generated(
+    db: &mut SqliteConnection,
+    producer: &Producer,
+    chunks: MessageChunks,
+    finished: ProducerStatus,
+    written: &mut Vec<Uuid>,
+) -> QueryResult<i32> {
+    let mut generated = 0;
+    for chunk in chunks {
+        let chunk: Vec<(Uuid, NewMessage)> = chunk
+            .into_iter()
+            .map(|message| (Uuid::new_v4(), message))
+            .collect();
+
+        db.immediate_transaction(|db| {
+            for batch in chunk.chunks(INSERT_BATCH_SIZE) {
+                let rows: Vec<_> = batch
+                    .iter()
+                    .map(|(id, message)| {
+                        (
+                            messages::id.eq(SqliteUuid(*id)),
+                            messages::message_body.eq(&message.message_body),
+                            messages::produced_by.eq(SqliteUuid(message.produced_by)),
+                            messages::message_index.eq(message.message_index),
+                        )
+                    })
+                    .collect();
+
+                diesel::insert_into(messages::table)
+                    .values(rows)
+                    .execute(db)?;
+            }
+
+            diesel::update(producers::table.find(SqliteUuid(producer.id)))
+                .set(producers::generated_messages.eq(generated + chunk.len() as i32))
+                .execute(db)
+        })?;
+
+        generated += chunk.len() as i32;
+        written.extend(chunk.into_iter().map(|(id, _)| id));
+        println!("GENERATING {}/{}", generated, producer.number_messages);
+    }
+
+    diesel::update(producers::table.find(SqliteUuid(producer.id)))
+        .set((
+            producers::status.eq(finished),
+            producers::generated_messages.eq(generated),
+        ))
+        .execute(db)?;
+
+    Ok(generated)
+}
+
+/// Does the work of recover on a connection, in one transaction
+/// SQLite can not return the rows an UPDATE changes here, so the interrupted producers are read first, which the write lock keeps accurate
+fn recover_with_connection(
+    db: &mut SqliteConnection,
+    owner: Uuid,
+) -> Result<RecoveryReport, SMSManagerError> {
+    db.immediate_transaction(|db| {
+        let requeued_messages = diesel::update(
+            messages::table.filter(
+                messages::in_flight.eq(true).and(
+                    messages::lease_expires_at
+                        .lt(Utc::now())
+                        .or(messages::leased_by.eq(SqliteUuid(owner)))
+                        .or(messages::leased_by.is_null()),
+                ),
+            ),
+        )
+        .set((
+            messages::in_flight.eq(false),
+            messages::leased_by.eq(None::<SqliteUuid>),
+            messages::lease_expires_at.eq(None::<DateTime<Utc>>),
+        ))
+        .execute(db)?;
+
+        diesel::delete(
+            producer_leases::table.filter(
+                producer_leases::lease_expires_at
+                    .lt(Utc::now())
+                    .or(producer_leases::leased_by.eq(SqliteUuid(owner))),
+            ),
+        )
+        .execute(db)?;
+
+        let interrupted_sends = interrupt_producers(db, ProducerStatus::Sending)?;
+        let interrupted_pauses = interrupt_producers(db, ProducerStatus::Paused)?;
+
+        Ok(RecoveryReport {
+            requeued_messages,
+            interrupted_sends,
+            interrupted_pauses,
+        })
+    })
+    .map_err(SMSManagerError::DbError)
+}
+
+/// Sets the producers in the given status that no server holds a lease on to INTERRUPTED
+///
+/// # Returns
+/// The ids of the interrupted producers
+fn interrupt_producers(
+    db: &mut SqliteConnection,
+    status: ProducerStatus,
+) -> QueryResult<Vec<Uuid>> {
+    let interrupted: Vec<Uuid> = producers::table
+        .select(producers::id)
+        .filter(producers::status.eq(status).and(not(exists(
+            producer_leases::table.filter(producer_leases::producer_id.eq(producers::id)),
+        ))))
+        .load(db)?;
+
+    diesel::update(
+        producers::table.filter(producers::id.eq_any(interrupted.iter().copied().map(SqliteUuid))),
+    )
+    .set(producers::status.eq(ProducerStatus::Interrupted))
+    .execute(db)?;
+
+    Ok(interrupted)
+}
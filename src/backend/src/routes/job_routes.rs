@@ -0,0 +1,7 @@
+use axum::{routing::get, Router};
+
+use crate::{controllers::job_controllers::get_job_by_id, AppState};
+
+pub fn get_job_router() -> Router<AppState> {
+    Router::new().route("/:id", get(get_job_by_id))
+}
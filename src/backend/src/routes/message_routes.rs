@@ -0,0 +1,7 @@
+use axum::{routing::get, Router};
+
+use crate::{controllers::message_controllers::get_message_by_id, AppState};
+
+pub fn get_message_router() -> Router<AppState> {
+    Router::new().route("/:id", get(get_message_by_id))
+}
@@ -1 +1,3 @@
-pub mod producer_routes;
\ No newline at end of file
+pub mod producer_routes;
+pub mod job_routes;
+pub mod message_routes;
\ No newline at end of file
@@ -3,20 +3,39 @@ use axum::{
     Router,
 };
 
-use crate::controllers::producer_controllers::{
-    activate_producer, create_producer, delete_producer, generate_messages, get_all_producers,
-    get_producer_by_id, get_producer_progress_data, update_producer,
+use crate::{
+    controllers::{
+        message_controllers::{get_message_attempts, get_producer_messages},
+        producer_controllers::{
+            activate_producer, cancel_producer, create_producer, delete_producer,
+            generate_messages, get_all_producers, get_producer_by_id, get_producer_progress_data,
+            pause_producer, resume_producer, update_producer, update_producer_rate_limit,
+            update_producer_retry_policy, update_producer_seed, update_producer_transport,
+        },
+    },
+    AppState,
 };
 
-pub fn get_producer_router(
-) -> Router<diesel::r2d2::Pool<diesel::r2d2::ConnectionManager<diesel::PgConnection>>> {
+pub fn get_producer_router() -> Router<AppState> {
     Router::new()
         .route("/", get(get_all_producers))
         .route("/create", post(create_producer))
         .route("/:id", get(get_producer_by_id))
         .route("/:id/update", post(update_producer))
+        .route("/:id/transport", post(update_producer_transport))
+        .route("/:id/retry", post(update_producer_retry_policy))
+        .route("/:id/rate-limit", post(update_producer_rate_limit))
+        .route("/:id/seed", post(update_producer_seed))
         .route("/:id/generate", post(generate_messages))
         .route("/:id/send", post(activate_producer))
+        .route("/:id/pause", post(pause_producer))
+        .route("/:id/resume", post(resume_producer))
+        .route("/:id/cancel", post(cancel_producer))
         .route("/:id/progress", get(get_producer_progress_data))
+        .route("/:id/messages", get(get_producer_messages))
+        .route(
+            "/:id/messages/:message_id/attempts",
+            get(get_message_attempts),
+        )
         .route("/:id/delete", post(delete_producer))
 }
@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::utils::{
+    error::SMSManagerError,
+    job_registry::{JobRegistry, SendJob},
+};
+
+/// Gets the send job with the supplied id
+///
+/// # Parameters
+/// - jobs: The registry of jobs started by this process
+/// - job_id: The id of the job to get
+///
+/// ### Errors if the id is not a valid uuid or the job is not found
+pub async fn get_job_by_id(
+    jobs: &JobRegistry,
+    job_id: String,
+) -> Result<Arc<SendJob>, SMSManagerError> {
+    let job_uuid = Uuid::parse_str(&job_id)
+        .map_err(|_err| SMSManagerError::InvalidEncoding("Job Id Is Invalid".to_string()))?;
+
+    jobs.get(&job_uuid).ok_or(SMSManagerError::EmptyResult)
+}
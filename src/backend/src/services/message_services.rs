@@ -0,0 +1,142 @@
+use uuid::Uuid;
+
+use crate::{
+    diesel::models::{DeliveryAttempt, Message},
+    repositories::{MessageCursor, MessagePage, MessageQuery, MessageSort, Repositories},
+    utils::{
+        error::SMSManagerError,
+        message_state::MessageState,
+        pagination::{page_size, SortOrder},
+        uuid::parse_uuid,
+    },
+};
+
+/// Which of a producers messages to list, see list_messages
+#[derive(Debug, Clone, Default)]
+pub struct MessageFilters {
+    /// Only messages in one of these states, separated by commas such as SENT,FAILED. None lists every state
+    pub states: Option<String>,
+    /// Only messages whose body contains this, ignoring case
+    pub body: Option<String>,
+    /// Only messages whose latest attempt took at least this many milliseconds
+    pub min_time_took_ms: Option<i32>,
+    /// Only messages whose latest attempt took at most this many milliseconds
+    pub max_time_took_ms: Option<i32>,
+}
+
+/// Parses the id of a message sent by the client
+///
+/// ### Errors if the id is not a uuid
+fn parse_message_id(message_id: &str) -> Result<Uuid, SMSManagerError> {
+    Uuid::parse_str(message_id)
+        .map_err(|_err| SMSManagerError::InvalidEncoding("Message Id Is Invalid".to_string()))
+}
+
+/// Gets the message with the given id, whichever producer it belongs to
+///
+/// # Parameters
+/// - repos: The repositories to read the message from
+/// - message_id: The id of the message to get
+///
+/// ### Errors if the id is invalid or there is no such message
+pub async fn get_message_by_id(
+    repos: &Repositories,
+    message_id: String,
+) -> Result<Message, SMSManagerError> {
+    let message_uuid = parse_message_id(&message_id)?;
+
+    repos.messages.find(message_uuid).await
+}
+
+/// Gets a page of the producers messages, filtered and sorted, see MessageRepository::list_page
+///
+/// # Parameters
+/// - repos: The repositories to read the messages from
+/// - producer_id: The id of the producer whose messages to list
+/// - limit: The most messages in the page, None takes DEFAULT_PAGE_SIZE
+/// - cursor: The next_cursor of the previous page, None starts from the first message
+/// - filters: Which of the messages to list
+/// - sort: What the messages are sorted by
+/// - order: Which way the messages are sorted
+///
+/// ### Errors if the producer does not exist, the limit is out of range, a state or the cursor is invalid, the cursor was taken from a listing with another sort, the time range is empty, or the query fails
+pub async fn list_messages(
+    repos: &Repositories,
+    producer_id: String,
+    limit: Option<i64>,
+    cursor: Option<String>,
+    filters: MessageFilters,
+    sort: MessageSort,
+    order: SortOrder,
+) -> Result<MessagePage, SMSManagerError> {
+    let producer_uuid = parse_uuid(&producer_id)?;
+    let limit = page_size(limit)?;
+
+    let states = filters
+        .states
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|state| !state.is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<MessageState>, SMSManagerError>>()?;
+
+    if let (Some(min_time_took_ms), Some(max_time_took_ms)) =
+        (filters.min_time_took_ms, filters.max_time_took_ms)
+    {
+        if min_time_took_ms > max_time_took_ms {
+            return Err(SMSManagerError::InvalidEncoding(
+                "min_time_took_ms can not be more than max_time_took_ms".to_string(),
+            ));
+        }
+    }
+
+    let after = cursor
+        .map(|cursor| cursor.parse::<MessageCursor>())
+        .transpose()?;
+    if after.as_ref().is_some_and(|after| after.sort() != sort) {
+        return Err(SMSManagerError::InvalidEncoding(
+            "Cursor was taken from a listing with another sort".to_string(),
+        ));
+    }
+
+    // A producer without messages and a producer that does not exist should not look the same
+    repos.producers.find(producer_uuid).await?;
+
+    repos
+        .messages
+        .list_page(MessageQuery {
+            producer_id: producer_uuid,
+            states,
+            body: filters.body.filter(|body| !body.is_empty()),
+            min_time_took_ms: filters.min_time_took_ms,
+            max_time_took_ms: filters.max_time_took_ms,
+            sort,
+            order,
+            limit,
+            after,
+        })
+        .await
+}
+
+/// Gets every attempt made to deliver the message, in the order they were made
+///
+/// # Parameters
+/// - repos: The repositories to read the message and its attempts from
+/// - producer_id: The id of the producer that produced the message
+/// - message_id: The id of the message to get the attempts of
+///
+/// ### Errors if either id is invalid or the producer has no message with the given id
+pub async fn get_message_attempts(
+    repos: &Repositories,
+    producer_id: String,
+    message_id: String,
+) -> Result<Vec<DeliveryAttempt>, SMSManagerError> {
+    let producer_uuid = parse_uuid(&producer_id)?;
+    let message_uuid = parse_message_id(&message_id)?;
+
+    repos
+        .messages
+        .find_attempts(producer_uuid, message_uuid)
+        .await
+}
@@ -1 +1,4 @@
 pub mod producer_services;
+pub mod job_services;
+pub mod message_services;
+pub mod recovery_services;
\ No newline at end of file
@@ -1,35 +1,31 @@
-use std::{collections::VecDeque, sync::Arc};
+use std::sync::Arc;
 
+use uuid::Uuid;
 
-use diesel::{
-    dsl::insert_into,
-    query_dsl::methods::{FilterDsl, FindDsl},
-    BoolExpressionMethods, ExpressionMethods, RunQueryDsl,
-};
-use tokio::sync::Mutex;
-
-use crate::utils::message_utils::{generate_fake_messages, get_producer_info_from_messages};
-use crate::utils::sender::send_messages;
+use crate::services::recovery_services::spawn_lease_renewal;
+use crate::utils::message_utils::FakeMessageChunks;
+use crate::transports::TransportConfig;
+use crate::utils::rate_limiter::RateLimit;
+use crate::utils::retry_policy::RetryPolicy;
+use crate::utils::sender::{send_messages, DeliveryPipeline};
+use crate::utils::work_queue::{ClaimingWorkQueue, DEFAULT_CLAIM_BATCH_SIZE};
 use crate::{
-    diesel::{
-        models::{Message, NewProducer, Producer},
-        schema::{
-            messages::{dsl::messages, produced_by, sent},
-            producers::dsl::*,
-        },
-    },
+    diesel::models::{NewProducer, Producer, ProducerChanges},
+    repositories::{ProducerCursor, ProducerPage, ProducerQuery, ProducerSort, Repositories},
     transformers::producer_transformer::ProgressData,
     utils::{
         error::SMSManagerError,
+        job_registry::{JobRegistry, JobState, SendJob},
+        pagination::{page_size, SortOrder},
+        producer_status::{ProducerAction, ProducerStatus},
         uuid::parse_uuid,
     },
-    Database, PoolHandle,
 };
 
 /// Creates a producer in the database with the provided options. Sets the status to INACTIVE
 ///
 /// # Params
-/// - db: The database connection to make the request on
+/// - repos: The repositories to store the producer in
 /// - new_name: The name to assign the producer
 /// - new_number_messages: The number of messages this producer will generate when calling generate_messages
 /// - new_average_send_delay: The average time in seconds it will take for an individual sender to send a message
@@ -45,7 +41,7 @@ use crate::{
 /// takes on average 20 seconds to send each message and fails to send a message 10% of the time.
 /// When sending messages it will use the number of available cores on the machine.
 pub async fn create_producer(
-    db: &mut Database,
+    repos: &Repositories,
     new_name: String,
     new_number_messages: i32,
     new_average_send_delay: i32,
@@ -71,26 +67,23 @@ pub async fn create_producer(
         average_send_delay: new_average_send_delay,
         failure_rate: new_failure_rate,
         num_senders: senders,
-        status: "INACTIVE".to_string(),
+        status: ProducerStatus::Inactive,
     };
 
-    diesel::insert_into(producers)
-        .values(new_producer)
-        .get_result(db)
-        .map_err(SMSManagerError::DbError)
+    repos.producers.insert(new_producer).await
 }
 
 /// Updates the producer with the given id to have the provided values
 ///
 /// # Params
-/// - db: The database connection to make the request on
+/// - repos: The repositories to store the producer in
 /// - new_name: The name to assign the producer
 /// - new_number_messages: The number of messages this producer will generate when calling generate_messages
 /// - new_average_send_delay: The average time in seconds it will take for an individual sender to send a message
 /// - new_failure_rate: The average rate at which the senders will fail to send a message as a percentage from 0 - 100
 /// - senders: An optional number of senders to initialize when activating the producer, null indicates that it will use the number of cores available on the machine
 ///
-/// ### Errors if producer doesn't exist, database update fails, failure rate is not between 0 and 100, or number of messages or send delay < 1
+/// ### Errors if producer doesn't exist, its status does not allow updates, database update fails, failure rate is not between 0 and 100, or number of messages or send delay < 1
 ///
 /// # Example
 /// create_producer(db, "aProducerId", "New Producer", 100, 20, 10);
@@ -99,7 +92,7 @@ pub async fn create_producer(
 /// take on average 20 seconds to send each message and fail to send a message 10% of the time.
 /// When sending messages it will use the number of available cores on the machine.
 pub async fn update_producer(
-    db: &mut Database,
+    repos: &Repositories,
     producer_id: String,
     new_name: String,
     new_number_messages: i32,
@@ -122,160 +115,332 @@ pub async fn update_producer(
 
     let producer_uuid = parse_uuid(&producer_id)?;
 
-    diesel::update(producers.find(producer_uuid))
-        .set((
-            name.eq(new_name),
-            number_messages.eq(new_number_messages),
-            average_send_delay.eq(new_average_send_delay),
-            num_senders.eq(senders),
-            failure_rate.eq(new_failure_rate),
-        ))
-        .get_result(db)
-        .map_err(SMSManagerError::DbError)
+    repos
+        .producers
+        .update(
+            producer_uuid,
+            ProducerChanges {
+                name: Some(new_name),
+                number_messages: Some(new_number_messages),
+                average_send_delay: Some(new_average_send_delay),
+                num_senders: Some(senders),
+                failure_rate: Some(new_failure_rate),
+                ..ProducerChanges::default()
+            },
+        )
+        .await
 }
 
-/// Gets all the producers in the database
+/// Sets the transport the producer delivers its messages with
+///
+/// # Params
+/// - repos: The repositories to store the producer in
+/// - producer_id: The id of the producer to update
+/// - new_transport: The transport to deliver the producers messages with
+///
+/// ### Errors if producer doesn't exist, its status does not allow updates, the transport is invalid or the database update fails
+pub async fn update_producer_transport(
+    repos: &Repositories,
+    producer_id: String,
+    new_transport: TransportConfig,
+) -> Result<Producer, SMSManagerError> {
+    let producer_uuid = parse_uuid(&producer_id)?;
+
+    new_transport.validate()?;
+
+    let transport_value = serde_json::to_value(new_transport).map_err(|err| {
+        SMSManagerError::InvalidEncoding(format!("Could not encode transport: {}", err))
+    })?;
+
+    repos
+        .producers
+        .update(
+            producer_uuid,
+            ProducerChanges {
+                transport: Some(transport_value),
+                ..ProducerChanges::default()
+            },
+        )
+        .await
+}
+
+/// Sets the policy the producer retries failed messages with
+///
+/// # Params
+/// - repos: The repositories to store the producer in
+/// - producer_id: The id of the producer to update
+/// - new_retry_policy: The policy to retry the producers failed messages with
+///
+/// ### Errors if producer doesn't exist, its status does not allow updates, the policy is invalid or the database update fails
+pub async fn update_producer_retry_policy(
+    repos: &Repositories,
+    producer_id: String,
+    new_retry_policy: RetryPolicy,
+) -> Result<Producer, SMSManagerError> {
+    let producer_uuid = parse_uuid(&producer_id)?;
+
+    new_retry_policy.validate()?;
+
+    let retry_policy_value = serde_json::to_value(new_retry_policy).map_err(|err| {
+        SMSManagerError::InvalidEncoding(format!("Could not encode retry policy: {}", err))
+    })?;
+
+    repos
+        .producers
+        .update(
+            producer_uuid,
+            ProducerChanges {
+                retry_policy: Some(retry_policy_value),
+                ..ProducerChanges::default()
+            },
+        )
+        .await
+}
+
+/// Sets or clears the rate limit the producer delivers its messages under
+///
+/// # Params
+/// - repos: The repositories to store the producer in
+/// - producer_id: The id of the producer to update
+/// - new_rate_limit: The most attempts the producer can make, None lets it send as fast as its senders can
+///
+/// ### Errors if producer doesn't exist, its status does not allow updates, the limit is invalid or the database update fails
+pub async fn update_producer_rate_limit(
+    repos: &Repositories,
+    producer_id: String,
+    new_rate_limit: Option<RateLimit>,
+) -> Result<Producer, SMSManagerError> {
+    let producer_uuid = parse_uuid(&producer_id)?;
+
+    let rate_limit_value = match new_rate_limit {
+        Some(new_rate_limit) => {
+            new_rate_limit.validate()?;
+            Some(serde_json::to_value(new_rate_limit).map_err(|err| {
+                SMSManagerError::InvalidEncoding(format!("Could not encode rate limit: {}", err))
+            })?)
+        }
+        None => None,
+    };
+
+    repos
+        .producers
+        .update(
+            producer_uuid,
+            ProducerChanges {
+                rate_limit: Some(rate_limit_value),
+                ..ProducerChanges::default()
+            },
+        )
+        .await
+}
+
+/// Sets or clears the seed the producer generates and sends its messages with when a call is not given its own
+///
+/// # Params
+/// - repos: The repositories to store the producer in
+/// - producer_id: The id of the producer to update
+/// - new_seed: The seed to use, None draws different values every run
+///
+/// ### Errors if producer doesn't exist, its status does not allow updates or the database update fails
+pub async fn update_producer_seed(
+    repos: &Repositories,
+    producer_id: String,
+    new_seed: Option<i64>,
+) -> Result<Producer, SMSManagerError> {
+    let producer_uuid = parse_uuid(&producer_id)?;
+
+    repos
+        .producers
+        .update(
+            producer_uuid,
+            ProducerChanges {
+                seed: Some(new_seed),
+                ..ProducerChanges::default()
+            },
+        )
+        .await
+}
+
+/// Gets a page of the producers, filtered and sorted, see ProducerRepository::list_page
 ///
 /// # Parameters
-/// - db: The database connection to make the request with
+/// - repos: The repositories to read the producers from
+/// - limit: The most producers in the page, None takes DEFAULT_PAGE_SIZE
+/// - cursor: The next_cursor of the previous page, None starts from the first producer
+/// - statuses: Only producers in one of these statuses, separated by commas such as SENDING,PAUSED. None lists every status
+/// - name: Only producers whose name contains this, ignoring case
+/// - sort: What the producers are sorted by
+/// - order: Which way the producers are sorted
 ///
-/// ### Errors if query fails
-pub async fn get_all_producers(db: &mut Database) -> Result<Vec<Producer>, SMSManagerError> {
-    producers
-        .load::<Producer>(db)
-        .map_err(SMSManagerError::DbError)
+/// ### Errors if the limit is out of range, a status or the cursor is invalid, the cursor was taken from a listing with another sort, or the query fails
+pub async fn list_producers(
+    repos: &Repositories,
+    limit: Option<i64>,
+    cursor: Option<String>,
+    statuses: Option<String>,
+    name: Option<String>,
+    sort: ProducerSort,
+    order: SortOrder,
+) -> Result<ProducerPage, SMSManagerError> {
+    let limit = page_size(limit)?;
+
+    let statuses = statuses
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|status| !status.is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<ProducerStatus>, SMSManagerError>>()?;
+
+    let after = cursor
+        .map(|cursor| cursor.parse::<ProducerCursor>())
+        .transpose()?;
+    if after.as_ref().is_some_and(|after| after.sort() != sort) {
+        return Err(SMSManagerError::InvalidEncoding(
+            "Cursor was taken from a listing with another sort".to_string(),
+        ));
+    }
+
+    repos
+        .producers
+        .list_page(ProducerQuery {
+            statuses,
+            name: name.filter(|name| !name.is_empty()),
+            sort,
+            order,
+            limit,
+            after,
+        })
+        .await
 }
 
 /// Gets the producer with the supplied id from the database
 ///
 /// # Paramters
-/// - db: The database connection to make requests with
+/// - repos: The repositories to read and store the producer in
 /// - producer_id: The id of the producer to get
 ///
 /// ### Errors if producer is not found
 pub async fn get_producer_by_id(
-    db: &mut Database,
+    repos: &Repositories,
     producer_id: String,
 ) -> Result<Producer, SMSManagerError> {
     let producer_uuid = parse_uuid(&producer_id)?;
 
-    let found_producers: Vec<Producer> = producers
-        .filter(id.eq(producer_uuid))
-        .load(db)
-        .map_err(SMSManagerError::DbError)?;
-
-    if let Some(producer) = found_producers.first() {
-        return Ok(producer.clone());
-    }
-    Err(SMSManagerError::EmptyResult)
+    repos.producers.find(producer_uuid).await
 }
 
 /// Gets the progress data for the producer with the given producer id
 ///
 /// # Paramters
-/// - db: The database connection to make requests with
+/// - repos: The repositories to read and store the producer in
 /// - producer_id: The id of the producer to get the progress data for
 ///
 /// ### Errors if producer is not found
 pub async fn get_producer_progress_data(
-    db: &mut Database,
+    repos: &Repositories,
     producer_id: String,
 ) -> Result<ProgressData, SMSManagerError> {
-    let producer = get_producer_by_id(db, producer_id).await?;
-
-    let found_messages: Vec<Message> = messages
-        .filter(produced_by.eq(producer.id))
-        .load(db)
-        .map_err(SMSManagerError::DbError)?;
-
-    let (
-        number_messages_created,
-        number_messages_failed,
-        message_times,
-        number_messages_sent,
-        average_message_time,
-    ) = get_producer_info_from_messages(found_messages);
-
-    Ok(ProgressData {
-        number_messages_created,
-        number_messages_sent,
-        number_messages_failed,
-        average_message_time,
-        message_times,
-    })
+    let producer_uuid = parse_uuid(&producer_id)?;
+
+    let producer = repos.producers.find(producer_uuid).await?;
+    let stats = repos.messages.progress_stats(producer.id).await?;
+
+    Ok(ProgressData::from(stats))
 }
 
+/// How many messages are generated and written to the database at a time
+pub const GENERATION_CHUNK_SIZE: usize = 10_000;
+
 /// Generates the messages for the producer.
 /// Number of messages generated is set on the producers number_messages field
-/// Sets the producers status to generating prior to message creation, sets it to generated afterwards
+/// The messages are generated and written a chunk at a time, all or none of them so a failed generation leaves no messages behind, see MessageRepository::insert_generated
+/// Sets the producers status to generating prior to message creation and counts the messages written in generated_messages as each chunk is written, sets it to generated afterwards
+/// If generating fails the producer is put back the way it was
 ///
 /// # Paramters
-/// - db: The database connection to make requests with
+/// - repos: The repositories to read the producer from and write the messages to
 /// - producer_id: The id of the producer to generate the messages for
+/// - generation_seed: Generates the same message bodies every time it is given, None falls back to the producers seed
 ///
-/// ### Errors if producer is not found, number of messages is invalid, or updating statuses or inserting messages fails
+/// ### Errors if producer is not found, its status does not allow generating, number of messages is invalid, or updating statuses or inserting messages fails
 pub async fn generate_messages(
-    db: &mut Database,
+    repos: &Repositories,
     producer_id: String,
+    generation_seed: Option<i64>,
 ) -> Result<i32, SMSManagerError> {
-    let producer = get_producer_by_id(db, producer_id).await?;
-
-    let message_array = generate_fake_messages(producer.number_messages, producer.id)?;
+    let producer_uuid = parse_uuid(&producer_id)?;
 
-    println!("Inserting messages: {}", message_array.len());
+    let producer = repos.producers.find(producer_uuid).await?;
+    // Fails early on a status that can never be generated, insert_generated checks it again as it moves the producer to GENERATING
+    let generating = producer.status.apply(ProducerAction::Generate)?;
+    let finished = generating.apply(ProducerAction::FinishGenerating)?;
 
-    diesel::update(producers.find(producer.id))
-        .set(status.eq("GENERATING"))
-        .execute(db)
-        .map_err(SMSManagerError::DbError)?;
+    let chunks = FakeMessageChunks::new(
+        producer.number_messages,
+        producer.id,
+        generation_seed.or(producer.seed),
+        GENERATION_CHUNK_SIZE,
+    )?;
 
-    // Batch Insert
-    insert_into(messages)
-        .values(message_array)
-        .execute(db)
-        .map_err(SMSManagerError::DbError)?;
+    println!("Inserting messages: {}", producer.number_messages);
 
-    diesel::update(producers.find(producer.id))
-        .set(status.eq("GENERATED"))
-        .execute(db)
-        .map_err(SMSManagerError::DbError)?;
+    let number_messages = producer.number_messages;
+    repos
+        .messages
+        .insert_generated(producer, Box::new(chunks), generating, finished)
+        .await?;
 
-    Ok(producer.number_messages)
+    Ok(number_messages)
 }
 
-/// Sends the pending messages for the producer with the given id
-/// First queries all messages that have not been sent yet, then calculates the number of threads to use for sending the messages.
+/// Starts sending the pending messages for the producer with the given id as a background job and returns the job immediately
+/// First counts the messages that have not been sent yet and are not held by another send, then calculates the number of threads to use for sending the messages.
 /// The calculation first checks if the producer configured number of threads is a valid number of threads (between 1 and the max number of cpus) and clamps it if not
-/// Then the producers status is updated to SENDING
-/// Then a multiple producer single consumer structure with senders sending the messages and a database updater updating the sent messages is used. This ensures that we maximize how fast we can send out messages, while at the same time not overloading our database resources and allowing availability for queries to the database to be made
-/// Then the producers status is updated to EMPTY
+/// Then the producer is claimed, see claim_producer, which updates its status to SENDING and registers the job while no other claim of the producer can run
+/// Then a task is spawned that uses a multiple producer single consumer structure with senders sending the messages and a database updater updating the sent messages. This ensures that we maximize how fast we can send out messages, while at the same time not overloading our database resources and allowing availability for queries to the database to be made
+/// The senders claim the messages from the database a batch at a time, see work_queue, and every claimed message is leased to this process while the job runs so a restart can put it back in the queue, see recovery_services
+/// A producer that another server is already sending can be sent by this server too, both work through the same messages without sending any twice
+/// Once the queue is consumed the task updates the producers status to EMPTY when no unsent messages are left, or CANCELLED if the job was cancelled, and marks the job as completed.
+/// The task is not tied to the caller, so it keeps running if the request that started it goes away
 ///
 /// # Paramters
-/// - db: The database connection to make requests with
+/// - repos: The repositories the job reads and writes the producer and its messages through
+/// - jobs: The registry to track the started job in
 /// - producer_id: The id of the producer to send the messages of
+/// - send_seed: Gives each message the same simulated outcome every time it is given, None falls back to the producers seed
 ///
-/// ### Errors if producer is not found, finding the producers messages fails, or updating statuses or sending messages fails
+/// ### Errors if producer is not found, its status does not allow sending, this server is already sending it while a send on another server is joined instead, its transport is misconfigured, finding the producers messages fails, or updating the status fails
 pub async fn activate_producer(
-    pool: Arc<PoolHandle>,
+    repos: &Repositories,
+    jobs: &JobRegistry,
     producer_id: String,
-) -> Result<String, SMSManagerError> {
+    send_seed: Option<i64>,
+) -> Result<Arc<SendJob>, SMSManagerError> {
     let producer_uuid = parse_uuid(&producer_id)?;
-    let mut db = pool.get()?;
 
-    let producer = get_producer_by_id(&mut db, producer_id).await?;
-
-    if producer.status == "SENDING" {
-        return Err(SMSManagerError::GeneralException(
-            "Already sending messages".to_string(),
-        ));
-    }
+    let mut producer = get_producer_by_id(repos, producer_id).await?;
+    producer.seed = send_seed.or(producer.seed);
 
-    let found_messages: Vec<Message> = messages
-        .filter(produced_by.eq(producer_uuid).and(sent.eq(false)))
-        .load(&mut db)
-        .map_err(SMSManagerError::DbError)?;
+    // Fails early on a status that can never be sent, claim_producer checks it again while the producer is claimed
+    producer.status.apply(ProducerAction::Send)?;
 
-    let queue = Arc::new(Mutex::new(VecDeque::from(found_messages)));
+    let mut pipeline = DeliveryPipeline::for_producer(&producer)?;
+    if let Some(global_rate_limiter) = jobs.global_rate_limiter(&pipeline.clock) {
+        pipeline.rate_limiters.push(global_rate_limiter);
+    }
+    pipeline.updater = jobs.updater_config();
+    let pipeline = Arc::new(pipeline);
+
+    // Claiming a message leases it and marks it as in flight, so it can be recovered if the server stops before it is sent
+    let lease_owner = jobs.instance_id();
+    let found_messages_count = repos.messages.count_claimable(producer_uuid).await?;
+    let queue = Arc::new(ClaimingWorkQueue::new(
+        repos.messages.clone(),
+        producer_uuid,
+        lease_owner,
+        DEFAULT_CLAIM_BATCH_SIZE,
+    ));
 
     // Determine the number of threads to use (defaults to number of cores)
     let mut num_threads = match producer.num_senders {
@@ -291,40 +456,324 @@ pub async fn activate_producer(
 
     println!("Using {} threads.", num_threads);
 
-    diesel::update(producers.find(producer_uuid))
-        .set(status.eq("SENDING"))
-        .execute(&mut db)
-        .map_err(SMSManagerError::DbError)?;
+    let job = Arc::new(SendJob::new(producer_uuid, found_messages_count as usize));
+    if let Err(err) = claim_producer(repos, jobs, producer_uuid, job.clone()).await {
+        // The job may have been registered before the status failed to be written
+        job.fail(err.to_string());
+        return Err(err);
+    }
+
+    let repos = repos.clone();
+    let background_job = job.clone();
+    tokio::spawn(async move {
+        let lease_renewal = spawn_lease_renewal(repos.messages.clone(), producer_uuid, lease_owner);
+
+        let sent = send_messages(
+            queue,
+            repos.messages.clone(),
+            pipeline,
+            num_threads,
+            background_job.clone(),
+        )
+        .await;
+
+        lease_renewal.abort();
+
+        let cancelled = matches!(background_job.state(), JobState::Cancelled);
+
+        // The messages a cancelled or failed send did not get to go back in the queue for the next send
+        let released = repos
+            .messages
+            .release_leases(producer_uuid, lease_owner)
+            .await;
+
+        let result = match (sent, released) {
+            (Ok(_), Ok(_)) => finish_send(&repos, producer_uuid, cancelled).await,
+            (Err(err), _) | (_, Err(err)) => Err(err),
+        };
+
+        match result {
+            Ok(_) => background_job.complete(),
+            Err(err) => match interrupt_send(&repos, producer_uuid).await {
+                Ok(_) => background_job.fail(err.to_string()),
+                Err(interrupt_err) => background_job.fail(format!(
+                    "{}, and the producer could not be interrupted: {}",
+                    err, interrupt_err
+                )),
+            },
+        }
+    });
+
+    Ok(job)
+}
+
+/// Claims the producer for a send on this server, setting it to SENDING, leasing it to this server and registering the job in one claim, see ProducerRepository::claim_status
+/// Concurrent activations on any server run one after the other, so a later one sees the status and job the earlier one left behind
+/// Another server sending the producer does not stop this one joining it, so a SENDING producer is only a conflict on a server that already has a running job for it
+///
+/// # Paramters
+/// - repos: The repositories to claim the producer through
+/// - jobs: The registry to track the job in
+/// - producer_uuid: The producer to claim
+/// - job: The job that will send the producers messages
+///
+/// ### Errors with a conflict if the producers status does not allow sending or this server is already sending it, or if a query fails
+async fn claim_producer(
+    repos: &Repositories,
+    jobs: &JobRegistry,
+    producer_uuid: Uuid,
+    job: Arc<SendJob>,
+) -> Result<(), SMSManagerError> {
+    let jobs = jobs.clone();
+
+    repos
+        .producers
+        .claim_status(
+            producer_uuid,
+            jobs.instance_id(),
+            Box::new(move |current| {
+                let sending = current.apply(ProducerAction::Send)?;
+
+                if jobs.get_active_for_producer(&producer_uuid).is_some() {
+                    return Err(SMSManagerError::Conflict(format!(
+                        "Can not move the producer from {} to {}, this server is already sending it",
+                        current, sending
+                    )));
+                }
+
+                jobs.insert(job);
+                Ok(sending)
+            }),
+        )
+        .await
+        .map(|_| ())
+}
+
+/// Sets the status of the producer once this servers send has stopped
+/// A send that runs out of messages while another server is still sending the last of them leaves the producer SENDING, that server sets it to EMPTY when it is done
+///
+/// # Paramters
+/// - repos: The repositories to read the messages and write the producer through
+/// - producer_uuid: The producer whose send stopped
+/// - cancelled: Whether the send was cancelled rather than running out of messages
+///
+/// ### Errors if messages no send holds were left unsent, or counting the unsent messages or updating the status fails
+async fn finish_send(
+    repos: &Repositories,
+    producer_uuid: Uuid,
+    cancelled: bool,
+) -> Result<(), SMSManagerError> {
+    let action = if cancelled {
+        ProducerAction::Cancel
+    } else {
+        if repos.messages.count_unsent(producer_uuid).await? > 0 {
+            let left_over = repos.messages.count_claimable(producer_uuid).await?;
+            if left_over > 0 {
+                return Err(SMSManagerError::GeneralException(format!(
+                    "Sending stopped with {} messages left over",
+                    left_over
+                )));
+            }
+            return Ok(());
+        }
+        ProducerAction::FinishSending
+    };
 
-    send_messages(queue, pool, &producer, num_threads).await;
+    repos
+        .producers
+        .transition(producer_uuid, action)
+        .await
+        .map(|_| ())
+}
 
-    diesel::update(producers.find(producer_uuid))
-        .set(status.eq("EMPTY"))
-        .execute(&mut db)
-        .map_err(SMSManagerError::DbError)?;
+/// Sets the producer to INTERRUPTED once this servers send has failed, so it can be sent again to pick up where it left off
+/// A producer whose messages are still leased by another server is left SENDING, that server sets its status when it is done
+///
+/// # Paramters
+/// - repos: The repositories to read the messages and write the producer through
+/// - producer_uuid: The producer whose send failed
+///
+/// ### Errors if counting the unsent messages or updating the status fails
+async fn interrupt_send(repos: &Repositories, producer_uuid: Uuid) -> Result<(), SMSManagerError> {
+    let unsent = repos.messages.count_unsent(producer_uuid).await?;
+    if repos.messages.count_claimable(producer_uuid).await? < unsent {
+        return Ok(());
+    }
 
-    Ok("All items processed.".to_string())
+    repos
+        .producers
+        .transition(producer_uuid, ProducerAction::Interrupt)
+        .await
+        .map(|_| ())
+}
+
+/// Pauses the in progress send of the producer with the given id and sets its status to PAUSED
+/// Messages already being sent are allowed to finish, the rest stay queued until the send is resumed
+///
+/// # Paramters
+/// - repos: The repositories to read and store the producer in
+/// - jobs: The registry to find the producers send job in
+/// - producer_id: The id of the producer to pause
+///
+/// ### Errors if producer is not found, the producer is not sending, the send is not running, its status changed before it could be updated, or updating the status fails
+pub async fn pause_producer(
+    repos: &Repositories,
+    jobs: &JobRegistry,
+    producer_id: String,
+) -> Result<Arc<SendJob>, SMSManagerError> {
+    let producer_uuid = parse_uuid(&producer_id)?;
+
+    let job = get_active_job(repos, jobs, producer_uuid, ProducerAction::Pause).await?;
+
+    if !job.pause() {
+        return Err(SMSManagerError::Conflict(
+            "Only a running send can be paused".to_string(),
+        ));
+    }
+
+    // The job is only paused if the producer is as well
+    if let Err(err) = transition_send(repos, job.producer_id, ProducerAction::Pause).await {
+        job.resume();
+        return Err(err);
+    }
+
+    Ok(job)
+}
+
+/// Resumes the paused send of the producer with the given id and sets its status back to SENDING
+///
+/// # Paramters
+/// - repos: The repositories to read and store the producer in
+/// - jobs: The registry to find the producers send job in
+/// - producer_id: The id of the producer to resume
+///
+/// ### Errors if producer is not found, the producer is not sending, the send is not paused, its status changed before it could be updated, or updating the status fails
+pub async fn resume_producer(
+    repos: &Repositories,
+    jobs: &JobRegistry,
+    producer_id: String,
+) -> Result<Arc<SendJob>, SMSManagerError> {
+    let producer_uuid = parse_uuid(&producer_id)?;
+
+    let job = get_active_job(repos, jobs, producer_uuid, ProducerAction::Resume).await?;
+
+    if !job.resume() {
+        return Err(SMSManagerError::Conflict(
+            "Only a paused send can be resumed".to_string(),
+        ));
+    }
+
+    if let Err(err) = transition_send(repos, job.producer_id, ProducerAction::Resume).await {
+        job.pause();
+        return Err(err);
+    }
+
+    Ok(job)
+}
+
+/// Cancels the running or paused send of the producer with the given id and sets its status to CANCELLED
+/// Messages already being sent are allowed to finish, the rest are left with sent = false so a later send picks them up
+///
+/// # Paramters
+/// - repos: The repositories to read and store the producer in
+/// - jobs: The registry to find the producers send job in
+/// - producer_id: The id of the producer to cancel the send of
+///
+/// ### Errors if producer is not found, the producer is not sending, its status changed before it could be updated, or updating the status fails
+pub async fn cancel_producer(
+    repos: &Repositories,
+    jobs: &JobRegistry,
+    producer_id: String,
+) -> Result<Arc<SendJob>, SMSManagerError> {
+    let producer_uuid = parse_uuid(&producer_id)?;
+
+    let job = get_active_job(repos, jobs, producer_uuid, ProducerAction::Cancel).await?;
+
+    if !job.cancel() {
+        return Err(SMSManagerError::Conflict(
+            "Send has already finished".to_string(),
+        ));
+    }
+
+    // A cancelled job can not be taken back, its senders stop either way and the send finishing sets the status
+    transition_send(repos, job.producer_id, ProducerAction::Cancel).await?;
+
+    Ok(job)
+}
+
+/// Moves the producer along the transition table for an action taken on its send
+///
+/// # Paramters
+/// - repos: The repositories to store the producer in
+/// - producer_uuid: The producer whose send the action is taken on
+/// - action: The action taken on the send
+///
+/// ### Errors with the conflict of the transition table if the producers status changed since it was read so the action can no longer be taken, or if reading or updating the status fails
+async fn transition_send(
+    repos: &Repositories,
+    producer_uuid: Uuid,
+    action: ProducerAction,
+) -> Result<(), SMSManagerError> {
+    if repos.producers.transition(producer_uuid, action).await? {
+        return Ok(());
+    }
+
+    let producer = repos.producers.find(producer_uuid).await?;
+    producer.status.apply(action)?;
+
+    // The status changed and changed back before it was read again
+    Err(SMSManagerError::Conflict(format!(
+        "Can not {} the producer, its status changed while it was being updated",
+        action
+    )))
+}
+
+/// Gets the unfinished send job of the producer with the given id, for an action taken on the send
+///
+/// ### Errors if producer is not found, its status does not allow the action, or it has no unfinished send job on this server
+async fn get_active_job(
+    repos: &Repositories,
+    jobs: &JobRegistry,
+    producer_uuid: Uuid,
+    action: ProducerAction,
+) -> Result<Arc<SendJob>, SMSManagerError> {
+    let producer = repos.producers.find(producer_uuid).await?;
+
+    producer
+        .status
+        .apply(action)
+        .map_err(|err| match (producer.status, err) {
+            (ProducerStatus::Interrupted, SMSManagerError::Conflict(reason)) => {
+                SMSManagerError::Conflict(format!(
+                    "{}, sending was interrupted by a restart or a failed send so send the producer again to pick up where it left off",
+                    reason
+                ))
+            }
+            (_, err) => err,
+        })?;
+
+    jobs.get_active_for_producer(&producer.id)
+        .ok_or(SMSManagerError::Conflict(format!(
+            "Can not {} the producer, it is {} on another server",
+            action, producer.status
+        )))
 }
 
 /// Deletes the producer and messages with the given id
 ///
 /// # Paramters
-/// - db: The database connection to make requests with
+/// - repos: The repositories to delete the producer and its messages from
 /// - producer_id: The id of the producer to delete
 ///
-/// ### Errors if producer is not found, deleting the messages fails, or deleting the producer fails
+/// ### Errors if producer is not found, its status does not allow deleting, deleting the messages fails, or deleting the producer fails
 pub async fn delete_producer(
-    db: &mut Database,
+    repos: &Repositories,
     producer_id: String,
 ) -> Result<String, SMSManagerError> {
-    let producer = get_producer_by_id(db, producer_id).await?;
-
-    let _ = diesel::delete(messages)
-        .filter(produced_by.eq(producer.id))
-        .execute(db);
-    let _ = diesel::delete(producers)
-        .filter(id.eq(producer.id))
-        .execute(db);
+    let producer_uuid = parse_uuid(&producer_id)?;
+
+    // The status is checked as the producer is deleted, so a send claimed in the meantime is not deleted from under it
+    repos.producers.delete(producer_uuid).await?;
 
     Ok("Successfully deleted producer".to_string())
 }
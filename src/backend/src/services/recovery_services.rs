@@ -0,0 +1,54 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::{
+    repositories::{MessageRepository, RecoveryReport, Repositories},
+    utils::error::SMSManagerError,
+};
+
+/// How often a running send renews the leases on its messages, well within LEASE_DURATION so a slow renewal does not lose them
+pub const LEASE_RENEWAL_INTERVAL: Duration = Duration::from_secs(20);
+
+/// Renews the leases on the producer and its messages every LEASE_RENEWAL_INTERVAL until the returned task is aborted
+///
+/// # Parameters
+/// - messages: The repository to renew the leases in
+/// - producer_uuid: The producer whose messages are being sent
+/// - owner: The server holding the leases
+pub fn spawn_lease_renewal(
+    messages: Arc<dyn MessageRepository>,
+    producer_uuid: Uuid,
+    owner: Uuid,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(LEASE_RENEWAL_INTERVAL).await;
+
+            if let Err(err) = messages.renew_leases(producer_uuid, owner).await {
+                eprintln!(
+                    "Failed to renew the message leases of producer {}: {}",
+                    producer_uuid, err
+                );
+            }
+        }
+    })
+}
+
+/// Recovers the sends that were running when the server stopped
+/// Every in flight message whose lease has expired, or that was leased by the owner before it stopped, is put back in its producers queue
+/// Producers that were SENDING or PAUSED and that no server holds a live lease on are set to INTERRUPTED, so they can be sent again and pick up where they left off
+/// Producers still leased by another live server are left alone, as that server is still sending them even while it has nothing in flight between batches
+///
+/// # Parameters
+/// - repos: The repositories to recover the sends in
+/// - owner: The server that is starting up
+///
+/// ### Errors if any of the updates fail, in which case nothing is changed
+pub async fn recover_interrupted_sends(
+    repos: &Repositories,
+    owner: Uuid,
+) -> Result<RecoveryReport, SMSManagerError> {
+    repos.messages.recover(owner).await
+}
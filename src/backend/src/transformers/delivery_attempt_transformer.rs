@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+// The struct defining the delivery attempt format sent to the frontend
+#[derive(Serialize, Debug)]
+pub struct PublicDeliveryAttempt {
+    pub message_id: String,
+    pub attempt_number: i32,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub outcome: String,
+    pub error_code: Option<String>,
+    pub error_reason: Option<String>,
+    pub worker_id: i32,
+}
+
+/// convert the diesel type to the client type for JSON encoding
+impl From<crate::diesel::models::DeliveryAttempt> for PublicDeliveryAttempt {
+    fn from(value: crate::diesel::models::DeliveryAttempt) -> Self {
+        PublicDeliveryAttempt {
+            message_id: value.message_id.to_string(),
+            attempt_number: value.attempt_number,
+            started_at: value.started_at,
+            finished_at: value.finished_at,
+            outcome: value.outcome,
+            error_code: value.error_code,
+            error_reason: value.error_reason,
+            worker_id: value.worker_id,
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::utils::job_registry::SendJob;
+
+// The struct defining the send job format sent to the frontend
+#[derive(Serialize, Debug)]
+pub struct PublicJob {
+    pub id: String,
+    pub producer_id: String,
+    pub state: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub number_messages: usize,
+    pub number_messages_sent: usize,
+    pub number_messages_failed: usize,
+    pub error: Option<String>,
+}
+
+/// snapshot the live job into the client type for JSON encoding
+impl From<&SendJob> for PublicJob {
+    fn from(value: &SendJob) -> Self {
+        PublicJob {
+            id: value.id.to_string(),
+            producer_id: value.producer_id.to_string(),
+            state: value.state().to_string(),
+            started_at: value.started_at,
+            finished_at: value.finished_at(),
+            number_messages: value.number_messages,
+            number_messages_sent: value.number_sent(),
+            number_messages_failed: value.number_failed(),
+            error: value.error(),
+        }
+    }
+}
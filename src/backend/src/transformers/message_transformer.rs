@@ -0,0 +1,68 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::{repositories::MessagePage, utils::message_state::MessageState};
+
+// The struct defining the message format sent to the frontend
+#[derive(Serialize, Debug)]
+pub struct PublicMessage {
+    pub id: String,
+    pub produced_by: String,
+    pub message_body: String,
+    /// PENDING, SENT or FAILED
+    pub state: MessageState,
+    pub sent: bool,
+    pub failed: bool,
+    pub attempts: i32,
+    /// Whether a send holds the message right now
+    pub in_flight: bool,
+    /// The id the provider gave the message when it was delivered
+    pub provider_message_id: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub time_took_ms: Option<i32>,
+}
+
+// The struct defining a page of a message listing sent to the frontend
+#[derive(Serialize, Debug)]
+pub struct PublicMessagePage {
+    pub messages: Vec<PublicMessage>,
+    /// How many messages pass the filters, across every page
+    pub total: i64,
+    /// Passed back as the cursor to get the next page, None on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// convert the diesel type to the client type for JSON encoding
+impl From<crate::diesel::models::Message> for PublicMessage {
+    fn from(value: crate::diesel::models::Message) -> Self {
+        PublicMessage {
+            state: MessageState::of(&value),
+            id: value.id.to_string(),
+            produced_by: value.produced_by.to_string(),
+            message_body: value.message_body,
+            sent: value.sent,
+            failed: value.failed,
+            attempts: value.attempts,
+            in_flight: value.in_flight,
+            provider_message_id: value.provider_message_id,
+            started_at: value.started_at,
+            finished_at: value.finished_at,
+            time_took_ms: value.time_took_ms,
+        }
+    }
+}
+
+impl From<MessagePage> for PublicMessagePage {
+    fn from(value: MessagePage) -> Self {
+        PublicMessagePage {
+            messages: value
+                .messages
+                .into_iter()
+                .map(PublicMessage::from)
+                .collect(),
+            total: value.total,
+            next_cursor: value.next_cursor.map(|cursor| cursor.to_string()),
+        }
+    }
+}
@@ -1 +1,4 @@
-pub mod producer_transformer;
\ No newline at end of file
+pub mod producer_transformer;
+pub mod job_transformer;
+pub mod delivery_attempt_transformer;
+pub mod message_transformer;
\ No newline at end of file
@@ -1,5 +1,14 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 
+use crate::{
+    repositories::{MessageTimeBucket, ProducerPage, ProgressStats},
+    transports::TransportConfig,
+    utils::{producer_status::ProducerStatus, rate_limiter::RateLimit, retry_policy::RetryPolicy},
+};
+
 // The struct defining the producer format sent to the frontend
 #[derive(Serialize, Debug)]
 pub struct PublicProducer {
@@ -9,7 +18,29 @@ pub struct PublicProducer {
     pub average_send_delay: i32,
     pub failure_rate: i32,
     pub num_senders: Option<i32>,
+    /// Includes how far generation has got while generating, such as GENERATING 20000/100000
     pub status: String,
+    pub transport: String,
+    pub retry_policy: RetryPolicy,
+    /// None when the producer sends as fast as its senders can
+    pub rate_limit: Option<RateLimit>,
+    /// The seed messages are generated and sent with by default, None draws different values every run
+    pub seed: Option<i64>,
+    /// How many messages have been written so far while generating, or by the last generation once it is done
+    pub generated_messages: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+// The struct defining a page of the producer listing sent to the frontend
+#[derive(Serialize, Debug)]
+pub struct PublicProducerPage {
+    pub producers: Vec<PublicProducer>,
+    /// How many producers pass the filters, across every page
+    pub total: i64,
+    /// How many producers whose name passes the name filter are in each status, whatever the status filter is
+    pub status_counts: BTreeMap<String, i64>,
+    /// Passed back as the cursor to get the next page, None on the last page
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Serialize, Debug)]
@@ -17,8 +48,15 @@ pub struct ProgressData {
     pub number_messages_created: i32,
     pub number_messages_sent: i32,
     pub number_messages_failed: i32,
+    /// Messages that needed more than one attempt
+    pub number_messages_retried: i32,
+    /// Messages that needed more than one attempt and were delivered in the end
+    pub number_messages_recovered: i32,
+    /// In whole seconds, kept for clients that predate average_message_time_ms
     pub average_message_time: i32,
-    pub message_times: Vec<i32>,
+    pub average_message_time_ms: f64,
+    /// How many sent messages took each range of times, from the quickest to the slowest
+    pub message_time_histogram: Vec<MessageTimeBucket>,
 }
 
 #[derive(Serialize, Debug)]
@@ -37,7 +75,59 @@ impl From<crate::diesel::models::Producer> for PublicProducer {
             number_messages: value.number_messages,
             num_senders: value.num_senders,
             failure_rate: value.failure_rate,
-            status: value.status
+            transport: TransportConfig::try_from(&value.transport)
+                .map(|config| config.kind().to_string())
+                .unwrap_or("UNKNOWN".to_string()),
+            retry_policy: RetryPolicy::try_from(&value.retry_policy).unwrap_or_default(),
+            rate_limit: value
+                .rate_limit
+                .as_ref()
+                .and_then(|limit| RateLimit::try_from(limit).ok()),
+            seed: value.seed,
+            status: match value.status {
+                ProducerStatus::Generating => format!(
+                    "GENERATING {}/{}",
+                    value.generated_messages, value.number_messages
+                ),
+                other => other.to_string(),
+            },
+            generated_messages: value.generated_messages,
+            created_at: value.created_at,
+        }
+    }
+}
+
+/// convert the repository page to the client type for JSON encoding
+impl From<ProducerPage> for PublicProducerPage {
+    fn from(value: ProducerPage) -> Self {
+        PublicProducerPage {
+            producers: value
+                .producers
+                .into_iter()
+                .map(PublicProducer::from)
+                .collect(),
+            total: value.total,
+            status_counts: value
+                .status_counts
+                .into_iter()
+                .map(|(status, count)| (status.to_string(), count))
+                .collect(),
+            next_cursor: value.next_cursor.map(|cursor| cursor.to_string()),
+        }
+    }
+}
+
+impl From<ProgressStats> for ProgressData {
+    fn from(value: ProgressStats) -> Self {
+        ProgressData {
+            number_messages_created: value.number_messages_created as i32,
+            number_messages_sent: value.number_messages_sent as i32,
+            number_messages_failed: value.number_messages_failed as i32,
+            number_messages_retried: value.number_messages_retried as i32,
+            number_messages_recovered: value.number_messages_recovered as i32,
+            average_message_time: (value.average_message_time_ms / 1000.0) as i32,
+            average_message_time_ms: value.average_message_time_ms,
+            message_time_histogram: value.message_time_histogram,
         }
     }
 }
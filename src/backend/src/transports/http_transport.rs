@@ -0,0 +1,309 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::{Client, Method, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{diesel::models::Message, utils::error::SMSManagerError};
+
+use super::{DeliveryOutcome, FailureKind, MessageTransport};
+
+/// The placeholders a template can use, each is replaced with the matching field of the message being sent
+pub const TEMPLATE_PLACEHOLDERS: [&str; 3] = ["{{message_id}}", "{{producer_id}}", "{{body}}"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HttpMethod {
+    Get,
+    #[default]
+    Post,
+    Put,
+}
+
+/// The body sent with each request, every string in it is templated
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "format", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HttpBody {
+    /// Sent as application/json, strings anywhere in the template are templated
+    Json { template: Value },
+    /// Sent as application/x-www-form-urlencoded
+    Form { fields: BTreeMap<String, String> },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HttpAuth {
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    Bearer {
+        token: String,
+    },
+}
+
+/// How the gateways response is read, fields are JSON pointers into the response body such as /data/id
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ResponseMapping {
+    /// The statuses that count as delivered, any 2xx status when empty
+    #[serde(default)]
+    pub success_statuses: Vec<u16>,
+    /// A field that must hold one of success_values for the message to count as delivered
+    #[serde(default)]
+    pub success_field: Option<String>,
+    #[serde(default)]
+    pub success_values: Vec<Value>,
+    /// The field holding the id the gateway gave the message
+    #[serde(default)]
+    pub message_id_field: Option<String>,
+    /// The field holding the reason the gateway gives for a failure
+    #[serde(default)]
+    pub error_field: Option<String>,
+}
+
+/// Settings for delivering through a provider that takes one HTTP request per message
+/// The url, header values, body and auth are templates, see TEMPLATE_PLACEHOLDERS
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HttpGatewayConfig {
+    pub url: String,
+    #[serde(default)]
+    pub method: HttpMethod,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: Option<HttpBody>,
+    #[serde(default)]
+    pub auth: Option<HttpAuth>,
+    #[serde(default)]
+    pub response: ResponseMapping,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+impl HttpGatewayConfig {
+    /// Checks the url is a valid http url and the response mapping is complete
+    ///
+    /// ### Errors if the url is invalid, success_field is set without success_values, or the timeout is zero
+    pub fn validate(&self) -> Result<(), SMSManagerError> {
+        let example_url = TEMPLATE_PLACEHOLDERS
+            .iter()
+            .fold(self.url.clone(), |url, placeholder| {
+                url.replace(placeholder, "placeholder")
+            });
+        match Url::parse(&example_url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+            _ => {
+                return Err(SMSManagerError::GeneralException(format!(
+                    "Gateway url {} is not a valid http url",
+                    self.url
+                )))
+            }
+        }
+
+        if self.response.success_field.is_some() && self.response.success_values.is_empty() {
+            return Err(SMSManagerError::GeneralException(
+                "Gateway success_field needs at least one success value".to_string(),
+            ));
+        }
+
+        if self.timeout_secs < 1 {
+            return Err(SMSManagerError::GeneralException(
+                "Gateway timeout must be greater than or equal to 1".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Delivers each message as a templated HTTP request to an SMS gateway
+pub struct HttpTransport {
+    config: HttpGatewayConfig,
+    client: Client,
+}
+
+impl HttpTransport {
+    /// Creates the transport and the HTTP client it sends with
+    ///
+    /// # Parameters
+    /// - config: The gateway to deliver to
+    ///
+    /// ### Errors if the HTTP client cannot be created
+    pub fn new(config: HttpGatewayConfig) -> Result<Self, SMSManagerError> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .map_err(|err| {
+                SMSManagerError::GeneralException(format!(
+                    "Could not create the HTTP client: {}",
+                    err
+                ))
+            })?;
+
+        Ok(HttpTransport { config, client })
+    }
+
+    /// Reads the response body and decides whether the gateway accepted the message
+    fn map_response(&self, status: u16, body: &str) -> DeliveryOutcome {
+        let mapping = &self.config.response;
+        let json: Option<Value> = serde_json::from_str(body).ok();
+        let field = |pointer: &Option<String>| {
+            pointer
+                .as_ref()
+                .and_then(|pointer| json.as_ref()?.pointer(pointer))
+        };
+
+        let status_ok = if mapping.success_statuses.is_empty() {
+            (200..300).contains(&status)
+        } else {
+            mapping.success_statuses.contains(&status)
+        };
+        let field_ok = match field(&mapping.success_field) {
+            Some(value) => mapping.success_values.contains(value),
+            None => mapping.success_field.is_none(),
+        };
+
+        if status_ok && field_ok {
+            return DeliveryOutcome::Delivered {
+                provider_message_id: field(&mapping.message_id_field).map(value_to_string),
+            };
+        }
+
+        let reason = field(&mapping.error_field)
+            .map(value_to_string)
+            .unwrap_or_else(|| "Gateway did not accept the message".to_string());
+        // A 2xx whose body says the send failed is treated as a rejection of the message
+        let kind = match status {
+            429 => FailureKind::Throttled,
+            500..=599 => FailureKind::ServerError,
+            _ => FailureKind::Rejected,
+        };
+        DeliveryOutcome::Failed {
+            kind,
+            reason: format!("Gateway responded {}: {}", status, reason),
+        }
+    }
+}
+
+#[async_trait]
+impl MessageTransport for HttpTransport {
+    async fn send(&self, message: &Message) -> DeliveryOutcome {
+        let url = match Url::parse(&render_url(&self.config.url, message)) {
+            Ok(url) => url,
+            Err(err) => {
+                return DeliveryOutcome::Failed {
+                    kind: FailureKind::Rejected,
+                    reason: format!("Invalid gateway url: {}", err),
+                }
+            }
+        };
+
+        let method = match self.config.method {
+            HttpMethod::Get => Method::GET,
+            HttpMethod::Post => Method::POST,
+            HttpMethod::Put => Method::PUT,
+        };
+
+        let mut request = self.client.request(method, url);
+
+        for (name, value) in &self.config.headers {
+            request = request.header(name, render(value, message));
+        }
+
+        request = match &self.config.auth {
+            Some(HttpAuth::Basic { username, password }) => request.basic_auth(
+                render(username, message),
+                password.as_ref().map(|password| render(password, message)),
+            ),
+            Some(HttpAuth::Bearer { token }) => request.bearer_auth(render(token, message)),
+            None => request,
+        };
+
+        request = match &self.config.body {
+            Some(HttpBody::Json { template }) => request.json(&render_json(template, message)),
+            Some(HttpBody::Form { fields }) => {
+                let rendered: BTreeMap<&String, String> = fields
+                    .iter()
+                    .map(|(name, value)| (name, render(value, message)))
+                    .collect();
+                request.form(&rendered)
+            }
+            None => request,
+        };
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(err) => {
+                let kind = if err.is_timeout() {
+                    FailureKind::Timeout
+                } else {
+                    FailureKind::Connection
+                };
+                return DeliveryOutcome::Failed {
+                    kind,
+                    reason: format!("Gateway request failed: {}", err),
+                };
+            }
+        };
+
+        let status = response.status().as_u16();
+        let body = response.text().await.unwrap_or_default();
+
+        self.map_response(status, &body)
+    }
+}
+
+/// Fills the placeholders in a template with the fields of the message
+///
+/// # Parameters
+/// - template: The text to fill in
+/// - message: The message being sent
+pub fn render(template: &str, message: &Message) -> String {
+    template
+        .replace("{{message_id}}", &message.id.to_string())
+        .replace("{{producer_id}}", &message.produced_by.to_string())
+        .replace("{{body}}", &message.message_body)
+}
+
+/// Fills the placeholders in a url template, percent encoding the values so they stay inside their component
+fn render_url(template: &str, message: &Message) -> String {
+    let encode =
+        |value: &str| url::form_urlencoded::byte_serialize(value.as_bytes()).collect::<String>();
+
+    template
+        .replace("{{message_id}}", &encode(&message.id.to_string()))
+        .replace("{{producer_id}}", &encode(&message.produced_by.to_string()))
+        .replace("{{body}}", &encode(&message.message_body))
+}
+
+/// Fills the placeholders in every string of a JSON template, leaving its structure alone
+fn render_json(template: &Value, message: &Message) -> Value {
+    match template {
+        Value::String(text) => Value::String(render(text, message)),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render_json(item, message))
+                .collect(),
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone(), render_json(value, message)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,180 @@
+use std::{fmt::Display, sync::Arc};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diesel::models::{Message, Producer},
+    utils::{
+        clock::{Clock, SystemClock},
+        error::SMSManagerError,
+    },
+};
+
+use self::{
+    http_transport::{HttpGatewayConfig, HttpTransport},
+    simulated_transport::{SimulatedConfig, SimulatedTransport},
+    smpp::{SmppConfig, SmppTransport},
+};
+
+pub mod http_transport;
+pub mod simulated_transport;
+pub mod smpp;
+
+/// Why a delivery failed, retry policies decide which of these are worth another attempt
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FailureKind {
+    /// The provider could not be reached
+    Connection,
+    /// The provider did not answer in time
+    Timeout,
+    /// The provider asked us to slow down
+    Throttled,
+    /// The provider failed on its side
+    ServerError,
+    /// The provider refused the message, sending it again will not help
+    Rejected,
+    /// The simulated transport rolled a failure
+    Simulated,
+}
+
+impl Display for FailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            FailureKind::Connection => "CONNECTION",
+            FailureKind::Timeout => "TIMEOUT",
+            FailureKind::Throttled => "THROTTLED",
+            FailureKind::ServerError => "SERVER_ERROR",
+            FailureKind::Rejected => "REJECTED",
+            FailureKind::Simulated => "SIMULATED",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// The result of trying to deliver a single message
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeliveryOutcome {
+    /// The id the provider gave the message, if it gave one
+    Delivered {
+        provider_message_id: Option<String>,
+    },
+    Failed {
+        kind: FailureKind,
+        reason: String,
+    },
+}
+
+impl DeliveryOutcome {
+    pub fn is_failure(&self) -> bool {
+        matches!(self, DeliveryOutcome::Failed { .. })
+    }
+
+    pub fn provider_message_id(&self) -> Option<String> {
+        match self {
+            DeliveryOutcome::Delivered {
+                provider_message_id,
+            } => provider_message_id.clone(),
+            DeliveryOutcome::Failed { .. } => None,
+        }
+    }
+}
+
+/// A way of delivering messages, the sender threads hand every message they take off the queue to the producers transport
+#[async_trait]
+pub trait MessageTransport: Send + Sync {
+    /// Attempts to deliver the message, resolving once the delivery has succeeded or failed
+    ///
+    /// # Parameters
+    /// - message: The message to deliver
+    async fn send(&self, message: &Message) -> DeliveryOutcome;
+
+    /// Releases anything the transport holds open, called once every sender thread has finished
+    async fn close(&self) {}
+}
+
+/// The transport a producer delivers its messages with, stored as JSON on the producer
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransportConfig {
+    /// Waits around the producers average send delay and fails at the producers failure rate
+    Simulated(SimulatedConfig),
+    /// Submits every message to an SMSC over SMPP 3.4
+    Smpp(SmppConfig),
+    /// Sends every message as a templated request to an HTTP SMS gateway
+    Http(HttpGatewayConfig),
+}
+
+impl TransportConfig {
+    /// The name of the transport as shown to clients
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TransportConfig::Simulated(_) => "SIMULATED",
+            TransportConfig::Smpp(_) => "SMPP",
+            TransportConfig::Http(_) => "HTTP",
+        }
+    }
+
+    /// Checks the configuration can be used to build a transport
+    ///
+    /// ### Errors if the transport specific settings are invalid
+    pub fn validate(&self) -> Result<(), SMSManagerError> {
+        match self {
+            TransportConfig::Simulated(config) => config.validate(),
+            TransportConfig::Smpp(config) => config.validate(),
+            TransportConfig::Http(config) => config.validate(),
+        }
+    }
+
+    /// Creates the clock deliveries through the transport are timed with, real transports always run in real time
+    pub fn build_clock(&self) -> Arc<dyn Clock> {
+        match self {
+            TransportConfig::Simulated(config) => config.clock.build(),
+            _ => Arc::new(SystemClock),
+        }
+    }
+}
+
+impl TryFrom<&serde_json::Value> for TransportConfig {
+    type Error = SMSManagerError;
+
+    fn try_from(value: &serde_json::Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value.clone()).map_err(|err| {
+            SMSManagerError::InvalidEncoding(format!("Invalid transport configuration: {}", err))
+        })
+    }
+}
+
+/// Builds the transport configured on the producer
+///
+/// # Parameters
+/// - producer: The producer whose messages will be delivered with the transport
+/// - clock: The clock a simulated transport waits on
+///
+/// ### Errors if the stored transport configuration is invalid
+pub fn build_transport(
+    producer: &Producer,
+    clock: Arc<dyn Clock>,
+) -> Result<Arc<dyn MessageTransport>, SMSManagerError> {
+    let config = TransportConfig::try_from(&producer.transport)?;
+
+    let transport: Arc<dyn MessageTransport> = match config {
+        TransportConfig::Simulated(config) => {
+            let mut transport =
+                SimulatedTransport::new(producer.average_send_delay, producer.failure_rate, clock)
+                    .with_seed(producer.seed);
+            if let Some(latency) = config.latency {
+                transport = transport.with_latency(latency);
+            }
+            if let Some(failure) = config.failure {
+                transport = transport.with_failure_model(failure);
+            }
+            Arc::new(transport)
+        }
+        TransportConfig::Smpp(config) => Arc::new(SmppTransport::new(config)),
+        TransportConfig::Http(config) => Arc::new(HttpTransport::new(config)?),
+    };
+
+    Ok(transport)
+}
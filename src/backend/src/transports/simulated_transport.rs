@@ -0,0 +1,156 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diesel::models::Message,
+    utils::{
+        clock::{Clock, ClockConfig},
+        error::SMSManagerError,
+        failure_model::{FailureContext, FailureModel, FailureState},
+        latency_model::LatencyModel,
+        random_utils::{get_random_wait_time, message_rng},
+    },
+};
+
+use super::{DeliveryOutcome, FailureKind, MessageTransport};
+
+/// Settings for the simulated transport, the delay and failure rate come from the producer itself
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct SimulatedConfig {
+    /// The clock the simulated deliveries wait on, so a long simulation can run in a fraction of the time
+    #[serde(default)]
+    pub clock: ClockConfig,
+    /// How long each delivery takes, None waits the producers average send delay give or take up to 5 seconds
+    #[serde(default)]
+    pub latency: Option<LatencyModel>,
+    /// When each delivery fails, None fails every delivery independently at the producers failure rate
+    #[serde(default)]
+    pub failure: Option<FailureModel>,
+}
+
+impl SimulatedConfig {
+    /// ### Errors if the clock, latency or failure model is invalid
+    pub fn validate(&self) -> Result<(), SMSManagerError> {
+        self.clock.validate()?;
+        if let Some(latency) = &self.latency {
+            latency.validate()?;
+        }
+        match &self.failure {
+            Some(failure) => failure.validate(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Pretends to deliver messages by waiting around an average delay and then rolling for a failure
+pub struct SimulatedTransport {
+    average_send_delay: i32,
+    clock: Arc<dyn Clock>,
+    latency: Option<LatencyModel>,
+    failure: FailureModel,
+    failure_state: Mutex<FailureState>,
+    in_flight: AtomicUsize,
+    started_at: DateTime<Utc>,
+    seed: Option<i64>,
+}
+
+impl SimulatedTransport {
+    /// # Parameters
+    /// - average_send_delay: The average time in seconds a delivery takes
+    /// - failure_rate: The percentage from 0 - 100 of deliveries that fail
+    /// - clock: The clock to wait out each delivery on
+    pub fn new(average_send_delay: i32, failure_rate: i32, clock: Arc<dyn Clock>) -> Self {
+        SimulatedTransport {
+            average_send_delay,
+            failure: FailureModel::Bernoulli {
+                failure_rate: failure_rate as f64 / 100.0,
+            },
+            failure_state: Mutex::new(FailureState::default()),
+            in_flight: AtomicUsize::new(0),
+            started_at: clock.now(),
+            clock,
+            latency: None,
+            seed: None,
+        }
+    }
+
+    /// Draws each deliveries delay from the given model instead of around the average send delay
+    ///
+    /// # Parameters
+    /// - latency: The model to draw delays from
+    pub fn with_latency(mut self, latency: LatencyModel) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Decides which deliveries fail with the given model instead of at the producers failure rate
+    ///
+    /// # Parameters
+    /// - failure: The model to roll for failures with
+    pub fn with_failure_model(mut self, failure: FailureModel) -> Self {
+        self.failure = failure;
+        self
+    }
+
+    /// Draws each deliveries delay and failure roll from the given seed, so a message gets the same outcome on every run with the seed
+    /// The Gilbert-Elliott and load dependent models also depend on the deliveries around a message, so they only repeat when the messages are sent in the same order
+    ///
+    /// # Parameters
+    /// - seed: The seed to draw with, None draws different values every run
+    pub fn with_seed(mut self, seed: Option<i64>) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+#[async_trait]
+impl MessageTransport for SimulatedTransport {
+    async fn send(&self, message: &Message) -> DeliveryOutcome {
+        let mut latency_rng = message_rng(self.seed, message, "latency");
+        let wait_time = match &self.latency {
+            Some(latency) => latency.sample(&mut latency_rng),
+            None => Duration::from_secs(get_random_wait_time(
+                &self.average_send_delay,
+                &mut latency_rng,
+            )),
+        };
+
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+
+        // Non-blocking async sleep
+        self.clock.sleep(wait_time).await;
+
+        let context = FailureContext {
+            elapsed: (self.clock.now() - self.started_at)
+                .to_std()
+                .unwrap_or_default(),
+            // Counted as the delivery finishes, so the load includes every delivery still waiting alongside it
+            in_flight: self.in_flight.fetch_sub(1, Ordering::SeqCst),
+        };
+        let failure = self.failure.roll(
+            &mut self.failure_state.lock().unwrap(),
+            &context,
+            &mut message_rng(self.seed, message, "failure"),
+        );
+
+        if let Some(reason) = failure {
+            return DeliveryOutcome::Failed {
+                kind: FailureKind::Simulated,
+                reason: reason.to_string(),
+            };
+        }
+
+        DeliveryOutcome::Delivered {
+            provider_message_id: None,
+        }
+    }
+}
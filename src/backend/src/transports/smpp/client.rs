@@ -0,0 +1,347 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc, Mutex as StdMutex, Weak,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    io::AsyncWriteExt,
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpStream,
+    },
+    sync::{oneshot, Mutex, Semaphore},
+    task::JoinHandle,
+    time::{sleep, timeout},
+};
+
+use super::{
+    pdu::{
+        command_status, read_pdu, BindRequest, Pdu, PduBody, PduError, SubmitSm, INTERFACE_VERSION,
+    },
+    BindMode, SmppConfig,
+};
+
+/// Errors raised by an SMPP session
+#[derive(Debug)]
+pub enum SmppError {
+    Pdu(PduError),
+    /// The SMSC refused to bind with the given command status
+    BindRejected(u32),
+    /// The SMSC answered with a PDU that does not match the request
+    UnexpectedResponse(u32),
+    /// The SMSC did not answer within the response timeout
+    Timeout,
+    /// The connection to the SMSC is gone
+    Closed,
+}
+
+impl Display for SmppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SmppError::Pdu(err) => write!(f, "{}", err),
+            SmppError::BindRejected(status) => write!(
+                f,
+                "SMSC rejected the bind with {} (0x{:08X})",
+                command_status::name(*status),
+                status
+            ),
+            SmppError::UnexpectedResponse(id) => {
+                write!(f, "SMSC answered with unexpected command 0x{:08X}", id)
+            }
+            SmppError::Timeout => write!(f, "Timed out waiting for the SMSC to respond"),
+            SmppError::Closed => write!(f, "The SMSC connection is closed"),
+        }
+    }
+}
+
+impl From<PduError> for SmppError {
+    fn from(error: PduError) -> Self {
+        SmppError::Pdu(error)
+    }
+}
+
+impl From<std::io::Error> for SmppError {
+    fn from(error: std::io::Error) -> Self {
+        SmppError::Pdu(PduError::Io(error))
+    }
+}
+
+/// What the SMSC answered to a submit_sm
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubmitResult {
+    pub command_status: u32,
+    pub message_id: String,
+}
+
+/// The state the session shares with its reader task
+struct SessionState {
+    pending: StdMutex<HashMap<u32, oneshot::Sender<Pdu>>>,
+    window: Semaphore,
+    closed: AtomicBool,
+}
+
+impl SessionState {
+    /// Marks the session closed and fails every request still waiting on a response
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.window.close();
+        // Dropping the senders wakes the waiting requests with an error
+        self.pending.lock().unwrap().clear();
+    }
+}
+
+/// A bound ESME session with an SMSC
+/// Submits are asynchronous, any number of callers can submit at once and up to window_size of them are left waiting on the SMSC at a time.
+/// A reader task matches responses to requests by sequence number and a keep alive task sends enquire_link on an interval
+pub struct SmppSession {
+    writer: Arc<Mutex<OwnedWriteHalf>>,
+    state: Arc<SessionState>,
+    sequence: AtomicU32,
+    response_timeout: Duration,
+    reader: JoinHandle<()>,
+    keep_alive: StdMutex<Option<JoinHandle<()>>>,
+}
+
+impl SmppSession {
+    /// Connects to the configured SMSC, binds and starts the enquire_link keep alive
+    ///
+    /// # Parameters
+    /// - config: The SMSC to connect to and the credentials to bind with
+    ///
+    /// ### Errors if the connection fails, or the SMSC rejects or does not answer the bind
+    pub async fn connect(config: &SmppConfig) -> Result<Arc<SmppSession>, SmppError> {
+        let response_timeout = Duration::from_secs(config.response_timeout_secs);
+
+        let stream = timeout(
+            response_timeout,
+            TcpStream::connect((config.host.as_str(), config.port)),
+        )
+        .await
+        .map_err(|_err| SmppError::Timeout)??;
+        let (read_half, write_half) = stream.into_split();
+
+        let writer = Arc::new(Mutex::new(write_half));
+        let state = Arc::new(SessionState {
+            pending: StdMutex::new(HashMap::new()),
+            window: Semaphore::new(config.window_size),
+            closed: AtomicBool::new(false),
+        });
+
+        let reader = tokio::spawn(read_responses(read_half, state.clone(), writer.clone()));
+
+        let session = Arc::new(SmppSession {
+            writer,
+            state,
+            sequence: AtomicU32::new(0),
+            response_timeout,
+            reader,
+            keep_alive: StdMutex::new(None),
+        });
+
+        session.bind(config).await?;
+
+        let keep_alive = tokio::spawn(keep_alive(
+            Arc::downgrade(&session),
+            Duration::from_secs(config.enquire_link_interval_secs),
+        ));
+        *session.keep_alive.lock().unwrap() = Some(keep_alive);
+
+        Ok(session)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.closed.load(Ordering::SeqCst)
+    }
+
+    /// Submits a message, waiting for a free slot in the window first
+    ///
+    /// # Parameters
+    /// - submit: The submit_sm to send
+    ///
+    /// ### Errors if the session is closed or the SMSC does not answer in time
+    pub async fn submit(&self, submit: SubmitSm) -> Result<SubmitResult, SmppError> {
+        let _permit = self
+            .state
+            .window
+            .acquire()
+            .await
+            .map_err(|_err| SmppError::Closed)?;
+
+        let response = self.request(PduBody::SubmitSm(submit)).await?;
+
+        match response.body {
+            PduBody::SubmitSmResp { message_id } => Ok(SubmitResult {
+                command_status: response.command_status,
+                message_id,
+            }),
+            // A generic_nack carries the reason the SMSC could not parse the submit
+            PduBody::GenericNack => Ok(SubmitResult {
+                command_status: response.command_status,
+                message_id: String::new(),
+            }),
+            other => Err(SmppError::UnexpectedResponse(other.command_id())),
+        }
+    }
+
+    /// Checks the SMSC is still answering
+    ///
+    /// ### Errors if the session is closed or the SMSC does not answer in time
+    pub async fn enquire_link(&self) -> Result<(), SmppError> {
+        self.request(PduBody::EnquireLink).await.map(|_| ())
+    }
+
+    /// Unbinds from the SMSC and closes the session
+    ///
+    /// ### Errors if the session is already closed or the SMSC does not answer in time
+    pub async fn unbind(&self) -> Result<(), SmppError> {
+        let result = self.request(PduBody::Unbind).await.map(|_| ());
+        self.state.close();
+        let _ = self.writer.lock().await.shutdown().await;
+        result
+    }
+
+    async fn bind(&self, config: &SmppConfig) -> Result<(), SmppError> {
+        let request = BindRequest {
+            system_id: config.system_id.clone(),
+            password: config.password.clone(),
+            system_type: config.system_type.clone(),
+            interface_version: INTERFACE_VERSION,
+            addr_ton: 0,
+            addr_npi: 0,
+            address_range: String::new(),
+        };
+        let body = match config.bind_mode {
+            BindMode::Transmitter => PduBody::BindTransmitter(request),
+            BindMode::Transceiver => PduBody::BindTransceiver(request),
+        };
+
+        let response = self.request(body).await?;
+
+        match response.body {
+            PduBody::BindTransmitterResp { .. } | PduBody::BindTransceiverResp { .. }
+                if response.command_status == command_status::ESME_ROK =>
+            {
+                Ok(())
+            }
+            PduBody::BindTransmitterResp { .. }
+            | PduBody::BindTransceiverResp { .. }
+            | PduBody::GenericNack => Err(SmppError::BindRejected(response.command_status)),
+            other => Err(SmppError::UnexpectedResponse(other.command_id())),
+        }
+    }
+
+    /// Sends a request and waits for the response with the same sequence number
+    async fn request(&self, body: PduBody) -> Result<Pdu, SmppError> {
+        if self.is_closed() {
+            return Err(SmppError::Closed);
+        }
+
+        let sequence_number = self.next_sequence_number();
+        let (tx, rx) = oneshot::channel();
+        self.state
+            .pending
+            .lock()
+            .unwrap()
+            .insert(sequence_number, tx);
+
+        let bytes = Pdu::new(sequence_number, body).encode();
+        if let Err(err) = self.writer.lock().await.write_all(&bytes).await {
+            self.state.close();
+            return Err(SmppError::from(err));
+        }
+
+        match timeout(self.response_timeout, rx).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(SmppError::Closed),
+            Err(_) => {
+                self.state.pending.lock().unwrap().remove(&sequence_number);
+                Err(SmppError::Timeout)
+            }
+        }
+    }
+
+    /// Sequence numbers run from 1 to 0x7FFFFFFF and then wrap around
+    fn next_sequence_number(&self) -> u32 {
+        let next = |current: u32| {
+            if current >= 0x7FFF_FFFF {
+                1
+            } else {
+                current + 1
+            }
+        };
+        let previous = self
+            .sequence
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+                Some(next(current))
+            })
+            .unwrap(); // The closure always returns Some
+        next(previous)
+    }
+}
+
+impl Drop for SmppSession {
+    fn drop(&mut self) {
+        self.reader.abort();
+        if let Some(keep_alive) = self.keep_alive.lock().unwrap().take() {
+            keep_alive.abort();
+        }
+    }
+}
+
+/// Hands responses to the requests waiting on them and answers the requests the SMSC makes of us
+async fn read_responses(
+    mut read_half: OwnedReadHalf,
+    state: Arc<SessionState>,
+    writer: Arc<Mutex<OwnedWriteHalf>>,
+) {
+    while let Ok(Some(pdu)) = read_pdu(&mut read_half).await {
+        if pdu.is_response() {
+            if let Some(waiting) = state.pending.lock().unwrap().remove(&pdu.sequence_number) {
+                let _ = waiting.send(pdu);
+            }
+            continue;
+        }
+
+        let (status, body) = match pdu.body {
+            PduBody::EnquireLink => (command_status::ESME_ROK, PduBody::EnquireLinkResp),
+            PduBody::Unbind => (command_status::ESME_ROK, PduBody::UnbindResp),
+            // Delivery receipts and mobile originated messages are acknowledged but not used
+            PduBody::DeliverSm(_) => (command_status::ESME_ROK, PduBody::DeliverSmResp),
+            _ => (command_status::ESME_RINVCMDID, PduBody::GenericNack),
+        };
+        let is_unbind = matches!(body, PduBody::UnbindResp);
+
+        let response = Pdu::response(pdu.sequence_number, status, body).encode();
+        if writer.lock().await.write_all(&response).await.is_err() || is_unbind {
+            break;
+        }
+    }
+
+    state.close();
+}
+
+/// Sends enquire_link on the interval until the session is dropped or stops answering
+async fn keep_alive(session: Weak<SmppSession>, interval: Duration) {
+    loop {
+        sleep(interval).await;
+
+        let Some(session) = session.upgrade() else {
+            break;
+        };
+
+        if session.is_closed() {
+            break;
+        }
+
+        if let Err(err) = session.enquire_link().await {
+            eprintln!("SMSC stopped answering enquire_link: {}", err);
+            session.state.close();
+            break;
+        }
+    }
+}
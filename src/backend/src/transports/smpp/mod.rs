@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{diesel::models::Message, utils::error::SMSManagerError};
+
+use self::{
+    client::{SmppError, SmppSession},
+    pdu::{command_status, SubmitSm, MAX_SHORT_MESSAGE_LENGTH},
+};
+
+use super::{DeliveryOutcome, FailureKind, MessageTransport};
+
+pub mod client;
+pub mod pdu;
+pub mod simulator;
+
+/// How the ESME binds to the SMSC
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BindMode {
+    #[default]
+    Transmitter,
+    Transceiver,
+}
+
+/// Connection and addressing settings for delivering through an SMSC over SMPP 3.4
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SmppConfig {
+    pub host: String,
+    pub port: u16,
+    pub system_id: String,
+    pub password: String,
+    #[serde(default)]
+    pub system_type: String,
+    #[serde(default)]
+    pub bind_mode: BindMode,
+    pub source_addr: String,
+    #[serde(default)]
+    pub source_addr_ton: u8,
+    #[serde(default)]
+    pub source_addr_npi: u8,
+    /// Messages carry no recipient of their own, so every submit goes to this address
+    pub destination_addr: String,
+    #[serde(default)]
+    pub dest_addr_ton: u8,
+    #[serde(default)]
+    pub dest_addr_npi: u8,
+    /// The most submits left waiting on a submit_sm_resp at once
+    #[serde(default = "default_window_size")]
+    pub window_size: usize,
+    #[serde(default = "default_enquire_link_interval_secs")]
+    pub enquire_link_interval_secs: u64,
+    #[serde(default = "default_response_timeout_secs")]
+    pub response_timeout_secs: u64,
+}
+
+fn default_window_size() -> usize {
+    10
+}
+
+fn default_enquire_link_interval_secs() -> u64 {
+    30
+}
+
+fn default_response_timeout_secs() -> u64 {
+    10
+}
+
+impl SmppConfig {
+    /// Checks the settings fit the field sizes SMPP 3.4 allows
+    ///
+    /// ### Errors if a field is empty, too long, or a timing value is zero
+    pub fn validate(&self) -> Result<(), SMSManagerError> {
+        let limits = [
+            ("system_id", &self.system_id, 15),
+            ("password", &self.password, 8),
+            ("system_type", &self.system_type, 12),
+            ("source_addr", &self.source_addr, 20),
+            ("destination_addr", &self.destination_addr, 20),
+        ];
+        for (field, value, max_length) in limits {
+            if value.len() > max_length {
+                return Err(SMSManagerError::GeneralException(format!(
+                    "SMPP {} must be at most {} characters",
+                    field, max_length
+                )));
+            }
+        }
+
+        if self.host.is_empty() || self.system_id.is_empty() || self.destination_addr.is_empty() {
+            return Err(SMSManagerError::GeneralException(
+                "SMPP host, system_id and destination_addr are required".to_string(),
+            ));
+        }
+
+        if self.window_size < 1
+            || self.enquire_link_interval_secs < 1
+            || self.response_timeout_secs < 1
+        {
+            return Err(SMSManagerError::GeneralException(
+                "SMPP window size, enquire link interval and response timeout must be greater than or equal to 1"
+                    .to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Delivers messages to an SMSC as an SMPP 3.4 ESME
+/// The session is bound on the first send and shared by every sender thread, so their submits are windowed over one connection.
+/// A dropped session is rebound on the next send
+pub struct SmppTransport {
+    config: SmppConfig,
+    session: Mutex<Option<Arc<SmppSession>>>,
+}
+
+impl SmppTransport {
+    pub fn new(config: SmppConfig) -> Self {
+        SmppTransport {
+            config,
+            session: Mutex::new(None),
+        }
+    }
+
+    async fn session(&self) -> Result<Arc<SmppSession>, SmppError> {
+        let mut session = self.session.lock().await;
+
+        if let Some(existing) = session.as_ref() {
+            if !existing.is_closed() {
+                return Ok(existing.clone());
+            }
+        }
+
+        let bound = SmppSession::connect(&self.config).await?;
+        *session = Some(bound.clone());
+        Ok(bound)
+    }
+}
+
+#[async_trait]
+impl MessageTransport for SmppTransport {
+    async fn send(&self, message: &Message) -> DeliveryOutcome {
+        let short_message = message.message_body.as_bytes().to_vec();
+        if short_message.len() > MAX_SHORT_MESSAGE_LENGTH {
+            return DeliveryOutcome::Failed {
+                kind: FailureKind::Rejected,
+                reason: format!(
+                    "Message is longer than the {} octets a submit_sm can carry",
+                    MAX_SHORT_MESSAGE_LENGTH
+                ),
+            };
+        }
+
+        let session = match self.session().await {
+            Ok(session) => session,
+            Err(err) => {
+                return DeliveryOutcome::Failed {
+                    kind: error_kind(&err),
+                    reason: format!("Could not bind to the SMSC: {}", err),
+                }
+            }
+        };
+
+        let submit = SubmitSm {
+            source_addr_ton: self.config.source_addr_ton,
+            source_addr_npi: self.config.source_addr_npi,
+            source_addr: self.config.source_addr.clone(),
+            dest_addr_ton: self.config.dest_addr_ton,
+            dest_addr_npi: self.config.dest_addr_npi,
+            destination_addr: self.config.destination_addr.clone(),
+            short_message,
+            ..Default::default()
+        };
+
+        match session.submit(submit).await {
+            Ok(result) if result.command_status == command_status::ESME_ROK => {
+                DeliveryOutcome::Delivered {
+                    provider_message_id: Some(result.message_id)
+                        .filter(|message_id| !message_id.is_empty()),
+                }
+            }
+            Ok(result) => DeliveryOutcome::Failed {
+                kind: status_kind(result.command_status),
+                reason: format!(
+                    "SMSC rejected the message with {} (0x{:08X})",
+                    command_status::name(result.command_status),
+                    result.command_status
+                ),
+            },
+            Err(err) => DeliveryOutcome::Failed {
+                kind: error_kind(&err),
+                reason: err.to_string(),
+            },
+        }
+    }
+
+    async fn close(&self) {
+        if let Some(session) = self.session.lock().await.take() {
+            if let Err(err) = session.unbind().await {
+                eprintln!("Could not unbind from the SMSC: {}", err);
+            }
+        }
+    }
+}
+
+/// Classifies a submit_sm_resp error status
+fn status_kind(status: u32) -> FailureKind {
+    match status {
+        command_status::ESME_RTHROTTLED | command_status::ESME_RMSGQFUL => FailureKind::Throttled,
+        command_status::ESME_RSYSERR | command_status::ESME_RUNKNOWNERR => FailureKind::ServerError,
+        _ => FailureKind::Rejected,
+    }
+}
+
+/// Classifies a session error
+fn error_kind(error: &SmppError) -> FailureKind {
+    match error {
+        SmppError::Timeout => FailureKind::Timeout,
+        SmppError::BindRejected(_) => FailureKind::Rejected,
+        SmppError::Pdu(_) | SmppError::UnexpectedResponse(_) | SmppError::Closed => {
+            FailureKind::Connection
+        }
+    }
+}
@@ -0,0 +1,471 @@
+use std::{fmt::Display, io};
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Every PDU starts with a 16 octet header of command_length, command_id, command_status and sequence_number
+pub const HEADER_LENGTH: usize = 16;
+
+/// Anything larger than this is not a PDU we would ever exchange and is treated as a corrupt stream
+pub const MAX_PDU_LENGTH: usize = 64 * 1024;
+
+/// The SMPP version advertised when binding
+pub const INTERFACE_VERSION: u8 = 0x34;
+
+/// The longest body that fits in the short_message field of a submit_sm
+pub const MAX_SHORT_MESSAGE_LENGTH: usize = 254;
+
+pub mod command_id {
+    pub const GENERIC_NACK: u32 = 0x8000_0000;
+    pub const BIND_TRANSMITTER: u32 = 0x0000_0002;
+    pub const BIND_TRANSMITTER_RESP: u32 = 0x8000_0002;
+    pub const SUBMIT_SM: u32 = 0x0000_0004;
+    pub const SUBMIT_SM_RESP: u32 = 0x8000_0004;
+    pub const DELIVER_SM: u32 = 0x0000_0005;
+    pub const DELIVER_SM_RESP: u32 = 0x8000_0005;
+    pub const UNBIND: u32 = 0x0000_0006;
+    pub const UNBIND_RESP: u32 = 0x8000_0006;
+    pub const BIND_TRANSCEIVER: u32 = 0x0000_0009;
+    pub const BIND_TRANSCEIVER_RESP: u32 = 0x8000_0009;
+    pub const ENQUIRE_LINK: u32 = 0x0000_0015;
+    pub const ENQUIRE_LINK_RESP: u32 = 0x8000_0015;
+}
+
+pub mod command_status {
+    pub const ESME_ROK: u32 = 0x0000_0000;
+    pub const ESME_RINVMSGLEN: u32 = 0x0000_0001;
+    pub const ESME_RINVCMDLEN: u32 = 0x0000_0002;
+    pub const ESME_RINVCMDID: u32 = 0x0000_0003;
+    pub const ESME_RINVBNDSTS: u32 = 0x0000_0004;
+    pub const ESME_RALYBND: u32 = 0x0000_0005;
+    pub const ESME_RSYSERR: u32 = 0x0000_0008;
+    pub const ESME_RINVSRCADR: u32 = 0x0000_000A;
+    pub const ESME_RINVDSTADR: u32 = 0x0000_000B;
+    pub const ESME_RBINDFAIL: u32 = 0x0000_000D;
+    pub const ESME_RINVPASWD: u32 = 0x0000_000E;
+    pub const ESME_RINVSYSID: u32 = 0x0000_000F;
+    pub const ESME_RMSGQFUL: u32 = 0x0000_0014;
+    pub const ESME_RSUBMITFAIL: u32 = 0x0000_0045;
+    pub const ESME_RTHROTTLED: u32 = 0x0000_0058;
+    pub const ESME_RDELIVERYFAILURE: u32 = 0x0000_00FE;
+    pub const ESME_RUNKNOWNERR: u32 = 0x0000_00FF;
+
+    /// Gets the name the specification gives to a command status
+    ///
+    /// # Parameters
+    /// - status: The command_status of a response PDU
+    pub fn name(status: u32) -> &'static str {
+        match status {
+            ESME_ROK => "ESME_ROK",
+            ESME_RINVMSGLEN => "ESME_RINVMSGLEN",
+            ESME_RINVCMDLEN => "ESME_RINVCMDLEN",
+            ESME_RINVCMDID => "ESME_RINVCMDID",
+            ESME_RINVBNDSTS => "ESME_RINVBNDSTS",
+            ESME_RALYBND => "ESME_RALYBND",
+            ESME_RSYSERR => "ESME_RSYSERR",
+            ESME_RINVSRCADR => "ESME_RINVSRCADR",
+            ESME_RINVDSTADR => "ESME_RINVDSTADR",
+            ESME_RBINDFAIL => "ESME_RBINDFAIL",
+            ESME_RINVPASWD => "ESME_RINVPASWD",
+            ESME_RINVSYSID => "ESME_RINVSYSID",
+            ESME_RMSGQFUL => "ESME_RMSGQFUL",
+            ESME_RSUBMITFAIL => "ESME_RSUBMITFAIL",
+            ESME_RTHROTTLED => "ESME_RTHROTTLED",
+            ESME_RDELIVERYFAILURE => "ESME_RDELIVERYFAILURE",
+            ESME_RUNKNOWNERR => "ESME_RUNKNOWNERR",
+            _ => "UNKNOWN",
+        }
+    }
+}
+
+/// Errors raised while encoding or decoding PDUs
+#[derive(Debug)]
+pub enum PduError {
+    Io(io::Error),
+    /// The bytes on the wire do not form a valid PDU
+    Malformed(String),
+}
+
+impl Display for PduError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PduError::Io(err) => write!(f, "SMPP connection error: {}", err),
+            PduError::Malformed(reason) => write!(f, "Malformed SMPP PDU: {}", reason),
+        }
+    }
+}
+
+impl From<io::Error> for PduError {
+    fn from(error: io::Error) -> Self {
+        PduError::Io(error)
+    }
+}
+
+/// The body of a bind_transmitter or bind_transceiver
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BindRequest {
+    pub system_id: String,
+    pub password: String,
+    pub system_type: String,
+    pub interface_version: u8,
+    pub addr_ton: u8,
+    pub addr_npi: u8,
+    pub address_range: String,
+}
+
+/// The mandatory parameters of a submit_sm
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SubmitSm {
+    pub service_type: String,
+    pub source_addr_ton: u8,
+    pub source_addr_npi: u8,
+    pub source_addr: String,
+    pub dest_addr_ton: u8,
+    pub dest_addr_npi: u8,
+    pub destination_addr: String,
+    pub esm_class: u8,
+    pub protocol_id: u8,
+    pub priority_flag: u8,
+    pub schedule_delivery_time: String,
+    pub validity_period: String,
+    pub registered_delivery: u8,
+    pub replace_if_present_flag: u8,
+    pub data_coding: u8,
+    pub sm_default_msg_id: u8,
+    pub short_message: Vec<u8>,
+}
+
+/// The operations this client and the simulator exchange, anything else is carried as raw bytes
+#[derive(Debug, Clone, PartialEq)]
+pub enum PduBody {
+    BindTransmitter(BindRequest),
+    BindTransmitterResp { system_id: String },
+    BindTransceiver(BindRequest),
+    BindTransceiverResp { system_id: String },
+    SubmitSm(SubmitSm),
+    SubmitSmResp { message_id: String },
+    DeliverSm(Vec<u8>),
+    DeliverSmResp,
+    EnquireLink,
+    EnquireLinkResp,
+    Unbind,
+    UnbindResp,
+    GenericNack,
+    Unknown { command_id: u32, body: Vec<u8> },
+}
+
+impl PduBody {
+    pub fn command_id(&self) -> u32 {
+        match self {
+            PduBody::BindTransmitter(_) => command_id::BIND_TRANSMITTER,
+            PduBody::BindTransmitterResp { .. } => command_id::BIND_TRANSMITTER_RESP,
+            PduBody::BindTransceiver(_) => command_id::BIND_TRANSCEIVER,
+            PduBody::BindTransceiverResp { .. } => command_id::BIND_TRANSCEIVER_RESP,
+            PduBody::SubmitSm(_) => command_id::SUBMIT_SM,
+            PduBody::SubmitSmResp { .. } => command_id::SUBMIT_SM_RESP,
+            PduBody::DeliverSm(_) => command_id::DELIVER_SM,
+            PduBody::DeliverSmResp => command_id::DELIVER_SM_RESP,
+            PduBody::EnquireLink => command_id::ENQUIRE_LINK,
+            PduBody::EnquireLinkResp => command_id::ENQUIRE_LINK_RESP,
+            PduBody::Unbind => command_id::UNBIND,
+            PduBody::UnbindResp => command_id::UNBIND_RESP,
+            PduBody::GenericNack => command_id::GENERIC_NACK,
+            PduBody::Unknown { command_id, .. } => *command_id,
+        }
+    }
+}
+
+/// A single SMPP protocol data unit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pdu {
+    pub command_status: u32,
+    pub sequence_number: u32,
+    pub body: PduBody,
+}
+
+impl Pdu {
+    pub fn new(sequence_number: u32, body: PduBody) -> Self {
+        Pdu {
+            command_status: command_status::ESME_ROK,
+            sequence_number,
+            body,
+        }
+    }
+
+    /// Builds a response to a request with the given status
+    ///
+    /// # Parameters
+    /// - sequence_number: The sequence number of the request being answered
+    /// - command_status: The status to answer with
+    /// - body: The response body
+    pub fn response(sequence_number: u32, command_status: u32, body: PduBody) -> Self {
+        Pdu {
+            command_status,
+            sequence_number,
+            body,
+        }
+    }
+
+    pub fn command_id(&self) -> u32 {
+        self.body.command_id()
+    }
+
+    /// Whether this PDU answers a request, every response id has the high bit set
+    pub fn is_response(&self) -> bool {
+        self.command_id() & 0x8000_0000 != 0
+    }
+
+    /// Encodes the PDU, including its header, into the bytes sent on the wire
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = vec![];
+        match &self.body {
+            PduBody::BindTransmitter(bind) | PduBody::BindTransceiver(bind) => {
+                put_c_string(&mut body, &bind.system_id);
+                put_c_string(&mut body, &bind.password);
+                put_c_string(&mut body, &bind.system_type);
+                body.push(bind.interface_version);
+                body.push(bind.addr_ton);
+                body.push(bind.addr_npi);
+                put_c_string(&mut body, &bind.address_range);
+            }
+            PduBody::BindTransmitterResp { system_id }
+            | PduBody::BindTransceiverResp { system_id } => {
+                put_c_string(&mut body, system_id);
+            }
+            PduBody::SubmitSm(submit) => {
+                put_c_string(&mut body, &submit.service_type);
+                body.push(submit.source_addr_ton);
+                body.push(submit.source_addr_npi);
+                put_c_string(&mut body, &submit.source_addr);
+                body.push(submit.dest_addr_ton);
+                body.push(submit.dest_addr_npi);
+                put_c_string(&mut body, &submit.destination_addr);
+                body.push(submit.esm_class);
+                body.push(submit.protocol_id);
+                body.push(submit.priority_flag);
+                put_c_string(&mut body, &submit.schedule_delivery_time);
+                put_c_string(&mut body, &submit.validity_period);
+                body.push(submit.registered_delivery);
+                body.push(submit.replace_if_present_flag);
+                body.push(submit.data_coding);
+                body.push(submit.sm_default_msg_id);
+                body.push(submit.short_message.len() as u8);
+                body.extend_from_slice(&submit.short_message);
+            }
+            PduBody::SubmitSmResp { message_id } => put_c_string(&mut body, message_id),
+            PduBody::DeliverSm(raw) | PduBody::Unknown { body: raw, .. } => {
+                body.extend_from_slice(raw)
+            }
+            PduBody::DeliverSmResp => put_c_string(&mut body, ""),
+            PduBody::EnquireLink
+            | PduBody::EnquireLinkResp
+            | PduBody::Unbind
+            | PduBody::UnbindResp
+            | PduBody::GenericNack => {}
+        }
+
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + body.len());
+        bytes.extend_from_slice(&((HEADER_LENGTH + body.len()) as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.command_id().to_be_bytes());
+        bytes.extend_from_slice(&self.command_status.to_be_bytes());
+        bytes.extend_from_slice(&self.sequence_number.to_be_bytes());
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    /// Decodes a complete PDU, including its header
+    ///
+    /// # Parameters
+    /// - bytes: The bytes of exactly one PDU
+    ///
+    /// ### Errors if the length does not match the header or a field runs past the end of the PDU
+    pub fn decode(bytes: &[u8]) -> Result<Pdu, PduError> {
+        if bytes.len() < HEADER_LENGTH {
+            return Err(PduError::Malformed(
+                "PDU is shorter than its header".to_string(),
+            ));
+        }
+
+        let command_length = read_u32(&bytes[0..4]) as usize;
+        if command_length != bytes.len() {
+            return Err(PduError::Malformed(format!(
+                "command_length {} does not match the {} bytes received",
+                command_length,
+                bytes.len()
+            )));
+        }
+
+        let id = read_u32(&bytes[4..8]);
+        let command_status = read_u32(&bytes[8..12]);
+        let sequence_number = read_u32(&bytes[12..16]);
+        let mut reader = BodyReader {
+            bytes: &bytes[HEADER_LENGTH..],
+            position: 0,
+        };
+
+        let body = match id {
+            command_id::BIND_TRANSMITTER => PduBody::BindTransmitter(reader.bind_request()?),
+            command_id::BIND_TRANSCEIVER => PduBody::BindTransceiver(reader.bind_request()?),
+            command_id::BIND_TRANSMITTER_RESP => PduBody::BindTransmitterResp {
+                system_id: reader.optional_c_string()?,
+            },
+            command_id::BIND_TRANSCEIVER_RESP => PduBody::BindTransceiverResp {
+                system_id: reader.optional_c_string()?,
+            },
+            command_id::SUBMIT_SM => PduBody::SubmitSm(reader.submit_sm()?),
+            command_id::SUBMIT_SM_RESP => PduBody::SubmitSmResp {
+                message_id: reader.optional_c_string()?,
+            },
+            command_id::DELIVER_SM => PduBody::DeliverSm(reader.rest()),
+            command_id::DELIVER_SM_RESP => PduBody::DeliverSmResp,
+            command_id::ENQUIRE_LINK => PduBody::EnquireLink,
+            command_id::ENQUIRE_LINK_RESP => PduBody::EnquireLinkResp,
+            command_id::UNBIND => PduBody::Unbind,
+            command_id::UNBIND_RESP => PduBody::UnbindResp,
+            command_id::GENERIC_NACK => PduBody::GenericNack,
+            other => PduBody::Unknown {
+                command_id: other,
+                body: reader.rest(),
+            },
+        };
+
+        Ok(Pdu {
+            command_status,
+            sequence_number,
+            body,
+        })
+    }
+}
+
+/// Reads the next complete PDU off the stream
+///
+/// # Parameters
+/// - reader: The stream to read from
+///
+/// # Returns
+/// None if the stream closed cleanly before a new PDU started
+///
+/// ### Errors if the stream fails or the PDU is malformed
+pub async fn read_pdu<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<Pdu>, PduError> {
+    let mut length_bytes = [0u8; 4];
+    match reader.read_exact(&mut length_bytes).await {
+        Ok(_) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(PduError::Io(err)),
+    }
+
+    let command_length = u32::from_be_bytes(length_bytes) as usize;
+    if !(HEADER_LENGTH..=MAX_PDU_LENGTH).contains(&command_length) {
+        return Err(PduError::Malformed(format!(
+            "command_length {} is out of bounds",
+            command_length
+        )));
+    }
+
+    let mut bytes = vec![0u8; command_length];
+    bytes[0..4].copy_from_slice(&length_bytes);
+    reader.read_exact(&mut bytes[4..]).await?;
+
+    Pdu::decode(&bytes).map(Some)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn put_c_string(bytes: &mut Vec<u8>, value: &str) {
+    bytes.extend_from_slice(value.as_bytes());
+    bytes.push(0);
+}
+
+struct BodyReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl BodyReader<'_> {
+    fn u8(&mut self) -> Result<u8, PduError> {
+        let value = *self.bytes.get(self.position).ok_or(PduError::Malformed(
+            "Field runs past the end of the PDU".to_string(),
+        ))?;
+        self.position += 1;
+        Ok(value)
+    }
+
+    fn c_string(&mut self) -> Result<String, PduError> {
+        let remaining = &self.bytes[self.position..];
+        let end = remaining
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or(PduError::Malformed(
+                "C-Octet String is not terminated".to_string(),
+            ))?;
+        let value = String::from_utf8_lossy(&remaining[..end]).to_string();
+        self.position += end + 1;
+        Ok(value)
+    }
+
+    /// Error responses are allowed to leave their body out entirely
+    fn optional_c_string(&mut self) -> Result<String, PduError> {
+        if self.position >= self.bytes.len() {
+            return Ok(String::new());
+        }
+        self.c_string()
+    }
+
+    fn octets(&mut self, length: usize) -> Result<Vec<u8>, PduError> {
+        let end = self.position + length;
+        if end > self.bytes.len() {
+            return Err(PduError::Malformed(
+                "Octet string runs past the end of the PDU".to_string(),
+            ));
+        }
+        let value = self.bytes[self.position..end].to_vec();
+        self.position = end;
+        Ok(value)
+    }
+
+    fn rest(&mut self) -> Vec<u8> {
+        let value = self.bytes[self.position..].to_vec();
+        self.position = self.bytes.len();
+        value
+    }
+
+    fn bind_request(&mut self) -> Result<BindRequest, PduError> {
+        Ok(BindRequest {
+            system_id: self.c_string()?,
+            password: self.c_string()?,
+            system_type: self.c_string()?,
+            interface_version: self.u8()?,
+            addr_ton: self.u8()?,
+            addr_npi: self.u8()?,
+            address_range: self.c_string()?,
+        })
+    }
+
+    fn submit_sm(&mut self) -> Result<SubmitSm, PduError> {
+        let mut submit = SubmitSm {
+            service_type: self.c_string()?,
+            source_addr_ton: self.u8()?,
+            source_addr_npi: self.u8()?,
+            source_addr: self.c_string()?,
+            dest_addr_ton: self.u8()?,
+            dest_addr_npi: self.u8()?,
+            destination_addr: self.c_string()?,
+            esm_class: self.u8()?,
+            protocol_id: self.u8()?,
+            priority_flag: self.u8()?,
+            schedule_delivery_time: self.c_string()?,
+            validity_period: self.c_string()?,
+            registered_delivery: self.u8()?,
+            replace_if_present_flag: self.u8()?,
+            data_coding: self.u8()?,
+            sm_default_msg_id: self.u8()?,
+            short_message: vec![],
+        };
+        let sm_length = self.u8()? as usize;
+        submit.short_message = self.octets(sm_length)?;
+        // Optional TLVs are not used by this client, so they are skipped
+        self.rest();
+        Ok(submit)
+    }
+}
@@ -0,0 +1,238 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex as StdMutex,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    io::AsyncWriteExt,
+    net::{tcp::OwnedWriteHalf, TcpListener, TcpStream},
+    sync::Mutex,
+    task::JoinHandle,
+    time::sleep,
+};
+
+use super::pdu::{command_status, read_pdu, BindRequest, Pdu, PduBody, SubmitSm};
+
+/// Decides the command_status the simulator answers a submit_sm with
+pub type SubmitResponder = Arc<dyn Fn(&SubmitSm) -> u32 + Send + Sync>;
+
+/// How the simulated SMSC behaves
+#[derive(Clone)]
+pub struct SmscSimulatorConfig {
+    /// The system_id an ESME must bind with
+    pub system_id: String,
+    /// The password an ESME must bind with
+    pub password: String,
+    /// How long the SMSC takes to answer each submit_sm
+    pub response_delay: Duration,
+    pub responder: SubmitResponder,
+}
+
+impl Default for SmscSimulatorConfig {
+    fn default() -> Self {
+        SmscSimulatorConfig {
+            system_id: "simulator".to_string(),
+            password: "secret".to_string(),
+            response_delay: Duration::ZERO,
+            responder: Arc::new(|_| command_status::ESME_ROK),
+        }
+    }
+}
+
+/// What the simulator has seen across every connection
+#[derive(Default)]
+struct SimulatorStats {
+    submitted: StdMutex<Vec<SubmitSm>>,
+    enquire_links: AtomicUsize,
+    unbinds: AtomicUsize,
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+    next_message_id: AtomicUsize,
+}
+
+/// A minimal SMSC that speaks enough SMPP 3.4 to exercise the SMPP transport without a real carrier
+/// It listens on a random local port, checks bind credentials and answers submits after a delay with whatever status the responder picks
+pub struct SmscSimulator {
+    address: SocketAddr,
+    stats: Arc<SimulatorStats>,
+    listener: JoinHandle<()>,
+}
+
+impl SmscSimulator {
+    /// Starts listening for ESMEs on a random port on localhost
+    ///
+    /// # Parameters
+    /// - config: How the simulated SMSC behaves
+    ///
+    /// ### Errors if the listener cannot be bound
+    pub async fn start(config: SmscSimulatorConfig) -> std::io::Result<SmscSimulator> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?;
+        let stats = Arc::new(SimulatorStats::default());
+
+        let accept_stats = stats.clone();
+        let listener = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle_connection(
+                    stream,
+                    config.clone(),
+                    accept_stats.clone(),
+                ));
+            }
+        });
+
+        Ok(SmscSimulator {
+            address,
+            stats,
+            listener,
+        })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Every submit_sm accepted after a successful bind, in the order they arrived
+    pub fn submitted(&self) -> Vec<SubmitSm> {
+        self.stats.submitted.lock().unwrap().clone()
+    }
+
+    pub fn enquire_link_count(&self) -> usize {
+        self.stats.enquire_links.load(Ordering::SeqCst)
+    }
+
+    pub fn unbind_count(&self) -> usize {
+        self.stats.unbinds.load(Ordering::SeqCst)
+    }
+
+    /// The most submits that were waiting on a response at the same time
+    pub fn max_in_flight(&self) -> usize {
+        self.stats.max_in_flight.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for SmscSimulator {
+    fn drop(&mut self) {
+        self.listener.abort();
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    config: SmscSimulatorConfig,
+    stats: Arc<SimulatorStats>,
+) {
+    let (mut read_half, write_half) = stream.into_split();
+    let writer = Arc::new(Mutex::new(write_half));
+    let mut bound = false;
+
+    while let Ok(Some(pdu)) = read_pdu(&mut read_half).await {
+        let sequence_number = pdu.sequence_number;
+
+        let (status, body) = match pdu.body {
+            PduBody::BindTransmitter(bind) => (
+                check_bind(&config, &bind, bound),
+                PduBody::BindTransmitterResp {
+                    system_id: config.system_id.clone(),
+                },
+            ),
+            PduBody::BindTransceiver(bind) => (
+                check_bind(&config, &bind, bound),
+                PduBody::BindTransceiverResp {
+                    system_id: config.system_id.clone(),
+                },
+            ),
+            PduBody::SubmitSm(_) if !bound => (
+                command_status::ESME_RINVBNDSTS,
+                PduBody::SubmitSmResp {
+                    message_id: String::new(),
+                },
+            ),
+            PduBody::SubmitSm(submit) => {
+                // Submits are answered out of band so a slow SMSC still lets the ESME fill its window
+                tokio::spawn(answer_submit(
+                    submit,
+                    sequence_number,
+                    config.clone(),
+                    stats.clone(),
+                    writer.clone(),
+                ));
+                continue;
+            }
+            PduBody::EnquireLink => {
+                stats.enquire_links.fetch_add(1, Ordering::SeqCst);
+                (command_status::ESME_ROK, PduBody::EnquireLinkResp)
+            }
+            PduBody::Unbind => {
+                stats.unbinds.fetch_add(1, Ordering::SeqCst);
+                (command_status::ESME_ROK, PduBody::UnbindResp)
+            }
+            // Responses the ESME sends back need no answer
+            other if other.command_id() & 0x8000_0000 != 0 => continue,
+            _ => (command_status::ESME_RINVCMDID, PduBody::GenericNack),
+        };
+
+        if matches!(
+            body,
+            PduBody::BindTransmitterResp { .. } | PduBody::BindTransceiverResp { .. }
+        ) && status == command_status::ESME_ROK
+        {
+            bound = true;
+        }
+        let is_unbind = matches!(body, PduBody::UnbindResp);
+
+        let response = Pdu::response(sequence_number, status, body).encode();
+        if writer.lock().await.write_all(&response).await.is_err() || is_unbind {
+            break;
+        }
+    }
+}
+
+fn check_bind(config: &SmscSimulatorConfig, bind: &BindRequest, bound: bool) -> u32 {
+    if bound {
+        command_status::ESME_RALYBND
+    } else if bind.system_id != config.system_id {
+        command_status::ESME_RINVSYSID
+    } else if bind.password != config.password {
+        command_status::ESME_RINVPASWD
+    } else {
+        command_status::ESME_ROK
+    }
+}
+
+async fn answer_submit(
+    submit: SubmitSm,
+    sequence_number: u32,
+    config: SmscSimulatorConfig,
+    stats: Arc<SimulatorStats>,
+    writer: Arc<Mutex<OwnedWriteHalf>>,
+) {
+    let in_flight = stats.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+    stats.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+
+    sleep(config.response_delay).await;
+
+    let status = (config.responder)(&submit);
+    let message_id = if status == command_status::ESME_ROK {
+        format!(
+            "{:08X}",
+            stats.next_message_id.fetch_add(1, Ordering::SeqCst) + 1
+        )
+    } else {
+        String::new()
+    };
+    stats.submitted.lock().unwrap().push(submit);
+
+    stats.in_flight.fetch_sub(1, Ordering::SeqCst);
+    let response = Pdu::response(
+        sequence_number,
+        status,
+        PduBody::SubmitSmResp { message_id },
+    )
+    .encode();
+    let _ = writer.lock().await.write_all(&response).await;
+}
@@ -0,0 +1,30 @@
+use diesel::r2d2::{ConnectionManager, Pool, R2D2Connection};
+
+use super::error::SMSManagerError;
+
+/// Runs database work on Tokio's blocking thread pool, so diesel and r2d2 never stall a runtime worker thread
+/// The connection is checked out on the blocking thread as well, as waiting for a free connection blocks too
+/// Works with the pool of any backend, Postgres or SQLite
+///
+/// # Parameters
+/// - pool: The database pool to retrieve a connection from
+/// - work: The queries to run with the connection
+///
+/// ### Errors if no connection can be retrieved, the work fails, or the blocking task panics
+pub async fn run_blocking<C, T, F>(
+    pool: &Pool<ConnectionManager<C>>,
+    work: F,
+) -> Result<T, SMSManagerError>
+where
+    C: R2D2Connection + 'static,
+    F: FnOnce(&mut C) -> Result<T, SMSManagerError> + Send + 'static,
+    T: Send + 'static,
+{
+    let pool = pool.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut db = pool.get()?;
+        work(&mut db)
+    })
+    .await
+    .map_err(|err| SMSManagerError::GeneralException(format!("Database task failed: {}", err)))?
+}
@@ -0,0 +1,249 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use super::error::SMSManagerError;
+
+/// Where the sender threads read the time from and wait on, so a simulation can run faster than real time
+/// Times and durations going in and out of a clock are in the clocks own time, not wall time
+#[async_trait]
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Waits until the given amount of the clocks time has passed
+    ///
+    /// # Parameters
+    /// - duration: How long to wait in the clocks time
+    async fn sleep(&self, duration: Duration);
+
+    /// Registers a task that will wait on the clock, a discrete event clock only moves once every registered task is waiting
+    fn enter(&self) {}
+
+    /// Unregisters a task registered with enter
+    fn exit(&self) {}
+
+    /// Whether the clock is the wall clock, so its time can be shared with anything else running in real time
+    fn is_real_time(&self) -> bool {
+        false
+    }
+}
+
+/// The time the given duration after the start, or the latest time there is if that can not be represented
+///
+/// # Parameters
+/// - start: The time to count from
+/// - duration: How long after the start
+fn time_after(start: DateTime<Utc>, duration: Duration) -> DateTime<Utc> {
+    chrono::Duration::from_std(duration)
+        .ok()
+        .and_then(|duration| start.checked_add_signed(duration))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// Keeps a task registered with a clock until it is dropped, so a task that stops early can not freeze the clock
+pub struct ClockParticipant {
+    clock: Arc<dyn Clock>,
+}
+
+impl ClockParticipant {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        clock.enter();
+        ClockParticipant { clock }
+    }
+}
+
+impl Drop for ClockParticipant {
+    fn drop(&mut self) {
+        self.clock.exit();
+    }
+}
+
+/// Real time
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+
+    fn is_real_time(&self) -> bool {
+        true
+    }
+}
+
+/// Runs the given number of times faster than real time, starting from the moment it was created
+pub struct AcceleratedClock {
+    speed: f64,
+    started_at: DateTime<Utc>,
+    started: Instant,
+}
+
+impl AcceleratedClock {
+    /// # Parameters
+    /// - speed: How many seconds of the clocks time pass every real second
+    pub fn new(speed: f64) -> Self {
+        AcceleratedClock {
+            speed,
+            started_at: Utc::now(),
+            started: Instant::now(),
+        }
+    }
+}
+
+#[async_trait]
+impl Clock for AcceleratedClock {
+    fn now(&self) -> DateTime<Utc> {
+        // A fast enough clock can run past what a duration holds, it then stays at the latest time there is
+        let elapsed =
+            Duration::try_from_secs_f64(self.started.elapsed().as_secs_f64() * self.speed)
+                .unwrap_or(Duration::MAX);
+        time_after(self.started_at, elapsed)
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        let real_duration = Duration::try_from_secs_f64(duration.as_secs_f64() / self.speed)
+            .unwrap_or(Duration::MAX);
+        tokio::time::sleep(real_duration).await;
+    }
+}
+
+struct DiscreteEventState {
+    now: DateTime<Utc>,
+    participants: usize,
+    next_sleeper: u64,
+    /// When each sleeping task wakes up, earliest first and in the order they went to sleep for ties
+    wake_times: BinaryHeap<Reverse<(DateTime<Utc>, u64)>>,
+    sleepers: HashMap<u64, oneshot::Sender<()>>,
+}
+
+/// Skips straight to the next event instead of waiting for it
+/// Time stands still while any registered task is working and jumps to the earliest wake up once every one of them is asleep,
+/// so each sleep lasts exactly its duration in the clocks time however long the work around it takes in real time
+pub struct DiscreteEventClock {
+    state: Mutex<DiscreteEventState>,
+}
+
+impl Default for DiscreteEventClock {
+    fn default() -> Self {
+        DiscreteEventClock::new()
+    }
+}
+
+impl DiscreteEventClock {
+    /// Creates a clock that starts at the current real time
+    pub fn new() -> Self {
+        DiscreteEventClock {
+            state: Mutex::new(DiscreteEventState {
+                now: Utc::now(),
+                participants: 0,
+                next_sleeper: 0,
+                wake_times: BinaryHeap::new(),
+                sleepers: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Moves time forward to the earliest wake up and wakes everyone due then, if every registered task is asleep
+    fn advance(state: &mut DiscreteEventState) {
+        if state.sleepers.is_empty() || state.sleepers.len() < state.participants {
+            return;
+        }
+
+        let Some(Reverse((wake_at, _))) = state.wake_times.peek().copied() else {
+            return;
+        };
+        state.now = state.now.max(wake_at);
+
+        while let Some(Reverse((due_at, sleeper))) = state.wake_times.peek().copied() {
+            if due_at > wake_at {
+                break;
+            }
+            state.wake_times.pop();
+            if let Some(waker) = state.sleepers.remove(&sleeper) {
+                let _ = waker.send(());
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Clock for DiscreteEventClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.state.lock().unwrap().now
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        let woken = {
+            let mut state = self.state.lock().unwrap();
+            let wake_at = time_after(state.now, duration);
+            let sleeper = state.next_sleeper;
+            state.next_sleeper += 1;
+
+            let (waker, woken) = oneshot::channel();
+            state.wake_times.push(Reverse((wake_at, sleeper)));
+            state.sleepers.insert(sleeper, waker);
+            DiscreteEventClock::advance(&mut state);
+            woken
+        };
+
+        let _ = woken.await;
+    }
+
+    fn enter(&self) {
+        self.state.lock().unwrap().participants += 1;
+    }
+
+    fn exit(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.participants = state.participants.saturating_sub(1);
+        DiscreteEventClock::advance(&mut state);
+    }
+}
+
+/// How fast a simulated transports clock runs, stored as part of its transport configuration
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(tag = "mode", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ClockConfig {
+    #[default]
+    RealTime,
+    /// Runs speed times faster than real time
+    Accelerated { speed: f64 },
+    /// Skips every wait, see DiscreteEventClock
+    Instant,
+}
+
+impl ClockConfig {
+    /// Checks the clock can run
+    ///
+    /// ### Errors if an accelerated clock does not have a positive speed
+    pub fn validate(&self) -> Result<(), SMSManagerError> {
+        match self {
+            ClockConfig::Accelerated { speed } if !speed.is_finite() || *speed <= 0.0 => Err(
+                SMSManagerError::InvalidEncoding("Clock speed must be greater than 0".to_string()),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Creates a new clock running as configured
+    pub fn build(&self) -> Arc<dyn Clock> {
+        match self {
+            ClockConfig::RealTime => Arc::new(SystemClock),
+            ClockConfig::Accelerated { speed } => Arc::new(AcceleratedClock::new(*speed)),
+            ClockConfig::Instant => Arc::new(DiscreteEventClock::new()),
+        }
+    }
+}
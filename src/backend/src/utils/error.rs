@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 
 use axum::{
     http::StatusCode,
@@ -16,6 +16,8 @@ pub enum SMSManagerError {
     GeneralException(String),
     /// A query turned up empty that should not have
     EmptyResult,
+    /// The action can not be taken in the state the resource is in
+    Conflict(String),
 }
 
 impl From<diesel::result::Error> for SMSManagerError {
@@ -36,27 +38,33 @@ impl Debug for SMSManagerError {
     }
 }
 
+impl Display for SMSManagerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SMSManagerError::ConnError(error) => write!(f, "Could not connect to db: {}", error),
+            SMSManagerError::DbError(error) => write!(f, "Misc query error: {}", error),
+            SMSManagerError::InvalidEncoding(reason) => write!(f, "{}", reason),
+            SMSManagerError::GeneralException(error) => write!(f, "{}", error),
+            SMSManagerError::EmptyResult => {
+                write!(f, "Fetched an empty result that should not be!")
+            }
+            SMSManagerError::Conflict(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
 // This centralizes all different errors from our app in one place
 impl IntoResponse for SMSManagerError {
     fn into_response(self) -> Response {
-        let (status, reason) = match self {
-            SMSManagerError::ConnError(error) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Could not connect to db: {}", error),
-            ),
-            SMSManagerError::DbError(error) => (
-                StatusCode::BAD_REQUEST,
-                format!("Misc query error: {}", error),
-            ),
-            SMSManagerError::InvalidEncoding(reason) => (StatusCode::UNPROCESSABLE_ENTITY, reason),
-            SMSManagerError::GeneralException(error) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
-            }
-            SMSManagerError::EmptyResult => (
-                StatusCode::NOT_FOUND,
-                "Fetched an empty result that should not be!".to_string(),
-            ),
+        let status = match self {
+            SMSManagerError::ConnError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            SMSManagerError::DbError(_) => StatusCode::BAD_REQUEST,
+            SMSManagerError::InvalidEncoding(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            SMSManagerError::GeneralException(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            SMSManagerError::EmptyResult => StatusCode::NOT_FOUND,
+            SMSManagerError::Conflict(_) => StatusCode::CONFLICT,
         };
+        let reason = self.to_string();
 
         println!("Routing error: {}: {}", status, reason);
 
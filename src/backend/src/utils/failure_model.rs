@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::error::SMSManagerError;
+
+/// When a simulated delivery fails, every rate and probability is a fraction from 0 - 1
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "model", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FailureModel {
+    /// Every delivery fails independently at the same rate
+    Bernoulli { failure_rate: f64 },
+    /// Gilbert-Elliott two state model, the carrier moves between a good and a bad state before every delivery
+    /// so failures come in bursts whose average length is 1 / exit_burst deliveries
+    GilbertElliott {
+        /// The chance of moving from the good state to the bad state
+        enter_burst: f64,
+        /// The chance of moving from the bad state back to the good state
+        exit_burst: f64,
+        good_failure_rate: f64,
+        bad_failure_rate: f64,
+    },
+    /// Everything fails for outage_secs at the start of every period_secs, such as 30 seconds every 5 minutes
+    Outage {
+        period_secs: u64,
+        outage_secs: u64,
+        /// How far into the send the first outage starts
+        #[serde(default)]
+        offset_secs: u64,
+        /// The failure rate outside of an outage
+        #[serde(default)]
+        failure_rate: f64,
+    },
+    /// The failure rate climbs in a straight line from base to max as more deliveries are in flight at once
+    LoadDependent {
+        base_failure_rate: f64,
+        max_failure_rate: f64,
+        /// How many deliveries in flight it takes to reach the max failure rate
+        saturation: u32,
+    },
+}
+
+/// What a failure model remembers between deliveries
+#[derive(Debug, Default)]
+pub struct FailureState {
+    pub in_burst: bool,
+}
+
+/// What a failure model can base its roll on
+#[derive(Debug, Clone, Copy)]
+pub struct FailureContext {
+    /// How long the transport has been sending for
+    pub elapsed: Duration,
+    /// How many deliveries are in flight, including the one being rolled for
+    pub in_flight: usize,
+}
+
+impl FailureModel {
+    /// Checks every rate is a fraction and the windows make sense
+    ///
+    /// ### Errors if a rate is not within 0 - 1, the outage period is zero or shorter than its outage, or the saturation is zero
+    pub fn validate(&self) -> Result<(), SMSManagerError> {
+        let rates = match self {
+            FailureModel::Bernoulli { failure_rate } => vec![failure_rate],
+            FailureModel::GilbertElliott {
+                enter_burst,
+                exit_burst,
+                good_failure_rate,
+                bad_failure_rate,
+            } => vec![enter_burst, exit_burst, good_failure_rate, bad_failure_rate],
+            FailureModel::Outage {
+                period_secs,
+                outage_secs,
+                failure_rate,
+                ..
+            } => {
+                if *period_secs == 0 || outage_secs > period_secs {
+                    return Err(SMSManagerError::InvalidEncoding(
+                        "Outage period must be greater than 0 and at least as long as the outage"
+                            .to_string(),
+                    ));
+                }
+                vec![failure_rate]
+            }
+            FailureModel::LoadDependent {
+                base_failure_rate,
+                max_failure_rate,
+                saturation,
+            } => {
+                if *saturation == 0 {
+                    return Err(SMSManagerError::InvalidEncoding(
+                        "Load saturation must be greater than or equal to 1".to_string(),
+                    ));
+                }
+                vec![base_failure_rate, max_failure_rate]
+            }
+        };
+
+        if rates.iter().any(|rate| !(0.0..=1.0).contains(*rate)) {
+            return Err(SMSManagerError::InvalidEncoding(
+                "Failure model rates must be within 0 - 1".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Decides whether a delivery fails
+    ///
+    /// # Parameters
+    /// - state: What the model remembers from earlier deliveries, updated in place
+    /// - context: The state of the transport when the delivery finishes
+    /// - rng: The random number generator to roll with
+    ///
+    /// # Returns
+    /// Why the delivery failed, None if it was delivered
+    pub fn roll<R: Rng + ?Sized>(
+        &self,
+        state: &mut FailureState,
+        context: &FailureContext,
+        rng: &mut R,
+    ) -> Option<&'static str> {
+        match self {
+            FailureModel::Bernoulli { failure_rate } => {
+                chance(rng, *failure_rate).then_some("Simulated failure")
+            }
+            FailureModel::GilbertElliott {
+                enter_burst,
+                exit_burst,
+                good_failure_rate,
+                bad_failure_rate,
+            } => {
+                let switch_chance = if state.in_burst {
+                    exit_burst
+                } else {
+                    enter_burst
+                };
+                if chance(rng, *switch_chance) {
+                    state.in_burst = !state.in_burst;
+                }
+
+                if state.in_burst {
+                    chance(rng, *bad_failure_rate).then_some("Simulated failure during a burst")
+                } else {
+                    chance(rng, *good_failure_rate).then_some("Simulated failure")
+                }
+            }
+            FailureModel::Outage {
+                period_secs,
+                outage_secs,
+                offset_secs,
+                failure_rate,
+            } => {
+                let elapsed = context.elapsed.as_secs_f64() - *offset_secs as f64;
+                let into_period = elapsed.rem_euclid(*period_secs as f64);
+                if elapsed >= 0.0 && into_period < *outage_secs as f64 {
+                    return Some("Simulated outage");
+                }
+                chance(rng, *failure_rate).then_some("Simulated failure")
+            }
+            FailureModel::LoadDependent {
+                base_failure_rate,
+                max_failure_rate,
+                saturation,
+            } => {
+                let load = (context.in_flight as f64 / *saturation as f64).min(1.0);
+                let failure_rate =
+                    base_failure_rate + (max_failure_rate - base_failure_rate) * load;
+                chance(rng, failure_rate).then_some("Simulated failure under load")
+            }
+        }
+    }
+}
+
+/// Rolls for something that happens at the given rate, a rate outside 0 - 1 never happens
+fn chance<R: Rng + ?Sized>(rng: &mut R, rate: f64) -> bool {
+    (0.0..=1.0).contains(&rate) && rng.gen_bool(rate)
+}
@@ -0,0 +1,372 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use super::{
+    clock::Clock,
+    rate_limiter::{RateLimit, TokenBucket},
+    sender::UpdaterConfig,
+};
+
+/// The lifecycle states of a background send job
+/// The sender threads watch the state, so it doubles as the signal to pause, resume or cancel them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobState {
+    Running,
+    Paused,
+    Cancelled,
+    Completed,
+    Failed,
+}
+
+impl Display for JobState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            JobState::Running => "RUNNING",
+            JobState::Paused => "PAUSED",
+            JobState::Cancelled => "CANCELLED",
+            JobState::Completed => "COMPLETED",
+            JobState::Failed => "FAILED",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// A send of a producers pending messages running in the background
+/// Counts are updated by the message updater as results are written to the database
+pub struct SendJob {
+    pub id: Uuid,
+    pub producer_id: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub number_messages: usize,
+    number_sent: AtomicUsize,
+    number_failed: AtomicUsize,
+    finished_at: Mutex<Option<DateTime<Utc>>>,
+    error: Mutex<Option<String>>,
+    state: watch::Sender<JobState>,
+    finished: watch::Sender<bool>,
+}
+
+impl SendJob {
+    /// Creates a running job for the given producer
+    ///
+    /// # Parameters
+    /// - producer_id: The producer whose messages are being sent
+    /// - number_messages: The number of pending messages the job will process
+    pub fn new(producer_id: Uuid, number_messages: usize) -> Self {
+        let (state, _) = watch::channel(JobState::Running);
+        let (finished, _) = watch::channel(false);
+        SendJob {
+            id: Uuid::new_v4(),
+            producer_id,
+            started_at: Utc::now(),
+            number_messages,
+            number_sent: AtomicUsize::new(0),
+            number_failed: AtomicUsize::new(0),
+            finished_at: Mutex::new(None),
+            error: Mutex::new(None),
+            state,
+            finished,
+        }
+    }
+
+    pub fn state(&self) -> JobState {
+        *self.state.borrow()
+    }
+
+    pub fn number_sent(&self) -> usize {
+        self.number_sent.load(Ordering::SeqCst)
+    }
+
+    pub fn number_failed(&self) -> usize {
+        self.number_failed.load(Ordering::SeqCst)
+    }
+
+    /// Whether the job has stopped and will not process any more messages
+    pub fn is_finished(&self) -> bool {
+        *self.finished.borrow()
+    }
+
+    pub fn finished_at(&self) -> Option<DateTime<Utc>> {
+        *self.finished_at.lock().unwrap()
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    /// Records a processed message against the jobs counts
+    ///
+    /// # Parameters
+    /// - did_fail: Whether the message failed to send
+    pub fn record_result(&self, did_fail: bool) {
+        self.number_sent.fetch_add(1, Ordering::SeqCst);
+        if did_fail {
+            self.number_failed.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Signals the senders to stop taking new messages once their in flight message is done
+    ///
+    /// # Returns
+    /// Whether the job was running and is now paused
+    pub fn pause(&self) -> bool {
+        self.transition(JobState::Running, JobState::Paused)
+    }
+
+    /// Signals paused senders to continue consuming the queue
+    ///
+    /// # Returns
+    /// Whether the job was paused and is now running
+    pub fn resume(&self) -> bool {
+        self.transition(JobState::Paused, JobState::Running)
+    }
+
+    /// Signals the senders to exit once their in flight message is done, leaving the rest of the queue unsent
+    ///
+    /// # Returns
+    /// Whether the job was running or paused and is now cancelled
+    pub fn cancel(&self) -> bool {
+        !self.is_finished()
+            && (self.transition(JobState::Running, JobState::Cancelled)
+                || self.transition(JobState::Paused, JobState::Cancelled))
+    }
+
+    fn transition(&self, from: JobState, to: JobState) -> bool {
+        self.state.send_if_modified(|state| {
+            if *state != from {
+                return false;
+            }
+            *state = to;
+            true
+        })
+    }
+
+    /// Blocks a sender while the job is paused
+    ///
+    /// # Returns
+    /// Whether the sender should keep consuming the queue, false once the job is cancelled
+    pub async fn wait_while_paused(&self) -> bool {
+        let mut receiver = self.state.subscribe();
+        // The sender lives on self so the channel can not close while we wait
+        let state = match receiver.wait_for(|state| *state != JobState::Paused).await {
+            Ok(state) => *state,
+            Err(_) => return false,
+        };
+        state == JobState::Running
+    }
+
+    /// Marks the job as completed, a cancelled job keeps its cancelled state
+    pub fn complete(&self) {
+        self.state.send_if_modified(|state| {
+            if *state == JobState::Cancelled {
+                return false;
+            }
+            *state = JobState::Completed;
+            true
+        });
+        self.finish();
+    }
+
+    /// Marks the job as failed with the given reason
+    ///
+    /// # Parameters
+    /// - reason: Why the job could not finish
+    pub fn fail(&self, reason: String) {
+        *self.error.lock().unwrap() = Some(reason);
+        self.state.send_replace(JobState::Failed);
+        self.finish();
+    }
+
+    fn finish(&self) {
+        *self.finished_at.lock().unwrap() = Some(Utc::now());
+        self.finished.send_replace(true);
+    }
+
+    /// Waits until the job has stopped processing messages
+    pub async fn wait_until_finished(&self) {
+        let mut receiver = self.finished.subscribe();
+        // The sender lives on self so the channel can not close while we wait
+        let _ = receiver.wait_for(|finished| *finished).await;
+    }
+}
+
+/// How long a finished job can still be looked up before the registry drops it
+pub const FINISHED_JOB_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// The jobs of a registry, indexed by id and the unfinished ones by their producer as well
+#[derive(Default)]
+struct Jobs {
+    by_id: HashMap<Uuid, Arc<SendJob>>,
+    /// The latest job started for each producer, removed once it has finished
+    active_by_producer: HashMap<Uuid, Arc<SendJob>>,
+}
+
+/// Keeps track of the send jobs started by this process so they can be looked up after the request that started them has returned
+/// Finished jobs are kept for finished_job_retention, after which they are dropped the next time a job is started
+#[derive(Clone)]
+pub struct JobRegistry {
+    jobs: Arc<RwLock<Jobs>>,
+    /// How long a finished job can still be looked up
+    finished_job_retention: Duration,
+    /// The limit every producer in the process shares, with the bucket the producers sending in real time take from
+    global_rate_limit: Option<(RateLimit, Arc<TokenBucket>)>,
+    /// Identifies this process as the owner of the producer and message leases its jobs take, a new one every run unless set
+    instance_id: Uuid,
+    /// How every job batches the results it writes to the database
+    updater_config: UpdaterConfig,
+}
+
+impl Default for JobRegistry {
+    fn default() -> Self {
+        JobRegistry {
+            jobs: Arc::default(),
+            finished_job_retention: FINISHED_JOB_RETENTION,
+            global_rate_limit: None,
+            instance_id: Uuid::new_v4(),
+            updater_config: UpdaterConfig::default(),
+        }
+    }
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        JobRegistry::default()
+    }
+
+    /// Creates a registry whose jobs all share the given rate limit, on top of any limit their producer has
+    ///
+    /// # Parameters
+    /// - limit: The most attempts every producer in the process can make together
+    pub fn with_global_rate_limit(limit: RateLimit) -> Self {
+        JobRegistry {
+            global_rate_limit: Some((limit.clone(), Arc::new(TokenBucket::new(limit)))),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the id this process leases producers and messages under, which should stay the same across restarts so the leases it held can be taken back when it starts again
+    ///
+    /// # Parameters
+    /// - instance_id: The id of this server, unique among the servers sharing the database
+    pub fn with_instance_id(self, instance_id: Uuid) -> Self {
+        JobRegistry {
+            instance_id,
+            ..self
+        }
+    }
+
+    /// Sets how every job batches the results it writes to the database
+    ///
+    /// # Parameters
+    /// - updater_config: The batch size and flush interval of the updaters
+    pub fn with_updater_config(self, updater_config: UpdaterConfig) -> Self {
+        JobRegistry {
+            updater_config,
+            ..self
+        }
+    }
+
+    /// Sets how long a finished job can still be looked up before the registry drops it
+    ///
+    /// # Parameters
+    /// - finished_job_retention: How long after finishing a job is kept
+    pub fn with_finished_job_retention(self, finished_job_retention: Duration) -> Self {
+        JobRegistry {
+            finished_job_retention,
+            ..self
+        }
+    }
+
+    /// Gets the bucket of the global rate limit for a send timed by the given clock
+    /// Sends in real time share one bucket, while a send on a simulated clock gets a bucket of its own on that clock, as its time does not pass with anyone elses
+    ///
+    /// # Parameters
+    /// - clock: The clock of the sends pipeline
+    pub fn global_rate_limiter(&self, clock: &Arc<dyn Clock>) -> Option<Arc<TokenBucket>> {
+        let (limit, shared) = self.global_rate_limit.as_ref()?;
+
+        if clock.is_real_time() {
+            return Some(shared.clone());
+        }
+
+        Some(Arc::new(TokenBucket::with_clock(
+            limit.clone(),
+            clock.clone(),
+        )))
+    }
+
+    pub fn instance_id(&self) -> Uuid {
+        self.instance_id
+    }
+
+    pub fn updater_config(&self) -> UpdaterConfig {
+        self.updater_config
+    }
+
+    /// Adds a job to the registry as the active job of its producer, dropping the jobs that finished longer than finished_job_retention ago
+    ///
+    /// # Parameters
+    /// - job: The job that was started
+    pub fn insert(&self, job: Arc<SendJob>) {
+        let mut jobs = self.jobs.write().unwrap();
+
+        jobs.by_id.retain(|_, job| !self.is_expired(job));
+        jobs.active_by_producer.retain(|_, job| !job.is_finished());
+
+        jobs.active_by_producer.insert(job.producer_id, job.clone());
+        jobs.by_id.insert(job.id, job);
+    }
+
+    /// Gets the job with the given id, unless it finished longer than finished_job_retention ago
+    pub fn get(&self, job_id: &Uuid) -> Option<Arc<SendJob>> {
+        self.jobs
+            .read()
+            .unwrap()
+            .by_id
+            .get(job_id)
+            .filter(|job| !self.is_expired(job))
+            .cloned()
+    }
+
+    /// Gets the job that is still processing the messages of the given producer, if any
+    ///
+    /// # Parameters
+    /// - producer_id: The producer to find the unfinished job of
+    pub fn get_active_for_producer(&self, producer_id: &Uuid) -> Option<Arc<SendJob>> {
+        self.jobs
+            .read()
+            .unwrap()
+            .active_by_producer
+            .get(producer_id)
+            .filter(|job| !job.is_finished())
+            .cloned()
+    }
+
+    /// How many jobs the registry holds, finished ones included until they are dropped
+    pub fn len(&self) -> usize {
+        self.jobs.read().unwrap().by_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn is_expired(&self, job: &SendJob) -> bool {
+        job.finished_at().is_some_and(|finished_at| {
+            (Utc::now() - finished_at)
+                .to_std()
+                .is_ok_and(|age| age >= self.finished_job_retention)
+        })
+    }
+}
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use rand::{distributions::WeightedIndex, Rng};
+use rand_distr::{Distribution, Exp, LogNormal, Normal};
+use serde::{Deserialize, Serialize};
+
+use super::error::SMSManagerError;
+
+/// One bar of an empirical latency histogram
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LatencyBucket {
+    pub min_ms: u64,
+    pub max_ms: u64,
+    /// How often a delay lands in this bucket relative to the others
+    pub weight: f64,
+}
+
+/// How long a simulated delivery takes, every delay is in milliseconds
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "model", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LatencyModel {
+    /// Every delivery takes exactly the same time
+    Fixed { delay_ms: u64 },
+    /// Any delay between min and max is as likely as any other
+    Uniform { min_ms: u64, max_ms: u64 },
+    /// Delays cluster around the mean, anything below zero is treated as zero
+    Normal { mean_ms: u64, stddev_ms: u64 },
+    /// Most deliveries are quick but a long tail are slow, sigma is the spread of the logarithm of the delay
+    LogNormal { median_ms: u64, sigma: f64 },
+    /// Delays between independent events such as queueing at a busy carrier
+    Exponential { mean_ms: u64 },
+    /// Delays shaped like measured carrier latency, a bucket is picked by weight and the delay is spread evenly within it
+    Empirical { buckets: Vec<LatencyBucket> },
+}
+
+impl LatencyModel {
+    /// Checks the parameters describe a distribution that can be sampled
+    ///
+    /// ### Errors if a minimum is above its maximum, a spread is negative, a mean or median is zero, or the histogram has no weight
+    pub fn validate(&self) -> Result<(), SMSManagerError> {
+        let invalid = |reason: &str| {
+            Err(SMSManagerError::InvalidEncoding(format!(
+                "Invalid latency model: {}",
+                reason
+            )))
+        };
+
+        match self {
+            LatencyModel::Fixed { .. } => Ok(()),
+            LatencyModel::Uniform { min_ms, max_ms } if min_ms > max_ms => {
+                invalid("min_ms must be less than or equal to max_ms")
+            }
+            LatencyModel::Uniform { .. } | LatencyModel::Normal { .. } => Ok(()),
+            LatencyModel::LogNormal { median_ms, .. } if *median_ms == 0 => {
+                invalid("median_ms must be greater than 0")
+            }
+            LatencyModel::LogNormal { sigma, .. } if !sigma.is_finite() || *sigma < 0.0 => {
+                invalid("sigma must be greater than or equal to 0")
+            }
+            LatencyModel::LogNormal { .. } => Ok(()),
+            LatencyModel::Exponential { mean_ms } if *mean_ms == 0 => {
+                invalid("mean_ms must be greater than 0")
+            }
+            LatencyModel::Exponential { .. } => Ok(()),
+            LatencyModel::Empirical { buckets } => {
+                if buckets.iter().any(|bucket| bucket.min_ms > bucket.max_ms) {
+                    return invalid(
+                        "every bucket's min_ms must be less than or equal to its max_ms",
+                    );
+                }
+                if buckets
+                    .iter()
+                    .any(|bucket| !bucket.weight.is_finite() || bucket.weight < 0.0)
+                {
+                    return invalid("every bucket's weight must be greater than or equal to 0");
+                }
+                if buckets.iter().map(|bucket| bucket.weight).sum::<f64>() <= 0.0 {
+                    return invalid("the buckets must have a total weight greater than 0");
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Draws how long one delivery takes
+    ///
+    /// # Parameters
+    /// - rng: The random number generator to draw with
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Duration {
+        let delay_ms = match self {
+            LatencyModel::Fixed { delay_ms } => *delay_ms as f64,
+            LatencyModel::Uniform { min_ms, max_ms } => rng.gen_range(*min_ms..=*max_ms) as f64,
+            LatencyModel::Normal { mean_ms, stddev_ms } => {
+                Normal::new(*mean_ms as f64, *stddev_ms as f64)
+                    .map(|normal| normal.sample(rng))
+                    .unwrap_or(*mean_ms as f64)
+            }
+            LatencyModel::LogNormal { median_ms, sigma } => {
+                LogNormal::new((*median_ms as f64).ln(), *sigma)
+                    .map(|log_normal| log_normal.sample(rng))
+                    .unwrap_or(*median_ms as f64)
+            }
+            LatencyModel::Exponential { mean_ms } => Exp::new(1.0 / *mean_ms as f64)
+                .map(|exp| exp.sample(rng))
+                .unwrap_or(*mean_ms as f64),
+            LatencyModel::Empirical { buckets } => {
+                match WeightedIndex::new(buckets.iter().map(|bucket| bucket.weight)) {
+                    Ok(index) => {
+                        let bucket = &buckets[index.sample(rng)];
+                        rng.gen_range(bucket.min_ms..=bucket.max_ms) as f64
+                    }
+                    Err(_) => 0.0,
+                }
+            }
+        };
+
+        Duration::try_from_secs_f64(delay_ms.max(0.0) / 1000.0).unwrap_or_default()
+    }
+}
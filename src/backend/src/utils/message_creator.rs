@@ -1,3 +1,4 @@
+use rand::Rng;
 use uuid::Uuid;
 
 use crate::diesel::models::NewMessage;
@@ -9,10 +10,17 @@ use super::random_utils::generate_random_string;
 ///
 /// # Parameters
 /// - producer_id: The id of the producer that is creating the message
-pub fn create_message(producer_id: Uuid) -> NewMessage {
-    let body = generate_random_string();
+/// - message_index: The position of the message among the messages generated with it
+/// - rng: The random number generator to draw the body with
+pub fn create_message<R: Rng + ?Sized>(
+    producer_id: Uuid,
+    message_index: i32,
+    rng: &mut R,
+) -> NewMessage {
+    let body = generate_random_string(rng);
     NewMessage {
         message_body: body,
         produced_by: producer_id,
+        message_index,
     }
 }
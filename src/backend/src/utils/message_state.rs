@@ -0,0 +1,61 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use super::error::SMSManagerError;
+use crate::diesel::models::Message;
+
+/// Where a message is in its delivery, worked out from its sent and failed columns
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MessageState {
+    /// Not sent yet, including messages in flight or waiting on a retry
+    Pending,
+    /// Delivered
+    Sent,
+    /// Every attempt to deliver it failed
+    Failed,
+}
+
+impl MessageState {
+    /// The state of the message
+    pub fn of(message: &Message) -> Self {
+        if !message.sent {
+            MessageState::Pending
+        } else if message.failed {
+            MessageState::Failed
+        } else {
+            MessageState::Sent
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageState::Pending => "PENDING",
+            MessageState::Sent => "SENT",
+            MessageState::Failed => "FAILED",
+        }
+    }
+}
+
+impl Display for MessageState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for MessageState {
+    type Err = SMSManagerError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "PENDING" => Ok(MessageState::Pending),
+            "SENT" => Ok(MessageState::Sent),
+            "FAILED" => Ok(MessageState::Failed),
+            _ => Err(SMSManagerError::InvalidEncoding(format!(
+                "Unknown message state: {}",
+                value
+            ))),
+        }
+    }
+}
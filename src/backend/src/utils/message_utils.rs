@@ -1,43 +1,108 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 use uuid::Uuid;
 
-use crate::diesel::models::{Message, NewMessage};
+use crate::{
+    diesel::models::{Message, NewMessage},
+    repositories::{MessageTimeBucket, ProgressStats},
+};
 
-use super::{error::SMSManagerError, message_creator::create_message};
+use super::{error::SMSManagerError, message_creator::create_message, random_utils::seeded_rng};
 
-/// Gets producer info from list of messages
+/// Gets how long the message took to send in milliseconds
+/// Messages sent before sends were timed in milliseconds only have their time in whole seconds
 ///
 /// # Paramters
-/// - messages: The list of messages that will be used to extract the producers progress from
+/// - message: The message to get the time of
+pub fn get_message_time_ms(message: &Message) -> Option<i32> {
+    message
+        .time_took_ms
+        .or(message.time_took.map(|seconds| seconds * 1000))
+}
+
+/// How many buckets at most the times of a producers messages are spread into
+pub const MESSAGE_TIME_BUCKETS: i64 = 50;
+
+/// Gets the width of the buckets that spread the times from min_ms to max_ms into at most MESSAGE_TIME_BUCKETS buckets of whole milliseconds
+///
+/// # Paramters
+/// - min_ms: The shortest time
+/// - max_ms: The longest time
+pub fn message_time_bucket_width(min_ms: i32, max_ms: i32) -> i64 {
+    let span = max_ms as i64 - min_ms as i64 + 1;
+    ((span + MESSAGE_TIME_BUCKETS - 1) / MESSAGE_TIME_BUCKETS).max(1)
+}
+
+/// Builds the histogram of the times from min_ms to max_ms, filling in the empty buckets between the counted ones
+/// Bucket n holds the times from min_ms + (n - 1) * width up to min_ms + n * width, the same as width_bucket in Postgres
 ///
-/// # Returns
-/// Tuple in format of (number_messages_created, number_messages_failed, message_times, number_messages_sent, average_message_time)
-pub fn get_producer_info_from_messages(messages: Vec<Message>) -> (i32, i32, Vec<i32>, i32, i32) {
-    let number_of_messages = messages.len();
+/// # Paramters
+/// - min_ms: The shortest time
+/// - max_ms: The longest time
+/// - counts: How many times fell in each bucket that has any, by the number of the bucket starting from 1
+pub fn message_time_histogram(
+    min_ms: i32,
+    max_ms: i32,
+    counts: Vec<(i64, i64)>,
+) -> Vec<MessageTimeBucket> {
+    let width = message_time_bucket_width(min_ms, max_ms);
+    let number_buckets = (max_ms as i64 - min_ms as i64 + width) / width;
 
-    let number_of_failed_messages = messages.iter().filter(|val| val.failed).count();
+    (1..=number_buckets)
+        .map(|bucket| {
+            let from_ms = min_ms as i64 + (bucket - 1) * width;
+            MessageTimeBucket {
+                from_ms: from_ms as i32,
+                to_ms: (from_ms + width) as i32,
+                count: counts
+                    .iter()
+                    .filter(|(counted_bucket, _)| *counted_bucket == bucket)
+                    .map(|(_, count)| count)
+                    .sum(),
+            }
+        })
+        .collect()
+}
 
-    let sent_messages = messages
+/// Gets the progress stats of a producer from all of its messages
+///
+/// # Paramters
+/// - messages: Every message of the producer
+pub fn get_progress_stats_from_messages(messages: &[Message]) -> ProgressStats {
+    let retried = messages.iter().filter(|val| val.attempts > 1);
+    let message_times: Vec<i32> = messages
         .iter()
-        .filter(|val| val.sent && val.time_took.is_some());
+        .filter(|val| val.sent)
+        .filter_map(get_message_time_ms)
+        .collect();
 
-    let mut message_times = vec![];
-    let (total_time_for_message, count) = sent_messages.fold((0u32, 0), |(sum, count), item| {
-        message_times.push(item.time_took.unwrap()); // we can unwrap as we checked the value exists in the initial filter
-        (sum + item.time_took.unwrap() as u32, count + 1)
-    });
+    let mut stats = ProgressStats {
+        number_messages_created: messages.len() as i64,
+        number_messages_sent: message_times.len() as i64,
+        number_messages_failed: messages.iter().filter(|val| val.failed).count() as i64,
+        number_messages_retried: retried.clone().count() as i64,
+        number_messages_recovered: retried.filter(|val| val.sent && !val.failed).count() as i64,
+        ..Default::default()
+    };
 
-    let mut average_time_for_message: f64 = 0.0;
-    if count != 0 {
-        average_time_for_message = total_time_for_message as f64 / count as f64
-    }
+    let (Some(&min_ms), Some(&max_ms)) = (message_times.iter().min(), message_times.iter().max())
+    else {
+        return stats;
+    };
+    let total_time: u64 = message_times
+        .iter()
+        .map(|time_ms| (*time_ms).max(0) as u64)
+        .sum();
+    stats.average_message_time_ms = total_time as f64 / message_times.len() as f64;
 
-    (
-        number_of_messages as i32,
-        number_of_failed_messages as i32,
-        message_times,
-        count,
-        average_time_for_message as i32,
-    )
+    let width = message_time_bucket_width(min_ms, max_ms);
+    let counts = message_times
+        .iter()
+        .map(|time_ms| ((*time_ms as i64 - min_ms as i64) / width + 1, 1))
+        .collect();
+    stats.message_time_histogram = message_time_histogram(min_ms, max_ms, counts);
+
+    stats
 }
 
 /// Generates total_messages new objects that can be inserted as messages on the given producer_id
@@ -45,17 +110,87 @@ pub fn get_producer_info_from_messages(messages: Vec<Message>) -> (i32, i32, Vec
 /// # Parameters
 /// - number_messages: The number of messages to generate
 /// - producer_id: The producer that is generating the objects
+/// - seed: Generates the same bodies in the same order every time it is given, None generates different bodies every time
 pub fn generate_fake_messages(
     number_messages: i32,
     producer_id: Uuid,
+    seed: Option<i64>,
 ) -> Result<Vec<NewMessage>, SMSManagerError> {
-    let mut messages = Vec::with_capacity(number_messages.try_into().map_err(|_err| {
-        SMSManagerError::InvalidEncoding("Could not initialize message array".to_string())
-    })?);
+    let chunks = FakeMessageChunks::new(
+        number_messages,
+        producer_id,
+        seed,
+        number_messages.max(1) as usize,
+    )?;
+
+    Ok(chunks.flatten().collect())
+}
+
+/// Generates a producers messages a chunk at a time, so only one chunk is held in memory however many messages there are
+/// The chunks of a seed join up into the same bodies generate_fake_messages gives with the seed
+pub struct FakeMessageChunks {
+    rng: ChaCha8Rng,
+    producer_id: Uuid,
+    /// The index the next message is given, counting from 0 across every chunk
+    next_index: i32,
+    remaining: usize,
+    chunk_size: usize,
+}
+
+impl FakeMessageChunks {
+    /// # Parameters
+    /// - number_messages: The number of messages to generate across every chunk
+    /// - producer_id: The producer that is generating the objects
+    /// - seed: Generates the same bodies in the same order every time it is given, None generates different bodies every time
+    /// - chunk_size: The most messages in a chunk
+    ///
+    /// ### Errors if the number of messages is negative or the chunk size is zero
+    pub fn new(
+        number_messages: i32,
+        producer_id: Uuid,
+        seed: Option<i64>,
+        chunk_size: usize,
+    ) -> Result<Self, SMSManagerError> {
+        let remaining = number_messages.try_into().map_err(|_err| {
+            SMSManagerError::InvalidEncoding("Could not initialize message array".to_string())
+        })?;
+        if chunk_size == 0 {
+            return Err(SMSManagerError::GeneralException(
+                "Chunk size must be greater than 0".to_string(),
+            ));
+        }
 
-    for _ in 0..number_messages {
-        messages.push(create_message(producer_id));
+        Ok(FakeMessageChunks {
+            rng: match seed {
+                Some(seed) => seeded_rng(seed, "messages"),
+                None => ChaCha8Rng::from_entropy(),
+            },
+            producer_id,
+            next_index: 0,
+            remaining,
+            chunk_size,
+        })
     }
+}
+
+impl Iterator for FakeMessageChunks {
+    type Item = Vec<NewMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
 
-    Ok(messages)
+        let size = self.remaining.min(self.chunk_size);
+        self.remaining -= size;
+
+        let first_index = self.next_index;
+        self.next_index += size as i32;
+
+        Some(
+            (first_index..self.next_index)
+                .map(|message_index| create_message(self.producer_id, message_index, &mut self.rng))
+                .collect(),
+        )
+    }
 }
@@ -3,4 +3,15 @@ pub mod message_creator;
 pub mod uuid;
 pub mod random_utils;
 pub mod message_utils;
-pub mod sender;
\ No newline at end of file
+pub mod sender;
+pub mod job_registry;
+pub mod retry_policy;
+pub mod rate_limiter;
+pub mod clock;
+pub mod latency_model;
+pub mod failure_model;
+pub mod work_queue;
+pub mod producer_status;
+pub mod blocking;
+pub mod pagination;
+pub mod message_state;
\ No newline at end of file
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+use super::error::SMSManagerError;
+
+/// How many items a page holds when the request does not say
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+
+/// The most items a page can hold, so one request can not read a whole table
+pub const MAX_PAGE_SIZE: i64 = 500;
+
+/// Which way a listing is sorted
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Checks the page size a request asked for
+///
+/// # Parameters
+/// - limit: The requested page size, None takes DEFAULT_PAGE_SIZE
+///
+/// # Returns
+/// The number of items to put in the page
+///
+/// ### Errors if the limit is not between 1 and MAX_PAGE_SIZE
+pub fn page_size(limit: Option<i64>) -> Result<i64, SMSManagerError> {
+    match limit {
+        None => Ok(DEFAULT_PAGE_SIZE),
+        Some(limit) if (1..=MAX_PAGE_SIZE).contains(&limit) => Ok(limit),
+        Some(_) => Err(SMSManagerError::InvalidEncoding(format!(
+            "Limit must be within 1 - {}",
+            MAX_PAGE_SIZE
+        ))),
+    }
+}
+
+/// Turns the position a page ended at into the opaque cursor handed to the client, hex encoded so it can be put in a url as it is
+///
+/// # Parameters
+/// - position: The position to encode
+pub fn encode_cursor(position: &str) -> String {
+    position
+        .as_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Reads back the position a cursor made by encode_cursor stands for
+///
+/// # Parameters
+/// - cursor: The cursor the client sent
+///
+/// ### Errors if the cursor was not made by encode_cursor
+pub fn decode_cursor(cursor: &str) -> Result<String, SMSManagerError> {
+    let invalid = || SMSManagerError::InvalidEncoding("Cursor Is Invalid".to_string());
+
+    if !cursor.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|index| {
+            cursor
+                .get(index..index + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+        })
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(invalid)?;
+
+    String::from_utf8(bytes).map_err(|_err| invalid())
+}
+
+/// The LIKE pattern matching text that contains the filter, the wildcards in the filter itself are escaped with a backslash
+///
+/// # Parameters
+/// - filter: The text to look for
+pub fn contains_pattern(filter: &str) -> String {
+    format!(
+        "%{}%",
+        filter
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    )
+}
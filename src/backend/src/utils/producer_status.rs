@@ -0,0 +1,231 @@
+use std::{fmt::Display, io::Write, str::FromStr};
+
+use diesel::{
+    deserialize::{self, FromSql, FromSqlRow},
+    expression::AsExpression,
+    pg::{Pg, PgValue},
+    serialize::{self, IsNull, Output, ToSql},
+};
+use serde::{Deserialize, Serialize};
+
+use super::error::SMSManagerError;
+use crate::diesel::schema::sql_types::ProducerStatus as ProducerStatusType;
+
+/// The states a producer moves through, stored as the producer_status Postgres enum, or by name in SQLite
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = ProducerStatusType)]
+#[cfg_attr(
+    feature = "sqlite",
+    diesel(sql_type = crate::diesel::sqlite_schema::sql_types::ProducerStatus)
+)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ProducerStatus {
+    /// Created, no messages have been generated yet
+    Inactive,
+    Generating,
+    /// Has messages waiting to be sent
+    Generated,
+    Sending,
+    Paused,
+    /// Its send was cancelled, the unsent messages wait for the next send
+    Cancelled,
+    /// Every message has been sent
+    Empty,
+    /// Its send was cut off by the server stopping, see recovery_services, or by the send failing
+    Interrupted,
+}
+
+/// Everything that can be done to a producer that depends on its status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProducerAction {
+    /// Changing any of its settings
+    Update,
+    Generate,
+    FinishGenerating,
+    /// Starting a send, or joining the send another server sharing the storage is running
+    Send,
+    Pause,
+    Resume,
+    Cancel,
+    /// Its last message being sent
+    FinishSending,
+    /// The server stopping while it was sending or paused, or its send failing
+    Interrupt,
+    Delete,
+}
+
+/// The statuses nothing is running for, so its settings and messages can be changed
+const SETTLED: &[ProducerStatus] = &[
+    ProducerStatus::Inactive,
+    ProducerStatus::Generated,
+    ProducerStatus::Cancelled,
+    ProducerStatus::Empty,
+    ProducerStatus::Interrupted,
+];
+
+impl ProducerAction {
+    /// The transition table, every status the action can be taken from and the status it moves the producer to
+    /// None leaves the status as it is
+    pub fn transition(&self) -> (&'static [ProducerStatus], Option<ProducerStatus>) {
+        match self {
+            ProducerAction::Update | ProducerAction::Delete => (SETTLED, None),
+            ProducerAction::Generate => (SETTLED, Some(ProducerStatus::Generating)),
+            ProducerAction::FinishGenerating => (
+                &[ProducerStatus::Generating],
+                Some(ProducerStatus::Generated),
+            ),
+            // A producer that is SENDING can be sent by another server as well, on purpose, so several servers share its messages
+            // Only the server already sending it refuses, see producer_services::claim_producer
+            ProducerAction::Send => (
+                &[
+                    ProducerStatus::Generated,
+                    ProducerStatus::Sending,
+                    ProducerStatus::Cancelled,
+                    ProducerStatus::Empty,
+                    ProducerStatus::Interrupted,
+                ],
+                Some(ProducerStatus::Sending),
+            ),
+            ProducerAction::Pause => (&[ProducerStatus::Sending], Some(ProducerStatus::Paused)),
+            ProducerAction::Resume => (&[ProducerStatus::Paused], Some(ProducerStatus::Sending)),
+            ProducerAction::Cancel => (
+                &[ProducerStatus::Sending, ProducerStatus::Paused],
+                Some(ProducerStatus::Cancelled),
+            ),
+            ProducerAction::FinishSending => {
+                (&[ProducerStatus::Sending], Some(ProducerStatus::Empty))
+            }
+            ProducerAction::Interrupt => (
+                &[ProducerStatus::Sending, ProducerStatus::Paused],
+                Some(ProducerStatus::Interrupted),
+            ),
+        }
+    }
+
+    /// Every status the action can be taken from
+    pub fn allowed_from(&self) -> &'static [ProducerStatus] {
+        self.transition().0
+    }
+}
+
+impl Display for ProducerAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self {
+            ProducerAction::Update => "update",
+            ProducerAction::Generate => "generate",
+            ProducerAction::FinishGenerating => "finish generating",
+            ProducerAction::Send => "send",
+            ProducerAction::Pause => "pause",
+            ProducerAction::Resume => "resume",
+            ProducerAction::Cancel => "cancel",
+            ProducerAction::FinishSending => "finish sending",
+            ProducerAction::Interrupt => "interrupt",
+            ProducerAction::Delete => "delete",
+        };
+        write!(f, "{}", action)
+    }
+}
+
+impl ProducerStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProducerStatus::Inactive => "INACTIVE",
+            ProducerStatus::Generating => "GENERATING",
+            ProducerStatus::Generated => "GENERATED",
+            ProducerStatus::Sending => "SENDING",
+            ProducerStatus::Paused => "PAUSED",
+            ProducerStatus::Cancelled => "CANCELLED",
+            ProducerStatus::Empty => "EMPTY",
+            ProducerStatus::Interrupted => "INTERRUPTED",
+        }
+    }
+
+    /// Checks the action can be taken on a producer with this status
+    ///
+    /// # Parameters
+    /// - action: The action to take
+    ///
+    /// # Returns
+    /// The status the producer moves to
+    ///
+    /// ### Errors with a conflict naming this status and the one the action asks for if the transition table does not allow it
+    pub fn apply(self, action: ProducerAction) -> Result<ProducerStatus, SMSManagerError> {
+        let (allowed_from, next) = action.transition();
+
+        if allowed_from.contains(&self) {
+            return Ok(next.unwrap_or(self));
+        }
+
+        Err(SMSManagerError::Conflict(match next {
+            Some(next) => format!("Can not move the producer from {} to {}", self, next),
+            None => format!("Can not {} the producer while it is {}", action, self),
+        }))
+    }
+}
+
+impl Display for ProducerStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ProducerStatus {
+    type Err = SMSManagerError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "INACTIVE" => Ok(ProducerStatus::Inactive),
+            "GENERATING" => Ok(ProducerStatus::Generating),
+            "GENERATED" => Ok(ProducerStatus::Generated),
+            "SENDING" => Ok(ProducerStatus::Sending),
+            "PAUSED" => Ok(ProducerStatus::Paused),
+            "CANCELLED" => Ok(ProducerStatus::Cancelled),
+            "EMPTY" => Ok(ProducerStatus::Empty),
+            "INTERRUPTED" => Ok(ProducerStatus::Interrupted),
+            _ => Err(SMSManagerError::InvalidEncoding(format!(
+                "Unknown producer status: {}",
+                value
+            ))),
+        }
+    }
+}
+
+impl ToSql<ProducerStatusType, Pg> for ProducerStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<ProducerStatusType, Pg> for ProducerStatus {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let value = std::str::from_utf8(bytes.as_bytes())?;
+        value
+            .parse()
+            .map_err(|err: SMSManagerError| err.to_string().into())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl ToSql<crate::diesel::sqlite_schema::sql_types::ProducerStatus, diesel::sqlite::Sqlite>
+    for ProducerStatus
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, diesel::sqlite::Sqlite>) -> serialize::Result {
+        out.set_value(self.as_str());
+        Ok(IsNull::No)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl FromSql<crate::diesel::sqlite_schema::sql_types::ProducerStatus, diesel::sqlite::Sqlite>
+    for ProducerStatus
+{
+    fn from_sql(mut value: diesel::sqlite::SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        value
+            .read_text()
+            .parse()
+            .map_err(|err: SMSManagerError| err.to_string().into())
+    }
+}
@@ -1,4 +1,7 @@
-use rand::{distributions::Alphanumeric, Rng};
+use rand::{distributions::Alphanumeric, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::diesel::models::Message;
 
 /// Returns a true or false value at the given percentage to be false
 /// If invalid percentage, defaults the percentage to 50
@@ -16,10 +19,12 @@ pub fn random_chance(mut percentage: i32) -> bool {
 }
 
 /// Generates a random alphanumeric string between 0 and 100 characters long
-pub fn generate_random_string() -> String {
-    let length = rand::thread_rng().gen_range(0..=100);
-    rand::thread_rng()
-        .sample_iter(&Alphanumeric)
+///
+/// # Parameters
+/// - rng: The random number generator to draw with
+pub fn generate_random_string<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let length = rng.gen_range(0..=100);
+    rng.sample_iter(&Alphanumeric)
         .take(length)
         .map(char::from)
         .collect()
@@ -29,9 +34,10 @@ pub fn generate_random_string() -> String {
 /// 
 /// # Parameters
 /// - average: The average to base the time around
-pub fn get_random_wait_time(average: &i32) -> u64 {
-    let random_change = rand::thread_rng().gen_range(0..=5);
-    let random_sign = rand::thread_rng().gen_range(-1..=1);
+/// - rng: The random number generator to draw with
+pub fn get_random_wait_time<R: Rng + ?Sized>(average: &i32, rng: &mut R) -> u64 {
+    let random_change = rng.gen_range(0..=5);
+    let random_sign = rng.gen_range(-1..=1);
     let mut wait_time = average + (random_sign * random_change);
     if wait_time < 0 {
         wait_time = *average;
@@ -39,3 +45,39 @@ pub fn get_random_wait_time(average: &i32) -> u64 {
 
     wait_time as u64
 }
+
+/// Creates a random number generator that gives the same numbers every time it is created with the same seed and key
+/// Different keys give independent streams, so adding a draw to one use of the seed does not shift any other
+///
+/// # Parameters
+/// - seed: The seed of the run
+/// - key: What the numbers will be used for
+pub fn seeded_rng(seed: i64, key: &str) -> ChaCha8Rng {
+    // FNV-1a, unlike the standard library hasher it is guaranteed to give the same hash on every platform and release
+    let hash = seed
+        .to_le_bytes()
+        .iter()
+        .chain(key.as_bytes())
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        });
+    ChaCha8Rng::seed_from_u64(hash)
+}
+
+/// Gets the random number generator to draw the values of one attempt at a message from
+/// With a seed the values only depend on the seed, the index of the message and the attempt, so they do not change with the order the senders take messages in
+/// The index is given at generation, so a producer generated again with the same seed draws the same values, while messages that share a body, such as the empty ones, still draw their own
+///
+/// # Parameters
+/// - seed: The seed of the run, None draws fresh random numbers
+/// - message: The message being attempted
+/// - purpose: What the numbers will be used for, such as latency
+pub fn message_rng(seed: Option<i64>, message: &Message, purpose: &str) -> ChaCha8Rng {
+    match seed {
+        Some(seed) => seeded_rng(
+            seed,
+            &format!("{}:{}:{}", purpose, message.attempts, message.message_index),
+        ),
+        None => ChaCha8Rng::from_entropy(),
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    clock::{Clock, SystemClock},
+    error::SMSManagerError,
+};
+
+/// The window a rate limits messages over
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RatePeriod {
+    #[default]
+    Second,
+    Minute,
+}
+
+impl RatePeriod {
+    fn as_secs(&self) -> f64 {
+        match self {
+            RatePeriod::Second => 1.0,
+            RatePeriod::Minute => 60.0,
+        }
+    }
+}
+
+/// A cap on how many delivery attempts are made, such as 10 messages per second with bursts of 20
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RateLimit {
+    /// The sustained number of attempts allowed every period
+    pub rate: u32,
+    #[serde(default)]
+    pub per: RatePeriod,
+    /// How many attempts can be made back to back after the limit has sat idle, 1 spaces every attempt out evenly
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+}
+
+fn default_burst() -> u32 {
+    1
+}
+
+impl TryFrom<&serde_json::Value> for RateLimit {
+    type Error = SMSManagerError;
+
+    fn try_from(value: &serde_json::Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value.clone())
+            .map_err(|err| SMSManagerError::InvalidEncoding(format!("Invalid rate limit: {}", err)))
+    }
+}
+
+impl RateLimit {
+    /// Checks the limit lets messages through
+    ///
+    /// ### Errors if the rate or burst < 1
+    pub fn validate(&self) -> Result<(), SMSManagerError> {
+        if self.rate < 1 || self.burst < 1 {
+            return Err(SMSManagerError::GeneralException(
+                "Rate limit rate and burst must be greater than or equal to 1".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// How many attempts the limit allows every second
+    pub fn per_second(&self) -> f64 {
+        self.rate as f64 / self.per.as_secs()
+    }
+}
+
+struct BucketState {
+    /// Goes below zero while callers are waiting on tokens they have reserved
+    tokens: f64,
+    last_refill: DateTime<Utc>,
+}
+
+/// Enforces a rate limit with a token bucket that refills at the sustained rate and holds up to the burst
+/// Every caller reserves the next token as it arrives, so callers are let through in the order they arrived and a busy sender can not starve the others
+pub struct TokenBucket {
+    limit: RateLimit,
+    clock: Arc<dyn Clock>,
+    state: Mutex<BucketState>,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket that refills in real time, so the first burst goes out straight away
+    ///
+    /// # Parameters
+    /// - limit: The rate limit to enforce
+    pub fn new(limit: RateLimit) -> Self {
+        TokenBucket::with_clock(limit, Arc::new(SystemClock))
+    }
+
+    /// Creates a full bucket that refills as the given clock moves
+    ///
+    /// # Parameters
+    /// - limit: The rate limit to enforce
+    /// - clock: The clock to measure the rate against and wait on
+    pub fn with_clock(limit: RateLimit, clock: Arc<dyn Clock>) -> Self {
+        TokenBucket {
+            state: Mutex::new(BucketState {
+                tokens: limit.burst as f64,
+                last_refill: clock.now(),
+            }),
+            limit,
+            clock,
+        }
+    }
+
+    pub fn limit(&self) -> &RateLimit {
+        &self.limit
+    }
+
+    /// Waits until the limit allows another attempt and takes it
+    pub async fn acquire(&self) {
+        let wait = {
+            let mut state = self.state.lock().unwrap();
+            let now = self.clock.now();
+            let elapsed = (now - state.last_refill).to_std().unwrap_or_default();
+            state.tokens = (state.tokens + elapsed.as_secs_f64() * self.limit.per_second())
+                .min(self.limit.burst as f64);
+            state.last_refill = now;
+
+            state.tokens -= 1.0;
+            -state.tokens.min(0.0) / self.limit.per_second()
+        };
+
+        if wait > 0.0 {
+            self.clock.sleep(Duration::from_secs_f64(wait)).await;
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::transports::FailureKind;
+
+use super::error::SMSManagerError;
+
+/// How a producer retries messages that failed to deliver, stored as JSON on the producer
+/// The default makes a single attempt, so a producer only retries once a policy is set
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// The most times a message is attempted, including the first attempt
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: i32,
+    /// How long to wait before the first retry
+    #[serde(default = "default_base_delay_ms")]
+    pub base_delay_ms: u64,
+    /// How much the wait grows by after every retry
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    /// The longest to wait before any retry
+    #[serde(default = "default_max_delay_ms")]
+    pub max_delay_ms: u64,
+    /// The fraction the wait is randomly moved up or down by, so failed messages do not all retry at once
+    #[serde(default)]
+    pub jitter: f64,
+    /// The kinds of failure that are worth another attempt
+    #[serde(default = "default_retryable")]
+    pub retryable: Vec<FailureKind>,
+}
+
+fn default_max_attempts() -> i32 {
+    1
+}
+
+fn default_base_delay_ms() -> u64 {
+    1000
+}
+
+fn default_multiplier() -> f64 {
+    2.0
+}
+
+fn default_max_delay_ms() -> u64 {
+    60_000
+}
+
+fn default_retryable() -> Vec<FailureKind> {
+    vec![
+        FailureKind::Connection,
+        FailureKind::Timeout,
+        FailureKind::Throttled,
+        FailureKind::ServerError,
+        FailureKind::Simulated,
+    ]
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: default_max_attempts(),
+            base_delay_ms: default_base_delay_ms(),
+            multiplier: default_multiplier(),
+            max_delay_ms: default_max_delay_ms(),
+            jitter: 0.0,
+            retryable: default_retryable(),
+        }
+    }
+}
+
+impl TryFrom<&serde_json::Value> for RetryPolicy {
+    type Error = SMSManagerError;
+
+    fn try_from(value: &serde_json::Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value.clone()).map_err(|err| {
+            SMSManagerError::InvalidEncoding(format!("Invalid retry policy: {}", err))
+        })
+    }
+}
+
+impl RetryPolicy {
+    /// Checks the policy makes sense
+    ///
+    /// ### Errors if max attempts < 1, multiplier < 1, or jitter is not between 0 and 1
+    pub fn validate(&self) -> Result<(), SMSManagerError> {
+        if self.max_attempts < 1 {
+            return Err(SMSManagerError::GeneralException(
+                "Max attempts must be greater than or equal to 1".to_string(),
+            ));
+        }
+
+        if self.multiplier.is_nan() || self.multiplier < 1.0 {
+            return Err(SMSManagerError::GeneralException(
+                "Retry multiplier must be greater than or equal to 1".to_string(),
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err(SMSManagerError::GeneralException(
+                "Retry jitter must be within 0 - 1".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Decides whether a failed message gets another attempt, and how long to wait before it
+    ///
+    /// # Parameters
+    /// - kind: Why the last attempt failed
+    /// - attempts_made: How many times the message has been attempted so far
+    ///
+    /// # Returns
+    /// The wait before the next attempt, None if the message has failed for good
+    pub fn retry_delay(&self, kind: FailureKind, attempts_made: i32) -> Option<Duration> {
+        self.retry_delay_with(kind, attempts_made, &mut rand::thread_rng())
+    }
+
+    /// Works out the wait before the next attempt like retry_delay, drawing the jitter from the given random number generator
+    ///
+    /// # Parameters
+    /// - kind: Why the last attempt failed
+    /// - attempts_made: How many times the message has been attempted so far
+    /// - rng: The random number generator to draw the jitter with
+    pub fn retry_delay_with<R: Rng + ?Sized>(
+        &self,
+        kind: FailureKind,
+        attempts_made: i32,
+        rng: &mut R,
+    ) -> Option<Duration> {
+        if attempts_made >= self.max_attempts || !self.retryable.contains(&kind) {
+            return None;
+        }
+
+        Some(self.backoff(attempts_made, rng.gen_range(-1.0..=1.0)))
+    }
+
+    /// The wait before the retry that follows the given number of attempts
+    ///
+    /// # Parameters
+    /// - attempts_made: How many times the message has been attempted so far
+    /// - jitter_roll: Where within the jitter range the wait lands, from -1 to 1
+    pub fn backoff(&self, attempts_made: i32, jitter_roll: f64) -> Duration {
+        let exponent = (attempts_made - 1).max(0);
+        let delay = self.base_delay_ms as f64 * self.multiplier.powi(exponent);
+        let capped = delay.min(self.max_delay_ms as f64);
+        let jittered = capped * (1.0 + self.jitter * jitter_roll.clamp(-1.0, 1.0));
+
+        Duration::from_millis(jittered.max(0.0) as u64)
+    }
+}
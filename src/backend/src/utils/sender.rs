@@ -1,143 +1,330 @@
-use std::{
-    collections::VecDeque,
-    sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
-    },
-    time::{Duration, SystemTime},
-};
+use std::{collections::HashMap, future::Future, sync::Arc, time::Duration};
 
-use diesel::{query_dsl::methods::FindDsl, ExpressionMethods, RunQueryDsl};
 use tokio::{
-    sync::{
-        mpsc::{self, Receiver, Sender},
-        Mutex, Notify,
-    },
-    task::JoinHandle,
-    time::sleep,
+    sync::mpsc::{self, Receiver, Sender},
+    task::{JoinError, JoinHandle},
+    time::MissedTickBehavior,
 };
 
 use crate::{
-    diesel::{
-        models::{Message, Producer},
-        schema::{
-            messages::{dsl::*, failed, sent, time_took},
-            producers::{dsl::*, status},
-        },
-    },
+    diesel::models::{Message, NewDeliveryAttempt, Producer},
+    repositories::MessageRepository,
+    transports::{build_transport, DeliveryOutcome, MessageTransport, TransportConfig},
     utils::{
+        clock::{Clock, ClockParticipant},
         error::SMSManagerError,
-        random_utils::{get_random_wait_time, random_chance},
+        job_registry::SendJob,
+        random_utils::message_rng,
+        rate_limiter::{RateLimit, TokenBucket},
+        retry_policy::RetryPolicy,
+        work_queue::WorkQueue,
     },
-    PoolHandle,
 };
 
+/// How many results the updater holds before writing them by default
+pub const DEFAULT_UPDATE_BATCH_SIZE: usize = 100;
+
+/// The most results the updater can write at once, each message takes 10 of the 65535 parameters a Postgres statement can have
+pub const MAX_UPDATE_BATCH_SIZE: usize = 5000;
+
+/// How long a result waits to be written by default when the batch does not fill up
+pub const DEFAULT_UPDATE_FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How many times the updater tries to write a batch before it gives up and fails the send
+pub const FLUSH_ATTEMPTS: usize = 3;
+
+/// How long the updater waits before trying a failed write again
+pub const FLUSH_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// How the updater batches its writes to the database
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UpdaterConfig {
+    /// The results held before they are written together
+    pub batch_size: usize,
+    /// The longest a result is held before it is written, however few results there are
+    pub flush_interval: Duration,
+}
+
+impl Default for UpdaterConfig {
+    fn default() -> Self {
+        UpdaterConfig {
+            batch_size: DEFAULT_UPDATE_BATCH_SIZE,
+            flush_interval: DEFAULT_UPDATE_FLUSH_INTERVAL,
+        }
+    }
+}
+
+impl UpdaterConfig {
+    /// Checks the batches can be written
+    ///
+    /// ### Errors if the batch size is not within 1 - MAX_UPDATE_BATCH_SIZE or the flush interval is zero
+    pub fn validate(&self) -> Result<(), SMSManagerError> {
+        if !(1..=MAX_UPDATE_BATCH_SIZE).contains(&self.batch_size) {
+            return Err(SMSManagerError::GeneralException(format!(
+                "Update batch size must be within 1 - {}",
+                MAX_UPDATE_BATCH_SIZE
+            )));
+        }
+        if self.flush_interval.is_zero() {
+            return Err(SMSManagerError::GeneralException(
+                "Update flush interval must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Everything a sender thread needs to deliver a message, built from the producers settings
+pub struct DeliveryPipeline {
+    pub transport: Arc<dyn MessageTransport>,
+    pub retry_policy: RetryPolicy,
+    /// Every limit an attempt must get through before it is made, such as the producers own and the process wide limit
+    pub rate_limiters: Vec<Arc<TokenBucket>>,
+    /// Where attempts are timed and waits are made, so a simulated producer can run faster than real time
+    pub clock: Arc<dyn Clock>,
+    /// Draws the retry jitter of each message from the seed, so retries wait the same on every run with the seed
+    pub seed: Option<i64>,
+    /// How the results of the attempts are written to the database
+    pub updater: UpdaterConfig,
+}
+
+impl DeliveryPipeline {
+    /// Builds the pipeline configured on the producer
+    ///
+    /// # Parameters
+    /// - producer: The producer whose messages will be delivered through the pipeline
+    ///
+    /// ### Errors if the stored transport, retry policy or rate limit is invalid
+    pub fn for_producer(producer: &Producer) -> Result<Self, SMSManagerError> {
+        let clock = TransportConfig::try_from(&producer.transport)?.build_clock();
+
+        let mut rate_limiters = vec![];
+        if let Some(value) = &producer.rate_limit {
+            let rate_limit = RateLimit::try_from(value)?;
+            rate_limit.validate()?;
+            rate_limiters.push(Arc::new(TokenBucket::with_clock(rate_limit, clock.clone())));
+        }
+
+        Ok(DeliveryPipeline {
+            transport: build_transport(producer, clock.clone())?,
+            retry_policy: RetryPolicy::try_from(&producer.retry_policy)?,
+            rate_limiters,
+            clock,
+            seed: producer.seed,
+            updater: UpdaterConfig::default(),
+        })
+    }
+
+    /// Waits until every rate limit allows another attempt
+    pub async fn wait_for_rate_limits(&self) {
+        for rate_limiter in &self.rate_limiters {
+            rate_limiter.acquire().await;
+        }
+    }
+}
+
+/// What a sender thread hands the updater after every attempt
+#[derive(Debug, Clone)]
+pub struct DeliveryUpdate {
+    /// The message as it should be stored after the attempt
+    pub message: Message,
+    /// The history row recording the attempt
+    pub attempt: NewDeliveryAttempt,
+}
+
 /// Chatgpt helped me with the general architecture of this setup
 /// Multi Producer Single Consumer architecuture for sending out many messages as individual threads, but only updating the database with one to not take up too many databse connnections
 /// # Parameters
 /// - queue: The queue of messages to consume
-/// - pool: The database pool to retrieve a datbase connection from
-/// - producer: The producer the messages are generated from
+/// - messages: The repository to write the results of the attempts to
+/// - pipeline: How the messages are delivered
 /// - num_threads: The number of threads used to consume the queue
+/// - job: The job tracking this send
+///
+/// ### Errors if a sender could not take its next message from the queue, or the results could not be written
 pub async fn send_messages(
-    queue: Arc<Mutex<VecDeque<Message>>>,
-    pool: Arc<PoolHandle>,
-    producer: &Producer,
+    queue: Arc<dyn WorkQueue>,
+    messages: Arc<dyn MessageRepository>,
+    pipeline: Arc<DeliveryPipeline>,
     num_threads: i32,
-) {
-    let mut handles: Vec<JoinHandle<()>> = vec![];
-    let (tx, rx) = mpsc::channel::<Message>(100);
-    let active_threads = Arc::new(AtomicUsize::new(num_threads.try_into().unwrap())); // Verified it is positive integer earlier
-    let notify: Arc<Notify> = Arc::new(Notify::new());
+    job: Arc<SendJob>,
+) -> Result<(), SMSManagerError> {
+    let mut handles: Vec<JoinHandle<Result<(), SMSManagerError>>> = vec![];
+    let (tx, rx) = mpsc::channel::<DeliveryUpdate>(100);
 
-    handles.extend(get_senders(
-        queue,
-        producer,
-        &tx,
-        active_threads,
-        &notify,
-        num_threads,
-    ));
+    handles.extend(get_senders(queue, &pipeline, &tx, num_threads, &job));
 
-    handles.push(get_message_updater(rx, pool));
+    let updater = get_message_updater(rx, messages, job, pipeline.updater);
 
-    notify.notified().await;
+    // Every sender is waited on before returning, so none is left running after the first one fails
+    // A sender that panicked fails the send when its handle is waited on
+    let mut sent = Ok(());
+    for handle in handles {
+        let result = task_result("sender", handle.await);
+        if sent.is_ok() {
+            sent = result;
+        }
+    }
 
     drop(tx);
 
-    for handle in handles {
-        handle.await.unwrap();
-    }
+    // A failed updater stops the senders too, so its error is the one reported
+    let result = task_result("updater", updater.await).and(sent);
+
+    pipeline.transport.close().await;
+
+    result
+}
+
+/// The result of a task of the send, a task that panicked or was aborted is an error like any other
+///
+/// # Parameters
+/// - task: What the task was doing, for the error
+/// - joined: What awaiting the tasks handle gave
+fn task_result(
+    task: &str,
+    joined: Result<Result<(), SMSManagerError>, JoinError>,
+) -> Result<(), SMSManagerError> {
+    joined.unwrap_or_else(|err| {
+        Err(SMSManagerError::GeneralException(format!(
+            "The {} stopped unexpectedly: {}",
+            task, err
+        )))
+    })
 }
 
-/// Consumes the queued messages by instantiating the given number of threads. As each message is processed, it adds the updated message to the sender
+/// Consumes the queued messages by instantiating the given number of threads. As each message is attempted, it adds the updated message and the attempt to the sender
 ///
 /// # Parameters
 /// - queue: The queue of messages to consume
-/// - producer: The producer the messages are generated from
-/// - tx: The sender of the updated messages once processed
-/// - active_threads: The active threads used to be notified of completion
-/// - notify: Notifier that is called once the thread has completed consuming the queue
+/// - pipeline: How each thread delivers its messages
+/// - tx: The sender of the updated messages after every attempt
 /// - num_threads: The number of threads to create
+/// - job: The job whose state pauses or cancels the threads between messages
 ///
 /// # Returns
-/// The handles of the created threads
+/// The handles of the created threads, a thread that could not take its next message from the queue or hand over a result stops with the error
 pub fn get_senders(
-    queue: Arc<Mutex<VecDeque<Message>>>,
-    producer: &Producer,
-    tx: &Sender<Message>,
-    active_threads: Arc<AtomicUsize>,
-    notify: &Arc<Notify>,
+    queue: Arc<dyn WorkQueue>,
+    pipeline: &Arc<DeliveryPipeline>,
+    tx: &Sender<DeliveryUpdate>,
     num_threads: i32,
-) -> Vec<JoinHandle<()>> {
+    job: &Arc<SendJob>,
+) -> Vec<JoinHandle<Result<(), SMSManagerError>>> {
     let mut handles = vec![];
 
     // Spawn the threads and process the queue
-    for _ in 0..num_threads {
+    for worker_id in 0..num_threads {
         let queue = Arc::clone(&queue);
-        let producer = producer.clone();
+        let pipeline = Arc::clone(pipeline);
         let tx = tx.clone(); // Clone the sender for each thread
-        let active_threads = Arc::clone(&active_threads);
-        let notify = Arc::clone(notify);
+        let job = Arc::clone(job);
 
         let handle = tokio::spawn(async move {
-            while let Some(item) = {
-                let mut q = queue.lock().await;
-                q.pop_front()
-            } {
+            let participant = ClockParticipant::new(pipeline.clock.clone());
+
+            let result = 'messages: loop {
+                // Waiting out a pause before taking the next message lets in flight messages drain while the rest stay queued
+                if !job.wait_while_paused().await {
+                    break Ok(());
+                }
+
+                let mut item = match queue.next().await {
+                    Ok(Some(item)) => item,
+                    Ok(None) => break Ok(()),
+                    Err(err) => break Err(err),
+                };
+
                 println!("Processing item: {}", item.id);
 
-                let begin_time = SystemTime::now();
+                loop {
+                    pipeline.wait_for_rate_limits().await;
 
-                let wait_time = get_random_wait_time(&producer.average_send_delay);
+                    let started_at = pipeline.clock.now();
 
-                // Non-blocking async sleep
-                sleep(Duration::from_secs(wait_time)).await;
+                    let outcome = pipeline.transport.send(&item).await;
 
-                let time = SystemTime::now()
-                    .duration_since(begin_time)
-                    .unwrap()
-                    .as_secs() as i32;
+                    let finished_at = pipeline.clock.now();
+                    let time_ms = (finished_at - started_at).num_milliseconds() as i32;
 
-                let did_fail = random_chance(producer.failure_rate);
+                    item.attempts += 1;
 
-                let updated_message = Message {
-                    id: item.id,
-                    sent: true,
-                    time_took: Some(time),
-                    failed: did_fail,
-                    message_body: item.message_body,
-                    produced_by: item.produced_by,
-                };
+                    let (retry_delay, error_code, error_reason) = match &outcome {
+                        DeliveryOutcome::Failed { kind, reason } => {
+                            println!(
+                                "Attempt {} failed to deliver item {}: {}",
+                                item.attempts, item.id, reason
+                            );
+                            (
+                                pipeline.retry_policy.retry_delay_with(
+                                    *kind,
+                                    item.attempts,
+                                    &mut message_rng(pipeline.seed, &item, "retry"),
+                                ),
+                                Some(kind.to_string()),
+                                Some(reason.to_string()),
+                            )
+                        }
+                        DeliveryOutcome::Delivered { .. } => (None, None, None),
+                    };
+
+                    let attempt = NewDeliveryAttempt {
+                        message_id: item.id,
+                        attempt_number: item.attempts,
+                        started_at,
+                        finished_at,
+                        outcome: if outcome.is_failure() {
+                            "FAILED".to_string()
+                        } else {
+                            "DELIVERED".to_string()
+                        },
+                        error_code,
+                        error_reason,
+                        worker_id,
+                    };
 
-                if tx.send(updated_message).await.is_err() {
-                    eprintln!("Failed to send message to the updater queue.");
+                    // A message waiting on a retry is left unsent so it is picked up again if the send stops before the retry
+                    let updated_message = Message {
+                        sent: retry_delay.is_none(),
+                        time_took: Some(time_ms / 1000),
+                        started_at: Some(started_at),
+                        finished_at: Some(finished_at),
+                        time_took_ms: Some(time_ms),
+                        failed: retry_delay.is_none() && outcome.is_failure(),
+                        // The lease is kept while the message waits on a retry
+                        in_flight: item.in_flight && retry_delay.is_some(),
+                        provider_message_id: outcome.provider_message_id(),
+                        ..item.clone()
+                    };
+
+                    let update = DeliveryUpdate {
+                        message: updated_message,
+                        attempt,
+                    };
+
+                    // The updater only stops early when it could not write the results, which fails the send
+                    if tx.send(update).await.is_err() {
+                        break 'messages Err(SMSManagerError::GeneralException(format!(
+                            "The updater stopped before the result of message {} could be written",
+                            item.id
+                        )));
+                    }
+
+                    let Some(delay) = retry_delay else {
+                        break;
+                    };
+
+                    pipeline.clock.sleep(delay).await;
+
+                    if !job.wait_while_paused().await {
+                        break 'messages Ok(());
+                    }
                 }
-            }
+            };
 
-            if active_threads.fetch_sub(1, Ordering::SeqCst) == 1 {
-                notify.notify_one();
-            }
+            drop(participant);
+
+            result
         });
 
         handles.push(handle);
@@ -146,34 +333,137 @@ pub fn get_senders(
     handles
 }
 
-/// Reads messages from the receiver and updates the database with the updated messages
+/// Reads attempts from the receiver and writes them to the repository in batches, flushing once the batch is full or the flush interval has passed
+/// Each flush updates every message at once, records every attempt at once and counts the sent messages towards the job
+/// Flushes run one after the other, while the updater waits on a flush the senders keep filling the channel
+/// A flush that still fails after FLUSH_ATTEMPTS stops the updater with the error, which closes the channel and stops the senders
 ///
 /// # Paramters
-/// - rx: The receiver that will be used to receive incoming updated messages
-/// - pool: The database pool to retrieve a datbase connection from
-pub fn get_message_updater(mut rx: Receiver<Message>, pool: Arc<PoolHandle>) -> JoinHandle<()> {
+/// - rx: The receiver that will be used to receive incoming updated messages and their attempts
+/// - messages: The repository to write the results to
+/// - job: The job to record each persisted result against
+/// - config: How many results to hold and for how long before writing them
+pub fn get_message_updater(
+    mut rx: Receiver<DeliveryUpdate>,
+    messages: Arc<dyn MessageRepository>,
+    job: Arc<SendJob>,
+    config: UpdaterConfig,
+) -> JoinHandle<Result<(), SMSManagerError>> {
     tokio::spawn(async move {
-        let mut db = pool.get().unwrap();
-        while let Some(message) = rx.recv().await {
-            // Update the message in the database
-            match diesel::update(messages.find(message.id))
-                .set((
-                    sent.eq(message.sent),
-                    time_took.eq(message.time_took),
-                    failed.eq(message.failed),
-                ))
-                .execute(&mut db)
-            {
-                Ok(_) => println!("Updated message {} in the database.", message.id),
-                Err(err) => eprintln!("Failed to update message {}: {}", message.id, err),
+        let mut batch: Vec<DeliveryUpdate> = Vec::with_capacity(config.batch_size);
+        let mut flush_timer = tokio::time::interval(config.flush_interval);
+        flush_timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                received = rx.recv() => match received {
+                    Some(update) => {
+                        batch.push(update);
+                        if batch.len() < config.batch_size {
+                            continue;
+                        }
+                    }
+                    None => break,
+                },
+                _ = flush_timer.tick() => {
+                    if batch.is_empty() {
+                        continue;
+                    }
+                }
             }
 
-            let _ = diesel::update(producers.find(message.produced_by))
-                .set(status.eq("SENDING"))
-                .execute(&mut db)
-                .map_err(SMSManagerError::DbError);
+            flush_updates(messages.as_ref(), std::mem::take(&mut batch), &job).await?;
+        }
+
+        if !batch.is_empty() {
+            flush_updates(messages.as_ref(), batch, &job).await?;
         }
 
         println!("Database updater thread finished. Queue is empty.");
+        Ok(())
     })
 }
+
+/// Writes a batch of results to the repository
+///
+/// # Paramters
+/// - messages: The repository to write the results to
+/// - batch: The results to write, in the order they were attempted
+/// - job: The job to record each persisted result against
+///
+/// ### Errors if the messages or the attempts could not be written after FLUSH_ATTEMPTS tries
+async fn flush_updates(
+    messages: &dyn MessageRepository,
+    batch: Vec<DeliveryUpdate>,
+    job: &SendJob,
+) -> Result<(), SMSManagerError> {
+    // A message retried within the batch is only written as it was after its latest attempt
+    let mut latest: HashMap<uuid::Uuid, usize> = HashMap::new();
+    let mut updated_messages: Vec<Message> = vec![];
+    let mut attempts = vec![];
+    for DeliveryUpdate { message, attempt } in batch {
+        attempts.push(attempt);
+        match latest.get(&message.id) {
+            Some(index) => updated_messages[*index] = message,
+            None => {
+                latest.insert(message.id, updated_messages.len());
+                updated_messages.push(message);
+            }
+        }
+    }
+
+    // Only the last attempt of a message counts towards the job
+    let results: Vec<bool> = updated_messages
+        .iter()
+        .filter(|message| message.sent)
+        .map(|message| message.failed)
+        .collect();
+    let count = updated_messages.len();
+
+    with_retries(|| messages.update_results(updated_messages.clone()))
+        .await
+        .map_err(|err| {
+            SMSManagerError::GeneralException(format!(
+                "Failed to update {} messages: {}",
+                count, err
+            ))
+        })?;
+    for did_fail in results {
+        job.record_result(did_fail);
+    }
+    println!("Updated {} messages in the database.", count);
+
+    let count = attempts.len();
+    with_retries(|| messages.insert_attempts(attempts.clone()))
+        .await
+        .map_err(|err| {
+            SMSManagerError::GeneralException(format!(
+                "Failed to record {} attempts: {}",
+                count, err
+            ))
+        })?;
+
+    Ok(())
+}
+
+/// Makes the write until it succeeds, up to FLUSH_ATTEMPTS times with FLUSH_RETRY_DELAY between them
+/// Each write is made all at once or not at all, so a failed one can be made again
+///
+/// ### Errors with the error of the last try if every try fails
+async fn with_retries<F, Fut>(mut write: F) -> Result<usize, SMSManagerError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<usize, SMSManagerError>>,
+{
+    let mut tries = 1;
+    loop {
+        match write().await {
+            Ok(count) => return Ok(count),
+            Err(_) if tries < FLUSH_ATTEMPTS => {
+                tries += 1;
+                tokio::time::sleep(FLUSH_RETRY_DELAY).await;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
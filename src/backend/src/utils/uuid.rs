@@ -12,3 +12,39 @@ pub fn parse_uuid(val: &str) -> Result<Uuid, SMSManagerError> {
     Uuid::parse_str(val)
         .map_err(|_err| SMSManagerError::InvalidEncoding("Producer Id Is Invalid".to_string()))
 }
+
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::SqliteUuid;
+
+/// SQLite has no uuid type, so uuids are written to its text columns in their lowercase hyphenated form
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use diesel::{
+        deserialize::{self, FromSql},
+        expression::AsExpression,
+        serialize::{self, IsNull, Output, ToSql},
+        sqlite::{Sqlite, SqliteValue},
+    };
+    use uuid::Uuid;
+
+    use crate::diesel::sqlite_schema::sql_types::Uuid as TextUuid;
+
+    /// Binds a uuid to a SQLite uuid column, as diesel only lets uuid itself be bound as the Postgres uuid type
+    /// Reading the columns gives plain uuids
+    #[derive(Debug, Clone, Copy, AsExpression)]
+    #[diesel(sql_type = TextUuid)]
+    pub struct SqliteUuid(pub Uuid);
+
+    impl ToSql<TextUuid, Sqlite> for SqliteUuid {
+        fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+            out.set_value(self.0.hyphenated().to_string());
+            Ok(IsNull::No)
+        }
+    }
+
+    impl FromSql<TextUuid, Sqlite> for Uuid {
+        fn from_sql(mut value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+            Ok(Uuid::parse_str(value.read_text())?)
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    diesel::models::Message, repositories::MessageRepository, utils::error::SMSManagerError,
+};
+
+/// How many messages a sender claims from the repository at a time by default
+pub const DEFAULT_CLAIM_BATCH_SIZE: i64 = 100;
+
+/// Where the sender threads take the messages they deliver from
+#[async_trait]
+pub trait WorkQueue: Send + Sync {
+    /// Takes the next message to deliver
+    ///
+    /// # Returns
+    /// The message, None once there is nothing left to deliver
+    ///
+    /// ### Errors if the messages can not be taken, which is not the same as there being none left
+    async fn next(&self) -> Result<Option<Message>, SMSManagerError>;
+}
+
+/// A queue of messages that are already loaded, such as in the tests
+#[async_trait]
+impl WorkQueue for Mutex<VecDeque<Message>> {
+    async fn next(&self) -> Result<Option<Message>, SMSManagerError> {
+        Ok(self.lock().await.pop_front())
+    }
+}
+
+/// Claims the producers unsent messages from the repository a small batch at a time, see MessageRepository::claim
+/// Every claimed message is leased to the owner, so several servers can work through the same producer without sending anything twice
+/// and only a batch of messages is held in memory however many the producer has
+pub struct ClaimingWorkQueue {
+    messages: Arc<dyn MessageRepository>,
+    producer_id: Uuid,
+    owner: Uuid,
+    batch_size: i64,
+    claimed: Mutex<VecDeque<Message>>,
+}
+
+impl ClaimingWorkQueue {
+    /// # Parameters
+    /// - messages: The repository to claim messages from
+    /// - producer_id: The producer whose messages are delivered
+    /// - owner: The server the claimed messages are leased to
+    /// - batch_size: How many messages to claim at a time
+    pub fn new(
+        messages: Arc<dyn MessageRepository>,
+        producer_id: Uuid,
+        owner: Uuid,
+        batch_size: i64,
+    ) -> Self {
+        ClaimingWorkQueue {
+            messages,
+            producer_id,
+            owner,
+            batch_size,
+            claimed: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl WorkQueue for ClaimingWorkQueue {
+    async fn next(&self) -> Result<Option<Message>, SMSManagerError> {
+        if let Some(message) = self.claimed.lock().await.pop_front() {
+            return Ok(Some(message));
+        }
+
+        // The lock is not held while claiming, so the other senders can take what is left of the batch in the meantime
+        let batch = self
+            .messages
+            .claim(self.producer_id, self.owner, self.batch_size)
+            .await?;
+
+        let mut claimed = self.claimed.lock().await;
+        claimed.extend(batch);
+        Ok(claimed.pop_front())
+    }
+}
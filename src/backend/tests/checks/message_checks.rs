@@ -0,0 +1,304 @@
+use backend::{
+    repositories::{MessageCursor, MessagePage, MessageSort, Repositories},
+    services::{
+        message_services::{get_message_by_id, list_messages, MessageFilters},
+        producer_services::{create_producer, generate_messages},
+    },
+    utils::{error::SMSManagerError, message_state::MessageState, pagination::SortOrder},
+};
+use uuid::Uuid;
+
+use crate::test_utils::a_browsed_producer;
+
+/// Lists the messages of a_browsed_producer every way they can be paged, sorted and filtered
+pub async fn check_list_messages(repos: &Repositories) {
+    let (producer, messages) = a_browsed_producer(repos).await;
+    let list = |limit: Option<i64>,
+                cursor: Option<String>,
+                filters: MessageFilters,
+                sort: MessageSort,
+                order: SortOrder| {
+        list_messages(
+            repos,
+            producer.id.to_string(),
+            limit,
+            cursor,
+            filters,
+            sort,
+            order,
+        )
+    };
+    let bodies = |page: &MessagePage| -> Vec<String> {
+        page.messages
+            .iter()
+            .map(|message| message.message_body.clone())
+            .collect()
+    };
+
+    // Following the cursor walks every message once, in id order
+    let first = list(
+        Some(4),
+        None,
+        MessageFilters::default(),
+        MessageSort::Id,
+        SortOrder::Asc,
+    )
+    .await
+    .unwrap();
+    assert_eq!(first.total, 6);
+    assert_eq!(first.messages.len(), 4);
+    let second = list(
+        Some(4),
+        first.next_cursor.as_ref().map(|cursor| cursor.to_string()),
+        MessageFilters::default(),
+        MessageSort::Id,
+        SortOrder::Asc,
+    )
+    .await
+    .unwrap();
+    assert!(second.next_cursor.is_none());
+    let ids: Vec<_> = first
+        .messages
+        .iter()
+        .chain(second.messages.iter())
+        .map(|message| message.id)
+        .collect();
+    let mut sorted_ids: Vec<_> = messages.iter().map(|message| message.id).collect();
+    sorted_ids.sort();
+    assert_eq!(ids, sorted_ids);
+
+    let failed = list(
+        None,
+        None,
+        MessageFilters {
+            states: Some("FAILED".to_string()),
+            ..Default::default()
+        },
+        MessageSort::TimeTook,
+        SortOrder::Asc,
+    )
+    .await
+    .unwrap();
+    assert_eq!(bodies(&failed), vec!["Hello Eve", "Hello Bob"]);
+
+    let not_failed = list(
+        None,
+        None,
+        MessageFilters {
+            states: Some("SENT, PENDING".to_string()),
+            ..Default::default()
+        },
+        MessageSort::Id,
+        SortOrder::Asc,
+    )
+    .await
+    .unwrap();
+    assert_eq!(not_failed.total, 4);
+
+    let hellos = list(
+        None,
+        None,
+        MessageFilters {
+            body: Some("hello".to_string()),
+            ..Default::default()
+        },
+        MessageSort::Attempts,
+        SortOrder::Asc,
+    )
+    .await
+    .unwrap();
+    assert_eq!(hellos.total, 3);
+    assert_eq!(hellos.messages[0].message_body, "Hello Alice");
+
+    // Wildcards in the body filter are matched as they are
+    let discounts = list(
+        None,
+        None,
+        MessageFilters {
+            body: Some("%".to_string()),
+            ..Default::default()
+        },
+        MessageSort::Id,
+        SortOrder::Asc,
+    )
+    .await
+    .unwrap();
+    assert_eq!(bodies(&discounts), vec!["100% off"]);
+
+    let in_range = list(
+        None,
+        None,
+        MessageFilters {
+            min_time_took_ms: Some(80),
+            max_time_took_ms: Some(200),
+            ..Default::default()
+        },
+        MessageSort::TimeTook,
+        SortOrder::Desc,
+    )
+    .await
+    .unwrap();
+    assert_eq!(bodies(&in_range), vec!["Hello Alice", "Hello Eve"]);
+
+    // Slowest first, the pending messages have no time so they come last
+    let slowest = list(
+        Some(2),
+        None,
+        MessageFilters::default(),
+        MessageSort::TimeTook,
+        SortOrder::Desc,
+    )
+    .await
+    .unwrap();
+    assert_eq!(bodies(&slowest), vec!["Hello Bob", "Hello Alice"]);
+    let slower = list(
+        Some(2),
+        slowest.next_cursor.map(|cursor| cursor.to_string()),
+        MessageFilters::default(),
+        MessageSort::TimeTook,
+        SortOrder::Desc,
+    )
+    .await
+    .unwrap();
+    assert_eq!(bodies(&slower), vec!["Hello Eve", "Goodbye Carol"]);
+    let pending = list(
+        Some(2),
+        slower.next_cursor.map(|cursor| cursor.to_string()),
+        MessageFilters::default(),
+        MessageSort::TimeTook,
+        SortOrder::Desc,
+    )
+    .await
+    .unwrap();
+    assert!(pending.messages.iter().all(|message| !message.sent));
+    assert!(pending.next_cursor.is_none());
+
+    let message = get_message_by_id(repos, messages[1].id.to_string())
+        .await
+        .unwrap();
+    assert_eq!(message.message_body, "Hello Bob");
+    assert_eq!(MessageState::of(&message), MessageState::Failed);
+    assert_eq!(message.time_took_ms, Some(300));
+    assert!(matches!(
+        get_message_by_id(repos, Uuid::new_v4().to_string()).await,
+        Err(SMSManagerError::EmptyResult)
+    ));
+    assert!(matches!(
+        get_message_by_id(repos, "not-a-uuid".to_string()).await,
+        Err(SMSManagerError::InvalidEncoding(_))
+    ));
+
+    assert!(matches!(
+        list_messages(
+            repos,
+            Uuid::new_v4().to_string(),
+            None,
+            None,
+            MessageFilters::default(),
+            MessageSort::Id,
+            SortOrder::Asc,
+        )
+        .await,
+        Err(SMSManagerError::EmptyResult)
+    ));
+    for filters in [
+        MessageFilters {
+            states: Some("LOST".to_string()),
+            ..Default::default()
+        },
+        MessageFilters {
+            min_time_took_ms: Some(200),
+            max_time_took_ms: Some(100),
+            ..Default::default()
+        },
+    ] {
+        assert!(matches!(
+            list(None, None, filters, MessageSort::Id, SortOrder::Asc).await,
+            Err(SMSManagerError::InvalidEncoding(_))
+        ));
+    }
+    let id_cursor = MessageCursor::of(&messages[0], MessageSort::Id).to_string();
+    match list(
+        None,
+        Some(id_cursor),
+        MessageFilters::default(),
+        MessageSort::Attempts,
+        SortOrder::Asc,
+    )
+    .await
+    {
+        Err(SMSManagerError::InvalidEncoding(msg)) => {
+            assert_eq!(msg, "Cursor was taken from a listing with another sort");
+        }
+        _ => panic!("Expected InvalidEncoding"),
+    }
+}
+
+/// Writes the results of two claimed messages, the finished one gives its lease up while the one waiting on a retry keeps it
+pub async fn check_update_results_releases_finished_messages(repos: &Repositories) {
+    let producer = create_producer(repos, "Leased Producer".to_string(), 2, 1, 0, None)
+        .await
+        .unwrap();
+    generate_messages(repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+    let owner = Uuid::new_v4();
+    let mut claimed = repos.messages.claim(producer.id, owner, 2).await.unwrap();
+    let lease_expires_at = claimed[1].lease_expires_at;
+
+    claimed[0].sent = true;
+    claimed[0].attempts = 1;
+    claimed[0].in_flight = false;
+    claimed[1].attempts = 1;
+    repos
+        .messages
+        .update_results(claimed.clone())
+        .await
+        .unwrap();
+
+    let finished = repos.messages.find(claimed[0].id).await.unwrap();
+    assert!(finished.sent && !finished.in_flight);
+    assert_eq!(finished.leased_by, None);
+    assert_eq!(finished.lease_expires_at, None);
+    let retrying = repos.messages.find(claimed[1].id).await.unwrap();
+    assert!(retrying.in_flight);
+    assert_eq!(retrying.attempts, 1);
+    assert_eq!(retrying.leased_by, Some(owner));
+    assert_eq!(retrying.lease_expires_at, lease_expires_at);
+}
+
+/// Counts the messages of a_browsed_producer and spreads their times into a histogram, then does the same for a producer with no messages
+pub async fn check_progress_stats(repos: &Repositories) {
+    let (producer, _) = a_browsed_producer(repos).await;
+
+    let stats = repos.messages.progress_stats(producer.id).await.unwrap();
+    assert_eq!(stats.number_messages_created, 6);
+    assert_eq!(stats.number_messages_sent, 4);
+    assert_eq!(stats.number_messages_failed, 2);
+    assert_eq!(stats.number_messages_retried, 3);
+    assert_eq!(stats.number_messages_recovered, 1);
+    assert_eq!(stats.average_message_time_ms, 135.0);
+
+    // The 261 milliseconds from 40 to 300 are spread over 44 buckets 6 wide
+    let histogram = stats.message_time_histogram;
+    assert_eq!(histogram.len(), 44);
+    let counted: Vec<(usize, i32, i64)> = histogram
+        .iter()
+        .enumerate()
+        .filter(|(_, bucket)| bucket.count > 0)
+        .map(|(index, bucket)| (index, bucket.from_ms, bucket.count))
+        .collect();
+    assert_eq!(
+        counted,
+        vec![(0, 40, 1), (6, 76, 1), (13, 118, 1), (43, 298, 1)]
+    );
+    assert_eq!(histogram[43].to_ms, 304);
+
+    let empty = create_producer(repos, "Empty Producer".to_string(), 1, 1, 0, None)
+        .await
+        .unwrap();
+    let stats = repos.messages.progress_stats(empty.id).await.unwrap();
+    assert_eq!(stats.number_messages_created, 0);
+    assert_eq!(stats.average_message_time_ms, 0.0);
+    assert!(stats.message_time_histogram.is_empty());
+}
@@ -0,0 +1,2 @@
+pub mod message_checks;
+pub mod producer_checks;
\ No newline at end of file
@@ -0,0 +1,566 @@
+use std::sync::Arc;
+
+use backend::{
+    diesel::models::{NewMessage, ProducerChanges},
+    repositories::{ProducerCursor, ProducerSort, Repositories},
+    services::{
+        message_services::get_message_attempts,
+        producer_services::{
+            activate_producer, create_producer, delete_producer, generate_messages,
+            get_producer_by_id, get_producer_progress_data, list_producers,
+            update_producer_retry_policy, update_producer_seed, update_producer_transport,
+            GENERATION_CHUNK_SIZE,
+        },
+    },
+    transports::{simulated_transport::SimulatedConfig, TransportConfig},
+    utils::{
+        clock::ClockConfig,
+        error::SMSManagerError,
+        job_registry::JobRegistry,
+        pagination::SortOrder,
+        producer_status::{ProducerAction, ProducerStatus},
+        retry_policy::RetryPolicy,
+    },
+};
+use uuid::Uuid;
+
+use crate::test_utils::{a_producer_sending_with, an_instant_producer};
+
+/// Generates messages over several chunks then deletes the producer along with them
+pub async fn check_generate_and_delete_producer(repos: &Repositories) {
+    let number = GENERATION_CHUNK_SIZE as i32 * 2 + 5;
+    let producer = create_producer(repos, "Valid Producer".to_string(), number, 1, 0, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        generate_messages(repos, producer.id.to_string(), Some(7))
+            .await
+            .unwrap(),
+        number
+    );
+
+    let generated = get_producer_by_id(repos, producer.id.to_string())
+        .await
+        .unwrap();
+    assert_eq!(generated.status, ProducerStatus::Generated);
+    assert_eq!(generated.generated_messages, number);
+    let progress_data = get_producer_progress_data(repos, producer.id.to_string())
+        .await
+        .unwrap();
+    assert_eq!(progress_data.number_messages_created, number);
+    assert_eq!(progress_data.number_messages_sent, 0);
+
+    delete_producer(repos, producer.id.to_string())
+        .await
+        .unwrap();
+    assert!(matches!(
+        get_producer_by_id(repos, producer.id.to_string()).await,
+        Err(SMSManagerError::EmptyResult)
+    ));
+    assert!(repos
+        .messages
+        .list_for_producer(producer.id)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+/// Sends every message of an instant producer once and records an attempt for each
+pub async fn check_activate_producer(repos: &Repositories) {
+    let jobs = JobRegistry::new();
+    let producer = an_instant_producer(repos, 50).await;
+    let _ = generate_messages(repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    let job = activate_producer(repos, &jobs, producer.id.to_string(), None)
+        .await
+        .unwrap();
+    job.wait_until_finished().await;
+
+    assert_eq!(job.number_sent(), 50);
+    assert_eq!(
+        get_producer_by_id(repos, producer.id.to_string())
+            .await
+            .unwrap()
+            .status,
+        ProducerStatus::Empty
+    );
+
+    let sent_messages = repos.messages.list_for_producer(producer.id).await.unwrap();
+    assert!(sent_messages.iter().all(|message| message.sent
+        && !message.in_flight
+        && message.attempts == 1
+        && message.finished_at.is_some()));
+    assert_eq!(repos.messages.count_unsent(producer.id).await.unwrap(), 0);
+
+    let attempts = get_message_attempts(
+        repos,
+        producer.id.to_string(),
+        sent_messages[0].id.to_string(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(attempts.len(), 1);
+    assert_eq!(attempts[0].message_id, sent_messages[0].id);
+    assert_eq!(attempts[0].outcome, "DELIVERED");
+}
+
+/// Activates the producer from six requests at once, only one of which starts a send
+///
+/// # Parameters
+/// - repos: The repositories to run the check against
+/// - transport: How the producer delivers its messages, slow enough that the send is still running when the last request claims the producer
+pub async fn check_activate_producer_parallel_requests(
+    repos: &Repositories,
+    transport: TransportConfig,
+) {
+    let producer = a_producer_sending_with(repos, 200, transport).await;
+    let _ = generate_messages(repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    // Every request reaches activation at the same moment
+    let jobs = JobRegistry::new();
+    let barrier = Arc::new(tokio::sync::Barrier::new(6));
+    let requests: Vec<_> = (0..6)
+        .map(|_| {
+            let (repos, jobs, barrier) = (repos.clone(), jobs.clone(), barrier.clone());
+            let producer_id = producer.id.to_string();
+            tokio::spawn(async move {
+                barrier.wait().await;
+                activate_producer(&repos, &jobs, producer_id, None).await
+            })
+        })
+        .collect();
+
+    let mut started_jobs = vec![];
+    let mut conflicts = 0;
+    for request in requests {
+        match request.await.unwrap() {
+            Ok(job) => started_jobs.push(job),
+            Err(SMSManagerError::Conflict(msg)) => {
+                assert_eq!(
+                    msg,
+                    "Can not move the producer from SENDING to SENDING, this server is already sending it"
+                );
+                conflicts += 1;
+            }
+            Err(err) => panic!("Expected Conflict error, got {}", err),
+        }
+    }
+
+    assert_eq!(started_jobs.len(), 1);
+    assert_eq!(conflicts, 5);
+
+    let job = started_jobs.pop().unwrap();
+    job.wait_until_finished().await;
+    assert_eq!(job.number_sent(), 200);
+    assert!(repos
+        .messages
+        .list_for_producer(producer.id)
+        .await
+        .unwrap()
+        .iter()
+        .all(|message| message.sent && message.attempts == 1));
+}
+
+/// Writes a chunk of messages then one the foreign key turns away, the messages already written are deleted and the producer is put back
+pub async fn check_failed_generation_is_undone(repos: &Repositories) {
+    let producer = create_producer(repos, "Valid Producer".to_string(), 6, 1, 0, None)
+        .await
+        .unwrap();
+
+    // The second chunk belongs to a producer that does not exist, which the foreign key turns away
+    let a_chunk = |produced_by: Uuid| {
+        (0..3)
+            .map(|index| NewMessage {
+                message_body: "Hello".to_string(),
+                produced_by,
+                message_index: index,
+            })
+            .collect::<Vec<NewMessage>>()
+    };
+    let chunks = vec![a_chunk(producer.id), a_chunk(Uuid::new_v4())];
+
+    let result = repos
+        .messages
+        .insert_generated(
+            producer.clone(),
+            Box::new(chunks.into_iter()),
+            ProducerStatus::Generating,
+            ProducerStatus::Generated,
+        )
+        .await;
+    assert!(matches!(result, Err(SMSManagerError::DbError(_))));
+
+    let restored = get_producer_by_id(repos, producer.id.to_string())
+        .await
+        .unwrap();
+    assert_eq!(restored.status, ProducerStatus::Inactive);
+    assert_eq!(restored.generated_messages, 0);
+    assert!(repos
+        .messages
+        .list_for_producer(producer.id)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+/// Reads a producer, lets a send claim it, then tries every write a request that read it before the claim would make
+/// Each write checks the status as it is made, so none of them lands on the sending producer
+pub async fn check_writes_refused_once_a_send_claims_the_producer(repos: &Repositories) {
+    let producer = an_instant_producer(repos, 3).await;
+    generate_messages(repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+    let stale = repos.producers.find(producer.id).await.unwrap();
+
+    assert!(repos
+        .producers
+        .transition(producer.id, ProducerAction::Send)
+        .await
+        .unwrap());
+
+    let chunk: Vec<NewMessage> = (0..3)
+        .map(|index| NewMessage {
+            message_body: "Hello".to_string(),
+            produced_by: producer.id,
+            message_index: index,
+        })
+        .collect();
+    let generated = repos
+        .messages
+        .insert_generated(
+            stale,
+            Box::new(std::iter::once(chunk)),
+            ProducerStatus::Generating,
+            ProducerStatus::Generated,
+        )
+        .await;
+    let updated = repos
+        .producers
+        .update(
+            producer.id,
+            ProducerChanges {
+                name: Some("Renamed".to_string()),
+                ..ProducerChanges::default()
+            },
+        )
+        .await;
+    let deleted = repos.producers.delete(producer.id).await;
+
+    for (result, expected) in [
+        (
+            generated.map(|_| ()),
+            "Can not move the producer from SENDING to GENERATING",
+        ),
+        (
+            updated.map(|_| ()),
+            "Can not update the producer while it is SENDING",
+        ),
+        (deleted, "Can not delete the producer while it is SENDING"),
+    ] {
+        match result {
+            Err(SMSManagerError::Conflict(msg)) => assert_eq!(msg, expected),
+            other => panic!("Expected Conflict error, got {:?}", other.err()),
+        }
+    }
+
+    let stored = repos.producers.find(producer.id).await.unwrap();
+    assert_eq!(stored.status, ProducerStatus::Sending);
+    assert_eq!(stored.name, producer.name);
+    assert_eq!(
+        repos
+            .messages
+            .list_for_producer(producer.id)
+            .await
+            .unwrap()
+            .len(),
+        3
+    );
+}
+
+/// Generates the messages of one producer from six requests at once, only one of them may write its messages
+/// The generation has to outlast the other requests arriving, which a repository writing to memory does not
+pub async fn check_generate_messages_parallel_requests(repos: &Repositories) {
+    let number_messages = 2 * GENERATION_CHUNK_SIZE as i32;
+    let producer = create_producer(
+        repos,
+        "Valid Producer".to_string(),
+        number_messages,
+        1,
+        0,
+        None,
+    )
+    .await
+    .unwrap();
+
+    // Every request reads the producer before any of them has moved it to GENERATING
+    let barrier = Arc::new(tokio::sync::Barrier::new(6));
+    let requests: Vec<_> = (0..6)
+        .map(|_| {
+            let (repos, barrier) = (repos.clone(), barrier.clone());
+            let producer_id = producer.id.to_string();
+            tokio::spawn(async move {
+                barrier.wait().await;
+                generate_messages(&repos, producer_id, None).await
+            })
+        })
+        .collect();
+
+    let mut generations = 0;
+    let mut conflicts = 0;
+    for request in requests {
+        match request.await.unwrap() {
+            Ok(generated) => {
+                assert_eq!(generated, number_messages);
+                generations += 1;
+            }
+            Err(SMSManagerError::Conflict(msg)) => {
+                assert_eq!(
+                    msg,
+                    "Can not move the producer from GENERATING to GENERATING"
+                );
+                conflicts += 1;
+            }
+            Err(err) => panic!("Expected Conflict error, got {}", err),
+        }
+    }
+
+    assert_eq!(generations, 1);
+    assert_eq!(conflicts, 5);
+    assert_eq!(
+        repos.messages.count_unsent(producer.id).await.unwrap(),
+        number_messages as i64
+    );
+}
+
+/// Generates and sends two fresh producers with the same seed, which give the same statistics
+/// even though their messages have different ids
+pub async fn check_same_seed_sends_alike(repos: &Repositories) {
+    let jobs = JobRegistry::new();
+    let mut stats = Vec::new();
+    for _ in 0..2 {
+        let producer = create_producer(repos, "Seeded Producer".to_string(), 200, 1, 30, Some(2))
+            .await
+            .unwrap();
+        update_producer_transport(
+            repos,
+            producer.id.to_string(),
+            TransportConfig::Simulated(SimulatedConfig {
+                clock: ClockConfig::Instant,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        update_producer_retry_policy(
+            repos,
+            producer.id.to_string(),
+            RetryPolicy {
+                max_attempts: 3,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        update_producer_seed(repos, producer.id.to_string(), Some(42))
+            .await
+            .unwrap();
+
+        generate_messages(repos, producer.id.to_string(), None)
+            .await
+            .unwrap();
+        let job = activate_producer(repos, &jobs, producer.id.to_string(), None)
+            .await
+            .unwrap();
+        job.wait_until_finished().await;
+
+        stats.push(repos.messages.progress_stats(producer.id).await.unwrap());
+    }
+
+    assert!(stats[0].number_messages_failed > 0);
+    assert!(stats[0].number_messages_recovered > 0);
+    assert_eq!(stats[0], stats[1]);
+}
+
+/// Lists five producers every way they can be paged, sorted and filtered
+pub async fn check_list_producers(repos: &Repositories) {
+    for name in ["delta", "alpha", "charlie", "bravo", "echo"] {
+        create_producer(repos, name.to_string(), 1, 1, 0, None)
+            .await
+            .unwrap();
+    }
+    let charlie = list_producers(
+        repos,
+        None,
+        None,
+        None,
+        Some("charlie".to_string()),
+        ProducerSort::Name,
+        SortOrder::Asc,
+    )
+    .await
+    .unwrap()
+    .producers
+    .remove(0);
+    let _ = generate_messages(repos, charlie.id.to_string(), None)
+        .await
+        .unwrap();
+
+    // Following the cursors walks every producer once, in name order
+    let mut names = vec![];
+    let mut cursor = None;
+    loop {
+        let page = list_producers(
+            repos,
+            Some(2),
+            cursor,
+            None,
+            None,
+            ProducerSort::Name,
+            SortOrder::Asc,
+        )
+        .await
+        .unwrap();
+        assert_eq!(page.total, 5);
+        assert!(page.producers.len() <= 2);
+        names.extend(page.producers.into_iter().map(|producer| producer.name));
+
+        match page.next_cursor {
+            Some(next_cursor) => cursor = Some(next_cursor.to_string()),
+            None => break,
+        }
+    }
+    assert_eq!(names, vec!["alpha", "bravo", "charlie", "delta", "echo"]);
+
+    let page = list_producers(
+        repos,
+        Some(3),
+        None,
+        None,
+        None,
+        ProducerSort::Name,
+        SortOrder::Desc,
+    )
+    .await
+    .unwrap();
+    let names: Vec<String> = page.producers.into_iter().map(|p| p.name).collect();
+    assert_eq!(names, vec!["echo", "delta", "charlie"]);
+
+    let page = list_producers(
+        repos,
+        None,
+        page.next_cursor.map(|cursor| cursor.to_string()),
+        None,
+        None,
+        ProducerSort::Name,
+        SortOrder::Desc,
+    )
+    .await
+    .unwrap();
+    let names: Vec<String> = page.producers.into_iter().map(|p| p.name).collect();
+    assert_eq!(names, vec!["bravo", "alpha"]);
+    assert!(page.next_cursor.is_none());
+
+    let page = list_producers(
+        repos,
+        None,
+        None,
+        None,
+        None,
+        ProducerSort::CreatedAt,
+        SortOrder::Asc,
+    )
+    .await
+    .unwrap();
+    let names: Vec<String> = page.producers.into_iter().map(|p| p.name).collect();
+    assert_eq!(names, vec!["delta", "alpha", "charlie", "bravo", "echo"]);
+
+    // The status counts ignore the status filter, so every status can still be offered
+    let page = list_producers(
+        repos,
+        None,
+        None,
+        Some("GENERATED, SENDING".to_string()),
+        None,
+        ProducerSort::CreatedAt,
+        SortOrder::Asc,
+    )
+    .await
+    .unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.producers[0].id, charlie.id);
+    let mut status_counts = page.status_counts;
+    status_counts.sort_by_key(|(status, _)| status.to_string());
+    assert_eq!(
+        status_counts,
+        vec![
+            (ProducerStatus::Generated, 1),
+            (ProducerStatus::Inactive, 4)
+        ]
+    );
+
+    let page = list_producers(
+        repos,
+        None,
+        None,
+        None,
+        Some("LP".to_string()),
+        ProducerSort::Name,
+        SortOrder::Asc,
+    )
+    .await
+    .unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.producers[0].name, "alpha");
+    assert_eq!(page.status_counts, vec![(ProducerStatus::Inactive, 1)]);
+
+    // Wildcards in the name filter are matched as they are
+    let page = list_producers(
+        repos,
+        None,
+        None,
+        None,
+        Some("%".to_string()),
+        ProducerSort::Name,
+        SortOrder::Asc,
+    )
+    .await
+    .unwrap();
+    assert_eq!(page.total, 0);
+    assert!(page.producers.is_empty());
+
+    let name_cursor = ProducerCursor::of(&charlie, ProducerSort::Name).to_string();
+    match list_producers(
+        repos,
+        None,
+        Some(name_cursor),
+        None,
+        None,
+        ProducerSort::CreatedAt,
+        SortOrder::Asc,
+    )
+    .await
+    {
+        Err(SMSManagerError::InvalidEncoding(msg)) => {
+            assert_eq!(msg, "Cursor was taken from a listing with another sort");
+        }
+        _ => panic!("Expected InvalidEncoding"),
+    }
+    assert!(matches!(
+        list_producers(
+            repos,
+            None,
+            None,
+            Some("BOGUS".to_string()),
+            None,
+            ProducerSort::Name,
+            SortOrder::Asc,
+        )
+        .await,
+        Err(SMSManagerError::InvalidEncoding(_))
+    ));
+}
@@ -1 +1,4 @@
-pub mod services;
\ No newline at end of file
+pub mod services;
+pub mod utils;
+#[cfg(feature = "sqlite")]
+pub mod repositories;
\ No newline at end of file
@@ -0,0 +1 @@
+pub mod sqlite_repository_test;
\ No newline at end of file
@@ -0,0 +1,255 @@
+use backend::{
+    repositories::ProducerSort,
+    services::{
+        producer_services::{
+            create_producer, generate_messages, get_producer_by_id, list_producers, update_producer,
+        },
+        recovery_services::recover_interrupted_sends,
+    },
+    transports::{simulated_transport::SimulatedConfig, TransportConfig},
+    utils::{
+        error::SMSManagerError,
+        latency_model::LatencyModel,
+        pagination::SortOrder,
+        producer_status::{ProducerAction, ProducerStatus},
+    },
+};
+use uuid::Uuid;
+
+use crate::{
+    checks::{
+        message_checks::{
+            check_list_messages, check_progress_stats,
+            check_update_results_releases_finished_messages,
+        },
+        producer_checks::{
+            check_activate_producer, check_activate_producer_parallel_requests,
+            check_failed_generation_is_undone, check_generate_and_delete_producer,
+            check_generate_messages_parallel_requests, check_list_producers,
+            check_same_seed_sends_alike, check_writes_refused_once_a_send_claims_the_producer,
+        },
+    },
+    test_utils::a_sqlite_database,
+};
+
+#[tokio::test]
+async fn test_create_and_update_producer_sqlite() {
+    let (repos, _database) = a_sqlite_database();
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 10, 1, 0, None)
+        .await
+        .unwrap();
+    assert_eq!(producer.status, ProducerStatus::Inactive);
+    assert_eq!(producer.generated_messages, 0);
+    assert!(matches!(
+        TransportConfig::try_from(&producer.transport).unwrap(),
+        TransportConfig::Simulated(_)
+    ));
+
+    let updated = update_producer(
+        &repos,
+        producer.id.to_string(),
+        "Renamed Producer".to_string(),
+        20,
+        2,
+        5,
+        Some(3),
+    )
+    .await
+    .unwrap();
+    assert_eq!(updated.id, producer.id);
+    assert_eq!(updated.name, "Renamed Producer");
+    assert_eq!(updated.num_senders, Some(3));
+
+    let found = get_producer_by_id(&repos, producer.id.to_string())
+        .await
+        .unwrap();
+    assert_eq!(found.number_messages, 20);
+    assert_eq!(found.failure_rate, 5);
+    assert_eq!(
+        list_producers(
+            &repos,
+            None,
+            None,
+            None,
+            None,
+            ProducerSort::default(),
+            SortOrder::default(),
+        )
+        .await
+        .unwrap()
+        .total,
+        1
+    );
+
+    assert!(matches!(
+        get_producer_by_id(&repos, Uuid::new_v4().to_string()).await,
+        Err(SMSManagerError::EmptyResult)
+    ));
+}
+
+#[tokio::test]
+async fn test_list_producers_sqlite() {
+    let (repos, _database) = a_sqlite_database();
+    check_list_producers(&repos).await;
+}
+
+#[tokio::test]
+async fn test_list_messages_sqlite() {
+    let (repos, _database) = a_sqlite_database();
+    check_list_messages(&repos).await;
+}
+
+#[tokio::test]
+async fn test_progress_stats_sqlite() {
+    let (repos, _database) = a_sqlite_database();
+    check_progress_stats(&repos).await;
+}
+
+#[tokio::test]
+async fn test_update_results_releases_finished_messages_sqlite() {
+    let (repos, _database) = a_sqlite_database();
+    check_update_results_releases_finished_messages(&repos).await;
+}
+
+#[tokio::test]
+async fn test_generate_and_delete_producer_sqlite() {
+    let (repos, _database) = a_sqlite_database();
+    check_generate_and_delete_producer(&repos).await;
+}
+
+#[tokio::test]
+async fn test_failed_generation_is_undone_sqlite() {
+    let (repos, _database) = a_sqlite_database();
+    check_failed_generation_is_undone(&repos).await;
+}
+
+#[tokio::test]
+async fn test_activate_producer_sqlite() {
+    let (repos, _database) = a_sqlite_database();
+    check_activate_producer(&repos).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_activate_producer_parallel_requests_sqlite() {
+    let (repos, _database) = a_sqlite_database();
+    // A writer waiting on SQLite backs off for a while, so the sends take long enough for the first job to still be running when the last request gets to claim the producer
+    check_activate_producer_parallel_requests(
+        &repos,
+        TransportConfig::Simulated(SimulatedConfig {
+            latency: Some(LatencyModel::Fixed { delay_ms: 10 }),
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_claim_and_recover_sqlite() {
+    let (repos, _database) = a_sqlite_database();
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 5, 1, 0, Some(1))
+        .await
+        .unwrap();
+    let _ = generate_messages(&repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+    let (owner, other_owner) = (Uuid::new_v4(), Uuid::new_v4());
+
+    // Both servers send the producer, the second joins the send of the first
+    for server in [owner, other_owner] {
+        repos
+            .producers
+            .claim_status(
+                producer.id,
+                server,
+                Box::new(|current| current.apply(ProducerAction::Send)),
+            )
+            .await
+            .unwrap();
+    }
+
+    // Claims come back in the order Postgres would give them, by uuid
+    let claimed = repos.messages.claim(producer.id, owner, 3).await.unwrap();
+    assert_eq!(claimed.len(), 3);
+    assert!(claimed.windows(2).all(|pair| pair[0].id < pair[1].id));
+    assert!(claimed
+        .iter()
+        .all(|message| message.in_flight && message.leased_by == Some(owner)));
+    let taken = repos
+        .messages
+        .claim(producer.id, other_owner, 10)
+        .await
+        .unwrap();
+    assert_eq!(taken.len(), 2);
+    assert!(taken.iter().all(|message| !claimed
+        .iter()
+        .any(|claimed_message| claimed_message.id == message.id)));
+    assert_eq!(
+        repos.messages.count_claimable(producer.id).await.unwrap(),
+        0
+    );
+
+    assert_eq!(
+        repos
+            .messages
+            .renew_leases(producer.id, owner)
+            .await
+            .unwrap(),
+        3
+    );
+
+    // Another server starting up leaves the live leases alone
+    let report = recover_interrupted_sends(&repos, Uuid::new_v4())
+        .await
+        .unwrap();
+    assert_eq!(report.requeued_messages, 0);
+    assert!(report.interrupted_sends.is_empty());
+
+    let report = recover_interrupted_sends(&repos, owner).await.unwrap();
+    assert_eq!(report.requeued_messages, 3);
+    assert!(report.interrupted_sends.is_empty());
+    assert_eq!(
+        repos.messages.count_claimable(producer.id).await.unwrap(),
+        3
+    );
+
+    // Once the other server gives its leases up as well no server holds the producer
+    assert_eq!(
+        repos
+            .messages
+            .release_leases(producer.id, other_owner)
+            .await
+            .unwrap(),
+        2
+    );
+    let report = recover_interrupted_sends(&repos, other_owner)
+        .await
+        .unwrap();
+    assert_eq!(report.requeued_messages, 0);
+    assert_eq!(report.interrupted_sends, vec![producer.id]);
+    assert_eq!(
+        get_producer_by_id(&repos, producer.id.to_string())
+            .await
+            .unwrap()
+            .status,
+        ProducerStatus::Interrupted
+    );
+}
+
+#[tokio::test]
+async fn test_writes_refused_once_a_send_claims_the_producer_sqlite() {
+    let (repos, _database) = a_sqlite_database();
+    check_writes_refused_once_a_send_claims_the_producer(&repos).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_generate_messages_parallel_requests_sqlite() {
+    let (repos, _database) = a_sqlite_database();
+    check_generate_messages_parallel_requests(&repos).await;
+}
+
+#[tokio::test]
+async fn test_same_seed_sends_alike_sqlite() {
+    let (repos, _database) = a_sqlite_database();
+    check_same_seed_sends_alike(&repos).await;
+}
@@ -0,0 +1,60 @@
+use backend::{
+    repositories::Repositories,
+    services::{
+        job_services::get_job_by_id,
+        producer_services::{activate_producer, create_producer, generate_messages},
+    },
+    utils::{error::SMSManagerError, job_registry::JobRegistry},
+};
+
+use crate::test_utils::cleanup_and_prepare;
+
+#[tokio::test]
+async fn test_get_job_by_id() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 2, 1, 0, Some(2))
+        .await
+        .unwrap();
+
+    let _ = generate_messages(&repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    let jobs = JobRegistry::new();
+    let job = activate_producer(&repos, &jobs, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    let found_job = get_job_by_id(&jobs, job.id.to_string()).await.unwrap();
+
+    assert_eq!(found_job.id, job.id);
+    assert_eq!(found_job.producer_id, producer.id);
+    assert_eq!(found_job.number_messages, 2);
+
+    job.wait_until_finished().await;
+}
+
+#[tokio::test]
+async fn test_get_job_by_id_not_found() {
+    let jobs = JobRegistry::new();
+
+    let result = get_job_by_id(&jobs, uuid::Uuid::new_v4().to_string()).await;
+
+    assert!(matches!(result, Err(SMSManagerError::EmptyResult)));
+}
+
+#[tokio::test]
+async fn test_get_job_by_id_invalid_id() {
+    let jobs = JobRegistry::new();
+
+    let result = get_job_by_id(&jobs, "not-a-uuid".to_string()).await;
+
+    match result {
+        Err(SMSManagerError::InvalidEncoding(message)) => {
+            assert_eq!(message, "Job Id Is Invalid");
+        }
+        _ => panic!("Expected InvalidEncoding error"),
+    }
+}
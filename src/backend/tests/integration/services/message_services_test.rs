@@ -0,0 +1,130 @@
+use backend::{
+    diesel::{
+        models::{Message, NewDeliveryAttempt, NewMessage},
+        schema::{delivery_attempts, messages},
+    },
+    repositories::Repositories,
+    services::{message_services::get_message_attempts, producer_services::create_producer},
+    utils::error::SMSManagerError,
+};
+use chrono::{Duration, Utc};
+use diesel::RunQueryDsl;
+use uuid::Uuid;
+
+use crate::{
+    checks::message_checks::{
+        check_list_messages, check_progress_stats, check_update_results_releases_finished_messages,
+    },
+    test_utils::cleanup_and_prepare,
+};
+
+#[tokio::test]
+async fn test_get_message_attempts_in_order() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 1, 1, 0, Some(1))
+        .await
+        .unwrap();
+
+    let message: Message = diesel::insert_into(messages::table)
+        .values(NewMessage {
+            message_body: String::from("Test Message"),
+            produced_by: producer.id,
+            message_index: 0,
+        })
+        .get_result(&mut db)
+        .unwrap();
+
+    let started_at = Utc::now();
+    let new_attempts: Vec<NewDeliveryAttempt> =
+        [(2, "DELIVERED", None), (1, "FAILED", Some("TIMEOUT"))]
+            .into_iter()
+            .map(|(number, outcome, code)| NewDeliveryAttempt {
+                message_id: message.id,
+                attempt_number: number,
+                started_at: started_at + Duration::seconds(number as i64),
+                finished_at: started_at + Duration::seconds(number as i64 + 1),
+                outcome: outcome.to_string(),
+                error_code: code.map(str::to_string),
+                error_reason: code.map(|_| "Gateway request failed".to_string()),
+                worker_id: 3,
+            })
+            .collect();
+    diesel::insert_into(delivery_attempts::table)
+        .values(new_attempts)
+        .execute(&mut db)
+        .unwrap();
+
+    let attempts = get_message_attempts(&repos, producer.id.to_string(), message.id.to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(attempts.len(), 2);
+    assert_eq!(attempts[0].attempt_number, 1);
+    assert_eq!(attempts[0].outcome, "FAILED");
+    assert_eq!(attempts[0].error_code, Some("TIMEOUT".to_string()));
+    assert_eq!(attempts[1].attempt_number, 2);
+    assert_eq!(attempts[1].outcome, "DELIVERED");
+    assert_eq!(attempts[1].worker_id, 3);
+}
+
+#[tokio::test]
+async fn test_get_message_attempts_wrong_producer() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 1, 1, 0, Some(1))
+        .await
+        .unwrap();
+
+    let message: Message = diesel::insert_into(messages::table)
+        .values(NewMessage {
+            message_body: String::from("Test Message"),
+            produced_by: producer.id,
+            message_index: 0,
+        })
+        .get_result(&mut db)
+        .unwrap();
+
+    let result =
+        get_message_attempts(&repos, Uuid::new_v4().to_string(), message.id.to_string()).await;
+
+    assert!(matches!(result, Err(SMSManagerError::EmptyResult)));
+}
+
+#[tokio::test]
+async fn test_get_message_attempts_invalid_id() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+
+    let result =
+        get_message_attempts(&repos, Uuid::new_v4().to_string(), "not-a-uuid".to_string()).await;
+
+    match result {
+        Err(SMSManagerError::InvalidEncoding(message)) => {
+            assert_eq!(message, "Message Id Is Invalid");
+        }
+        _ => panic!("Expected InvalidEncoding error"),
+    }
+}
+
+#[tokio::test]
+async fn test_list_messages() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    check_list_messages(&Repositories::postgres(pool)).await;
+}
+
+#[tokio::test]
+async fn test_progress_stats() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    check_progress_stats(&Repositories::postgres(pool)).await;
+}
+
+#[tokio::test]
+async fn test_update_results_releases_finished_messages() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    check_update_results_releases_finished_messages(&Repositories::postgres(pool)).await;
+}
@@ -1,2 +1,6 @@
 pub mod producer_services_test;
-pub mod sender_test;
\ No newline at end of file
+pub mod sender_test;
+pub mod job_services_test;
+pub mod message_services_test;
+
+pub mod recovery_services_test;
\ No newline at end of file
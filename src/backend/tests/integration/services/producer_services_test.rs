@@ -1,23 +1,42 @@
-use std::sync::Arc;
-
 use backend::{
     diesel::{
         models::{Message, NewMessageFull},
         schema::messages::dsl::*,
     },
+    repositories::{MessageTimeBucket, ProducerSort, Repositories},
     services::producer_services::{
-        activate_producer, create_producer, delete_producer, generate_messages, get_all_producers,
-        get_producer_by_id, get_producer_progress_data, update_producer,
+        activate_producer, cancel_producer, create_producer, delete_producer, generate_messages,
+        get_producer_by_id, get_producer_progress_data, list_producers, pause_producer,
+        resume_producer, update_producer, update_producer_rate_limit, update_producer_retry_policy,
+        update_producer_seed, update_producer_transport,
+    },
+    transformers::producer_transformer::PublicProducer,
+    transports::{simulated_transport::SimulatedConfig, FailureKind, TransportConfig},
+    utils::{
+        clock::ClockConfig,
+        error::SMSManagerError,
+        job_registry::{JobRegistry, JobState},
+        pagination::SortOrder,
+        producer_status::ProducerStatus,
+        rate_limiter::{RateLimit, RatePeriod},
+        retry_policy::RetryPolicy,
     },
-    utils::error::SMSManagerError,
 };
-use diesel::RunQueryDsl;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
 
-use crate::test_utils::cleanup_and_prepare;
+use crate::{
+    checks::producer_checks::{
+        check_activate_producer_parallel_requests, check_failed_generation_is_undone,
+        check_generate_messages_parallel_requests, check_list_producers,
+        check_same_seed_sends_alike, check_writes_refused_once_a_send_claims_the_producer,
+    },
+    test_utils::cleanup_and_prepare,
+};
 
 #[tokio::test]
 async fn test_create_producer_valid_data() {
-    let mut db = cleanup_and_prepare().await.unwrap().get().unwrap();
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
 
     let new_name = "Valid Producer".to_string();
     let new_number_messages = 100;
@@ -26,7 +45,7 @@ async fn test_create_producer_valid_data() {
     let senders = Some(4);
 
     let result = create_producer(
-        &mut db,
+        &repos,
         new_name,
         new_number_messages,
         new_average_send_delay,
@@ -42,12 +61,13 @@ async fn test_create_producer_valid_data() {
     assert_eq!(producer.average_send_delay, 20);
     assert_eq!(producer.failure_rate, 10);
     assert_eq!(producer.num_senders, Some(4));
-    assert_eq!(producer.status, "INACTIVE");
+    assert_eq!(producer.status, ProducerStatus::Inactive);
 }
 
 #[tokio::test]
 async fn test_create_producer_invalid_failure_rate() {
-    let mut db = cleanup_and_prepare().await.unwrap().get().unwrap();
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
 
     let new_name = "Invalid Failure Rate".to_string();
     let new_number_messages = 100;
@@ -56,7 +76,7 @@ async fn test_create_producer_invalid_failure_rate() {
     let senders = Some(4);
 
     let result = create_producer(
-        &mut db,
+        &repos,
         new_name,
         new_number_messages,
         new_average_send_delay,
@@ -76,7 +96,8 @@ async fn test_create_producer_invalid_failure_rate() {
 
 #[tokio::test]
 async fn test_create_producer_invalid_number_messages() {
-    let mut db = cleanup_and_prepare().await.unwrap().get().unwrap();
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
 
     let new_name = "Invalid Number of Messages".to_string();
     let new_number_messages = 0; // Invalid number of messages
@@ -85,7 +106,7 @@ async fn test_create_producer_invalid_number_messages() {
     let senders = Some(4);
 
     let result = create_producer(
-        &mut db,
+        &repos,
         new_name,
         new_number_messages,
         new_average_send_delay,
@@ -108,7 +129,8 @@ async fn test_create_producer_invalid_number_messages() {
 
 #[tokio::test]
 async fn test_create_producer_invalid_average_send_delay() {
-    let mut db = cleanup_and_prepare().await.unwrap().get().unwrap();
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
 
     let new_name = "Invalid Average Send Delay".to_string();
     let new_number_messages = 100;
@@ -117,7 +139,7 @@ async fn test_create_producer_invalid_average_send_delay() {
     let senders = Some(4);
 
     let result = create_producer(
-        &mut db,
+        &repos,
         new_name,
         new_number_messages,
         new_average_send_delay,
@@ -140,7 +162,8 @@ async fn test_create_producer_invalid_average_send_delay() {
 
 #[tokio::test]
 async fn test_update_producer_valid_data() {
-    let mut db = cleanup_and_prepare().await.unwrap().get().unwrap();
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
 
     let new_name = "Updated Producer";
     let new_number_messages = 100;
@@ -149,7 +172,7 @@ async fn test_update_producer_valid_data() {
     let senders = Some(4);
 
     let producer = create_producer(
-        &mut db,
+        &repos,
         new_name.to_string(),
         new_number_messages,
         new_average_send_delay,
@@ -159,7 +182,7 @@ async fn test_update_producer_valid_data() {
     .await;
 
     let result = update_producer(
-        &mut db,
+        &repos,
         producer.unwrap().id.to_string(),
         new_name.to_string(),
         new_number_messages,
@@ -175,12 +198,13 @@ async fn test_update_producer_valid_data() {
     assert_eq!(producer.number_messages, new_number_messages);
     assert_eq!(producer.average_send_delay, new_average_send_delay);
     assert_eq!(producer.failure_rate, new_failure_rate);
-    assert_eq!(producer.status, "INACTIVE");
+    assert_eq!(producer.status, ProducerStatus::Inactive);
 }
 
 #[tokio::test]
 async fn test_update_producer_invalid_failure_rate() {
-    let mut db = cleanup_and_prepare().await.unwrap().get().unwrap();
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
 
     let producer_id = "some-uuid";
     let new_name = "Invalid Failure Rate".to_string();
@@ -191,7 +215,7 @@ async fn test_update_producer_invalid_failure_rate() {
 
     // Call update_producer with invalid data
     let result = update_producer(
-        &mut db,
+        &repos,
         producer_id.to_string(),
         new_name,
         new_number_messages,
@@ -213,7 +237,8 @@ async fn test_update_producer_invalid_failure_rate() {
 
 #[tokio::test]
 async fn test_update_producer_invalid_number_messages() {
-    let mut db = cleanup_and_prepare().await.unwrap().get().unwrap();
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
 
     let producer_id = "some-uuid";
     let new_name = "Invalid Number of Messages".to_string();
@@ -223,7 +248,7 @@ async fn test_update_producer_invalid_number_messages() {
     let senders = Some(4);
 
     let result = update_producer(
-        &mut db,
+        &repos,
         producer_id.to_string(),
         new_name,
         new_number_messages,
@@ -247,7 +272,8 @@ async fn test_update_producer_invalid_number_messages() {
 
 #[tokio::test]
 async fn test_update_producer_not_found() {
-    let mut db = cleanup_and_prepare().await.unwrap().get().unwrap();
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
 
     let producer_id = "some-uuid";
     let new_name = "Updated Producer".to_string();
@@ -257,7 +283,7 @@ async fn test_update_producer_not_found() {
     let senders = Some(4);
 
     let result = update_producer(
-        &mut db,
+        &repos,
         producer_id.to_string(),
         new_name,
         new_number_messages,
@@ -271,8 +297,205 @@ async fn test_update_producer_not_found() {
 }
 
 #[tokio::test]
-async fn test_get_all_producers() {
-    let mut db = cleanup_and_prepare().await.unwrap().get().unwrap();
+async fn test_update_producer_transport() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 100, 20, 10, Some(4))
+        .await
+        .unwrap();
+    assert_eq!(
+        TransportConfig::try_from(&producer.transport).unwrap(),
+        TransportConfig::Simulated(SimulatedConfig::default())
+    );
+
+    let result = update_producer_transport(
+        &repos,
+        producer.id.to_string(),
+        TransportConfig::Simulated(SimulatedConfig::default()),
+    )
+    .await;
+
+    assert!(result.is_ok());
+    assert_eq!(
+        TransportConfig::try_from(&result.unwrap().transport).unwrap(),
+        TransportConfig::Simulated(SimulatedConfig::default())
+    );
+}
+
+#[tokio::test]
+async fn test_update_producer_transport_not_found() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+
+    let result = update_producer_transport(
+        &repos,
+        uuid::Uuid::new_v4().to_string(),
+        TransportConfig::Simulated(SimulatedConfig::default()),
+    )
+    .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_update_producer_retry_policy() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 100, 20, 10, Some(4))
+        .await
+        .unwrap();
+    assert_eq!(
+        RetryPolicy::try_from(&producer.retry_policy).unwrap(),
+        RetryPolicy::default()
+    );
+
+    let new_policy = RetryPolicy {
+        max_attempts: 5,
+        base_delay_ms: 250,
+        multiplier: 3.0,
+        max_delay_ms: 10_000,
+        jitter: 0.2,
+        retryable: vec![FailureKind::Timeout, FailureKind::Throttled],
+    };
+
+    let result =
+        update_producer_retry_policy(&repos, producer.id.to_string(), new_policy.clone()).await;
+
+    assert!(result.is_ok());
+    assert_eq!(
+        RetryPolicy::try_from(&result.unwrap().retry_policy).unwrap(),
+        new_policy
+    );
+}
+
+#[tokio::test]
+async fn test_update_producer_retry_policy_invalid() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 100, 20, 10, Some(4))
+        .await
+        .unwrap();
+
+    let result = update_producer_retry_policy(
+        &repos,
+        producer.id.to_string(),
+        RetryPolicy {
+            max_attempts: 0,
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_update_producer_rate_limit() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 100, 20, 10, Some(4))
+        .await
+        .unwrap();
+    assert_eq!(producer.rate_limit, None);
+
+    let new_limit = RateLimit {
+        rate: 600,
+        per: RatePeriod::Minute,
+        burst: 20,
+    };
+
+    let limited =
+        update_producer_rate_limit(&repos, producer.id.to_string(), Some(new_limit.clone()))
+            .await
+            .unwrap();
+    assert_eq!(PublicProducer::from(limited).rate_limit, Some(new_limit));
+
+    let cleared = update_producer_rate_limit(&repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(cleared.rate_limit, None);
+}
+
+#[tokio::test]
+async fn test_update_producer_seed() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 100, 20, 10, Some(4))
+        .await
+        .unwrap();
+    assert_eq!(producer.seed, None);
+
+    let seeded = update_producer_seed(&repos, producer.id.to_string(), Some(42))
+        .await
+        .unwrap();
+    assert_eq!(PublicProducer::from(seeded).seed, Some(42));
+
+    let cleared = update_producer_seed(&repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(cleared.seed, None);
+}
+
+#[tokio::test]
+async fn test_generate_messages_with_seed() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+
+    let mut generated_bodies = vec![];
+    for _ in 0..2 {
+        let producer = create_producer(&repos, "Seeded Producer".to_string(), 20, 20, 10, Some(4))
+            .await
+            .unwrap();
+        generate_messages(&repos, producer.id.to_string(), Some(42))
+            .await
+            .unwrap();
+
+        let mut bodies: Vec<String> = messages
+            .filter(produced_by.eq(producer.id))
+            .select(message_body)
+            .load(&mut db)
+            .unwrap();
+        bodies.sort();
+        generated_bodies.push(bodies);
+    }
+
+    assert_eq!(generated_bodies[0].len(), 20);
+    assert_eq!(generated_bodies[0], generated_bodies[1]);
+}
+
+#[tokio::test]
+async fn test_update_producer_rate_limit_invalid() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 100, 20, 10, Some(4))
+        .await
+        .unwrap();
+
+    let result = update_producer_rate_limit(
+        &repos,
+        producer.id.to_string(),
+        Some(RateLimit {
+            rate: 0,
+            per: RatePeriod::Second,
+            burst: 1,
+        }),
+    )
+    .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_list_producers_first_page() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
 
     let new_name = "Valid Producer".to_string();
     let new_number_messages = 100;
@@ -281,7 +504,7 @@ async fn test_get_all_producers() {
     let senders = Some(4);
 
     let _ = create_producer(
-        &mut db,
+        &repos,
         new_name,
         new_number_messages,
         new_average_send_delay,
@@ -290,21 +513,31 @@ async fn test_get_all_producers() {
     )
     .await;
 
-    let result = get_all_producers(&mut db).await;
+    let result = list_producers(
+        &repos,
+        None,
+        None,
+        None,
+        None,
+        ProducerSort::default(),
+        SortOrder::default(),
+    )
+    .await;
 
     assert!(result.is_ok());
-    let producers = result.unwrap();
+    let producers = result.unwrap().producers;
     assert_eq!(producers.first().unwrap().name, "Valid Producer");
     assert_eq!(producers.first().unwrap().number_messages, 100);
     assert_eq!(producers.first().unwrap().average_send_delay, 20);
     assert_eq!(producers.first().unwrap().failure_rate, 10);
     assert_eq!(producers.first().unwrap().num_senders, Some(4));
-    assert_eq!(producers.first().unwrap().status, "INACTIVE");
+    assert_eq!(producers.first().unwrap().status, ProducerStatus::Inactive);
 }
 
 #[tokio::test]
 async fn test_get_producer_by_id() {
-    let mut db = cleanup_and_prepare().await.unwrap().get().unwrap();
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
 
     let new_name = "Valid Producer".to_string();
     let new_number_messages = 100;
@@ -313,7 +546,7 @@ async fn test_get_producer_by_id() {
     let senders = Some(4);
 
     let result = create_producer(
-        &mut db,
+        &repos,
         new_name,
         new_number_messages,
         new_average_send_delay,
@@ -322,7 +555,7 @@ async fn test_get_producer_by_id() {
     )
     .await;
 
-    let result = get_producer_by_id(&mut db, result.unwrap().id.to_string()).await;
+    let result = get_producer_by_id(&repos, result.unwrap().id.to_string()).await;
 
     assert!(result.is_ok());
     let producer = result.unwrap();
@@ -331,12 +564,14 @@ async fn test_get_producer_by_id() {
     assert_eq!(producer.average_send_delay, 20);
     assert_eq!(producer.failure_rate, 10);
     assert_eq!(producer.num_senders, Some(4));
-    assert_eq!(producer.status, "INACTIVE");
+    assert_eq!(producer.status, ProducerStatus::Inactive);
 }
 
 #[tokio::test]
 async fn test_get_producer_progress() {
-    let mut db = cleanup_and_prepare().await.unwrap().get().unwrap();
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
 
     let new_name = "Valid Producer".to_string();
     let new_number_messages = 100;
@@ -345,7 +580,7 @@ async fn test_get_producer_progress() {
     let senders = Some(4);
 
     let producer = create_producer(
-        &mut db,
+        &repos,
         new_name,
         new_number_messages,
         new_average_send_delay,
@@ -375,20 +610,140 @@ async fn test_get_producer_progress() {
         .get_results(&mut db)
         .unwrap();
 
-    let progress_data = get_producer_progress_data(&mut db, producer.id.to_string())
+    let progress_data = get_producer_progress_data(&repos, producer.id.to_string())
         .await
         .unwrap();
 
     assert_eq!(progress_data.average_message_time, 5);
-    assert_eq!(progress_data.message_times, vec![5]);
+    assert_eq!(progress_data.average_message_time_ms, 5000.0);
+    assert_eq!(
+        progress_data.message_time_histogram,
+        vec![MessageTimeBucket {
+            from_ms: 5000,
+            to_ms: 5001,
+            count: 1
+        }]
+    );
     assert_eq!(progress_data.number_messages_created, 2);
     assert_eq!(progress_data.number_messages_sent, 1);
     assert_eq!(progress_data.number_messages_failed, 0);
+    assert_eq!(progress_data.number_messages_retried, 0);
+    assert_eq!(progress_data.number_messages_recovered, 0);
+}
+
+#[tokio::test]
+async fn test_get_producer_progress_in_milliseconds() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 2, 1, 0, Some(1))
+        .await
+        .unwrap();
+
+    let times_ms = [250, 1750];
+    let new_messages: Vec<NewMessageFull> = times_ms
+        .iter()
+        .map(|time_ms| NewMessageFull {
+            message_body: String::from("Test Message"),
+            sent: true,
+            failed: false,
+            time_took: Some(time_ms / 1000),
+            produced_by: producer.id,
+        })
+        .collect();
+
+    let inserted: Vec<Message> = diesel::insert_into(messages)
+        .values(new_messages)
+        .get_results(&mut db)
+        .unwrap();
+
+    for (message, time_ms) in inserted.iter().zip(times_ms) {
+        diesel::update(messages.find(message.id))
+            .set(time_took_ms.eq(time_ms))
+            .execute(&mut db)
+            .unwrap();
+    }
+
+    let progress_data = get_producer_progress_data(&repos, producer.id.to_string())
+        .await
+        .unwrap();
+
+    // 1501 milliseconds from the quickest to the slowest spread over buckets 31 wide
+    let histogram = &progress_data.message_time_histogram;
+    assert_eq!(histogram.len(), 49);
+    assert_eq!(
+        histogram[0],
+        MessageTimeBucket {
+            from_ms: 250,
+            to_ms: 281,
+            count: 1
+        }
+    );
+    assert_eq!(
+        histogram[48],
+        MessageTimeBucket {
+            from_ms: 1738,
+            to_ms: 1769,
+            count: 1
+        }
+    );
+    assert_eq!(histogram.iter().map(|bucket| bucket.count).sum::<i64>(), 2);
+    assert_eq!(progress_data.average_message_time_ms, 1000.0);
+    assert_eq!(progress_data.average_message_time, 1);
+}
+
+#[tokio::test]
+async fn test_get_producer_progress_retries() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 3, 1, 0, Some(1))
+        .await
+        .unwrap();
+
+    // (failed, attempts) for a recovered, an exhausted and a first try message
+    let outcomes = [(false, 2), (true, 3), (false, 1)];
+
+    let new_messages: Vec<NewMessageFull> = outcomes
+        .iter()
+        .map(|(did_fail, _)| NewMessageFull {
+            message_body: String::from("Test Message"),
+            sent: true,
+            failed: *did_fail,
+            time_took: Some(1),
+            produced_by: producer.id,
+        })
+        .collect();
+
+    let inserted: Vec<Message> = diesel::insert_into(messages)
+        .values(new_messages)
+        .get_results(&mut db)
+        .unwrap();
+
+    for (message, (_, attempts_made)) in inserted.iter().zip(outcomes) {
+        diesel::update(messages.find(message.id))
+            .set(attempts.eq(attempts_made))
+            .execute(&mut db)
+            .unwrap();
+    }
+
+    let progress_data = get_producer_progress_data(&repos, producer.id.to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(progress_data.number_messages_sent, 3);
+    assert_eq!(progress_data.number_messages_failed, 1);
+    assert_eq!(progress_data.number_messages_retried, 2);
+    assert_eq!(progress_data.number_messages_recovered, 1);
 }
 
 #[tokio::test]
 async fn test_generate_messages() {
-    let mut db = cleanup_and_prepare().await.unwrap().get().unwrap();
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
 
     let new_name = "Valid Producer".to_string();
     let new_number_messages = 100;
@@ -397,7 +752,7 @@ async fn test_generate_messages() {
     let senders = Some(4);
 
     let producer = create_producer(
-        &mut db,
+        &repos,
         new_name,
         new_number_messages,
         new_average_send_delay,
@@ -407,7 +762,7 @@ async fn test_generate_messages() {
     .await
     .unwrap();
 
-    let number_of_messages = generate_messages(&mut db, producer.id.to_string())
+    let number_of_messages = generate_messages(&repos, producer.id.to_string(), None)
         .await
         .unwrap();
 
@@ -417,9 +772,42 @@ async fn test_generate_messages() {
     assert_eq!(created_messages.len(), 100);
 }
 
+#[tokio::test]
+async fn test_generate_messages_past_the_bind_parameter_limit() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+
+    // A single INSERT of this many messages needs more than the 65535 parameters Postgres allows
+    let producer = create_producer(&repos, "Large Producer".to_string(), 45_000, 1, 0, Some(1))
+        .await
+        .unwrap();
+
+    let number_of_messages = generate_messages(&repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    assert_eq!(number_of_messages, 45_000);
+    assert_eq!(
+        messages
+            .filter(produced_by.eq(producer.id))
+            .count()
+            .get_result::<i64>(&mut db)
+            .unwrap(),
+        45_000
+    );
+
+    let generated = get_producer_by_id(&repos, producer.id.to_string())
+        .await
+        .unwrap();
+    assert_eq!(generated.status, ProducerStatus::Generated);
+    assert_eq!(generated.generated_messages, 45_000);
+}
+
 #[tokio::test]
 async fn test_activate_producer() {
     let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
     let mut db = pool.get().unwrap();
 
     let new_name = "Valid Producer".to_string();
@@ -429,7 +817,7 @@ async fn test_activate_producer() {
     let senders = Some(4);
 
     let producer = create_producer(
-        &mut db,
+        &repos,
         new_name,
         new_number_messages,
         new_average_send_delay,
@@ -439,22 +827,363 @@ async fn test_activate_producer() {
     .await
     .unwrap();
 
-    let _ = generate_messages(&mut db, producer.id.to_string())
+    let _ = generate_messages(&repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    let jobs = JobRegistry::new();
+    let job = activate_producer(&repos, &jobs, producer.id.to_string(), None)
         .await
         .unwrap();
 
-    let _ = activate_producer(Arc::new(pool), producer.id.to_string()).await;
+    // The request returns before any message has been sent
+    assert_eq!(job.state(), JobState::Running);
+    assert_eq!(job.number_messages, 10);
+
+    job.wait_until_finished().await;
 
     let created_messages: Vec<Message> = messages.load(&mut db).unwrap();
 
+    assert_eq!(job.state(), JobState::Completed);
+    assert!(job.finished_at().is_some());
+    assert_eq!(job.number_sent(), 10);
+    assert_eq!(
+        job.number_failed(),
+        created_messages.iter().filter(|mes| mes.failed).count()
+    );
     assert_eq!(created_messages.len(), 10);
     assert!(created_messages.iter().all(|mes| mes.sent));
     assert!(created_messages.iter().any(|mes| mes.failed));
+    assert_eq!(
+        get_producer_by_id(&repos, producer.id.to_string())
+            .await
+            .unwrap()
+            .status,
+        ProducerStatus::Empty
+    );
+}
+
+#[tokio::test]
+async fn test_activate_producer_with_instant_clock() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+
+    // Two hundred 20 second sends would take at least 1000 seconds on four real senders
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 200, 20, 0, Some(4))
+        .await
+        .unwrap();
+    let _ = update_producer_transport(
+        &repos,
+        producer.id.to_string(),
+        TransportConfig::Simulated(SimulatedConfig {
+            clock: ClockConfig::Instant,
+            ..Default::default()
+        }),
+    )
+    .await
+    .unwrap();
+    let _ = generate_messages(&repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    let start = std::time::Instant::now();
+    let jobs = JobRegistry::new();
+    let job = activate_producer(&repos, &jobs, producer.id.to_string(), None)
+        .await
+        .unwrap();
+    job.wait_until_finished().await;
+
+    assert!(start.elapsed() < std::time::Duration::from_secs(60));
+    let created_messages: Vec<Message> = messages.load(&mut db).unwrap();
+    assert_eq!(job.number_sent(), 200);
+    assert!(created_messages
+        .iter()
+        .all(|mes| (15..=25).contains(&mes.time_took.unwrap())));
+}
+
+#[tokio::test]
+async fn test_activate_producer_on_two_servers() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 300, 1, 0, Some(2))
+        .await
+        .unwrap();
+    let _ = update_producer_transport(
+        &repos,
+        producer.id.to_string(),
+        TransportConfig::Simulated(SimulatedConfig {
+            clock: ClockConfig::Instant,
+            ..Default::default()
+        }),
+    )
+    .await
+    .unwrap();
+    let _ = generate_messages(&repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    // Each registry stands in for a server sharing the database
+    let first_server = JobRegistry::new();
+    let second_server = JobRegistry::new();
+    let first_job = activate_producer(&repos, &first_server, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    match activate_producer(&repos, &first_server, producer.id.to_string(), None).await {
+        Err(SMSManagerError::Conflict(msg)) => {
+            assert_eq!(
+                msg,
+                "Can not move the producer from SENDING to SENDING, this server is already sending it"
+            );
+        }
+        _ => panic!("Expected Conflict error"),
+    }
+
+    let second_job = activate_producer(&repos, &second_server, producer.id.to_string(), None)
+        .await
+        .unwrap();
+    first_job.wait_until_finished().await;
+    second_job.wait_until_finished().await;
+
+    // Every message was sent exactly once between the two servers
+    assert_eq!(first_job.number_sent() + second_job.number_sent(), 300);
+    let created_messages: Vec<Message> = messages.load(&mut db).unwrap();
+    assert!(created_messages
+        .iter()
+        .all(|mes| mes.sent && mes.attempts == 1 && !mes.in_flight));
+    assert_eq!(
+        get_producer_by_id(&repos, producer.id.to_string())
+            .await
+            .unwrap()
+            .status,
+        ProducerStatus::Empty
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_activate_producer_parallel_requests() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    check_activate_producer_parallel_requests(
+        &Repositories::postgres(pool),
+        TransportConfig::Simulated(SimulatedConfig {
+            clock: ClockConfig::Instant,
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_failed_generation_is_undone() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    check_failed_generation_is_undone(&Repositories::postgres(pool)).await;
+}
+
+#[tokio::test]
+async fn test_writes_refused_once_a_send_claims_the_producer() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    check_writes_refused_once_a_send_claims_the_producer(&Repositories::postgres(pool)).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_generate_messages_parallel_requests() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    check_generate_messages_parallel_requests(&Repositories::postgres(pool)).await;
+}
+
+#[tokio::test]
+async fn test_same_seed_sends_alike() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    check_same_seed_sends_alike(&Repositories::postgres(pool)).await;
+}
+
+#[tokio::test]
+async fn test_pause_and_resume_producer() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 4, 1, 0, Some(1))
+        .await
+        .unwrap();
+
+    let _ = generate_messages(&repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    let jobs = JobRegistry::new();
+    let job = activate_producer(&repos, &jobs, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    let paused_job = pause_producer(&repos, &jobs, producer.id.to_string())
+        .await
+        .unwrap();
+    assert_eq!(paused_job.id, job.id);
+    assert_eq!(job.state(), JobState::Paused);
+
+    // Give the in flight message time to drain
+    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+
+    let unsent_messages: Vec<Message> = messages.filter(sent.eq(false)).load(&mut db).unwrap();
+    assert!(unsent_messages.len() >= 3);
+    assert!(!job.is_finished());
+    assert_eq!(
+        get_producer_by_id(&repos, producer.id.to_string())
+            .await
+            .unwrap()
+            .status,
+        ProducerStatus::Paused
+    );
+
+    assert!(pause_producer(&repos, &jobs, producer.id.to_string())
+        .await
+        .is_err());
+
+    let _ = resume_producer(&repos, &jobs, producer.id.to_string())
+        .await
+        .unwrap();
+    assert_eq!(job.state(), JobState::Running);
+
+    job.wait_until_finished().await;
+
+    let sent_messages: Vec<Message> = messages.filter(sent.eq(true)).load(&mut db).unwrap();
+    assert_eq!(sent_messages.len(), 4);
+    assert_eq!(job.state(), JobState::Completed);
+}
+
+#[tokio::test]
+async fn test_cancel_producer() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 4, 1, 0, Some(1))
+        .await
+        .unwrap();
+
+    let _ = generate_messages(&repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    let jobs = JobRegistry::new();
+    let job = activate_producer(&repos, &jobs, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    let _ = cancel_producer(&repos, &jobs, producer.id.to_string())
+        .await
+        .unwrap();
+
+    job.wait_until_finished().await;
+
+    let unsent_messages: Vec<Message> = messages.filter(sent.eq(false)).load(&mut db).unwrap();
+    assert!(unsent_messages.len() >= 3);
+    // The cancelled send gives up its leases on the messages it did not get to
+    assert!(unsent_messages.iter().all(|message| !message.in_flight));
+    assert_eq!(job.state(), JobState::Cancelled);
+    assert_eq!(
+        get_producer_by_id(&repos, producer.id.to_string())
+            .await
+            .unwrap()
+            .status,
+        ProducerStatus::Cancelled
+    );
+
+    // A later send picks up the messages the cancelled send left behind
+    let next_job = activate_producer(&repos, &jobs, producer.id.to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(next_job.number_messages, unsent_messages.len());
+
+    let _ = cancel_producer(&repos, &jobs, producer.id.to_string()).await;
+    next_job.wait_until_finished().await;
+}
+
+#[tokio::test]
+async fn test_pause_producer_not_sending() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 4, 1, 0, Some(1))
+        .await
+        .unwrap();
+
+    let result = pause_producer(&repos, &JobRegistry::new(), producer.id.to_string()).await;
+
+    match result {
+        Err(SMSManagerError::Conflict(msg)) => {
+            assert_eq!(msg, "Can not move the producer from INACTIVE to PAUSED");
+        }
+        _ => panic!("Expected Conflict error"),
+    }
+}
+
+#[tokio::test]
+async fn test_producer_changes_while_sending_conflict() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 4, 1, 0, Some(1))
+        .await
+        .unwrap();
+
+    match activate_producer(&repos, &JobRegistry::new(), producer.id.to_string(), None).await {
+        Err(SMSManagerError::Conflict(msg)) => {
+            assert_eq!(msg, "Can not move the producer from INACTIVE to SENDING");
+        }
+        _ => panic!("Expected Conflict error"),
+    }
+
+    let _ = generate_messages(&repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    let jobs = JobRegistry::new();
+    let job = activate_producer(&repos, &jobs, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    match generate_messages(&repos, producer.id.to_string(), None).await {
+        Err(SMSManagerError::Conflict(msg)) => {
+            assert_eq!(msg, "Can not move the producer from SENDING to GENERATING");
+        }
+        _ => panic!("Expected Conflict error"),
+    }
+
+    match delete_producer(&repos, producer.id.to_string()).await {
+        Err(SMSManagerError::Conflict(msg)) => {
+            assert_eq!(msg, "Can not delete the producer while it is SENDING");
+        }
+        _ => panic!("Expected Conflict error"),
+    }
+
+    assert!(matches!(
+        update_producer_seed(&repos, producer.id.to_string(), Some(7)).await,
+        Err(SMSManagerError::Conflict(_))
+    ));
+
+    let _ = cancel_producer(&repos, &jobs, producer.id.to_string())
+        .await
+        .unwrap();
+    job.wait_until_finished().await;
+
+    // Once the send has stopped the producer can be changed again
+    assert_eq!(
+        delete_producer(&repos, producer.id.to_string())
+            .await
+            .unwrap(),
+        "Successfully deleted producer"
+    );
 }
 
 #[tokio::test]
 async fn test_delete_producer() {
-    let mut db = cleanup_and_prepare().await.unwrap().get().unwrap();
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
 
     let new_name = "Valid Producer".to_string();
     let new_number_messages = 100;
@@ -463,7 +1192,7 @@ async fn test_delete_producer() {
     let senders = Some(4);
 
     let result = create_producer(
-        &mut db,
+        &repos,
         new_name,
         new_number_messages,
         new_average_send_delay,
@@ -473,9 +1202,15 @@ async fn test_delete_producer() {
     .await
     .unwrap();
 
-    let message = delete_producer(&mut db, result.id.to_string())
+    let message = delete_producer(&repos, result.id.to_string())
         .await
         .unwrap();
 
     assert_eq!(message, "Successfully deleted producer");
 }
+
+#[tokio::test]
+async fn test_list_producers() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    check_list_producers(&Repositories::postgres(pool)).await;
+}
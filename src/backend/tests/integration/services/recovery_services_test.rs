@@ -0,0 +1,306 @@
+use std::{collections::HashSet, sync::Arc};
+
+use backend::{
+    diesel::{
+        models::{Message, Producer},
+        schema::{
+            messages::dsl::*,
+            producer_leases,
+            producers::{dsl::producers, status},
+        },
+    },
+    repositories::Repositories,
+    services::{
+        producer_services::{
+            activate_producer, create_producer, generate_messages, get_producer_by_id,
+            resume_producer,
+        },
+        recovery_services::recover_interrupted_sends,
+    },
+    utils::{
+        error::SMSManagerError,
+        job_registry::JobRegistry,
+        producer_status::{ProducerAction, ProducerStatus},
+        work_queue::{ClaimingWorkQueue, WorkQueue},
+    },
+};
+use chrono::{Duration, Utc};
+use diesel::{BoolExpressionMethods, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::test_utils::cleanup_and_prepare;
+
+async fn a_generated_producer(repos: &Repositories, number: i32) -> Producer {
+    let producer = create_producer(repos, "Valid Producer".to_string(), number, 1, 0, Some(1))
+        .await
+        .unwrap();
+    generate_messages(repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+    producer
+}
+
+#[tokio::test]
+async fn test_claim_pending_messages() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+    let producer = a_generated_producer(&repos, 3).await;
+    let owner = Uuid::new_v4();
+
+    let leased = repos.messages.claim(producer.id, owner, 10).await.unwrap();
+    assert_eq!(leased.len(), 3);
+    assert!(leased
+        .iter()
+        .all(|message| message.in_flight && message.leased_by == Some(owner)));
+
+    // Another server can not take messages that are leased
+    let taken = repos
+        .messages
+        .claim(producer.id, Uuid::new_v4(), 10)
+        .await
+        .unwrap();
+    assert!(taken.is_empty());
+
+    assert_eq!(
+        repos
+            .messages
+            .renew_leases(producer.id, owner)
+            .await
+            .unwrap(),
+        3
+    );
+    assert_eq!(
+        repos
+            .messages
+            .release_leases(producer.id, owner)
+            .await
+            .unwrap(),
+        3
+    );
+
+    let released: Vec<Message> = messages
+        .filter(produced_by.eq(producer.id))
+        .load(&mut db)
+        .unwrap();
+    assert!(released.iter().all(|message| !message.in_flight));
+}
+
+#[tokio::test]
+async fn test_claim_pending_messages_takes_expired_leases() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+    let producer = a_generated_producer(&repos, 2).await;
+
+    let _ = repos
+        .messages
+        .claim(producer.id, Uuid::new_v4(), 10)
+        .await
+        .unwrap();
+    diesel::update(messages.filter(produced_by.eq(producer.id)))
+        .set(lease_expires_at.eq(Utc::now() - Duration::seconds(1)))
+        .execute(&mut db)
+        .unwrap();
+
+    let taken = repos
+        .messages
+        .claim(producer.id, Uuid::new_v4(), 10)
+        .await
+        .unwrap();
+    assert_eq!(taken.len(), 2);
+}
+
+#[tokio::test]
+async fn test_claim_pending_messages_in_batches() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let producer = a_generated_producer(&repos, 5).await;
+    let owner = Uuid::new_v4();
+
+    let first = repos.messages.claim(producer.id, owner, 2).await.unwrap();
+    assert_eq!(first.len(), 2);
+    assert_eq!(
+        repos.messages.count_claimable(producer.id).await.unwrap(),
+        3
+    );
+
+    // The messages the owner already holds are not claimed again
+    let second = repos.messages.claim(producer.id, owner, 10).await.unwrap();
+    assert_eq!(second.len(), 3);
+    assert!(second
+        .iter()
+        .all(|message| first.iter().all(|claimed| claimed.id != message.id)));
+    assert_eq!(
+        repos.messages.count_claimable(producer.id).await.unwrap(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_work_queues_share_messages_without_duplicates() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let producer = a_generated_producer(&repos, 25).await;
+
+    // Two servers working through the same producer at once
+    let first: Arc<dyn WorkQueue> = Arc::new(ClaimingWorkQueue::new(
+        repos.messages.clone(),
+        producer.id,
+        Uuid::new_v4(),
+        3,
+    ));
+    let second: Arc<dyn WorkQueue> = Arc::new(ClaimingWorkQueue::new(
+        repos.messages.clone(),
+        producer.id,
+        Uuid::new_v4(),
+        4,
+    ));
+
+    let drain = |queue: Arc<dyn WorkQueue>| {
+        tokio::spawn(async move {
+            let mut taken = vec![];
+            while let Some(message) = queue.next().await.unwrap() {
+                taken.push(message.id);
+            }
+            taken
+        })
+    };
+    let (first_taken, second_taken) = (drain(first), drain(second));
+    let first_taken = first_taken.await.unwrap();
+    let second_taken = second_taken.await.unwrap();
+
+    let unique: HashSet<Uuid> = first_taken.iter().chain(&second_taken).copied().collect();
+    assert_eq!(first_taken.len() + second_taken.len(), 25);
+    assert_eq!(unique.len(), 25);
+}
+
+#[tokio::test]
+async fn test_recover_interrupted_sends() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+    let sending = a_generated_producer(&repos, 2).await;
+    let paused = a_generated_producer(&repos, 2).await;
+    let generated = a_generated_producer(&repos, 2).await;
+    let shared = a_generated_producer(&repos, 2).await;
+    let between_batches = a_generated_producer(&repos, 2).await;
+    let owner = Uuid::new_v4();
+    let (dead_server, live_server) = (Uuid::new_v4(), Uuid::new_v4());
+    let send = |server: Uuid, producer_id: Uuid| {
+        repos.producers.claim_status(
+            producer_id,
+            server,
+            Box::new(|current| current.apply(ProducerAction::Send)),
+        )
+    };
+
+    // A server that has since died leased the sending producer and its messages, and this server leased the generated producers before it stopped
+    send(dead_server, sending.id).await.unwrap();
+    let _ = repos
+        .messages
+        .claim(sending.id, dead_server, 10)
+        .await
+        .unwrap();
+    diesel::update(messages.filter(produced_by.eq(sending.id)))
+        .set(lease_expires_at.eq(Utc::now() - Duration::seconds(1)))
+        .execute(&mut db)
+        .unwrap();
+    diesel::update(producer_leases::table.filter(producer_leases::producer_id.eq(sending.id)))
+        .set(producer_leases::lease_expires_at.eq(Utc::now() - Duration::seconds(1)))
+        .execute(&mut db)
+        .unwrap();
+    let _ = repos.messages.claim(generated.id, owner, 10).await.unwrap();
+    // Another server is still sending the shared producer, and the last producer with nothing in flight until it claims its next batch
+    send(live_server, shared.id).await.unwrap();
+    let _ = repos
+        .messages
+        .claim(shared.id, live_server, 10)
+        .await
+        .unwrap();
+    send(live_server, between_batches.id).await.unwrap();
+    diesel::update(producers.find(paused.id))
+        .set(status.eq(ProducerStatus::Paused))
+        .execute(&mut db)
+        .unwrap();
+
+    let report = recover_interrupted_sends(&repos, owner).await.unwrap();
+
+    assert_eq!(report.requeued_messages, 4);
+    assert_eq!(report.interrupted_sends, vec![sending.id]);
+    assert_eq!(report.interrupted_pauses, vec![paused.id]);
+
+    let in_flight_messages: Vec<Message> =
+        messages.filter(in_flight.eq(true)).load(&mut db).unwrap();
+    assert_eq!(in_flight_messages.len(), 2);
+    assert!(in_flight_messages
+        .iter()
+        .all(|message| message.produced_by == shared.id));
+
+    for (producer, expected_status) in [
+        (sending, ProducerStatus::Interrupted),
+        (paused, ProducerStatus::Interrupted),
+        (generated, ProducerStatus::Generated),
+        (shared, ProducerStatus::Sending),
+        (between_batches, ProducerStatus::Sending),
+    ] {
+        assert_eq!(
+            get_producer_by_id(&repos, producer.id.to_string())
+                .await
+                .unwrap()
+                .status,
+            expected_status
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_interrupted_producer_sends_again() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+    let producer = a_generated_producer(&repos, 2).await;
+    let jobs = JobRegistry::new();
+
+    // This server was sending the producer before it restarted
+    let _ = repos
+        .messages
+        .claim(producer.id, jobs.instance_id(), 10)
+        .await
+        .unwrap();
+    diesel::update(producers.find(producer.id))
+        .set(status.eq(ProducerStatus::Sending))
+        .execute(&mut db)
+        .unwrap();
+
+    let _ = recover_interrupted_sends(&repos, jobs.instance_id())
+        .await
+        .unwrap();
+
+    match resume_producer(&repos, &jobs, producer.id.to_string()).await {
+        Err(SMSManagerError::Conflict(msg)) => {
+            assert!(msg.starts_with("Can not move the producer from INTERRUPTED to SENDING"));
+            assert!(msg.contains("interrupted by a restart"));
+        }
+        _ => panic!("Expected Conflict error"),
+    }
+
+    let job = activate_producer(&repos, &jobs, producer.id.to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(job.number_messages, 2);
+    job.wait_until_finished().await;
+
+    let sent_messages: Vec<Message> = messages
+        .filter(sent.eq(true).and(in_flight.eq(false)))
+        .load(&mut db)
+        .unwrap();
+    assert_eq!(sent_messages.len(), 2);
+    assert_eq!(
+        get_producer_by_id(&repos, producer.id.to_string())
+            .await
+            .unwrap()
+            .status,
+        ProducerStatus::Empty
+    );
+}
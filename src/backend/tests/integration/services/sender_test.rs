@@ -1,21 +1,51 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use backend::{
     diesel::{
-        models::{Message, NewMessage},
-        schema::messages::dsl::*,
+        models::{DeliveryAttempt, Message, NewDeliveryAttempt, NewMessage},
+        schema::{
+            delivery_attempts,
+            messages::dsl::*,
+            producers::{dsl::producers, status},
+        },
+    },
+    repositories::Repositories,
+    services::producer_services::{create_producer, get_producer_by_id},
+    utils::{
+        job_registry::SendJob,
+        producer_status::ProducerStatus,
+        sender::{get_message_updater, DeliveryUpdate, UpdaterConfig},
     },
-    services::producer_services::create_producer,
-    utils::sender::get_message_updater,
 };
-use diesel::RunQueryDsl;
+use chrono::{DateTime, Utc};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
 use tokio::sync::mpsc;
+use uuid::Uuid;
 
 use crate::test_utils::cleanup_and_prepare;
 
+fn an_attempt(message_id: Uuid, failure: Option<&str>) -> NewDeliveryAttempt {
+    NewDeliveryAttempt {
+        message_id,
+        attempt_number: 1,
+        started_at: Utc::now(),
+        finished_at: Utc::now(),
+        outcome: if failure.is_some() {
+            "FAILED"
+        } else {
+            "DELIVERED"
+        }
+        .to_string(),
+        error_code: failure.map(|_| "SIMULATED".to_string()),
+        error_reason: failure.map(str::to_string),
+        worker_id: 0,
+    }
+}
+
 #[tokio::test]
 async fn test_message_updater_updates_message_statuses() {
     let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
     let mut db: diesel::r2d2::PooledConnection<
         diesel::r2d2::ConnectionManager<diesel::PgConnection>,
     > = pool.get().unwrap();
@@ -27,7 +57,7 @@ async fn test_message_updater_updates_message_statuses() {
     let senders = Some(4);
 
     let producer = create_producer(
-        &mut db,
+        &repos,
         new_name,
         new_number_messages,
         new_average_send_delay,
@@ -40,10 +70,12 @@ async fn test_message_updater_updates_message_statuses() {
     let message1 = NewMessage {
         message_body: String::from("Test Message 1"),
         produced_by: producer.id,
+        message_index: 0,
     };
     let message2 = NewMessage {
         message_body: String::from("Test Message 2"),
         produced_by: producer.id,
+        message_index: 1,
     };
 
     let created_messages: Vec<Message> = diesel::insert_into(messages)
@@ -52,30 +84,65 @@ async fn test_message_updater_updates_message_statuses() {
         .unwrap();
 
     let (tx, rx) = mpsc::channel(10);
+    let message_started_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let message_finished_at = DateTime::from_timestamp_millis(1_700_000_005_250).unwrap();
 
     let _ = tx
-        .send(Message {
-            id: created_messages.first().unwrap().id,
-            message_body: "aBody".to_string(),
-            sent: true,
-            failed: false,
-            time_took: Some(5),
-            produced_by: producer.id,
+        .send(DeliveryUpdate {
+            message: Message {
+                id: created_messages.first().unwrap().id,
+                message_body: "aBody".to_string(),
+                sent: true,
+                failed: false,
+                time_took: Some(5),
+                produced_by: producer.id,
+                provider_message_id: None,
+                attempts: 0,
+                started_at: Some(message_started_at),
+                finished_at: Some(message_finished_at),
+                time_took_ms: Some(5250),
+                in_flight: false,
+                leased_by: None,
+                lease_expires_at: None,
+                message_index: 0,
+            },
+            attempt: an_attempt(created_messages.first().unwrap().id, None),
         })
         .await;
 
     let _ = tx
-        .send(Message {
-            id: created_messages.last().unwrap().id,
-            message_body: "aBody".to_string(),
-            sent: true,
-            failed: true,
-            time_took: Some(10),
-            produced_by: producer.id,
+        .send(DeliveryUpdate {
+            message: Message {
+                id: created_messages.last().unwrap().id,
+                message_body: "aBody".to_string(),
+                sent: true,
+                failed: true,
+                time_took: Some(10),
+                produced_by: producer.id,
+                provider_message_id: None,
+                attempts: 0,
+                started_at: None,
+                finished_at: None,
+                time_took_ms: None,
+                in_flight: false,
+                leased_by: None,
+                lease_expires_at: None,
+                message_index: 0,
+            },
+            attempt: an_attempt(
+                created_messages.last().unwrap().id,
+                Some("Simulated failure"),
+            ),
         })
         .await;
 
-    let handle = get_message_updater(rx, Arc::new(pool));
+    let job = Arc::new(SendJob::new(producer.id, 2));
+    let handle = get_message_updater(
+        rx,
+        repos.messages.clone(),
+        job.clone(),
+        UpdaterConfig::default(),
+    );
 
     drop(tx);
 
@@ -86,8 +153,243 @@ async fn test_message_updater_updates_message_statuses() {
     assert!(updated_messages.first().unwrap().sent);
     assert!(!updated_messages.first().unwrap().failed);
     assert_eq!(updated_messages.first().unwrap().time_took, Some(5));
+    assert_eq!(updated_messages.first().unwrap().time_took_ms, Some(5250));
+    assert_eq!(
+        updated_messages.first().unwrap().started_at,
+        Some(message_started_at)
+    );
+    assert_eq!(
+        updated_messages.first().unwrap().finished_at,
+        Some(message_finished_at)
+    );
 
     assert!(updated_messages.last().unwrap().sent);
     assert!(updated_messages.last().unwrap().failed);
     assert_eq!(updated_messages.last().unwrap().time_took, Some(10));
+
+    assert_eq!(job.number_sent(), 2);
+    assert_eq!(job.number_failed(), 1);
+
+    let recorded_attempts: Vec<DeliveryAttempt> = delivery_attempts::table
+        .order(delivery_attempts::outcome.asc())
+        .load(&mut db)
+        .unwrap();
+    assert_eq!(recorded_attempts.len(), 2);
+    assert_eq!(recorded_attempts[0].outcome, "DELIVERED");
+    assert_eq!(
+        recorded_attempts[0].message_id,
+        created_messages.first().unwrap().id
+    );
+    assert_eq!(recorded_attempts[1].outcome, "FAILED");
+    assert_eq!(
+        recorded_attempts[1].error_code,
+        Some("SIMULATED".to_string())
+    );
+    assert_eq!(
+        recorded_attempts[1].error_reason,
+        Some("Simulated failure".to_string())
+    );
+}
+
+fn an_updated_message(message: &Message, attempt_number: i32, delivered: bool) -> Message {
+    Message {
+        sent: delivered,
+        attempts: attempt_number,
+        time_took_ms: Some(100 * attempt_number),
+        in_flight: !delivered,
+        ..message.clone()
+    }
+}
+
+#[tokio::test]
+async fn test_message_updater_writes_batches() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 3, 1, 0, Some(1))
+        .await
+        .unwrap();
+    let created_messages: Vec<Message> = diesel::insert_into(messages)
+        .values(
+            (1..=3)
+                .map(|number| NewMessage {
+                    message_body: format!("Test Message {}", number),
+                    produced_by: producer.id,
+                    message_index: number - 1,
+                })
+                .collect::<Vec<NewMessage>>(),
+        )
+        .get_results(&mut db)
+        .unwrap();
+
+    let (tx, rx) = mpsc::channel(10);
+    let job = Arc::new(SendJob::new(producer.id, 3));
+    let handle = get_message_updater(
+        rx,
+        repos.messages.clone(),
+        job.clone(),
+        UpdaterConfig {
+            batch_size: 3,
+            flush_interval: Duration::from_secs(60),
+        },
+    );
+
+    // The first message fails and is retried within the batch
+    let first = &created_messages[0];
+    for update in [
+        an_updated_message(first, 1, false),
+        an_updated_message(first, 2, true),
+        an_updated_message(&created_messages[1], 1, true),
+    ] {
+        let attempt = an_attempt(update.id, None);
+        let _ = tx
+            .send(DeliveryUpdate {
+                message: update,
+                attempt,
+            })
+            .await;
+    }
+
+    // A full batch is written straight away
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let stored: Message = messages.find(first.id).first(&mut db).unwrap();
+    assert!(stored.sent);
+    assert_eq!(stored.attempts, 2);
+    assert_eq!(stored.time_took_ms, Some(200));
+    assert!(!stored.in_flight);
+    assert_eq!(job.number_sent(), 2);
+
+    // The updater never writes the status, activation owns it, so a pause is kept
+    diesel::update(producers.find(producer.id))
+        .set(status.eq(ProducerStatus::Paused))
+        .execute(&mut db)
+        .unwrap();
+    let last = an_updated_message(&created_messages[2], 1, true);
+    let _ = tx
+        .send(DeliveryUpdate {
+            attempt: an_attempt(last.id, None),
+            message: last,
+        })
+        .await;
+
+    // What is left is written when the senders are done
+    drop(tx);
+    let _ = handle.await;
+
+    let stored_messages: Vec<Message> = messages.load(&mut db).unwrap();
+    assert!(stored_messages.iter().all(|message| message.sent));
+    assert_eq!(job.number_sent(), 3);
+    assert_eq!(
+        delivery_attempts::table
+            .count()
+            .get_result::<i64>(&mut db)
+            .unwrap(),
+        4
+    );
+    assert_eq!(
+        get_producer_by_id(&repos, producer.id.to_string())
+            .await
+            .unwrap()
+            .status,
+        ProducerStatus::Paused
+    );
+}
+
+#[tokio::test]
+async fn test_message_updater_flushes_on_interval() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 1, 1, 0, Some(1))
+        .await
+        .unwrap();
+    let created_message: Message = diesel::insert_into(messages)
+        .values(NewMessage {
+            message_body: "Test Message".to_string(),
+            produced_by: producer.id,
+            message_index: 0,
+        })
+        .get_result(&mut db)
+        .unwrap();
+
+    let (tx, rx) = mpsc::channel(10);
+    let job = Arc::new(SendJob::new(producer.id, 1));
+    let handle = get_message_updater(
+        rx,
+        repos.messages.clone(),
+        job.clone(),
+        UpdaterConfig {
+            batch_size: 100,
+            flush_interval: Duration::from_millis(50),
+        },
+    );
+
+    let _ = tx
+        .send(DeliveryUpdate {
+            message: an_updated_message(&created_message, 1, true),
+            attempt: an_attempt(created_message.id, None),
+        })
+        .await;
+
+    // The batch is far from full, but the result is still written while the send carries on
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let stored: Message = messages.find(created_message.id).first(&mut db).unwrap();
+    assert!(stored.sent);
+    assert_eq!(job.number_sent(), 1);
+
+    drop(tx);
+    let _ = handle.await;
+}
+
+#[tokio::test]
+async fn test_message_updater_fails_when_results_can_not_be_written() {
+    let pool = cleanup_and_prepare().await.unwrap();
+    let repos = Repositories::postgres(pool.clone());
+    let mut db = pool.get().unwrap();
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 1, 1, 0, Some(1))
+        .await
+        .unwrap();
+    let created_message: Message = diesel::insert_into(messages)
+        .values(NewMessage {
+            message_body: "Test Message".to_string(),
+            produced_by: producer.id,
+            message_index: 0,
+        })
+        .get_result(&mut db)
+        .unwrap();
+
+    let (tx, rx) = mpsc::channel(10);
+    let job = Arc::new(SendJob::new(producer.id, 1));
+    let handle = get_message_updater(
+        rx,
+        repos.messages.clone(),
+        job.clone(),
+        UpdaterConfig {
+            batch_size: 1,
+            flush_interval: Duration::from_secs(60),
+        },
+    );
+
+    // The attempt is for a message that does not exist, so it can never be recorded
+    let _ = tx
+        .send(DeliveryUpdate {
+            message: an_updated_message(&created_message, 1, true),
+            attempt: an_attempt(Uuid::new_v4(), None),
+        })
+        .await;
+
+    let err = handle.await.unwrap().unwrap_err();
+    assert!(err.to_string().starts_with("Failed to record 1 attempts"));
+
+    // The senders find the updater gone rather than their results silently dropped
+    assert!(tx
+        .send(DeliveryUpdate {
+            message: an_updated_message(&created_message, 1, true),
+            attempt: an_attempt(created_message.id, None),
+        })
+        .await
+        .is_err());
 }
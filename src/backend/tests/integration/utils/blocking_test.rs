@@ -0,0 +1,48 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use backend::utils::{blocking::run_blocking, error::SMSManagerError};
+use diesel::RunQueryDsl;
+
+use crate::test_utils::cleanup_and_prepare;
+
+#[tokio::test]
+async fn test_run_blocking_keeps_the_runtime_free() {
+    let pool = cleanup_and_prepare().await.unwrap();
+
+    // The test runtime has a single thread, so the ticker only runs if the query is not holding it
+    let ticks = Arc::new(AtomicUsize::new(0));
+    let ticker_ticks = ticks.clone();
+    let ticker = tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            ticker_ticks.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+
+    run_blocking(&pool, |db| {
+        diesel::sql_query("SELECT pg_sleep(0.5)")
+            .execute(db)
+            .map_err(SMSManagerError::DbError)
+    })
+    .await
+    .unwrap();
+    ticker.abort();
+
+    assert!(ticks.load(Ordering::SeqCst) >= 10);
+}
+
+#[tokio::test]
+async fn test_run_blocking_returns_the_work_error() {
+    let pool = cleanup_and_prepare().await.unwrap();
+
+    let result: Result<(), SMSManagerError> =
+        run_blocking(&pool, |_db| Err(SMSManagerError::EmptyResult)).await;
+
+    assert!(matches!(result, Err(SMSManagerError::EmptyResult)));
+}
@@ -0,0 +1 @@
+pub mod blocking_test;
\ No newline at end of file
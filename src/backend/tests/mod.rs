@@ -1,3 +1,4 @@
 pub mod unit;
 pub mod integration;
-pub mod test_utils;
\ No newline at end of file
+pub mod test_utils;
+pub mod checks;
\ No newline at end of file
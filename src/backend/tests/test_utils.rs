@@ -1,7 +1,18 @@
 use backend::{
-    diesel::schema::{messages, producers}, PoolHandle,
+    diesel::{
+        models::{Message, NewMessage, Producer},
+        schema::{messages, producers},
+    },
+    repositories::Repositories,
+    services::producer_services::{create_producer, update_producer_transport},
+    transports::{simulated_transport::SimulatedConfig, TransportConfig},
+    utils::{clock::ClockConfig, producer_status::ProducerStatus},
+    PoolHandle,
+};
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
 };
-use diesel::{prelude::*, r2d2::{ConnectionManager, Pool}};
 use dotenvy::dotenv;
 
 pub async fn cleanup_and_prepare() -> Result<PoolHandle, diesel::result::Error> {
@@ -21,3 +32,112 @@ pub async fn cleanup_and_prepare() -> Result<PoolHandle, diesel::result::Error>
 
     Ok(db.clone())
 }
+
+/// A producer whose simulated sends take no real time
+pub async fn an_instant_producer(repos: &Repositories, number: i32) -> Producer {
+    a_producer_sending_with(
+        repos,
+        number,
+        TransportConfig::Simulated(SimulatedConfig {
+            clock: ClockConfig::Instant,
+            ..Default::default()
+        }),
+    )
+    .await
+}
+
+/// A producer with two senders that delivers its messages through the transport
+pub async fn a_producer_sending_with(
+    repos: &Repositories,
+    number: i32,
+    transport: TransportConfig,
+) -> Producer {
+    let producer = create_producer(repos, "Valid Producer".to_string(), number, 1, 0, Some(2))
+        .await
+        .unwrap();
+    update_producer_transport(repos, producer.id.to_string(), transport)
+        .await
+        .unwrap()
+}
+
+/// A producer with a message in each state, returned in the order of their bodies
+/// Alice and Carol were sent, Bob and Eve failed, the other two are pending
+pub async fn a_browsed_producer(repos: &Repositories) -> (Producer, Vec<Message>) {
+    let bodies = [
+        "Hello Alice",
+        "Hello Bob",
+        "Goodbye Carol",
+        "100% off",
+        "Goodbye Dave",
+        "Hello Eve",
+    ];
+    let producer = create_producer(repos, "Browsed Producer".to_string(), 6, 1, 0, None)
+        .await
+        .unwrap();
+    let chunk: Vec<NewMessage> = bodies
+        .iter()
+        .enumerate()
+        .map(|(index, body)| NewMessage {
+            message_body: body.to_string(),
+            produced_by: producer.id,
+            message_index: index as i32,
+        })
+        .collect();
+    repos
+        .messages
+        .insert_generated(
+            producer.clone(),
+            Box::new(std::iter::once(chunk)),
+            ProducerStatus::Generating,
+            ProducerStatus::Generated,
+        )
+        .await
+        .unwrap();
+
+    let mut messages = repos.messages.list_for_producer(producer.id).await.unwrap();
+    messages.sort_by_key(|message| bodies.iter().position(|body| *body == message.message_body));
+    let outcomes = [
+        (true, false, Some(120), 1),
+        (true, true, Some(300), 3),
+        (true, false, Some(40), 2),
+        (false, false, None, 0),
+        (false, false, None, 0),
+        (true, true, Some(80), 3),
+    ];
+    for (message, (sent, failed, time_took_ms, attempts)) in messages.iter_mut().zip(outcomes) {
+        message.sent = sent;
+        message.failed = failed;
+        message.time_took_ms = time_took_ms;
+        message.attempts = attempts;
+    }
+    repos
+        .messages
+        .update_results(messages.clone())
+        .await
+        .unwrap();
+
+    (producer, messages)
+}
+
+/// A SQLite database file of its own, which is deleted along with its journal files once dropped
+#[cfg(feature = "sqlite")]
+pub struct SqliteDatabase {
+    pub path: std::path::PathBuf,
+}
+
+#[cfg(feature = "sqlite")]
+impl Drop for SqliteDatabase {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.path.display(), suffix));
+        }
+    }
+}
+
+/// Opens a new SQLite database in the temp directory, so every test gets an empty one and they can run in parallel
+#[cfg(feature = "sqlite")]
+pub fn a_sqlite_database() -> (backend::repositories::Repositories, SqliteDatabase) {
+    let path = std::env::temp_dir().join(format!("sms_manager_{}.db", uuid::Uuid::new_v4()));
+    let repos = backend::repositories::Repositories::sqlite(path.to_str().unwrap()).unwrap();
+    (repos, SqliteDatabase { path })
+}
@@ -0,0 +1,20 @@
+use backend::repositories::Repositories;
+
+use crate::checks::message_checks::{
+    check_list_messages, check_progress_stats, check_update_results_releases_finished_messages,
+};
+
+#[tokio::test]
+async fn test_list_messages_in_memory() {
+    check_list_messages(&Repositories::in_memory()).await;
+}
+
+#[tokio::test]
+async fn test_progress_stats_in_memory() {
+    check_progress_stats(&Repositories::in_memory()).await;
+}
+
+#[tokio::test]
+async fn test_update_results_releases_finished_messages_in_memory() {
+    check_update_results_releases_finished_messages(&Repositories::in_memory()).await;
+}
@@ -0,0 +1,3 @@
+pub mod message_services_test;
+pub mod producer_services_test;
+pub mod recovery_services_test;
\ No newline at end of file
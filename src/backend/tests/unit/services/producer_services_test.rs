@@ -0,0 +1,126 @@
+use backend::{
+    repositories::Repositories,
+    services::producer_services::{
+        activate_producer, create_producer, generate_messages, get_producer_by_id, pause_producer,
+        update_producer,
+    },
+    transports::{simulated_transport::SimulatedConfig, TransportConfig},
+    utils::{
+        clock::ClockConfig, error::SMSManagerError, job_registry::JobRegistry,
+        producer_status::ProducerStatus,
+    },
+};
+
+use crate::{
+    checks::producer_checks::{
+        check_activate_producer, check_activate_producer_parallel_requests,
+        check_generate_and_delete_producer, check_list_producers, check_same_seed_sends_alike,
+        check_writes_refused_once_a_send_claims_the_producer,
+    },
+    test_utils::an_instant_producer,
+};
+
+#[tokio::test]
+async fn test_create_and_update_producer_in_memory() {
+    let repos = Repositories::in_memory();
+
+    match create_producer(&repos, "Invalid Producer".to_string(), 10, 1, 101, None).await {
+        Err(SMSManagerError::GeneralException(msg)) => {
+            assert_eq!(msg, "Failure rate must be within 0 - 100");
+        }
+        _ => panic!("Expected GeneralException"),
+    }
+
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 10, 1, 0, None)
+        .await
+        .unwrap();
+    assert_eq!(producer.status, ProducerStatus::Inactive);
+    assert!(matches!(
+        TransportConfig::try_from(&producer.transport).unwrap(),
+        TransportConfig::Simulated(_)
+    ));
+
+    let updated = update_producer(
+        &repos,
+        producer.id.to_string(),
+        "Renamed Producer".to_string(),
+        20,
+        2,
+        5,
+        Some(3),
+    )
+    .await
+    .unwrap();
+    assert_eq!(updated.name, "Renamed Producer");
+    assert_eq!(updated.number_messages, 20);
+    assert_eq!(updated.num_senders, Some(3));
+    assert_eq!(
+        get_producer_by_id(&repos, producer.id.to_string())
+            .await
+            .unwrap()
+            .failure_rate,
+        5
+    );
+}
+
+#[tokio::test]
+async fn test_generate_and_delete_producer_in_memory() {
+    check_generate_and_delete_producer(&Repositories::in_memory()).await;
+}
+
+#[tokio::test]
+async fn test_list_producers_in_memory() {
+    check_list_producers(&Repositories::in_memory()).await;
+}
+
+#[tokio::test]
+async fn test_producer_status_conflicts_in_memory() {
+    let repos = Repositories::in_memory();
+    let jobs = JobRegistry::new();
+    let producer = an_instant_producer(&repos, 5).await;
+
+    match activate_producer(&repos, &jobs, producer.id.to_string(), None).await {
+        Err(SMSManagerError::Conflict(msg)) => {
+            assert_eq!(msg, "Can not move the producer from INACTIVE to SENDING");
+        }
+        _ => panic!("Expected Conflict error"),
+    }
+
+    let _ = generate_messages(&repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+
+    match pause_producer(&repos, &jobs, producer.id.to_string()).await {
+        Err(SMSManagerError::Conflict(msg)) => {
+            assert_eq!(msg, "Can not move the producer from GENERATED to PAUSED");
+        }
+        _ => panic!("Expected Conflict error"),
+    }
+}
+
+#[tokio::test]
+async fn test_activate_producer_in_memory() {
+    check_activate_producer(&Repositories::in_memory()).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_activate_producer_parallel_requests_in_memory() {
+    check_activate_producer_parallel_requests(
+        &Repositories::in_memory(),
+        TransportConfig::Simulated(SimulatedConfig {
+            clock: ClockConfig::Instant,
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_writes_refused_once_a_send_claims_the_producer_in_memory() {
+    check_writes_refused_once_a_send_claims_the_producer(&Repositories::in_memory()).await;
+}
+
+#[tokio::test]
+async fn test_same_seed_sends_alike_in_memory() {
+    check_same_seed_sends_alike(&Repositories::in_memory()).await;
+}
@@ -0,0 +1,83 @@
+use backend::{
+    repositories::Repositories,
+    services::{
+        producer_services::{create_producer, generate_messages, get_producer_by_id},
+        recovery_services::recover_interrupted_sends,
+    },
+    utils::producer_status::{ProducerAction, ProducerStatus},
+};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_recover_interrupted_sends_in_memory() {
+    let repos = Repositories::in_memory();
+    let producer = create_producer(&repos, "Valid Producer".to_string(), 3, 1, 0, Some(1))
+        .await
+        .unwrap();
+    let _ = generate_messages(&repos, producer.id.to_string(), None)
+        .await
+        .unwrap();
+    let between_batches = create_producer(&repos, "Valid Producer".to_string(), 3, 1, 0, Some(1))
+        .await
+        .unwrap();
+    let _ = generate_messages(&repos, between_batches.id.to_string(), None)
+        .await
+        .unwrap();
+    let owner = Uuid::new_v4();
+
+    // This server was sending two of the messages when it stopped
+    repos
+        .producers
+        .claim_status(
+            producer.id,
+            owner,
+            Box::new(|current| current.apply(ProducerAction::Send)),
+        )
+        .await
+        .unwrap();
+    // A live server is sending the other producer, with nothing in flight until it claims its next batch
+    repos
+        .producers
+        .claim_status(
+            between_batches.id,
+            Uuid::new_v4(),
+            Box::new(|current| current.apply(ProducerAction::Send)),
+        )
+        .await
+        .unwrap();
+    let claimed = repos.messages.claim(producer.id, owner, 2).await.unwrap();
+    assert_eq!(claimed.len(), 2);
+    assert_eq!(
+        repos.messages.count_claimable(producer.id).await.unwrap(),
+        1
+    );
+
+    // Another server starting up leaves the live leases alone
+    let report = recover_interrupted_sends(&repos, Uuid::new_v4())
+        .await
+        .unwrap();
+    assert_eq!(report.requeued_messages, 0);
+    assert!(report.interrupted_sends.is_empty());
+
+    let report = recover_interrupted_sends(&repos, owner).await.unwrap();
+    assert_eq!(report.requeued_messages, 2);
+    assert_eq!(report.interrupted_sends, vec![producer.id]);
+    assert_eq!(
+        get_producer_by_id(&repos, between_batches.id.to_string())
+            .await
+            .unwrap()
+            .status,
+        ProducerStatus::Sending
+    );
+    assert_eq!(
+        repos.messages.count_claimable(producer.id).await.unwrap(),
+        3
+    );
+    assert_eq!(
+        get_producer_by_id(&repos, producer.id.to_string())
+            .await
+            .unwrap()
+            .status,
+        ProducerStatus::Interrupted
+    );
+}
@@ -0,0 +1,302 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Form, Json, Router,
+};
+use backend::{
+    diesel::models::Message,
+    transports::{
+        http_transport::{
+            HttpAuth, HttpBody, HttpGatewayConfig, HttpMethod, HttpTransport, ResponseMapping,
+        },
+        DeliveryOutcome, FailureKind, MessageTransport, TransportConfig,
+    },
+};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use uuid::Uuid;
+
+/// A request the mock gateway received
+#[derive(Debug, Clone)]
+struct ReceivedRequest {
+    authorization: Option<String>,
+    api_key: Option<String>,
+    query: HashMap<String, String>,
+    body: Value,
+}
+
+type Received = Arc<Mutex<Vec<ReceivedRequest>>>;
+
+fn record(received: &Received, headers: &HeaderMap, query: HashMap<String, String>, body: Value) {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .map(|value| value.to_str().unwrap().to_string())
+    };
+    received.lock().unwrap().push(ReceivedRequest {
+        authorization: header("authorization"),
+        api_key: header("x-api-key"),
+        query,
+        body,
+    });
+}
+
+/// Starts a mock gateway on a random local port
+/// It accepts json on /json and forms on /form, rejects everything sent to /reject and answers /failed with a 200 whose body says the send failed
+async fn start_gateway() -> (SocketAddr, Received) {
+    let received: Received = Arc::new(Mutex::new(vec![]));
+
+    let app = Router::new()
+        .route(
+            "/json",
+            post(
+                |State(received): State<Received>,
+                 headers: HeaderMap,
+                 Query(query): Query<HashMap<String, String>>,
+                 Json(body): Json<Value>| async move {
+                    record(&received, &headers, query, body);
+                    Json(json!({ "status": "queued", "data": { "id": "gw-1" } }))
+                },
+            ),
+        )
+        .route(
+            "/form",
+            post(
+                |State(received): State<Received>,
+                 headers: HeaderMap,
+                 Form(body): Form<HashMap<String, String>>| async move {
+                    record(&received, &headers, HashMap::new(), json!(body));
+                    Json(json!({ "status": "queued", "data": { "id": 42 } }))
+                },
+            ),
+        )
+        .route(
+            "/reject",
+            post(|| async {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": "Invalid destination" })),
+                )
+            }),
+        )
+        .route(
+            "/failed",
+            post(|| async { Json(json!({ "status": "failed", "error": "No credit" })) }),
+        )
+        .with_state(received.clone());
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    (address, received)
+}
+
+fn gateway_config(address: SocketAddr, path: &str) -> HttpGatewayConfig {
+    HttpGatewayConfig {
+        url: format!("http://{}{}", address, path),
+        method: HttpMethod::Post,
+        headers: BTreeMap::new(),
+        body: None,
+        auth: None,
+        response: ResponseMapping {
+            success_statuses: vec![],
+            success_field: Some("/status".to_string()),
+            success_values: vec![json!("queued")],
+            message_id_field: Some("/data/id".to_string()),
+            error_field: Some("/error".to_string()),
+        },
+        timeout_secs: 5,
+    }
+}
+
+fn a_message() -> Message {
+    Message {
+        id: Uuid::new_v4(),
+        sent: false,
+        time_took: None,
+        failed: false,
+        message_body: String::from("Hello & welcome"),
+        produced_by: Uuid::new_v4(),
+        provider_message_id: None,
+        attempts: 0,
+        started_at: None,
+        finished_at: None,
+        time_took_ms: None,
+        in_flight: false,
+        leased_by: None,
+        lease_expires_at: None,
+        message_index: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_http_transport_sends_json_template() {
+    let (address, received) = start_gateway().await;
+    let message = a_message();
+    let transport = HttpTransport::new(HttpGatewayConfig {
+        url: format!("http://{}/json?ref={{{{message_id}}}}", address),
+        headers: BTreeMap::from([("X-Api-Key".to_string(), "key-{{producer_id}}".to_string())]),
+        body: Some(HttpBody::Json {
+            template: json!({ "to": "5551234", "text": "{{body}}", "tags": ["{{producer_id}}"], "priority": 1 }),
+        }),
+        auth: Some(HttpAuth::Bearer {
+            token: "token".to_string(),
+        }),
+        ..gateway_config(address, "/json")
+    })
+    .unwrap();
+
+    let outcome = transport.send(&message).await;
+
+    assert_eq!(
+        outcome,
+        DeliveryOutcome::Delivered {
+            provider_message_id: Some("gw-1".to_string())
+        }
+    );
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].authorization, Some("Bearer token".to_string()));
+    assert_eq!(
+        received[0].api_key,
+        Some(format!("key-{}", message.produced_by))
+    );
+    assert_eq!(received[0].query.get("ref"), Some(&message.id.to_string()));
+    assert_eq!(
+        received[0].body,
+        json!({ "to": "5551234", "text": "Hello & welcome", "tags": [message.produced_by.to_string()], "priority": 1 })
+    );
+}
+
+#[tokio::test]
+async fn test_http_transport_sends_form_with_basic_auth() {
+    let (address, received) = start_gateway().await;
+    let message = a_message();
+    let transport = HttpTransport::new(HttpGatewayConfig {
+        body: Some(HttpBody::Form {
+            fields: BTreeMap::from([
+                ("To".to_string(), "5551234".to_string()),
+                ("Body".to_string(), "{{body}}".to_string()),
+            ]),
+        }),
+        auth: Some(HttpAuth::Basic {
+            username: "user".to_string(),
+            password: Some("pass".to_string()),
+        }),
+        ..gateway_config(address, "/form")
+    })
+    .unwrap();
+
+    let outcome = transport.send(&message).await;
+
+    assert_eq!(
+        outcome,
+        DeliveryOutcome::Delivered {
+            provider_message_id: Some("42".to_string())
+        }
+    );
+    let received = received.lock().unwrap();
+    assert_eq!(
+        received[0].authorization,
+        Some("Basic dXNlcjpwYXNz".to_string())
+    );
+    assert_eq!(
+        received[0].body,
+        json!({ "To": "5551234", "Body": "Hello & welcome" })
+    );
+}
+
+#[tokio::test]
+async fn test_http_transport_maps_error_status_to_failed() {
+    let (address, _received) = start_gateway().await;
+    let transport = HttpTransport::new(gateway_config(address, "/reject")).unwrap();
+
+    let outcome = transport.send(&a_message()).await;
+
+    assert_eq!(
+        outcome,
+        DeliveryOutcome::Failed {
+            kind: FailureKind::Rejected,
+            reason: "Gateway responded 400: Invalid destination".to_string()
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_http_transport_maps_body_field_to_failed() {
+    let (address, _received) = start_gateway().await;
+    let transport = HttpTransport::new(gateway_config(address, "/failed")).unwrap();
+
+    let outcome = transport.send(&a_message()).await;
+
+    assert_eq!(
+        outcome,
+        DeliveryOutcome::Failed {
+            kind: FailureKind::Rejected,
+            reason: "Gateway responded 200: No credit".to_string()
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_http_transport_fails_when_gateway_unreachable() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+    let transport = HttpTransport::new(gateway_config(address, "/json")).unwrap();
+
+    let outcome = transport.send(&a_message()).await;
+
+    assert!(outcome.is_failure());
+}
+
+#[tokio::test]
+async fn test_http_config_validation() {
+    let address: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+
+    let not_http = TransportConfig::Http(HttpGatewayConfig {
+        url: "ftp://gateway/{{body}}".to_string(),
+        ..gateway_config(address, "/json")
+    });
+    let missing_values = TransportConfig::Http(HttpGatewayConfig {
+        response: ResponseMapping {
+            success_field: Some("/status".to_string()),
+            ..Default::default()
+        },
+        ..gateway_config(address, "/json")
+    });
+
+    assert!(TransportConfig::Http(gateway_config(address, "/json"))
+        .validate()
+        .is_ok());
+    assert!(not_http.validate().is_err());
+    assert!(missing_values.validate().is_err());
+}
+
+#[tokio::test]
+async fn test_http_config_parses_with_defaults() {
+    let config = TransportConfig::try_from(&json!({
+        "kind": "HTTP",
+        "url": "https://gateway.example.com/send",
+        "body": { "format": "JSON", "template": { "text": "{{body}}" } },
+        "auth": { "type": "BEARER", "token": "token" }
+    }))
+    .unwrap();
+
+    let TransportConfig::Http(config) = config else {
+        panic!("Expected an HTTP transport");
+    };
+    assert_eq!(config.method, HttpMethod::Post);
+    assert_eq!(config.timeout_secs, 10);
+    assert_eq!(config.response, ResponseMapping::default());
+}
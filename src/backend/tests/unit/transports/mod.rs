@@ -0,0 +1,4 @@
+pub mod http_transport_test;
+pub mod simulated_transport_test;
+pub mod smpp_test;
+pub mod transport_config_test;
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use backend::{
+    diesel::models::Message,
+    transports::{simulated_transport::SimulatedTransport, DeliveryOutcome, MessageTransport},
+    utils::{
+        clock::{Clock, DiscreteEventClock, SystemClock},
+        failure_model::FailureModel,
+        latency_model::LatencyModel,
+    },
+};
+use uuid::Uuid;
+
+fn a_message() -> Message {
+    Message {
+        id: Uuid::new_v4(),
+        sent: false,
+        time_took: None,
+        failed: false,
+        message_body: String::from("Test Message"),
+        produced_by: Uuid::new_v4(),
+        provider_message_id: None,
+        attempts: 0,
+        started_at: None,
+        finished_at: None,
+        time_took_ms: None,
+        in_flight: false,
+        leased_by: None,
+        lease_expires_at: None,
+        message_index: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_simulated_transport_delivers_without_failure_rate() {
+    let transport = SimulatedTransport::new(0, 0, Arc::new(SystemClock));
+
+    for _ in 0..10 {
+        assert_eq!(
+            transport.send(&a_message()).await,
+            DeliveryOutcome::Delivered {
+                provider_message_id: None
+            }
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_simulated_transport_always_fails_at_full_failure_rate() {
+    let transport = SimulatedTransport::new(0, 100, Arc::new(SystemClock));
+
+    for _ in 0..10 {
+        assert!(transport.send(&a_message()).await.is_failure());
+    }
+}
+
+#[tokio::test]
+async fn test_simulated_transport_waits_latency_model_on_its_clock() {
+    let clock = Arc::new(DiscreteEventClock::new());
+    let transport = SimulatedTransport::new(20, 0, clock.clone())
+        .with_latency(LatencyModel::Fixed { delay_ms: 1500 });
+    let clock_start = clock.now();
+
+    for _ in 0..4 {
+        transport.send(&a_message()).await;
+    }
+
+    assert_eq!(clock.now() - clock_start, chrono::Duration::seconds(6));
+}
+
+#[tokio::test]
+async fn test_simulated_transport_fails_during_outages() {
+    let clock = Arc::new(DiscreteEventClock::new());
+    let transport = SimulatedTransport::new(20, 0, clock.clone())
+        .with_latency(LatencyModel::Fixed { delay_ms: 10_000 })
+        .with_failure_model(FailureModel::Outage {
+            period_secs: 60,
+            outage_secs: 20,
+            offset_secs: 0,
+            failure_rate: 0.0,
+        });
+
+    let mut failures = vec![];
+    for _ in 0..12 {
+        failures.push(transport.send(&a_message()).await.is_failure());
+    }
+
+    // Deliveries finish every 10 seconds and fail while they land in the first 20 seconds of a minute
+    assert_eq!(
+        failures,
+        vec![true, false, false, false, false, true, true, false, false, false, false, true]
+    );
+}
+
+#[tokio::test]
+async fn test_simulated_transport_repeats_outcomes_with_a_seed() {
+    let messages: Vec<Message> = (0..20)
+        .map(|index| Message {
+            message_body: format!("Test Message {}", index),
+            ..a_message()
+        })
+        .collect();
+
+    let run = |seed: i64| {
+        let messages = messages.clone();
+        async move {
+            let clock = Arc::new(DiscreteEventClock::new());
+            let transport = SimulatedTransport::new(20, 50, clock.clone())
+                .with_latency(LatencyModel::Uniform {
+                    min_ms: 0,
+                    max_ms: 10_000,
+                })
+                .with_seed(Some(seed));
+
+            let mut outcomes = vec![];
+            for message in &messages {
+                let started_at = clock.now();
+                let failed = transport.send(message).await.is_failure();
+                outcomes.push((failed, clock.now() - started_at));
+            }
+            outcomes
+        }
+    };
+
+    let first = run(7).await;
+
+    assert_eq!(first, run(7).await);
+    assert_ne!(first, run(8).await);
+}
@@ -0,0 +1,272 @@
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+
+use backend::{
+    diesel::models::{Message, Producer},
+    transports::{
+        smpp::{
+            client::SmppSession,
+            pdu::{command_status, Pdu, PduBody, SubmitSm},
+            simulator::{SmscSimulator, SmscSimulatorConfig},
+            BindMode, SmppConfig, SmppTransport,
+        },
+        DeliveryOutcome, MessageTransport, TransportConfig,
+    },
+    utils::{
+        job_registry::SendJob,
+        producer_status::ProducerStatus,
+        sender::{get_senders, DeliveryPipeline},
+    },
+};
+use chrono::Utc;
+use serde_json::json;
+use tokio::{
+    sync::{mpsc, Mutex},
+    time::sleep,
+};
+use uuid::Uuid;
+
+fn smpp_config(simulator: &SmscSimulator) -> SmppConfig {
+    SmppConfig {
+        host: simulator.address().ip().to_string(),
+        port: simulator.address().port(),
+        system_id: "simulator".to_string(),
+        password: "secret".to_string(),
+        system_type: String::new(),
+        bind_mode: BindMode::Transmitter,
+        source_addr: "12345".to_string(),
+        source_addr_ton: 0,
+        source_addr_npi: 0,
+        destination_addr: "5551234".to_string(),
+        dest_addr_ton: 1,
+        dest_addr_npi: 1,
+        window_size: 10,
+        enquire_link_interval_secs: 30,
+        response_timeout_secs: 5,
+    }
+}
+
+fn message(body: &str) -> Message {
+    Message {
+        id: Uuid::new_v4(),
+        sent: false,
+        time_took: None,
+        failed: false,
+        message_body: body.to_string(),
+        produced_by: Uuid::new_v4(),
+        provider_message_id: None,
+        attempts: 0,
+        started_at: None,
+        finished_at: None,
+        time_took_ms: None,
+        in_flight: false,
+        leased_by: None,
+        lease_expires_at: None,
+        message_index: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_pdu_round_trip() {
+    let pdu = Pdu::new(
+        7,
+        PduBody::SubmitSm(SubmitSm {
+            source_addr: "12345".to_string(),
+            destination_addr: "5551234".to_string(),
+            short_message: b"Hello".to_vec(),
+            ..Default::default()
+        }),
+    );
+
+    let decoded = Pdu::decode(&pdu.encode()).unwrap();
+
+    assert_eq!(decoded, pdu);
+    assert!(!decoded.is_response());
+}
+
+#[tokio::test]
+async fn test_pdu_decode_rejects_bad_length() {
+    let mut bytes = Pdu::new(1, PduBody::EnquireLink).encode();
+    bytes.push(0);
+
+    assert!(Pdu::decode(&bytes).is_err());
+}
+
+#[tokio::test]
+async fn test_smpp_transport_delivers_message() {
+    let simulator = SmscSimulator::start(SmscSimulatorConfig::default())
+        .await
+        .unwrap();
+    let transport = SmppTransport::new(smpp_config(&simulator));
+
+    let outcome = transport.send(&message("Hello")).await;
+    transport.close().await;
+
+    assert_eq!(
+        outcome,
+        DeliveryOutcome::Delivered {
+            provider_message_id: Some("00000001".to_string())
+        }
+    );
+    let submitted = simulator.submitted();
+    assert_eq!(submitted.len(), 1);
+    assert_eq!(submitted[0].short_message, b"Hello".to_vec());
+    assert_eq!(submitted[0].destination_addr, "5551234");
+    assert_eq!(simulator.unbind_count(), 1);
+}
+
+#[tokio::test]
+async fn test_smpp_transport_maps_rejection_to_failed() {
+    let simulator = SmscSimulator::start(SmscSimulatorConfig {
+        responder: Arc::new(|submit: &SubmitSm| {
+            if submit.short_message == b"reject" {
+                command_status::ESME_RSUBMITFAIL
+            } else {
+                command_status::ESME_ROK
+            }
+        }),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let producer = Producer {
+        id: Uuid::new_v4(),
+        name: "aProducer".to_string(),
+        num_senders: None,
+        number_messages: 2,
+        status: ProducerStatus::Generated,
+        average_send_delay: 1,
+        failure_rate: 0,
+        transport: serde_json::to_value(TransportConfig::Smpp(smpp_config(&simulator))).unwrap(),
+        retry_policy: json!({}),
+        rate_limit: None,
+        seed: None,
+        generated_messages: 0,
+        created_at: Utc::now(),
+    };
+
+    let queue = Arc::new(Mutex::new(VecDeque::from(vec![
+        message("deliver"),
+        message("reject"),
+    ])));
+    let (tx, mut rx) = mpsc::channel(10);
+
+    let handles = get_senders(
+        queue,
+        &Arc::new(DeliveryPipeline::for_producer(&producer).unwrap()),
+        &tx,
+        1,
+        &Arc::new(SendJob::new(producer.id, 2)),
+    );
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    let delivered = rx.recv().await.unwrap().message;
+    let rejected = rx.recv().await.unwrap().message;
+
+    assert!(delivered.sent);
+    assert!(!delivered.failed);
+    assert!(rejected.sent);
+    assert!(rejected.failed);
+}
+
+#[tokio::test]
+async fn test_smpp_transport_reports_bind_failure() {
+    let simulator = SmscSimulator::start(SmscSimulatorConfig::default())
+        .await
+        .unwrap();
+    let transport = SmppTransport::new(SmppConfig {
+        password: "wrong".to_string(),
+        ..smpp_config(&simulator)
+    });
+
+    let outcome = transport.send(&message("Hello")).await;
+
+    match outcome {
+        DeliveryOutcome::Failed { reason, .. } => assert!(reason.contains("ESME_RINVPASWD")),
+        DeliveryOutcome::Delivered { .. } => panic!("Bind with the wrong password should fail"),
+    }
+    assert!(simulator.submitted().is_empty());
+}
+
+#[tokio::test]
+async fn test_smpp_transport_rejects_long_message() {
+    let simulator = SmscSimulator::start(SmscSimulatorConfig::default())
+        .await
+        .unwrap();
+    let transport = SmppTransport::new(smpp_config(&simulator));
+
+    let outcome = transport.send(&message(&"a".repeat(255))).await;
+
+    assert!(outcome.is_failure());
+    assert!(simulator.submitted().is_empty());
+}
+
+#[tokio::test]
+async fn test_smpp_session_sends_enquire_link() {
+    let simulator = SmscSimulator::start(SmscSimulatorConfig::default())
+        .await
+        .unwrap();
+    let session = SmppSession::connect(&SmppConfig {
+        enquire_link_interval_secs: 1,
+        ..smpp_config(&simulator)
+    })
+    .await
+    .unwrap();
+
+    sleep(Duration::from_millis(2500)).await;
+
+    assert!(simulator.enquire_link_count() >= 2);
+    assert!(!session.is_closed());
+}
+
+#[tokio::test]
+async fn test_smpp_transport_limits_window() {
+    let simulator = SmscSimulator::start(SmscSimulatorConfig {
+        response_delay: Duration::from_millis(200),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let transport = Arc::new(SmppTransport::new(SmppConfig {
+        window_size: 2,
+        ..smpp_config(&simulator)
+    }));
+
+    let mut handles = vec![];
+    for i in 0..6 {
+        let transport = transport.clone();
+        handles.push(tokio::spawn(async move {
+            transport.send(&message(&format!("Message {}", i))).await
+        }));
+    }
+    for handle in handles {
+        assert!(!handle.await.unwrap().is_failure());
+    }
+
+    assert_eq!(simulator.submitted().len(), 6);
+    assert_eq!(simulator.max_in_flight(), 2);
+}
+
+#[tokio::test]
+async fn test_smpp_config_validation() {
+    let simulator = SmscSimulator::start(SmscSimulatorConfig::default())
+        .await
+        .unwrap();
+
+    let too_long = TransportConfig::Smpp(SmppConfig {
+        password: "much_too_long".to_string(),
+        ..smpp_config(&simulator)
+    });
+    let no_window = TransportConfig::Smpp(SmppConfig {
+        window_size: 0,
+        ..smpp_config(&simulator)
+    });
+
+    assert!(TransportConfig::Smpp(smpp_config(&simulator))
+        .validate()
+        .is_ok());
+    assert!(too_long.validate().is_err());
+    assert!(no_window.validate().is_err());
+}
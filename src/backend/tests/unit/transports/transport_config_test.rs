@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use backend::{
+    diesel::models::Producer,
+    transports::{build_transport, simulated_transport::SimulatedConfig, TransportConfig},
+    utils::{clock::SystemClock, error::SMSManagerError, producer_status::ProducerStatus},
+};
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_transport_config_parses_kind() {
+    let config = TransportConfig::try_from(&json!({ "kind": "SIMULATED" })).unwrap();
+
+    assert_eq!(
+        config,
+        TransportConfig::Simulated(SimulatedConfig::default())
+    );
+    assert_eq!(config.kind(), "SIMULATED");
+}
+
+#[tokio::test]
+async fn test_transport_config_rejects_unknown_kind() {
+    let result = TransportConfig::try_from(&json!({ "kind": "CARRIER_PIGEON" }));
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_build_transport_rejects_invalid_config() {
+    let producer = Producer {
+        id: Uuid::new_v4(),
+        name: "aProducer".to_string(),
+        num_senders: None,
+        number_messages: 1,
+        status: ProducerStatus::Generated,
+        average_send_delay: 1,
+        failure_rate: 0,
+        transport: json!({ "kind": "CARRIER_PIGEON" }),
+        retry_policy: json!({}),
+        rate_limit: None,
+        seed: None,
+        generated_messages: 0,
+        created_at: Utc::now(),
+    };
+
+    assert!(build_transport(&producer, Arc::new(SystemClock)).is_err());
+}
+
+#[tokio::test]
+async fn test_simulated_config_validates_latency_model() {
+    let valid = TransportConfig::try_from(&json!({
+        "kind": "SIMULATED",
+        "latency": { "model": "UNIFORM", "min_ms": 200, "max_ms": 800 }
+    }))
+    .unwrap();
+    let invalid = TransportConfig::try_from(&json!({
+        "kind": "SIMULATED",
+        "latency": { "model": "UNIFORM", "min_ms": 800, "max_ms": 200 }
+    }))
+    .unwrap();
+    let stopped_clock = TransportConfig::try_from(&json!({
+        "kind": "SIMULATED",
+        "clock": { "mode": "ACCELERATED", "speed": 0 }
+    }))
+    .unwrap();
+
+    assert!(valid.validate().is_ok());
+    // Invalid parameters are the callers to fix, so they are rejected as unprocessable rather than as a server error
+    assert!(matches!(
+        invalid.validate(),
+        Err(SMSManagerError::InvalidEncoding(_))
+    ));
+    match stopped_clock.validate() {
+        Err(SMSManagerError::InvalidEncoding(msg)) => {
+            assert_eq!(msg, "Clock speed must be greater than 0")
+        }
+        other => panic!("Expected InvalidEncoding error, got {:?}", other),
+    }
+    assert!(TransportConfig::try_from(&json!({
+        "kind": "SIMULATED",
+        "latency": { "model": "GAMMA", "shape": 2 }
+    }))
+    .is_err());
+}
+
+#[tokio::test]
+async fn test_simulated_config_validates_failure_model() {
+    let valid = TransportConfig::try_from(&json!({
+        "kind": "SIMULATED",
+        "failure": { "model": "GILBERT_ELLIOTT", "enter_burst": 0.01, "exit_burst": 0.2, "good_failure_rate": 0.01, "bad_failure_rate": 0.9 }
+    }))
+    .unwrap();
+    let invalid = TransportConfig::try_from(&json!({
+        "kind": "SIMULATED",
+        "failure": { "model": "BERNOULLI", "failure_rate": 10 }
+    }))
+    .unwrap();
+
+    assert!(valid.validate().is_ok());
+    assert!(matches!(
+        invalid.validate(),
+        Err(SMSManagerError::InvalidEncoding(_))
+    ));
+}
@@ -0,0 +1,136 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use backend::utils::{
+    clock::{
+        AcceleratedClock, Clock, ClockConfig, ClockParticipant, DiscreteEventClock, SystemClock,
+    },
+    rate_limiter::{RateLimit, RatePeriod, TokenBucket},
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_accelerated_clock_runs_faster_than_real_time() {
+    let clock = AcceleratedClock::new(1000.0);
+    let start = Instant::now();
+    let clock_start = clock.now();
+
+    clock.sleep(Duration::from_secs(20)).await;
+
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!((clock.now() - clock_start).num_seconds() >= 20);
+}
+
+#[tokio::test]
+async fn test_discrete_event_clock_skips_waits() {
+    let clock = DiscreteEventClock::new();
+    let start = Instant::now();
+    let clock_start = clock.now();
+
+    clock.sleep(Duration::from_secs(3600)).await;
+
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_eq!(clock.now() - clock_start, chrono::Duration::hours(1));
+}
+
+#[tokio::test]
+async fn test_discrete_event_clock_wakes_participants_in_order() {
+    let clock: Arc<dyn Clock> = Arc::new(DiscreteEventClock::new());
+    let clock_start = clock.now();
+    let woken = Arc::new(Mutex::new(vec![]));
+
+    // Registering every participant before any starts keeps time still until they are all asleep
+    let participants: Vec<ClockParticipant> = (0..3)
+        .map(|_| ClockParticipant::new(clock.clone()))
+        .collect();
+    let handles: Vec<_> = participants
+        .into_iter()
+        .zip([(5, "slow"), (2, "fast"), (3, "medium")])
+        .map(|(participant, (seconds, name))| {
+            let clock = clock.clone();
+            let woken = woken.clone();
+            tokio::spawn(async move {
+                clock.sleep(Duration::from_secs(seconds)).await;
+                woken
+                    .lock()
+                    .unwrap()
+                    .push((name, (clock.now() - clock_start).num_seconds()));
+                drop(participant);
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    assert_eq!(
+        *woken.lock().unwrap(),
+        vec![("fast", 2), ("medium", 3), ("slow", 5)]
+    );
+}
+
+#[tokio::test]
+async fn test_rate_limit_waits_on_its_clock() {
+    let clock: Arc<dyn Clock> = Arc::new(DiscreteEventClock::new());
+    let bucket = TokenBucket::with_clock(
+        RateLimit {
+            rate: 1,
+            per: RatePeriod::Minute,
+            burst: 1,
+        },
+        clock.clone(),
+    );
+    let start = Instant::now();
+    let clock_start = clock.now();
+
+    for _ in 0..5 {
+        bucket.acquire().await;
+    }
+
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_eq!(clock.now() - clock_start, chrono::Duration::minutes(4));
+}
+
+#[tokio::test]
+async fn test_clock_config_parses_and_validates() {
+    let accelerated =
+        serde_json::from_value::<ClockConfig>(json!({ "mode": "ACCELERATED", "speed": 100 }))
+            .unwrap();
+    let instant = serde_json::from_value::<ClockConfig>(json!({ "mode": "INSTANT" })).unwrap();
+
+    assert_eq!(accelerated, ClockConfig::Accelerated { speed: 100.0 });
+    assert_eq!(instant, ClockConfig::Instant);
+    assert_eq!(ClockConfig::default(), ClockConfig::RealTime);
+    assert!(accelerated.validate().is_ok());
+    assert!(ClockConfig::Accelerated { speed: 0.0 }.validate().is_err());
+    assert!(ClockConfig::Accelerated { speed: f64::NAN }
+        .validate()
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_system_clock_follows_real_time() {
+    let clock = SystemClock;
+    let before = chrono::Utc::now();
+
+    clock.sleep(Duration::from_millis(20)).await;
+
+    assert!(clock.now() - before >= chrono::Duration::milliseconds(20));
+}
+
+#[tokio::test]
+async fn test_clocks_stop_at_the_latest_time_instead_of_overflowing() {
+    let accelerated = AcceleratedClock::new(f64::MAX);
+    tokio::time::sleep(Duration::from_millis(1)).await;
+
+    assert_eq!(accelerated.now(), chrono::DateTime::<chrono::Utc>::MAX_UTC);
+
+    let discrete = DiscreteEventClock::new();
+    discrete.sleep(Duration::MAX).await;
+    discrete.sleep(Duration::from_secs(1)).await;
+
+    assert_eq!(discrete.now(), chrono::DateTime::<chrono::Utc>::MAX_UTC);
+}
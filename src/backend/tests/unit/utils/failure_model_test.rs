@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use backend::utils::failure_model::{FailureContext, FailureModel, FailureState};
+use rand::{rngs::StdRng, SeedableRng};
+use serde_json::json;
+
+fn context(elapsed_secs: f64, in_flight: usize) -> FailureContext {
+    FailureContext {
+        elapsed: Duration::from_secs_f64(elapsed_secs),
+        in_flight,
+    }
+}
+
+/// Rolls the model the given number of times with the same context, returning whether each delivery failed
+fn roll_many(model: &FailureModel, rolls: usize, context: FailureContext) -> Vec<bool> {
+    let mut rng = StdRng::seed_from_u64(11);
+    let mut state = FailureState::default();
+    (0..rolls)
+        .map(|_| model.roll(&mut state, &context, &mut rng).is_some())
+        .collect()
+}
+
+fn failure_fraction(failures: &[bool]) -> f64 {
+    failures.iter().filter(|failed| **failed).count() as f64 / failures.len() as f64
+}
+
+#[tokio::test]
+async fn test_bernoulli_failures_match_rate() {
+    let failures = roll_many(
+        &FailureModel::Bernoulli { failure_rate: 0.3 },
+        10_000,
+        context(0.0, 1),
+    );
+
+    assert!((failure_fraction(&failures) - 0.3).abs() < 0.02);
+}
+
+#[tokio::test]
+async fn test_gilbert_elliott_failures_come_in_bursts() {
+    let model = FailureModel::GilbertElliott {
+        enter_burst: 0.05,
+        exit_burst: 0.2,
+        good_failure_rate: 0.0,
+        bad_failure_rate: 1.0,
+    };
+    let failures = roll_many(&model, 20_000, context(0.0, 1));
+
+    let mut bursts = vec![];
+    let mut current = 0;
+    for failed in &failures {
+        if *failed {
+            current += 1;
+        } else if current > 0 {
+            bursts.push(current);
+            current = 0;
+        }
+    }
+    let average_burst = bursts.iter().sum::<usize>() as f64 / bursts.len() as f64;
+
+    // The bad state holds for 1 / exit_burst deliveries and takes up enter / (enter + exit) of the time
+    assert!((average_burst - 5.0).abs() < 0.75);
+    assert!((failure_fraction(&failures) - 0.2).abs() < 0.03);
+}
+
+#[tokio::test]
+async fn test_outage_fails_everything_inside_its_window() {
+    let model = FailureModel::Outage {
+        period_secs: 300,
+        outage_secs: 30,
+        offset_secs: 60,
+        failure_rate: 0.0,
+    };
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut state = FailureState::default();
+    let mut fails_at =
+        |elapsed_secs: f64| model.roll(&mut state, &context(elapsed_secs, 1), &mut rng);
+
+    assert_eq!(fails_at(10.0), None);
+    assert_eq!(fails_at(60.0), Some("Simulated outage"));
+    assert_eq!(fails_at(89.9), Some("Simulated outage"));
+    assert_eq!(fails_at(90.0), None);
+    assert_eq!(fails_at(359.0), None);
+    assert_eq!(fails_at(365.0), Some("Simulated outage"));
+}
+
+#[tokio::test]
+async fn test_load_dependent_failures_rise_with_load() {
+    let model = FailureModel::LoadDependent {
+        base_failure_rate: 0.0,
+        max_failure_rate: 1.0,
+        saturation: 10,
+    };
+
+    assert_eq!(
+        failure_fraction(&roll_many(&model, 1000, context(0.0, 0))),
+        0.0
+    );
+    assert!((failure_fraction(&roll_many(&model, 10_000, context(0.0, 5))) - 0.5).abs() < 0.02);
+    assert_eq!(
+        failure_fraction(&roll_many(&model, 1000, context(0.0, 25))),
+        1.0
+    );
+}
+
+#[tokio::test]
+async fn test_failure_model_parses_and_validates() {
+    let outage: FailureModel = serde_json::from_value(json!({
+        "model": "OUTAGE",
+        "period_secs": 300,
+        "outage_secs": 30
+    }))
+    .unwrap();
+
+    assert_eq!(
+        outage,
+        FailureModel::Outage {
+            period_secs: 300,
+            outage_secs: 30,
+            offset_secs: 0,
+            failure_rate: 0.0
+        }
+    );
+    assert!(outage.validate().is_ok());
+
+    let invalid = [
+        FailureModel::Bernoulli { failure_rate: 1.5 },
+        FailureModel::GilbertElliott {
+            enter_burst: 0.1,
+            exit_burst: -0.1,
+            good_failure_rate: 0.0,
+            bad_failure_rate: 1.0,
+        },
+        FailureModel::Outage {
+            period_secs: 10,
+            outage_secs: 30,
+            offset_secs: 0,
+            failure_rate: 0.0,
+        },
+        FailureModel::LoadDependent {
+            base_failure_rate: 0.1,
+            max_failure_rate: 0.5,
+            saturation: 0,
+        },
+    ];
+    for model in invalid {
+        assert!(model.validate().is_err(), "{:?} should be invalid", model);
+    }
+}
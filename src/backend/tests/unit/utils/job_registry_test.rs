@@ -0,0 +1,85 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use backend::utils::{
+    clock::{Clock, DiscreteEventClock, SystemClock},
+    job_registry::{JobRegistry, SendJob},
+    rate_limiter::{RateLimit, RatePeriod},
+};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_job_registry_finds_the_active_job_of_a_producer() {
+    let jobs = JobRegistry::new();
+    let producer_id = Uuid::new_v4();
+
+    let finished = Arc::new(SendJob::new(producer_id, 10));
+    jobs.insert(finished.clone());
+    finished.complete();
+
+    assert!(jobs.get_active_for_producer(&producer_id).is_none());
+
+    let running = Arc::new(SendJob::new(producer_id, 10));
+    jobs.insert(running.clone());
+    jobs.insert(Arc::new(SendJob::new(Uuid::new_v4(), 10)));
+
+    let active = jobs.get_active_for_producer(&producer_id).unwrap();
+    assert_eq!(active.id, running.id);
+    // The finished job can still be looked up by its id
+    assert!(jobs.get(&finished.id).is_some());
+}
+
+#[tokio::test]
+async fn test_job_registry_drops_finished_jobs_after_retention() {
+    let jobs = JobRegistry::new().with_finished_job_retention(Duration::from_millis(50));
+
+    let finished = Arc::new(SendJob::new(Uuid::new_v4(), 10));
+    jobs.insert(finished.clone());
+    finished.fail("Failed".to_string());
+    let running = Arc::new(SendJob::new(Uuid::new_v4(), 10));
+    jobs.insert(running.clone());
+
+    assert!(jobs.get(&finished.id).is_some());
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert!(jobs.get(&finished.id).is_none());
+
+    // Starting another job drops the expired ones, unfinished jobs are never dropped
+    jobs.insert(Arc::new(SendJob::new(Uuid::new_v4(), 10)));
+    assert_eq!(jobs.len(), 2);
+    assert!(jobs.get(&running.id).is_some());
+}
+
+#[tokio::test]
+async fn test_job_registry_times_the_global_rate_limit_on_the_send_clock() {
+    let jobs = JobRegistry::with_global_rate_limit(RateLimit {
+        rate: 1,
+        per: RatePeriod::Minute,
+        burst: 1,
+    });
+
+    // Sends in real time all take from the same bucket
+    let system_clock: Arc<dyn Clock> = Arc::new(SystemClock);
+    let shared = jobs.global_rate_limiter(&system_clock).unwrap();
+    assert!(Arc::ptr_eq(
+        &shared,
+        &jobs.global_rate_limiter(&system_clock).unwrap()
+    ));
+
+    // A simulated send waits out the limit in its own time rather than real time
+    let clock: Arc<dyn Clock> = Arc::new(DiscreteEventClock::new());
+    let bucket = jobs.global_rate_limiter(&clock).unwrap();
+    let start = Instant::now();
+    let clock_start = clock.now();
+
+    for _ in 0..3 {
+        bucket.acquire().await;
+    }
+
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_eq!(clock.now() - clock_start, chrono::Duration::minutes(2));
+    assert!(JobRegistry::new().global_rate_limiter(&clock).is_none());
+}
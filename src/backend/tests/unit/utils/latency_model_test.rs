@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use backend::utils::latency_model::{LatencyBucket, LatencyModel};
+use rand::{rngs::StdRng, SeedableRng};
+use serde_json::json;
+
+fn samples_ms(model: &LatencyModel, count: usize) -> Vec<f64> {
+    let mut rng = StdRng::seed_from_u64(7);
+    (0..count)
+        .map(|_| model.sample(&mut rng).as_secs_f64() * 1000.0)
+        .collect()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+#[tokio::test]
+async fn test_fixed_latency() {
+    let model = LatencyModel::Fixed { delay_ms: 250 };
+
+    assert!(samples_ms(&model, 100).iter().all(|delay| *delay == 250.0));
+}
+
+#[tokio::test]
+async fn test_uniform_latency_stays_in_range() {
+    let model = LatencyModel::Uniform {
+        min_ms: 100,
+        max_ms: 300,
+    };
+    let delays = samples_ms(&model, 5000);
+
+    assert!(delays.iter().all(|delay| (100.0..=300.0).contains(delay)));
+    assert!((mean(&delays) - 200.0).abs() < 10.0);
+}
+
+#[tokio::test]
+async fn test_normal_latency_centers_on_mean() {
+    let model = LatencyModel::Normal {
+        mean_ms: 1000,
+        stddev_ms: 100,
+    };
+    let delays = samples_ms(&model, 5000);
+
+    assert!((mean(&delays) - 1000.0).abs() < 10.0);
+    assert!(delays.iter().all(|delay| *delay >= 0.0));
+}
+
+#[tokio::test]
+async fn test_log_normal_latency_centers_on_median() {
+    let model = LatencyModel::LogNormal {
+        median_ms: 500,
+        sigma: 0.5,
+    };
+    let mut delays = samples_ms(&model, 5001);
+    delays.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    assert!((delays[2500] - 500.0).abs() < 25.0);
+    // The long tail pulls the mean above the median
+    assert!(mean(&delays) > delays[2500]);
+}
+
+#[tokio::test]
+async fn test_exponential_latency_centers_on_mean() {
+    let model = LatencyModel::Exponential { mean_ms: 200 };
+    let delays = samples_ms(&model, 5000);
+
+    assert!((mean(&delays) - 200.0).abs() < 15.0);
+}
+
+#[tokio::test]
+async fn test_empirical_latency_follows_histogram() {
+    let model = LatencyModel::Empirical {
+        buckets: vec![
+            LatencyBucket {
+                min_ms: 100,
+                max_ms: 200,
+                weight: 3.0,
+            },
+            LatencyBucket {
+                min_ms: 900,
+                max_ms: 1000,
+                weight: 1.0,
+            },
+            LatencyBucket {
+                min_ms: 5000,
+                max_ms: 6000,
+                weight: 0.0,
+            },
+        ],
+    };
+    let delays = samples_ms(&model, 4000);
+    let fast = delays
+        .iter()
+        .filter(|delay| (100.0..=200.0).contains(*delay))
+        .count();
+    let slow = delays
+        .iter()
+        .filter(|delay| (900.0..=1000.0).contains(*delay))
+        .count();
+
+    assert_eq!(fast + slow, 4000);
+    assert!((2850..=3150).contains(&fast));
+}
+
+#[tokio::test]
+async fn test_latency_model_parses() {
+    let model: LatencyModel = serde_json::from_value(json!({
+        "model": "EMPIRICAL",
+        "buckets": [{ "min_ms": 10, "max_ms": 20, "weight": 1 }]
+    }))
+    .unwrap();
+
+    assert_eq!(
+        model,
+        LatencyModel::Empirical {
+            buckets: vec![LatencyBucket {
+                min_ms: 10,
+                max_ms: 20,
+                weight: 1.0
+            }]
+        }
+    );
+    assert!(serde_json::from_value::<LatencyModel>(
+        json!({ "model": "LOG_NORMAL", "median_ms": 5 })
+    )
+    .is_err());
+    assert_eq!(
+        LatencyModel::Fixed { delay_ms: 1500 }.sample(&mut rand::thread_rng()),
+        Duration::from_millis(1500)
+    );
+}
+
+#[tokio::test]
+async fn test_latency_model_validation() {
+    let invalid = [
+        LatencyModel::Uniform {
+            min_ms: 10,
+            max_ms: 5,
+        },
+        LatencyModel::LogNormal {
+            median_ms: 0,
+            sigma: 1.0,
+        },
+        LatencyModel::LogNormal {
+            median_ms: 10,
+            sigma: -1.0,
+        },
+        LatencyModel::Exponential { mean_ms: 0 },
+        LatencyModel::Empirical { buckets: vec![] },
+        LatencyModel::Empirical {
+            buckets: vec![LatencyBucket {
+                min_ms: 10,
+                max_ms: 20,
+                weight: -1.0,
+            }],
+        },
+    ];
+
+    assert!(LatencyModel::Normal {
+        mean_ms: 10,
+        stddev_ms: 0
+    }
+    .validate()
+    .is_ok());
+    for model in invalid {
+        assert!(model.validate().is_err(), "{:?} should be invalid", model);
+    }
+}
@@ -1,11 +1,21 @@
-use backend::utils::message_creator::create_message;
+use backend::{
+    diesel::models::Producer,
+    transformers::producer_transformer::PublicProducer,
+    utils::{
+        message_creator::create_message,
+        message_utils::{generate_fake_messages, FakeMessageChunks},
+        producer_status::ProducerStatus,
+    },
+};
+use chrono::Utc;
+use serde_json::json;
 use uuid::Uuid;
 
 #[tokio::test]
 async fn test_create_message() {
     let producer_id = Uuid::new_v4();
 
-    let result = create_message(producer_id);
+    let result = create_message(producer_id, 7, &mut rand::thread_rng());
 
     assert!(
         !result.message_body.is_empty(),
@@ -23,4 +33,87 @@ async fn test_create_message() {
         result.produced_by, producer_id,
         "The produced_by field should match the producer ID"
     );
+    assert_eq!(result.message_index, 7);
+}
+
+#[tokio::test]
+async fn test_generate_fake_messages_with_seed() {
+    let producer_id = Uuid::new_v4();
+    let bodies = |seed: Option<i64>| {
+        generate_fake_messages(50, producer_id, seed)
+            .unwrap()
+            .into_iter()
+            .map(|message| message.message_body)
+            .collect::<Vec<String>>()
+    };
+
+    assert_eq!(bodies(Some(42)), bodies(Some(42)));
+    assert_ne!(bodies(Some(42)), bodies(Some(43)));
+    assert_ne!(bodies(None), bodies(None));
+}
+
+#[test]
+fn test_fake_message_chunks() {
+    let producer_id = Uuid::new_v4();
+
+    let chunks: Vec<Vec<(i32, String)>> = FakeMessageChunks::new(25, producer_id, Some(42), 10)
+        .unwrap()
+        .map(|chunk| {
+            chunk
+                .into_iter()
+                .map(|message| (message.message_index, message.message_body))
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        chunks.iter().map(Vec::len).collect::<Vec<usize>>(),
+        vec![10, 10, 5]
+    );
+
+    // The chunks of a seed join up into the same messages as generating them all at once, indexed across the chunks
+    let all_at_once: Vec<(i32, String)> = generate_fake_messages(25, producer_id, Some(42))
+        .unwrap()
+        .into_iter()
+        .map(|message| (message.message_index, message.message_body))
+        .collect();
+    assert_eq!(chunks.concat(), all_at_once);
+    assert!(all_at_once
+        .iter()
+        .enumerate()
+        .all(|(position, (message_index, _))| *message_index == position as i32));
+
+    assert!(FakeMessageChunks::new(-1, producer_id, None, 10).is_err());
+    assert!(FakeMessageChunks::new(10, producer_id, None, 0).is_err());
+}
+
+#[test]
+fn test_public_producer_reports_generation_progress() {
+    let producer = Producer {
+        id: Uuid::new_v4(),
+        name: "aProducer".to_string(),
+        num_senders: None,
+        number_messages: 100_000,
+        status: ProducerStatus::Generating,
+        average_send_delay: 1,
+        failure_rate: 0,
+        transport: json!({ "kind": "SIMULATED" }),
+        retry_policy: json!({}),
+        rate_limit: None,
+        seed: None,
+        generated_messages: 20_000,
+        created_at: Utc::now(),
+    };
+
+    assert_eq!(
+        PublicProducer::from(producer.clone()).status,
+        "GENERATING 20000/100000"
+    );
+    assert_eq!(
+        PublicProducer::from(Producer {
+            status: ProducerStatus::Generated,
+            ..producer
+        })
+        .status,
+        "GENERATED"
+    );
 }
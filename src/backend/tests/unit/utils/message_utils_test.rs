@@ -0,0 +1,54 @@
+use backend::{
+    repositories::MessageTimeBucket,
+    utils::message_utils::{
+        message_time_bucket_width, message_time_histogram, MESSAGE_TIME_BUCKETS,
+    },
+};
+
+#[tokio::test]
+async fn test_message_time_bucket_width() {
+    assert_eq!(message_time_bucket_width(100, 100), 1);
+    assert_eq!(
+        message_time_bucket_width(0, MESSAGE_TIME_BUCKETS as i32 - 1),
+        1
+    );
+    assert_eq!(message_time_bucket_width(0, MESSAGE_TIME_BUCKETS as i32), 2);
+    assert_eq!(message_time_bucket_width(0, 9_999), 200);
+}
+
+#[tokio::test]
+async fn test_message_time_histogram_fills_empty_buckets() {
+    // 150 milliseconds spread over 50 buckets 3 wide
+    let histogram = message_time_histogram(0, 149, vec![(50, 1), (1, 2)]);
+
+    assert_eq!(histogram.len(), 50);
+    assert_eq!(
+        histogram[0],
+        MessageTimeBucket {
+            from_ms: 0,
+            to_ms: 3,
+            count: 2
+        }
+    );
+    assert_eq!(histogram[1].count, 0);
+    assert_eq!(
+        histogram[49],
+        MessageTimeBucket {
+            from_ms: 147,
+            to_ms: 150,
+            count: 1
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_message_time_histogram_single_time() {
+    assert_eq!(
+        message_time_histogram(250, 250, vec![(1, 3)]),
+        vec![MessageTimeBucket {
+            from_ms: 250,
+            to_ms: 251,
+            count: 3
+        }]
+    );
+}
@@ -1,4 +1,13 @@
 pub mod message_creator_test;
 pub mod random_utils_test;
 pub mod uuid_test;
-pub mod sender_test;
\ No newline at end of file
+pub mod sender_test;
+pub mod retry_policy_test;
+pub mod rate_limiter_test;
+pub mod clock_test;
+pub mod latency_model_test;
+pub mod failure_model_test;
+pub mod producer_status_test;
+pub mod pagination_test;
+pub mod message_utils_test;
+pub mod job_registry_test;
\ No newline at end of file
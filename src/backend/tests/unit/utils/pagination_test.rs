@@ -0,0 +1,119 @@
+use backend::{
+    repositories::{MessageCursor, ProducerCursor, ProducerSort},
+    utils::{
+        error::SMSManagerError,
+        pagination::{
+            contains_pattern, decode_cursor, encode_cursor, page_size, DEFAULT_PAGE_SIZE,
+            MAX_PAGE_SIZE,
+        },
+    },
+};
+use chrono::{TimeZone, Utc};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_page_size_defaults_and_bounds() {
+    assert_eq!(page_size(None).unwrap(), DEFAULT_PAGE_SIZE);
+    assert_eq!(page_size(Some(1)).unwrap(), 1);
+    assert_eq!(page_size(Some(MAX_PAGE_SIZE)).unwrap(), MAX_PAGE_SIZE);
+
+    for limit in [0, -1, MAX_PAGE_SIZE + 1] {
+        match page_size(Some(limit)) {
+            Err(SMSManagerError::InvalidEncoding(msg)) => {
+                assert_eq!(msg, format!("Limit must be within 1 - {}", MAX_PAGE_SIZE));
+            }
+            _ => panic!("Expected InvalidEncoding for limit {}", limit),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_cursor_round_trips() {
+    let cursor = encode_cursor("name:some id:a name with spaces & symbols");
+
+    assert!(cursor.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(
+        decode_cursor(&cursor).unwrap(),
+        "name:some id:a name with spaces & symbols"
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_cursor() {
+    for cursor in ["abc", "zz", "ff"] {
+        assert!(matches!(
+            decode_cursor(cursor),
+            Err(SMSManagerError::InvalidEncoding(_))
+        ));
+    }
+    assert!(matches!(
+        encode_cursor("unknown:key").parse::<ProducerCursor>(),
+        Err(SMSManagerError::InvalidEncoding(_))
+    ));
+    assert!(matches!(
+        encode_cursor("name:not-a-uuid:Producer").parse::<ProducerCursor>(),
+        Err(SMSManagerError::InvalidEncoding(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_producer_cursor_round_trips() {
+    let id = Uuid::new_v4();
+    let cursors = [
+        ProducerCursor::Name {
+            name: "Producer: with a colon".to_string(),
+            id,
+        },
+        ProducerCursor::CreatedAt {
+            created_at: Utc.timestamp_opt(1_800_000_000, 123_456_000).unwrap(),
+            id,
+        },
+    ];
+
+    for cursor in cursors {
+        let parsed: ProducerCursor = cursor.to_string().parse().unwrap();
+        assert_eq!(parsed, cursor);
+    }
+    assert_eq!(
+        ProducerCursor::Name {
+            name: String::new(),
+            id
+        }
+        .sort(),
+        ProducerSort::Name
+    );
+}
+
+#[tokio::test]
+async fn test_message_cursor_round_trips() {
+    let id = Uuid::new_v4();
+    let cursors = [
+        MessageCursor::Id { id },
+        MessageCursor::TimeTook {
+            time_took_ms: -1,
+            id,
+        },
+        MessageCursor::Attempts { attempts: 3, id },
+    ];
+
+    for cursor in cursors {
+        let parsed: MessageCursor = cursor.to_string().parse().unwrap();
+        assert_eq!(parsed, cursor);
+    }
+    for position in [
+        format!("id:{}:1", id),
+        format!("time_took:{}", id),
+        format!("attempts:{}:many", id),
+    ] {
+        assert!(matches!(
+            encode_cursor(&position).parse::<MessageCursor>(),
+            Err(SMSManagerError::InvalidEncoding(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_contains_pattern_escapes_wildcards() {
+    assert_eq!(contains_pattern("Producer"), "%Producer%");
+    assert_eq!(contains_pattern("100%_off\\"), "%100\\%\\_off\\\\%");
+}
@@ -0,0 +1,106 @@
+use backend::utils::{
+    error::SMSManagerError,
+    producer_status::{ProducerAction, ProducerStatus},
+};
+
+const ALL_STATUSES: [ProducerStatus; 8] = [
+    ProducerStatus::Inactive,
+    ProducerStatus::Generating,
+    ProducerStatus::Generated,
+    ProducerStatus::Sending,
+    ProducerStatus::Paused,
+    ProducerStatus::Cancelled,
+    ProducerStatus::Empty,
+    ProducerStatus::Interrupted,
+];
+
+#[tokio::test]
+async fn test_producer_status_round_trips_through_strings() {
+    for producer_status in ALL_STATUSES {
+        assert_eq!(
+            producer_status
+                .to_string()
+                .parse::<ProducerStatus>()
+                .unwrap(),
+            producer_status
+        );
+        assert_eq!(
+            serde_json::to_value(producer_status).unwrap(),
+            serde_json::json!(producer_status.as_str())
+        );
+    }
+
+    assert!(matches!(
+        "SENT".parse::<ProducerStatus>(),
+        Err(SMSManagerError::InvalidEncoding(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_producer_lifecycle_follows_transition_table() {
+    let mut producer_status = ProducerStatus::Inactive;
+    for (action, expected) in [
+        (ProducerAction::Generate, ProducerStatus::Generating),
+        (ProducerAction::FinishGenerating, ProducerStatus::Generated),
+        (ProducerAction::Send, ProducerStatus::Sending),
+        (ProducerAction::Pause, ProducerStatus::Paused),
+        (ProducerAction::Resume, ProducerStatus::Sending),
+        (ProducerAction::Interrupt, ProducerStatus::Interrupted),
+        (ProducerAction::Send, ProducerStatus::Sending),
+        (ProducerAction::Cancel, ProducerStatus::Cancelled),
+        (ProducerAction::Send, ProducerStatus::Sending),
+        (ProducerAction::FinishSending, ProducerStatus::Empty),
+        (ProducerAction::Generate, ProducerStatus::Generating),
+    ] {
+        producer_status = producer_status.apply(action).unwrap();
+        assert_eq!(producer_status, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_producer_status_rejects_illegal_transitions() {
+    match ProducerStatus::Inactive.apply(ProducerAction::Send) {
+        Err(SMSManagerError::Conflict(msg)) => {
+            assert_eq!(msg, "Can not move the producer from INACTIVE to SENDING");
+        }
+        _ => panic!("Expected Conflict error"),
+    }
+
+    match ProducerStatus::Sending.apply(ProducerAction::Delete) {
+        Err(SMSManagerError::Conflict(msg)) => {
+            assert_eq!(msg, "Can not delete the producer while it is SENDING");
+        }
+        _ => panic!("Expected Conflict error"),
+    }
+
+    // Nothing can be changed while messages are being written or sent
+    for producer_status in [
+        ProducerStatus::Generating,
+        ProducerStatus::Sending,
+        ProducerStatus::Paused,
+    ] {
+        for action in [
+            ProducerAction::Update,
+            ProducerAction::Generate,
+            ProducerAction::Delete,
+        ] {
+            assert!(producer_status.apply(action).is_err());
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_settled_producer_keeps_status_on_update() {
+    for producer_status in ALL_STATUSES {
+        let allowed = ProducerAction::Update
+            .allowed_from()
+            .contains(&producer_status);
+        match producer_status.apply(ProducerAction::Update) {
+            Ok(next) => {
+                assert!(allowed);
+                assert_eq!(next, producer_status);
+            }
+            Err(_) => assert!(!allowed),
+        }
+    }
+}
@@ -1,4 +1,11 @@
-use backend::utils::random_utils::{generate_random_string, get_random_wait_time, random_chance};
+use backend::{
+    diesel::models::Message,
+    utils::random_utils::{
+        generate_random_string, get_random_wait_time, message_rng, random_chance, seeded_rng,
+    },
+};
+use rand::Rng;
+use uuid::Uuid;
 
 /// Test for `random_chance`
 #[tokio::test]
@@ -49,7 +56,7 @@ async fn test_random_chance() {
 #[tokio::test]
 async fn test_generate_random_string() {
     for _ in 0..100 {
-        let generated = generate_random_string();
+        let generated = generate_random_string(&mut rand::thread_rng());
         assert!(
             generated.len() <= 100,
             "Generated string length should not exceed 100"
@@ -67,10 +74,96 @@ async fn test_get_random_wait_time() {
     let average = 10;
     let trials = 1000;
     for _ in 0..trials {
-        let wait_time = get_random_wait_time(&average);
+        let wait_time = get_random_wait_time(&average, &mut rand::thread_rng());
         assert!(
             (5..=15).contains(&wait_time),
             "Wait time should be within 5 of the average"
         );
     }
 }
+
+/// Test for `seeded_rng`
+#[tokio::test]
+async fn test_seeded_rng() {
+    let draw = |seed: i64, key: &str| {
+        let mut rng = seeded_rng(seed, key);
+        (0..10).map(|_| rng.gen()).collect::<Vec<u32>>()
+    };
+
+    let first = draw(42, "messages");
+
+    assert_eq!(
+        first,
+        draw(42, "messages"),
+        "The same seed and key should give the same numbers"
+    );
+    assert_ne!(
+        first,
+        draw(42, "latency"),
+        "A different key should give different numbers"
+    );
+    assert_ne!(
+        first,
+        draw(43, "messages"),
+        "A different seed should give different numbers"
+    );
+}
+
+/// Test for `message_rng`
+#[tokio::test]
+async fn test_message_rng() {
+    let message = Message {
+        id: Uuid::new_v4(),
+        message_body: String::from("Test Message"),
+        sent: false,
+        failed: false,
+        time_took: None,
+        produced_by: Uuid::new_v4(),
+        provider_message_id: None,
+        attempts: 0,
+        started_at: None,
+        finished_at: None,
+        time_took_ms: None,
+        in_flight: false,
+        leased_by: None,
+        lease_expires_at: None,
+        message_index: 0,
+    };
+    // The same message read back again, such as when it is claimed by another send
+    let same_message = message.clone();
+    // The same message generated again under a new id, which draws the same values
+    let regenerated = Message {
+        id: Uuid::new_v4(),
+        ..message.clone()
+    };
+    // Another message with the same body, which still draws its own values
+    let same_body = Message {
+        id: Uuid::new_v4(),
+        message_index: 1,
+        ..message.clone()
+    };
+    let next_attempt = Message {
+        attempts: 1,
+        ..message.clone()
+    };
+    let empty_bodies: Vec<Message> = (0..2)
+        .map(|message_index| Message {
+            id: Uuid::new_v4(),
+            message_body: String::new(),
+            message_index,
+            ..message.clone()
+        })
+        .collect();
+
+    let draw = |message: &Message| message_rng(Some(7), message, "latency").gen::<u64>();
+
+    assert_eq!(draw(&message), draw(&same_message));
+    assert_eq!(draw(&message), draw(&regenerated));
+    assert_ne!(draw(&message), draw(&same_body));
+    assert_ne!(draw(&empty_bodies[0]), draw(&empty_bodies[1]));
+    assert_ne!(draw(&message), draw(&next_attempt));
+    assert_ne!(
+        message_rng(Some(7), &message, "latency").gen::<u64>(),
+        message_rng(Some(7), &message, "failure").gen::<u64>()
+    );
+}
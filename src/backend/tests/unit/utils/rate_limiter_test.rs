@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use backend::utils::rate_limiter::{RateLimit, RatePeriod, TokenBucket};
+use serde_json::json;
+use tokio::time::Instant;
+
+#[tokio::test]
+async fn test_rate_limit_parses_with_defaults() {
+    let limit = RateLimit::try_from(&json!({ "rate": 30 })).unwrap();
+
+    assert_eq!(
+        limit,
+        RateLimit {
+            rate: 30,
+            per: RatePeriod::Second,
+            burst: 1,
+        }
+    );
+    assert!(RateLimit::try_from(&json!({ "per": "MINUTE" })).is_err());
+}
+
+#[tokio::test]
+async fn test_rate_limit_per_second() {
+    let per_minute = RateLimit {
+        rate: 120,
+        per: RatePeriod::Minute,
+        burst: 1,
+    };
+    let per_second = RateLimit {
+        rate: 5,
+        per: RatePeriod::Second,
+        burst: 1,
+    };
+
+    assert_eq!(per_minute.per_second(), 2.0);
+    assert_eq!(per_second.per_second(), 5.0);
+}
+
+#[tokio::test]
+async fn test_rate_limit_validation() {
+    let no_rate = RateLimit {
+        rate: 0,
+        per: RatePeriod::Second,
+        burst: 1,
+    };
+    let no_burst = RateLimit {
+        rate: 10,
+        per: RatePeriod::Second,
+        burst: 0,
+    };
+
+    assert!(
+        RateLimit::try_from(&json!({ "rate": 10, "per": "MINUTE", "burst": 5 }))
+            .unwrap()
+            .validate()
+            .is_ok()
+    );
+    assert!(no_rate.validate().is_err());
+    assert!(no_burst.validate().is_err());
+}
+
+#[tokio::test]
+async fn test_token_bucket_allows_burst_then_sustained_rate() {
+    let bucket = TokenBucket::new(RateLimit {
+        rate: 20,
+        per: RatePeriod::Second,
+        burst: 3,
+    });
+
+    let start = Instant::now();
+    for _ in 0..3 {
+        bucket.acquire().await;
+    }
+    assert!(start.elapsed() < Duration::from_millis(40));
+
+    for _ in 0..3 {
+        bucket.acquire().await;
+    }
+    // Three more tokens take 50ms each to refill
+    assert!(start.elapsed() >= Duration::from_millis(140));
+}
+
+#[tokio::test]
+async fn test_token_bucket_refills_while_idle() {
+    let bucket = TokenBucket::new(RateLimit {
+        rate: 100,
+        per: RatePeriod::Second,
+        burst: 2,
+    });
+
+    bucket.acquire().await;
+    bucket.acquire().await;
+    tokio::time::sleep(Duration::from_millis(30)).await;
+
+    let start = Instant::now();
+    bucket.acquire().await;
+    bucket.acquire().await;
+    assert!(start.elapsed() < Duration::from_millis(10));
+}
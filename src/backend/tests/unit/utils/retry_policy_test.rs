@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use backend::{transports::FailureKind, utils::retry_policy::RetryPolicy};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_retry_policy_defaults_to_single_attempt() {
+    let policy = RetryPolicy::try_from(&json!({})).unwrap();
+
+    assert_eq!(policy, RetryPolicy::default());
+    assert_eq!(policy.retry_delay(FailureKind::Timeout, 1), None);
+}
+
+#[tokio::test]
+async fn test_retry_policy_backs_off_exponentially() {
+    let policy = RetryPolicy {
+        max_attempts: 10,
+        base_delay_ms: 100,
+        multiplier: 2.0,
+        max_delay_ms: 500,
+        ..Default::default()
+    };
+
+    assert_eq!(policy.backoff(1, 0.0), Duration::from_millis(100));
+    assert_eq!(policy.backoff(2, 0.0), Duration::from_millis(200));
+    assert_eq!(policy.backoff(3, 0.0), Duration::from_millis(400));
+    assert_eq!(policy.backoff(4, 0.0), Duration::from_millis(500)); // Capped at the max delay
+}
+
+#[tokio::test]
+async fn test_retry_policy_jitter_stays_in_range() {
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        base_delay_ms: 1000,
+        jitter: 0.5,
+        ..Default::default()
+    };
+
+    assert_eq!(policy.backoff(1, -1.0), Duration::from_millis(500));
+    assert_eq!(policy.backoff(1, 1.0), Duration::from_millis(1500));
+
+    for _ in 0..100 {
+        let delay = policy.retry_delay(FailureKind::Timeout, 1).unwrap();
+        assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_millis(1500));
+    }
+}
+
+#[tokio::test]
+async fn test_retry_policy_stops_retrying() {
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        retryable: vec![FailureKind::Timeout],
+        ..Default::default()
+    };
+
+    assert!(policy.retry_delay(FailureKind::Timeout, 2).is_some());
+    assert!(policy.retry_delay(FailureKind::Timeout, 3).is_none());
+    assert!(policy.retry_delay(FailureKind::Connection, 1).is_none());
+}
+
+#[tokio::test]
+async fn test_retry_policy_validation() {
+    let no_attempts = RetryPolicy {
+        max_attempts: 0,
+        ..Default::default()
+    };
+    let shrinking = RetryPolicy {
+        multiplier: 0.5,
+        ..Default::default()
+    };
+    let too_much_jitter = RetryPolicy {
+        jitter: 1.5,
+        ..Default::default()
+    };
+
+    assert!(RetryPolicy::default().validate().is_ok());
+    assert!(no_attempts.validate().is_err());
+    assert!(shrinking.validate().is_err());
+    assert!(too_much_jitter.validate().is_err());
+}
@@ -1,16 +1,33 @@
 use std::{
-    collections::VecDeque,
-    sync::{atomic::AtomicUsize, Arc},
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Arc,
+    },
     time::Duration,
 };
 
+use async_trait::async_trait;
 use backend::{
     diesel::models::{Message, Producer},
-    utils::sender::get_senders,
+    repositories::Repositories,
+    transports::{DeliveryOutcome, FailureKind, MessageTransport},
+    utils::{
+        clock::SystemClock,
+        error::SMSManagerError,
+        job_registry::SendJob,
+        producer_status::ProducerStatus,
+        rate_limiter::{RateLimit, RatePeriod, TokenBucket},
+        retry_policy::RetryPolicy,
+        sender::{get_senders, send_messages, DeliveryPipeline, DeliveryUpdate, UpdaterConfig},
+        work_queue::WorkQueue,
+    },
 };
+use chrono::Utc;
+use serde_json::json;
 use tokio::{
-    sync::{mpsc, Mutex, Notify},
-    time::timeout,
+    sync::{mpsc, Mutex},
+    time::{timeout, Instant},
 };
 use uuid::Uuid;
 
@@ -22,9 +39,15 @@ async fn test_get_senders_processes_messages() {
         name: "aProducer".to_string(),
         num_senders: None,
         number_messages: 2,
-        status: "GENERATED".to_string(),
+        status: ProducerStatus::Generated,
         average_send_delay: 1, // Simulated 1-second delay
         failure_rate: 0,       // No failure rate for deterministic testing
+        transport: json!({ "kind": "SIMULATED" }),
+        retry_policy: json!({}),
+        rate_limit: None,
+        seed: None,
+        generated_messages: 0,
+        created_at: Utc::now(),
     };
 
     let message1 = Message {
@@ -34,6 +57,15 @@ async fn test_get_senders_processes_messages() {
         failed: false,
         message_body: String::from("Test Message 1"),
         produced_by: producer.id,
+        provider_message_id: None,
+        attempts: 0,
+        started_at: None,
+        finished_at: None,
+        time_took_ms: None,
+        in_flight: false,
+        leased_by: None,
+        lease_expires_at: None,
+        message_index: 0,
     };
     let message2 = Message {
         id: Uuid::new_v4(),
@@ -42,35 +74,43 @@ async fn test_get_senders_processes_messages() {
         failed: false,
         message_body: String::from("Test Message 2"),
         produced_by: producer.id,
+        provider_message_id: None,
+        attempts: 0,
+        started_at: None,
+        finished_at: None,
+        time_took_ms: None,
+        in_flight: false,
+        leased_by: None,
+        lease_expires_at: None,
+        message_index: 0,
     };
 
     let queue = Arc::new(Mutex::new(VecDeque::from(vec![message1, message2])));
 
     let (tx, mut rx) = mpsc::channel(10);
-    let active_threads = Arc::new(AtomicUsize::new(2)); // Two threads
-    let notify = Arc::new(Notify::new());
 
     let handles = get_senders(
         queue.clone(),
-        &producer,
+        &Arc::new(DeliveryPipeline::for_producer(&producer).unwrap()),
         &tx,
-        active_threads.clone(),
-        &notify,
         2,
+        &Arc::new(SendJob::new(producer.id, 2)),
     );
 
     for handle in handles {
-        handle.await.unwrap();
+        handle.await.unwrap().unwrap();
     }
 
     let processed_message1 = timeout(Duration::from_secs(3), rx.recv())
         .await
         .unwrap()
-        .unwrap();
+        .unwrap()
+        .message;
     let processed_message2 = timeout(Duration::from_secs(3), rx.recv())
         .await
         .unwrap()
-        .unwrap();
+        .unwrap()
+        .message;
 
     assert!(!processed_message1.failed);
     assert!(processed_message1.sent);
@@ -88,27 +128,748 @@ async fn test_get_senders_handles_empty_queue() {
         name: "aProducer".to_string(),
         num_senders: None,
         number_messages: 2,
-        status: "GENERATED".to_string(),
+        status: ProducerStatus::Generated,
         average_send_delay: 1,
         failure_rate: 0,
+        transport: json!({ "kind": "SIMULATED" }),
+        retry_policy: json!({}),
+        rate_limit: None,
+        seed: None,
+        generated_messages: 0,
+        created_at: Utc::now(),
     };
     let (tx, mut rx) = mpsc::channel(10);
-    let active_threads = Arc::new(AtomicUsize::new(1));
-    let notify = Arc::new(Notify::new());
 
     let handles = get_senders(
         queue.clone(),
-        &producer,
+        &Arc::new(DeliveryPipeline::for_producer(&producer).unwrap()),
         &tx,
-        active_threads.clone(),
-        &notify,
         1,
+        &Arc::new(SendJob::new(producer.id, 0)),
     );
 
     for handle in handles {
-        handle.await.unwrap();
+        handle.await.unwrap().unwrap();
     }
 
     assert!(rx.try_recv().is_err());
     assert!(queue.lock().await.is_empty());
 }
+
+/// A queue whose messages can never be taken, like a repository that can not be reached
+struct UnreachableQueue;
+
+#[async_trait]
+impl WorkQueue for UnreachableQueue {
+    async fn next(&self) -> Result<Option<Message>, SMSManagerError> {
+        Err(SMSManagerError::GeneralException(
+            "Database unreachable".to_string(),
+        ))
+    }
+}
+
+#[tokio::test]
+async fn test_get_senders_stops_with_queue_error() {
+    let producer = Producer {
+        id: Uuid::new_v4(),
+        name: "aProducer".to_string(),
+        num_senders: None,
+        number_messages: 2,
+        status: ProducerStatus::Generated,
+        average_send_delay: 1,
+        failure_rate: 0,
+        transport: json!({ "kind": "SIMULATED" }),
+        retry_policy: json!({}),
+        rate_limit: None,
+        seed: None,
+        generated_messages: 0,
+        created_at: Utc::now(),
+    };
+    let (tx, mut rx) = mpsc::channel(10);
+
+    let handles = get_senders(
+        Arc::new(UnreachableQueue),
+        &Arc::new(DeliveryPipeline::for_producer(&producer).unwrap()),
+        &tx,
+        2,
+        &Arc::new(SendJob::new(producer.id, 2)),
+    );
+
+    // A queue that can not be read is not mistaken for one that has run out
+    for handle in handles {
+        let err = handle.await.unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "Database unreachable");
+    }
+
+    assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_get_senders_stops_when_cancelled() {
+    let producer = Producer {
+        id: Uuid::new_v4(),
+        name: "aProducer".to_string(),
+        num_senders: None,
+        number_messages: 1,
+        status: ProducerStatus::Generated,
+        average_send_delay: 1,
+        failure_rate: 0,
+        transport: json!({ "kind": "SIMULATED" }),
+        retry_policy: json!({}),
+        rate_limit: None,
+        seed: None,
+        generated_messages: 0,
+        created_at: Utc::now(),
+    };
+    let message = Message {
+        id: Uuid::new_v4(),
+        sent: false,
+        time_took: None,
+        failed: false,
+        message_body: String::from("Test Message"),
+        produced_by: producer.id,
+        provider_message_id: None,
+        attempts: 0,
+        started_at: None,
+        finished_at: None,
+        time_took_ms: None,
+        in_flight: false,
+        leased_by: None,
+        lease_expires_at: None,
+        message_index: 0,
+    };
+    let queue = Arc::new(Mutex::new(VecDeque::from(vec![message])));
+    let (tx, mut rx) = mpsc::channel(10);
+    let job = Arc::new(SendJob::new(producer.id, 1));
+
+    assert!(job.cancel());
+
+    let handles = get_senders(
+        queue.clone(),
+        &Arc::new(DeliveryPipeline::for_producer(&producer).unwrap()),
+        &tx,
+        1,
+        &job,
+    );
+
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    assert!(rx.try_recv().is_err());
+    assert_eq!(queue.lock().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_get_senders_waits_while_paused() {
+    let producer = Producer {
+        id: Uuid::new_v4(),
+        name: "aProducer".to_string(),
+        num_senders: None,
+        number_messages: 1,
+        status: ProducerStatus::Generated,
+        average_send_delay: 1,
+        failure_rate: 0,
+        transport: json!({ "kind": "SIMULATED" }),
+        retry_policy: json!({}),
+        rate_limit: None,
+        seed: None,
+        generated_messages: 0,
+        created_at: Utc::now(),
+    };
+    let message = Message {
+        id: Uuid::new_v4(),
+        sent: false,
+        time_took: None,
+        failed: false,
+        message_body: String::from("Test Message"),
+        produced_by: producer.id,
+        provider_message_id: None,
+        attempts: 0,
+        started_at: None,
+        finished_at: None,
+        time_took_ms: None,
+        in_flight: false,
+        leased_by: None,
+        lease_expires_at: None,
+        message_index: 0,
+    };
+    let queue = Arc::new(Mutex::new(VecDeque::from(vec![message])));
+    let (tx, mut rx) = mpsc::channel(10);
+    let job = Arc::new(SendJob::new(producer.id, 1));
+
+    assert!(job.pause());
+
+    let handles = get_senders(
+        queue.clone(),
+        &Arc::new(DeliveryPipeline::for_producer(&producer).unwrap()),
+        &tx,
+        1,
+        &job,
+    );
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(queue.lock().await.len(), 1);
+
+    assert!(job.resume());
+
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    let processed_message = timeout(Duration::from_secs(3), rx.recv())
+        .await
+        .unwrap()
+        .unwrap()
+        .message;
+    assert!(processed_message.sent);
+    assert!(queue.lock().await.is_empty());
+}
+
+/// Test double that fails every delivery straight away
+struct FailingTransport;
+
+#[async_trait]
+impl MessageTransport for FailingTransport {
+    async fn send(&self, _message: &Message) -> DeliveryOutcome {
+        DeliveryOutcome::Failed {
+            kind: FailureKind::Simulated,
+            reason: "Always fails".to_string(),
+        }
+    }
+}
+
+/// Test double whose deliveries panic, like a transport with a bug in it
+struct PanickingTransport;
+
+#[async_trait]
+impl MessageTransport for PanickingTransport {
+    async fn send(&self, _message: &Message) -> DeliveryOutcome {
+        panic!("The transport broke");
+    }
+}
+
+#[tokio::test]
+async fn test_send_messages_fails_when_a_sender_panics() {
+    let producer_id = Uuid::new_v4();
+    let message = Message {
+        id: Uuid::new_v4(),
+        sent: false,
+        time_took: None,
+        failed: false,
+        message_body: String::from("Test Message"),
+        produced_by: producer_id,
+        provider_message_id: None,
+        attempts: 0,
+        started_at: None,
+        finished_at: None,
+        time_took_ms: None,
+        in_flight: false,
+        leased_by: None,
+        lease_expires_at: None,
+        message_index: 0,
+    };
+    let queue = Arc::new(Mutex::new(VecDeque::from(vec![message])));
+    let pipeline = Arc::new(DeliveryPipeline {
+        transport: Arc::new(PanickingTransport),
+        retry_policy: RetryPolicy::default(),
+        rate_limiters: vec![],
+        clock: Arc::new(SystemClock),
+        seed: None,
+        updater: UpdaterConfig::default(),
+    });
+
+    // The panic fails the send rather than leaving it waiting on the sender forever
+    let err = timeout(
+        Duration::from_secs(5),
+        send_messages(
+            queue,
+            Repositories::in_memory().messages,
+            pipeline,
+            1,
+            Arc::new(SendJob::new(producer_id, 1)),
+        ),
+    )
+    .await
+    .unwrap()
+    .unwrap_err();
+
+    assert!(err
+        .to_string()
+        .starts_with("The sender stopped unexpectedly"));
+}
+
+#[tokio::test]
+async fn test_get_senders_uses_transport_outcome() {
+    let producer_id = Uuid::new_v4();
+    let message = Message {
+        id: Uuid::new_v4(),
+        sent: false,
+        time_took: None,
+        failed: false,
+        message_body: String::from("Test Message"),
+        produced_by: producer_id,
+        provider_message_id: None,
+        attempts: 0,
+        started_at: None,
+        finished_at: None,
+        time_took_ms: None,
+        in_flight: false,
+        leased_by: None,
+        lease_expires_at: None,
+        message_index: 0,
+    };
+    let queue = Arc::new(Mutex::new(VecDeque::from(vec![message])));
+    let (tx, mut rx) = mpsc::channel(10);
+    let pipeline = Arc::new(DeliveryPipeline {
+        transport: Arc::new(FailingTransport),
+        retry_policy: RetryPolicy::default(),
+        rate_limiters: vec![],
+        clock: Arc::new(SystemClock),
+        seed: None,
+        updater: UpdaterConfig::default(),
+    });
+
+    let handles = get_senders(
+        queue.clone(),
+        &pipeline,
+        &tx,
+        1,
+        &Arc::new(SendJob::new(producer_id, 1)),
+    );
+
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    let processed_message = rx.try_recv().unwrap().message;
+    assert!(processed_message.sent);
+    assert!(processed_message.failed);
+    assert_eq!(processed_message.time_took, Some(0));
+}
+
+/// Test double that fails the given number of deliveries before delivering
+struct FlakyTransport {
+    failures_left: AtomicUsize,
+    kind: FailureKind,
+}
+
+#[async_trait]
+impl MessageTransport for FlakyTransport {
+    async fn send(&self, _message: &Message) -> DeliveryOutcome {
+        let failures_left = self.failures_left.load(SeqCst);
+        if failures_left == 0 {
+            return DeliveryOutcome::Delivered {
+                provider_message_id: None,
+            };
+        }
+        self.failures_left.store(failures_left - 1, SeqCst);
+        DeliveryOutcome::Failed {
+            kind: self.kind,
+            reason: "Flaky".to_string(),
+        }
+    }
+}
+
+/// Sends one message through a single sender thread and collects every update it makes
+async fn send_with_retries(
+    failures: usize,
+    kind: FailureKind,
+    max_attempts: i32,
+) -> Vec<DeliveryUpdate> {
+    let producer_id = Uuid::new_v4();
+    let message = Message {
+        id: Uuid::new_v4(),
+        sent: false,
+        time_took: None,
+        failed: false,
+        message_body: String::from("Test Message"),
+        produced_by: producer_id,
+        provider_message_id: None,
+        attempts: 0,
+        started_at: None,
+        finished_at: None,
+        time_took_ms: None,
+        in_flight: false,
+        leased_by: None,
+        lease_expires_at: None,
+        message_index: 0,
+    };
+    let queue = Arc::new(Mutex::new(VecDeque::from(vec![message])));
+    let (tx, mut rx) = mpsc::channel(10);
+    let pipeline = Arc::new(DeliveryPipeline {
+        transport: Arc::new(FlakyTransport {
+            failures_left: AtomicUsize::new(failures),
+            kind,
+        }),
+        retry_policy: RetryPolicy {
+            max_attempts,
+            base_delay_ms: 10,
+            ..Default::default()
+        },
+        rate_limiters: vec![],
+        clock: Arc::new(SystemClock),
+        seed: None,
+        updater: UpdaterConfig::default(),
+    });
+
+    let handles = get_senders(
+        queue,
+        &pipeline,
+        &tx,
+        1,
+        &Arc::new(SendJob::new(producer_id, 1)),
+    );
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    let mut updates = vec![];
+    while let Ok(update) = rx.try_recv() {
+        updates.push(update);
+    }
+    updates
+}
+
+#[tokio::test]
+async fn test_get_senders_fails_once_attempts_run_out() {
+    let updates = send_with_retries(5, FailureKind::Simulated, 3).await;
+
+    assert_eq!(updates.len(), 3);
+    for (index, update) in updates.iter().enumerate() {
+        assert_eq!(update.message.attempts, index as i32 + 1);
+        assert_eq!(update.attempt.attempt_number, index as i32 + 1);
+        assert_eq!(update.attempt.outcome, "FAILED");
+        assert_eq!(update.attempt.error_code, Some("SIMULATED".to_string()));
+        assert_eq!(update.attempt.error_reason, Some("Flaky".to_string()));
+        assert_eq!(update.attempt.worker_id, 0);
+        assert!(update.attempt.started_at <= update.attempt.finished_at);
+    }
+    assert!(!updates[0].message.sent && !updates[0].message.failed);
+    assert!(!updates[1].message.sent && !updates[1].message.failed);
+    assert!(updates[2].message.sent && updates[2].message.failed);
+}
+
+#[tokio::test]
+async fn test_get_senders_recovers_after_retry() {
+    let updates = send_with_retries(1, FailureKind::Timeout, 3).await;
+
+    assert_eq!(updates.len(), 2);
+    assert!(!updates[0].message.sent);
+    assert_eq!(updates[0].attempt.error_code, Some("TIMEOUT".to_string()));
+    assert!(updates[1].message.sent);
+    assert!(!updates[1].message.failed);
+    assert_eq!(updates[1].message.attempts, 2);
+    assert_eq!(updates[1].attempt.outcome, "DELIVERED");
+    assert_eq!(updates[1].attempt.error_code, None);
+}
+
+#[tokio::test]
+async fn test_get_senders_does_not_retry_rejected() {
+    let updates = send_with_retries(1, FailureKind::Rejected, 3).await;
+
+    assert_eq!(updates.len(), 1);
+    assert!(updates[0].message.sent && updates[0].message.failed);
+    assert_eq!(updates[0].message.attempts, 1);
+}
+
+#[tokio::test]
+async fn test_get_senders_respects_every_rate_limit() {
+    let producer_id = Uuid::new_v4();
+    let queued_messages: Vec<Message> = (0..5)
+        .map(|index| Message {
+            id: Uuid::new_v4(),
+            sent: false,
+            time_took: None,
+            failed: false,
+            message_body: format!("Test Message {}", index),
+            produced_by: producer_id,
+            provider_message_id: None,
+            attempts: 0,
+            started_at: None,
+            finished_at: None,
+            time_took_ms: None,
+            in_flight: false,
+            leased_by: None,
+            lease_expires_at: None,
+            message_index: 0,
+        })
+        .collect();
+    let queue = Arc::new(Mutex::new(VecDeque::from(queued_messages)));
+    let (tx, mut rx) = mpsc::channel(10);
+    let generous_limit = RateLimit {
+        rate: 1000,
+        per: RatePeriod::Second,
+        burst: 1000,
+    };
+    let strict_limit = RateLimit {
+        rate: 20,
+        per: RatePeriod::Second,
+        burst: 1,
+    };
+    let pipeline = Arc::new(DeliveryPipeline {
+        transport: Arc::new(FlakyTransport {
+            failures_left: AtomicUsize::new(0),
+            kind: FailureKind::Simulated,
+        }),
+        retry_policy: RetryPolicy::default(),
+        rate_limiters: vec![
+            Arc::new(TokenBucket::new(generous_limit)),
+            Arc::new(TokenBucket::new(strict_limit)),
+        ],
+        clock: Arc::new(SystemClock),
+        seed: None,
+        updater: UpdaterConfig::default(),
+    });
+
+    let start = Instant::now();
+    let handles = get_senders(
+        queue,
+        &pipeline,
+        &tx,
+        4,
+        &Arc::new(SendJob::new(producer_id, 5)),
+    );
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    // The first message goes out straight away, the other four wait 50ms each however many senders there are
+    assert!(start.elapsed() >= Duration::from_millis(190));
+    let mut delivered = 0;
+    while let Ok(update) = rx.try_recv() {
+        assert!(update.message.sent && !update.message.failed);
+        delivered += 1;
+    }
+    assert_eq!(delivered, 5);
+}
+
+#[tokio::test]
+async fn test_get_senders_records_simulated_time() {
+    let producer = Producer {
+        id: Uuid::new_v4(),
+        name: "aProducer".to_string(),
+        num_senders: None,
+        number_messages: 10,
+        status: ProducerStatus::Generated,
+        average_send_delay: 20,
+        failure_rate: 0,
+        transport: json!({ "kind": "SIMULATED", "clock": { "mode": "INSTANT" } }),
+        retry_policy: json!({}),
+        rate_limit: None,
+        seed: None,
+        generated_messages: 0,
+        created_at: Utc::now(),
+    };
+    let queued_messages: Vec<Message> = (0..10)
+        .map(|index| Message {
+            id: Uuid::new_v4(),
+            sent: false,
+            time_took: None,
+            failed: false,
+            message_body: format!("Test Message {}", index),
+            produced_by: producer.id,
+            provider_message_id: None,
+            attempts: 0,
+            started_at: None,
+            finished_at: None,
+            time_took_ms: None,
+            in_flight: false,
+            leased_by: None,
+            lease_expires_at: None,
+            message_index: 0,
+        })
+        .collect();
+    let queue = Arc::new(Mutex::new(VecDeque::from(queued_messages)));
+    let (tx, mut rx) = mpsc::channel(10);
+
+    let start = Instant::now();
+    let handles = get_senders(
+        queue,
+        &Arc::new(DeliveryPipeline::for_producer(&producer).unwrap()),
+        &tx,
+        3,
+        &Arc::new(SendJob::new(producer.id, 10)),
+    );
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    // Ten 15 - 25 second sends finish without waiting them out
+    assert!(start.elapsed() < Duration::from_secs(5));
+    let mut delivered = 0;
+    while let Ok(update) = rx.try_recv() {
+        let time_took = update.message.time_took.unwrap();
+        assert!((15..=25).contains(&time_took));
+        assert_eq!(
+            (update.attempt.finished_at - update.attempt.started_at).num_seconds(),
+            time_took as i64
+        );
+        delivered += 1;
+    }
+    assert_eq!(delivered, 10);
+}
+
+#[tokio::test]
+async fn test_get_senders_records_time_in_milliseconds() {
+    let producer = Producer {
+        id: Uuid::new_v4(),
+        name: "aProducer".to_string(),
+        num_senders: None,
+        number_messages: 3,
+        status: ProducerStatus::Generated,
+        average_send_delay: 1,
+        failure_rate: 0,
+        transport: json!({
+            "kind": "SIMULATED",
+            "clock": { "mode": "INSTANT" },
+            "latency": { "model": "FIXED", "delay_ms": 250 }
+        }),
+        retry_policy: json!({}),
+        rate_limit: None,
+        seed: None,
+        generated_messages: 0,
+        created_at: Utc::now(),
+    };
+    let queued_messages: Vec<Message> = (0..3)
+        .map(|index| Message {
+            id: Uuid::new_v4(),
+            sent: false,
+            time_took: None,
+            failed: false,
+            message_body: format!("Test Message {}", index),
+            produced_by: producer.id,
+            provider_message_id: None,
+            attempts: 0,
+            started_at: None,
+            finished_at: None,
+            time_took_ms: None,
+            in_flight: false,
+            leased_by: None,
+            lease_expires_at: None,
+            message_index: 0,
+        })
+        .collect();
+    let queue = Arc::new(Mutex::new(VecDeque::from(queued_messages)));
+    let (tx, mut rx) = mpsc::channel(10);
+
+    let handles = get_senders(
+        queue,
+        &Arc::new(DeliveryPipeline::for_producer(&producer).unwrap()),
+        &tx,
+        1,
+        &Arc::new(SendJob::new(producer.id, 3)),
+    );
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    let mut delivered = 0;
+    while let Ok(update) = rx.try_recv() {
+        // Under a second still reads as 0 in the whole second field
+        assert_eq!(update.message.time_took, Some(0));
+        assert_eq!(update.message.time_took_ms, Some(250));
+        assert_eq!(update.message.started_at, Some(update.attempt.started_at));
+        assert_eq!(update.message.finished_at, Some(update.attempt.finished_at));
+        delivered += 1;
+    }
+    assert_eq!(delivered, 3);
+}
+
+#[tokio::test]
+async fn test_get_senders_repeats_outcomes_with_a_seed() {
+    let producer = Producer {
+        id: Uuid::new_v4(),
+        name: "aProducer".to_string(),
+        num_senders: None,
+        number_messages: 20,
+        status: ProducerStatus::Generated,
+        average_send_delay: 20,
+        failure_rate: 50,
+        transport: json!({ "kind": "SIMULATED", "clock": { "mode": "INSTANT" } }),
+        retry_policy: json!({ "max_attempts": 3, "jitter": 0.5 }),
+        rate_limit: None,
+        seed: Some(42),
+        generated_messages: 0,
+        created_at: Utc::now(),
+    };
+
+    // Every message has the same body, so only their indexes tell their outcomes apart
+    // Each run generates the messages again under new ids, as sending the producer again would
+    let queued_messages = || -> Vec<Message> {
+        (0..20)
+            .map(|message_index| Message {
+                id: Uuid::new_v4(),
+                sent: false,
+                time_took: None,
+                failed: false,
+                message_body: String::new(),
+                produced_by: producer.id,
+                provider_message_id: None,
+                attempts: 0,
+                started_at: None,
+                finished_at: None,
+                time_took_ms: None,
+                in_flight: false,
+                leased_by: None,
+                lease_expires_at: None,
+                message_index,
+            })
+            .collect()
+    };
+
+    let mut runs = vec![];
+    for _ in 0..2 {
+        let queue = Arc::new(Mutex::new(VecDeque::from(queued_messages())));
+        let (tx, mut rx) = mpsc::channel(100);
+
+        let handles = get_senders(
+            queue,
+            &Arc::new(DeliveryPipeline::for_producer(&producer).unwrap()),
+            &tx,
+            4,
+            &Arc::new(SendJob::new(producer.id, 20)),
+        );
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+
+        let mut outcomes = HashMap::new();
+        while let Ok(update) = rx.try_recv() {
+            outcomes.insert(
+                (update.message.message_index, update.attempt.attempt_number),
+                (
+                    update.message.failed,
+                    update.message.time_took,
+                    update.attempt.finished_at - update.attempt.started_at,
+                ),
+            );
+        }
+        runs.push(outcomes);
+    }
+
+    assert!(runs[0].len() >= 20);
+    assert_eq!(runs[0], runs[1]);
+}
+
+#[test]
+fn test_updater_config_validate() {
+    assert!(UpdaterConfig::default().validate().is_ok());
+
+    for config in [
+        UpdaterConfig {
+            batch_size: 0,
+            ..Default::default()
+        },
+        UpdaterConfig {
+            batch_size: 100_000,
+            ..Default::default()
+        },
+        UpdaterConfig {
+            flush_interval: Duration::ZERO,
+            ..Default::default()
+        },
+    ] {
+        assert!(config.validate().is_err());
+    }
+}
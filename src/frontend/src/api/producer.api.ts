@@ -1,12 +1,26 @@
-import { Producer, ProducerArgs, ProgressData } from '../utils/types';
+import { Producer, ProducerArgs, ProducerPage, ProgressData, SendJob } from '../utils/types';
 import urls from '../utils/urls';
 import { analogFetch } from './fetch';
 
+/** The most producers the backend returns on one page */
+const PRODUCER_PAGE_SIZE = 500;
+
 /**
- * Fetches all producers
+ * Fetches every producer, following the cursor of each page until the last one
+ *
+ * @returns The producers of every page, in the order they were listed
  */
 export const getAllProducers = async () => {
-  return await analogFetch<Producer[]>(urls.ALL_PRODUCERS());
+  const producers: Producer[] = [];
+  let cursor: string | undefined;
+
+  do {
+    const page = await analogFetch<ProducerPage>(urls.ALL_PRODUCERS(PRODUCER_PAGE_SIZE, cursor));
+    producers.push(...page.producers);
+    cursor = page.next_cursor ?? undefined;
+  } while (cursor);
+
+  return producers;
 };
 
 /**
@@ -20,13 +34,13 @@ export const getProducerById = async (id: string) => {
 };
 
 /**
- * Sends the pending messages of the producer with the given id
+ * Starts sending the pending messages of the producer with the given id in the background
  *
  * @param id The id of the producer to activate
- * @returns A success message
+ * @returns The started send job
  */
 export const activateProducer = async (id: string) => {
-  return await analogFetch<string>(urls.ACTIVATE_PRODUCER(id), {
+  return await analogFetch<SendJob>(urls.ACTIVATE_PRODUCER(id), {
     method: 'POST'
   });
 };
@@ -2,9 +2,10 @@ import { Area, AreaChart, CartesianGrid, XAxis } from 'recharts';
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from './ui/card';
 import { ChartConfig, ChartContainer, ChartTooltip, ChartTooltipContent } from './ui/chart';
 import { useEffect, useState } from 'react';
+import { MessageTimeBucket } from '../utils/types';
 
 interface MessageDurationDistributionProps {
-  messageTimes: number[];
+  histogram: MessageTimeBucket[];
 }
 
 const chartConfig = {
@@ -19,31 +20,18 @@ interface GraphValue {
   y: number;
 }
 
-const MessageDurationDistribution = ({ messageTimes }: MessageDurationDistributionProps) => {
+const MessageDurationDistribution = ({ histogram }: MessageDurationDistributionProps) => {
   const [chartData, setChartData] = useState([] as GraphValue[]);
 
+  // The backend already groups the times into buckets, each is drawn at the time it starts from in seconds
   useEffect(() => {
-    const messageTimeMap = new Map<number, number>();
-    for (const time of messageTimes) {
-      if (messageTimeMap.has(time)) {
-        messageTimeMap.set(time, messageTimeMap.get(time)! + 1); // checked that it has it
-      } else {
-        messageTimeMap.set(time, 1);
-      }
-    }
-
-    const newChartData: GraphValue[] = [];
-    messageTimeMap.forEach((value, key) => {
-      newChartData.push({
-        x: key,
-        y: value
-      });
-    });
-
-    newChartData.sort((a, b) => a.x - b.x);
-
-    setChartData(newChartData);
-  }, [messageTimes]);
+    setChartData(
+      histogram.map((bucket) => ({
+        x: bucket.from_ms / 1000,
+        y: bucket.count
+      }))
+    );
+  }, [histogram]);
 
   return (
     <Card>
@@ -62,7 +50,7 @@ const MessageDurationDistribution = ({ messageTimes }: MessageDurationDistributi
             }}
           >
             <CartesianGrid vertical={false} />
-            <XAxis dataKey="x" tickLine={false} axisLine={false} tickMargin={8} tickFormatter={(value) => value} />
+            <XAxis dataKey="x" tickLine={false} axisLine={false} tickMargin={8} tickFormatter={(value) => `${value}s`} />
             <ChartTooltip cursor={false} content={<ChartTooltipContent indicator="line" />} />
             <Area dataKey="y" type="natural" fill="var(--color-desktop)" fillOpacity={0.4} stroke="var(--color-desktop)" />
           </AreaChart>
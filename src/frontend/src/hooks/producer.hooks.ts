@@ -9,7 +9,7 @@ import {
   getProducerProgress,
   updateProducer
 } from '../api/producer.api';
-import { Producer, ProducerArgs, ProgressData } from '../utils/types';
+import { Producer, ProducerArgs, ProgressData, SendJob } from '../utils/types';
 
 /**
  * Custom react hook to get all the producers from the backend
@@ -47,7 +47,7 @@ export const useGetProducerById = (id: string) => {
  */
 export const useActivateProducer = (id: string) => {
   const queryClient = useQueryClient();
-  return useMutation<string, Error>({
+  return useMutation<SendJob, Error>({
     mutationFn: async () => {
       return await activateProducer(id);
     },
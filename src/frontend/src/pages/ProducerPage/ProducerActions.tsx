@@ -55,7 +55,7 @@ const ProducerActions = ({ producer, onRefreshRateChanged }: ProducerActionsProp
     try {
       await activateProducer();
       toast({
-        title: 'Successfully sent all pending messages'
+        title: 'Started sending pending messages'
       });
     } catch (error) {
       if (error instanceof Error) {
@@ -60,7 +60,7 @@ const ProducerPage = () => {
           numberSent={producerProgress.number_messages_sent}
           totalMessages={producerProgress.number_messages_created}
         />
-        <MessageDurationDistribution messageTimes={producerProgress.message_times} />
+        <MessageDurationDistribution histogram={producerProgress.message_time_histogram} />
       </Grid2>
     </Box>
   );
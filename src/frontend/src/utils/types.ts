@@ -9,12 +9,41 @@ export interface ProducerArgs {
 export interface Producer extends ProducerArgs {
   id: string;
   status: string;
+  created_at: string;
+}
+
+export interface ProducerPage {
+  producers: Producer[];
+  total: number;
+  status_counts: Record<string, number>;
+  next_cursor: string | null;
+}
+
+export interface SendJob {
+  id: string;
+  producer_id: string;
+  state: string;
+  started_at: string;
+  finished_at?: string;
+  number_messages: number;
+  number_messages_sent: number;
+  number_messages_failed: number;
+  error?: string;
 }
 
 export interface ProgressData {
   number_messages_created: number;
   number_messages_sent: number;
   number_messages_failed: number;
+  number_messages_retried: number;
+  number_messages_recovered: number;
   average_message_time: number;
-  message_times: number[];
+  average_message_time_ms: number;
+  message_time_histogram: MessageTimeBucket[];
+}
+
+export interface MessageTimeBucket {
+  from_ms: number;
+  to_ms: number;
+  count: number;
 }
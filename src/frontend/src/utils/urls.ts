@@ -2,7 +2,8 @@ const API_URL: string = import.meta.env.BACKEND_URL || 'http://localhost:8080';
 
 /**************** Producers Endpoints ****************/
 const PRODUCERS = () => API_URL + '/producers';
-const ALL_PRODUCERS = () => `${PRODUCERS()}`;
+const ALL_PRODUCERS = (limit: number, cursor?: string) =>
+  `${PRODUCERS()}?limit=${limit}` + (cursor ? `&cursor=${encodeURIComponent(cursor)}` : '');
 const CREATE_PRODUCER = () => `${PRODUCERS()}/create`;
 const PRODUCER_BY_ID = (id: string) => `${PRODUCERS()}/${id}`;
 const UPDATE_PRODUCER = (id: string) => `${PRODUCER_BY_ID(id)}/update`;
@@ -28,9 +28,24 @@ describe('producerService', () => {
   });
 
   it('should call analogFetch with the correct URL for getAllProducers', async () => {
+    fetchSpy = getAnalogFetchSpy({ producers: [expectedValue], total: 1, next_cursor: null });
     const result = await getAllProducers();
-    expect(result).toStrictEqual(expectedValue);
-    expect(fetchSpy).toHaveBeenCalledWith(baseUrl + '/producers');
+    expect(result).toStrictEqual([expectedValue]);
+    expect(fetchSpy).toHaveBeenCalledTimes(1);
+    expect(fetchSpy).toHaveBeenCalledWith(baseUrl + '/producers?limit=500');
+  });
+
+  it('should follow the cursor of every page for getAllProducers', async () => {
+    const secondValue = { someKey: 'someOtherValue' };
+    fetchSpy = getAnalogFetchSpy({ producers: [secondValue], total: 2, next_cursor: null });
+    fetchSpy.mockResolvedValueOnce({ producers: [expectedValue], total: 2, next_cursor: 'next page' });
+
+    const result = await getAllProducers();
+
+    expect(result).toStrictEqual([expectedValue, secondValue]);
+    expect(fetchSpy).toHaveBeenCalledTimes(2);
+    expect(fetchSpy).toHaveBeenNthCalledWith(1, baseUrl + '/producers?limit=500');
+    expect(fetchSpy).toHaveBeenNthCalledWith(2, baseUrl + '/producers?limit=500&cursor=next%20page');
   });
 
   it('should call analogFetch with the correct URL for getProducerById', async () => {
@@ -14,14 +14,18 @@ vi.mock('recharts', async (original) => {
 
 describe('MessageDurationDistribution Component', () => {
   it('renders the title and description', () => {
-    render(<MessageDurationDistribution messageTimes={[]} />);
+    render(<MessageDurationDistribution histogram={[]} />);
     expect(screen.getByText('Message Duration Distribution')).toBeInTheDocument();
     expect(screen.getByText('Showing distribution of the time each message took to send')).toBeInTheDocument();
   });
 
   it('passes the correct chart data to AreaChart', () => {
-    const messageTimes = [1, 2, 2, 3, 3, 3];
-    render(<MessageDurationDistribution messageTimes={messageTimes} />);
+    const histogram = [
+      { from_ms: 1000, to_ms: 2000, count: 1 },
+      { from_ms: 2000, to_ms: 3000, count: 2 },
+      { from_ms: 3000, to_ms: 4000, count: 3 }
+    ];
+    render(<MessageDurationDistribution histogram={histogram} />);
 
     // Expected chart data
     const expectedData = [
@@ -39,8 +43,8 @@ describe('MessageDurationDistribution Component', () => {
     );
   });
 
-  it('renders empty chart data when messageTimes is empty', () => {
-    render(<MessageDurationDistribution messageTimes={[]} />);
+  it('renders empty chart data when the histogram is empty', () => {
+    render(<MessageDurationDistribution histogram={[]} />);
 
     // Verify AreaChart is called with an empty array
     expect(AreaChart).toHaveBeenCalledWith(
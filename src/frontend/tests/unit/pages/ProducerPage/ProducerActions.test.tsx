@@ -89,7 +89,7 @@ describe('ProducerActions', () => {
 
     await waitFor(() => expect(activateSpy).toHaveBeenCalledTimes(1));
     expect(toastFn).toHaveBeenCalledWith({
-      title: 'Successfully sent all pending messages'
+      title: 'Started sending pending messages'
     });
   });
 
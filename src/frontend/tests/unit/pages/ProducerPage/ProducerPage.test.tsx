@@ -37,8 +37,12 @@ describe('ProducerPage', () => {
     number_messages_failed: 10,
     number_messages_sent: 100,
     number_messages_created: 200,
-    message_times: [10, 20, 30],
-    average_message_time: 20
+    average_message_time: 20,
+    average_message_time_ms: 20000,
+    message_time_histogram: [
+      { from_ms: 10000, to_ms: 20000, count: 1 },
+      { from_ms: 20000, to_ms: 30000, count: 2 }
+    ]
   };
 
   let mockRefetch;
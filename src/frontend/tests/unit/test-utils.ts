@@ -85,7 +85,8 @@ export const createTestProducer = ({
   number_messages = 10,
   num_senders = 4,
   average_send_delay = 5,
-  failure_rate = 20
+  failure_rate = 20,
+  created_at = '2026-01-01T00:00:00Z'
 }: {
   id?: string;
   status?: string;
@@ -94,6 +95,7 @@ export const createTestProducer = ({
   num_senders?: number;
   average_send_delay?: number;
   failure_rate?: number;
+  created_at?: string;
 } = {}): Producer => {
   return {
     id,
@@ -102,6 +104,7 @@ export const createTestProducer = ({
     number_messages,
     num_senders,
     average_send_delay,
-    failure_rate
+    failure_rate,
+    created_at
   };
 };